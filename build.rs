@@ -0,0 +1,89 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Crates whose resolved versions go into output metadata and cache keys
+const RECORDED_CRATES: &[&str] = &[
+    "tree-sitter",
+    "tree-sitter-c",
+    "tree-sitter-cpp",
+    "tree-sitter-go",
+    "tree-sitter-java",
+    "tree-sitter-javascript",
+    "tree-sitter-rust",
+    "tree-sitter-typescript",
+    "tree-sitter-zig",
+];
+
+// Records the resolved tree-sitter and grammar crate versions from Cargo.lock as
+// TREESCAN_DEPENDENCY_VERSIONS ("name=version;..."), for output metadata and cache
+// keys (see src/version.rs). Cargo writes the lock file before building, but not
+// always next to this manifest: as a dependency it is the consuming workspace's, found
+// above the manifest or the build directory. A build where none of those holds every
+// recorded crate fails, rather than producing empty versions and cache keys.
+fn main() {
+    let lock_path = find_lock_file().unwrap_or_else(|| {
+        panic!(
+            "treescan: no Cargo.lock listing the tree-sitter crates was found above {} or {}; \
+             run `cargo generate-lockfile` in the workspace and build again",
+            env::var("CARGO_MANIFEST_DIR").unwrap_or_default(),
+            env::var("OUT_DIR").unwrap_or_default()
+        )
+    });
+    println!("cargo:rerun-if-changed={}", lock_path.display());
+
+    let versions = locked_versions(&fs::read_to_string(&lock_path).unwrap_or_default());
+    println!(
+        "cargo:rustc-env=TREESCAN_DEPENDENCY_VERSIONS={}",
+        versions.join(";")
+    );
+}
+
+// The nearest Cargo.lock above the manifest, then above the build directory, that
+// lists every recorded crate
+fn find_lock_file() -> Option<PathBuf> {
+    ["CARGO_MANIFEST_DIR", "OUT_DIR"]
+        .iter()
+        .filter_map(|var| env::var(var).ok())
+        .flat_map(|dir| {
+            Path::new(&dir)
+                .ancestors()
+                .map(|dir| dir.join("Cargo.lock"))
+                .collect::<Vec<_>>()
+        })
+        .find(|lock| {
+            fs::read_to_string(lock).is_ok_and(|text| {
+                let versions = locked_versions(&text);
+                RECORDED_CRATES.iter().all(|name| {
+                    versions
+                        .iter()
+                        .any(|entry| entry.split_once('=').is_some_and(|(n, _)| n == *name))
+                })
+            })
+        })
+}
+
+// "name=version" for each recorded crate in the lock file, sorted
+fn locked_versions(lock: &str) -> Vec<String> {
+    let mut versions = Vec::new();
+    let mut name = None;
+    for line in lock.lines() {
+        if line == "[[package]]" {
+            name = None;
+        } else if let Some(value) = quoted_value(line, "name") {
+            name = Some(value);
+        } else if let Some(version) = quoted_value(line, "version") {
+            if let Some(name) = name.take().filter(|name| RECORDED_CRATES.contains(name)) {
+                versions.push(format!("{}={}", name, version));
+            }
+        }
+    }
+    versions.sort();
+    versions
+}
+
+// `key = "value"` -> value
+fn quoted_value<'a>(line: &'a str, key: &str) -> Option<&'a str> {
+    let value = line.strip_prefix(key)?.trim_start().strip_prefix('=')?;
+    value.trim().strip_prefix('"')?.strip_suffix('"')
+}
@@ -1,8 +1,35 @@
-use std::ffi::{CStr, CString};
-use std::fs;
-use libc::c_char;
-use serde_json::{json, Value};
-use tree_sitter::{Language, Parser, Query, QueryCursor, StreamingIterator};
+use crate::ast::{parse_tree_with_timeout, ParseError};
+use crate::cache::stable_hash;
+use crate::injection::analyze_injections;
+use crate::macros::analyze_macro_bodies;
+use crate::plugin::{run_check, RuleCheck};
+use crate::script::{Script, ScriptContext, ScriptRule, Verdict};
+use crate::language::SupportedLanguage;
+use crate::length::{length_findings, LengthLimits};
+use crate::locale::{bundled_translation, Translation};
+use crate::chains::{long_call_chains, DEFAULT_MAX_CHAIN_LENGTH};
+use crate::commented_code::commented_out_code;
+use crate::conditions::{complex_conditions, DEFAULT_MAX_BOOLEAN_OPERATORS};
+use crate::empty_bodies::empty_bodies;
+use crate::explain::RuleDoc;
+use crate::naming::{default_naming_rules, naming_findings, NameKind, NamingRule};
+use crate::logging::{self, Level};
+use crate::metrics::FileMetrics;
+use crate::overrides::PathOverride;
+use crate::position::ColumnUnit;
+use crate::source::read_source;
+use crate::timings;
+use crate::trace;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use crate::report::AnalysisReport;
+use crate::version::version_metadata;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::io::Write;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use tree_sitter::{Language, Query, QueryCursor, QueryError, StreamingIterator, Tree};
 
 #[derive(Debug, Clone)]
 pub struct AnalysisResult {
@@ -14,9 +41,12 @@ pub struct AnalysisResult {
     pub text: String,
     pub suggestion: Option<String>,
     pub score_impact: f64,
+    pub category: Option<String>,
+    // Survives line shifts, see `fingerprint`
+    pub fingerprint: String,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Severity {
     Error,
     Warning,
@@ -25,6 +55,35 @@ pub enum Severity {
 }
 
 impl Severity {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "error" => Some(Severity::Error),
+            "warning" => Some(Severity::Warning),
+            "info" => Some(Severity::Info),
+            "style" => Some(Severity::Style),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Error => "Error",
+            Severity::Warning => "Warning",
+            Severity::Info => "Info",
+            Severity::Style => "Style",
+        }
+    }
+
+    // Error ranks highest, Style lowest
+    pub fn rank(&self) -> u8 {
+        match self {
+            Severity::Error => 3,
+            Severity::Warning => 2,
+            Severity::Info => 1,
+            Severity::Style => 0,
+        }
+    }
+
     pub fn base_score_impact(&self) -> f64 {
         match self {
             Severity::Error => -3.0,   // Critical issues
@@ -33,6 +92,16 @@ impl Severity {
             Severity::Style => -0.2,   // Style preferences
         }
     }
+
+    // Estimated minutes to fix one finding when the rule has no configured value
+    pub fn default_remediation_minutes(&self) -> u32 {
+        match self {
+            Severity::Error => 30,
+            Severity::Warning => 15,
+            Severity::Info => 5,
+            Severity::Style => 2,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -43,6 +112,18 @@ pub struct AnalysisRule {
     pub message_template: String,
     pub suggestion: Option<String>,
     pub weight_multiplier: f64, // Custom weight for specific rules
+    // Report only this capture of the query instead of every capture
+    pub capture: Option<String>,
+    // Extra filter supplied by a plugin (see `plugin`)
+    pub check: Option<RuleCheck>,
+    // Decides whether and how each capture is reported (see `script`)
+    pub script: Option<Script>,
+    // Groups related rules (e.g. "error-handling") in reports
+    pub category: Option<String>,
+    // locale -> message and suggestion shipped with the rule (see `locale`)
+    pub translations: BTreeMap<String, Translation>,
+    // Description and example for `rules explain` (see `explain`)
+    pub doc: Option<RuleDoc>,
 }
 
 impl AnalysisRule {
@@ -60,6 +141,12 @@ impl AnalysisRule {
             message_template: message,
             suggestion,
             weight_multiplier: 1.0, // Default weight
+            capture: None,
+            check: None,
+            script: None,
+            category: None,
+            translations: BTreeMap::new(),
+            doc: None,
         }
     }
 
@@ -67,6 +154,58 @@ impl AnalysisRule {
         self.weight_multiplier = weight;
         self
     }
+
+    pub fn with_capture(mut self, capture: &str) -> Self {
+        self.capture = Some(capture.to_string());
+        self
+    }
+
+    pub fn with_category(mut self, category: &str) -> Self {
+        self.category = Some(category.to_string());
+        self
+    }
+
+    pub fn with_translations(mut self, translations: BTreeMap<String, Translation>) -> Self {
+        self.translations = translations;
+        self
+    }
+
+    pub fn with_doc(mut self, doc: RuleDoc) -> Self {
+        self.doc = (doc != RuleDoc::default()).then_some(doc);
+        self
+    }
+}
+
+// Rules about Results and errors being dropped instead of handled
+pub const ERROR_HANDLING: &str = "error-handling";
+// Rules about goroutines and synchronization
+pub const CONCURRENCY: &str = "concurrency";
+// Rules about promises whose rejections go unhandled
+pub const PROMISES: &str = "promises";
+
+// Standard library calls that do I/O; defaulting their Result hides the failure
+const RUST_IO_CALLS: &str = "^(read|read_to_string|read_to_end|read_line|read_dir|write|write_all|\
+                             metadata|open|create|create_dir|create_dir_all|remove_file|remove_dir_all|\
+                             rename|copy|canonicalize|output|status|connect|bind)$";
+
+// How a rule treats findings in test code (test files and Rust `#[cfg(test)]` / `#[test]` items)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TestCodePolicy {
+    Off,
+    // Multiplies the score impact; 1.0 reports test findings like any other
+    Weight(f64),
+}
+
+impl TestCodePolicy {
+    // "off", "on" or a weight
+    pub fn from_json(value: &Value) -> Option<Self> {
+        match value {
+            Value::String(s) if s == "off" => Some(TestCodePolicy::Off),
+            Value::String(s) if s == "on" => Some(TestCodePolicy::Weight(1.0)),
+            Value::Number(n) => n.as_f64().map(TestCodePolicy::Weight),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -77,6 +216,8 @@ pub struct CodeScore {
     pub breakdown: ScoreBreakdown,
     pub rating: String,
     pub summary: String,
+    // Estimated effort to fix every finding (SQALE-style technical debt)
+    pub remediation_minutes: u32,
 }
 
 #[derive(Debug, Clone)]
@@ -94,15 +235,580 @@ pub struct ScoreBreakdown {
 
 pub struct CodeAnalyzer {
     rules: Vec<AnalysisRule>,
+    // Compiled once on first use; rule queries are the bulk of per-file cost otherwise.
+    // Shared with every analyzer that has the same rules (see `shared_queries`), and
+    // kept with the grammar they were compiled for.
+    compiled_queries: OnceLock<(Language, Arc<Vec<Query>>)>,
+    max_issues: Option<usize>,
+    parse_timeout: Option<Duration>,
+    column_unit: ColumnUnit,
+    magic_number_allowlist: Vec<String>,
+    remediation_minutes: BTreeMap<String, u32>,
+    ratings: RatingScale,
+    test_code_rules: BTreeMap<String, TestCodePolicy>,
+    // Every finding is in test code
+    test_file: bool,
+    // Analyzers for code embedded in string literals (see `injection`)
+    injected: Vec<(SupportedLanguage, CodeAnalyzer)>,
+    // Re-parse Rust macro bodies and run the rules over them (see `macros`)
+    macro_bodies: bool,
+    length_limits: LengthLimits,
+    // Report call chains longer than this (see `chains`)
+    max_chain_length: Option<usize>,
+    // Report empty if/else/catch blocks and functions (see `empty_bodies`)
+    empty_bodies: bool,
+    // Report comment blocks that parse as code (see `commented_code`)
+    commented_code: bool,
+    // Report declared names that break these patterns (see `naming`)
+    naming: Vec<NamingRule>,
+    // Report ternaries nested in ternaries (see `conditions`)
+    nested_ternaries: bool,
+    // Report `&&`/`||` expressions with more operators than this
+    max_boolean_operators: Option<usize>,
+    // Language rule text is reported in (see `locale`); None is English
+    locale: Option<String>,
+}
+
+// Project-level tuning for rules whose findings depend on local conventions
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuleConfig {
+    pub magic_number_allowlist: Vec<String>,
+    // `_test.go` files are full of table-driven literals, so they are skipped by default
+    pub magic_numbers_in_tests: bool,
+    // Directories of `<lang>/lints.scm` files whose captures become extra rules
+    pub query_dirs: Vec<PathBuf>,
+    // rule name -> minutes to fix one finding, overriding the severity default
+    pub remediation_minutes: BTreeMap<String, u32>,
+    pub ratings: RatingScale,
+    // rule name -> treatment inside test code
+    pub in_tests: BTreeMap<String, TestCodePolicy>,
+    // Analyze code embedded in string literals with its own language's rules
+    pub injections: bool,
+    // Re-parse Rust macro bodies; off by default since it is a heuristic
+    pub macro_bodies: bool,
+    // Shared libraries whose rules extend the built-in analyzers (see `plugin`)
+    pub plugins: Vec<PathBuf>,
+    // Query rules filtered by a script (see `script`)
+    pub scripts: Vec<ScriptRule>,
+    // Names of installed rule packs to enable (see `rule_pack`)
+    pub packs: Vec<String>,
+    // Rules below this severity are dropped rather than down-weighted
+    pub min_severity: Option<Severity>,
+    pub length_limits: LengthLimits,
+    // None turns `long_call_chain` off
+    pub max_chain_length: Option<usize>,
+    // language -> kind -> pattern replacing the default; None stops checking that kind
+    pub naming: BTreeMap<SupportedLanguage, BTreeMap<NameKind, Option<String>>>,
+    // None turns `complex_boolean_expression` off
+    pub max_boolean_operators: Option<usize>,
+    // Report rule text in this language where a translation exists
+    pub locale: Option<String>,
+    // Settings for parts of the tree (see `overrides`); from the project config
+    pub overrides: Vec<PathOverride>,
+}
+
+impl Default for RuleConfig {
+    fn default() -> Self {
+        RuleConfig {
+            magic_number_allowlist: DEFAULT_MAGIC_NUMBER_ALLOWLIST
+                .iter()
+                .map(|v| v.to_string())
+                .collect(),
+            magic_numbers_in_tests: false,
+            query_dirs: Vec::new(),
+            remediation_minutes: BTreeMap::new(),
+            ratings: RatingScale::default(),
+            // unwrap() is the idiomatic way to fail a Rust test
+            in_tests: BTreeMap::from([("unwrap_usage".to_string(), TestCodePolicy::Off)]),
+            injections: true,
+            macro_bodies: false,
+            plugins: Vec::new(),
+            scripts: Vec::new(),
+            packs: Vec::new(),
+            min_severity: None,
+            length_limits: LengthLimits::default(),
+            max_chain_length: Some(DEFAULT_MAX_CHAIN_LENGTH),
+            naming: BTreeMap::new(),
+            max_boolean_operators: Some(DEFAULT_MAX_BOOLEAN_OPERATORS),
+            locale: None,
+            overrides: Vec::new(),
+        }
+    }
+}
+
+impl RuleConfig {
+    // Reads `magic_number_allowlist` (numbers or strings), `magic_numbers_in_tests`,
+    // `query_dirs`, `remediation_minutes` ({"rule": minutes}), `in_tests` ({"rule": "off" |
+    // "on" | weight}, merged over the defaults), `injections`, `macro_bodies`, `plugins`,
+    // `scripts`, `packs`, `min_severity`, `max_line_length`, `max_file_lines`,
+    // `max_chain_length` and `max_boolean_operators` (0 turns them off), `naming`, `locale` and
+    // `ratings`. Malformed `scripts` entries are dropped and malformed `ratings` keep the
+    // default bands (`ProjectConfig::load` rejects them up front)
+    pub fn from_json(config: &Value) -> Self {
+        let mut rules = RuleConfig::default();
+        rules.apply_json(config);
+        rules
+    }
+
+    // Sets what `config` names and leaves the rest, for path overrides
+    pub fn apply_json(&mut self, config: &Value) {
+        let rules = self;
+        if let Some(values) = config.get("magic_number_allowlist").and_then(Value::as_array) {
+            rules.magic_number_allowlist = values
+                .iter()
+                .map(|v| v.as_str().map_or_else(|| v.to_string(), str::to_string))
+                .collect();
+        }
+        if let Some(in_tests) = config.get("magic_numbers_in_tests").and_then(Value::as_bool) {
+            rules.magic_numbers_in_tests = in_tests;
+        }
+        if let Some(dirs) = config.get("query_dirs").and_then(Value::as_array) {
+            rules.query_dirs = dirs.iter().filter_map(Value::as_str).map(PathBuf::from).collect();
+        }
+        if let Some(minutes) = config.get("remediation_minutes").and_then(Value::as_object) {
+            rules.remediation_minutes = minutes
+                .iter()
+                .filter_map(|(rule, m)| Some((rule.clone(), u32::try_from(m.as_u64()?).ok()?)))
+                .collect();
+        }
+        if let Some(in_tests) = config.get("in_tests").and_then(Value::as_object) {
+            for (rule, policy) in in_tests {
+                if let Some(policy) = TestCodePolicy::from_json(policy) {
+                    rules.in_tests.insert(rule.clone(), policy);
+                }
+            }
+        }
+        if let Some(injections) = config.get("injections").and_then(Value::as_bool) {
+            rules.injections = injections;
+        }
+        if let Some(scripts) = config.get("scripts").and_then(Value::as_array) {
+            rules.scripts = scripts
+                .iter()
+                .filter_map(|script| ScriptRule::from_json(script).ok())
+                .collect();
+        }
+        if let Some(packs) = config.get("packs").and_then(Value::as_array) {
+            rules.packs = packs.iter().filter_map(Value::as_str).map(str::to_string).collect();
+        }
+        if let Some(plugins) = config.get("plugins").and_then(Value::as_array) {
+            rules.plugins = plugins.iter().filter_map(Value::as_str).map(PathBuf::from).collect();
+        }
+        if let Some(macro_bodies) = config.get("macro_bodies").and_then(Value::as_bool) {
+            rules.macro_bodies = macro_bodies;
+        }
+        if let Some(min) = config.get("min_severity").and_then(Value::as_str) {
+            rules.min_severity = Severity::from_name(min);
+        }
+        if let Some(max) = config.get("max_line_length").and_then(Value::as_u64) {
+            rules.length_limits.max_line_length = Some(max as usize);
+        }
+        if let Some(max) = config.get("max_file_lines").and_then(Value::as_u64) {
+            rules.length_limits.max_file_lines = Some(max as usize);
+        }
+        if let Some(max) = config.get("max_chain_length").and_then(Value::as_u64) {
+            rules.max_chain_length = (max > 0).then_some(max as usize);
+        }
+        if let Some(max) = config.get("max_boolean_operators").and_then(Value::as_u64) {
+            rules.max_boolean_operators = (max > 0).then_some(max as usize);
+        }
+        // {"rust": {"function": "^[a-z_]+$", "constant": null}}; null or "" turns a kind off
+        if let Some(languages) = config.get("naming").and_then(Value::as_object) {
+            for (lang, kinds) in languages {
+                let (Some(lang), Some(kinds)) = (SupportedLanguage::from_name(lang), kinds.as_object())
+                else {
+                    continue;
+                };
+                for (kind, pattern) in kinds {
+                    if let Some(kind) = NameKind::from_name(kind) {
+                        let pattern = pattern.as_str().filter(|p| !p.is_empty()).map(str::to_string);
+                        rules.naming.entry(lang).or_default().insert(kind, pattern);
+                    }
+                }
+            }
+        }
+        if let Some(locale) = config.get("locale").and_then(Value::as_str) {
+            rules.locale = Some(locale.to_string());
+        }
+        if let Some(ratings) = config.get("ratings").and_then(|v| RatingScale::from_json(v).ok()) {
+            rules.ratings = ratings;
+        }
+    }
+
+    // These settings with every override matching `path` applied in turn
+    pub fn for_path(&self, path: &Path) -> RuleConfig {
+        let mut rules = self.clone();
+        for (_, path_override) in self.matching_overrides(path) {
+            rules.apply_json(&path_override.rules);
+        }
+        rules
+    }
+
+    // Which overrides apply to `path`, as a bit per override, so files with the same
+    // settings can share an analyzer
+    pub fn override_mask(&self, path: &Path) -> u64 {
+        self.matching_overrides(path)
+            .fold(0, |mask, (index, _)| mask | 1 << index)
+    }
+
+    fn matching_overrides<'a>(
+        &'a self,
+        path: &'a Path,
+    ) -> impl Iterator<Item = (usize, &'a PathOverride)> + 'a {
+        self.overrides
+            .iter()
+            .enumerate()
+            .filter(move |(_, path_override)| path_override.matches(path))
+    }
+}
+
+const DEFAULT_MAGIC_NUMBER_ALLOWLIST: &[&str] = &["0", "1", "2"];
+
+impl Default for CodeAnalyzer {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl CodeAnalyzer {
     pub fn new() -> Self {
-        CodeAnalyzer { rules: Vec::new() }
+        CodeAnalyzer {
+            rules: Vec::new(),
+            compiled_queries: OnceLock::new(),
+            max_issues: None,
+            parse_timeout: None,
+            column_unit: ColumnUnit::default(),
+            magic_number_allowlist: RuleConfig::default().magic_number_allowlist,
+            remediation_minutes: BTreeMap::new(),
+            ratings: RatingScale::default(),
+            test_code_rules: BTreeMap::new(),
+            test_file: false,
+            injected: Vec::new(),
+            macro_bodies: false,
+            length_limits: LengthLimits::default(),
+            max_chain_length: None,
+            empty_bodies: false,
+            commented_code: false,
+            naming: Vec::new(),
+            nested_ternaries: false,
+            max_boolean_operators: None,
+            locale: None,
+        }
+    }
+
+    // Unit for reported columns; lines are always counted on '\n' (CRLF-safe)
+    pub fn with_column_unit(mut self, unit: ColumnUnit) -> Self {
+        self.column_unit = unit;
+        self.injected = self.map_injected(|analyzer| analyzer.with_column_unit(unit));
+        self
+    }
+
+    // Give up on files whose parse takes longer than `timeout`
+    pub fn with_parse_timeout(mut self, timeout: Duration) -> Self {
+        self.parse_timeout = Some(timeout);
+        self.injected = self.map_injected(|analyzer| analyzer.with_parse_timeout(timeout));
+        self
+    }
+
+    // Analyzes string literals holding code of these languages (see `injection`)
+    pub fn with_injected_analyzers(mut self, analyzers: Vec<(SupportedLanguage, CodeAnalyzer)>) -> Self {
+        self.injected = analyzers;
+        self
+    }
+
+    // Also analyzes the token trees of Rust macro invocations and macro_rules
+    pub fn with_macro_bodies(mut self, enabled: bool) -> Self {
+        self.macro_bodies = enabled;
+        self
+    }
+
+    // Reports `line_too_long` and `file_too_long` past these limits
+    pub fn with_length_limits(mut self, limits: LengthLimits) -> Self {
+        self.length_limits = limits;
+        self
+    }
+
+    // None turns `long_call_chain` off
+    pub fn with_max_chain_length(mut self, max: Option<usize>) -> Self {
+        self.max_chain_length = max;
+        self
+    }
+
+    pub fn with_empty_body_checks(mut self, enabled: bool) -> Self {
+        self.empty_bodies = enabled;
+        self
+    }
+
+    pub fn with_commented_code_checks(mut self, enabled: bool) -> Self {
+        self.commented_code = enabled;
+        self
+    }
+
+    pub fn with_nested_ternary_checks(mut self, enabled: bool) -> Self {
+        self.nested_ternaries = enabled;
+        self
+    }
+
+    // Swaps each rule's message and suggestion for its own translation, else the bundled
+    // one. Whole-tree passes compute their messages, so only their suggestions change.
+    pub fn with_locale(mut self, locale: Option<&str>) -> Self {
+        let Some(locale) = locale.filter(|locale| *locale != "en") else {
+            return self;
+        };
+        for rule in &mut self.rules {
+            let translation = rule
+                .translations
+                .get(locale)
+                .cloned()
+                .or_else(|| bundled_translation(locale, &rule.name));
+            if let Some(translation) = translation {
+                if let Some(message) = translation.message {
+                    rule.message_template = message;
+                }
+                rule.suggestion = translation.suggestion.or(rule.suggestion.take());
+            }
+        }
+        self.locale = Some(locale.to_string());
+        self.injected = self.map_injected(|analyzer| analyzer.with_locale(Some(locale)));
+        self
+    }
+
+    // None turns `complex_boolean_expression` off
+    pub fn with_max_boolean_operators(mut self, max: Option<usize>) -> Self {
+        self.max_boolean_operators = max;
+        self
+    }
+
+    pub fn with_naming_rules(mut self, rules: Vec<NamingRule>) -> Self {
+        self.naming = rules;
+        self
+    }
+
+    // Replaces the pattern for each kind listed; None stops checking that kind.
+    // Invalid patterns are dropped (`ProjectConfig::load` rejects them up front).
+    pub fn with_naming_overrides(mut self, overrides: &BTreeMap<NameKind, Option<String>>) -> Self {
+        for (kind, pattern) in overrides {
+            self.naming.retain(|rule| rule.kind != *kind);
+            if let Some(rule) = pattern.as_deref().and_then(|p| NamingRule::new(*kind, p).ok()) {
+                self.naming.push(rule);
+            }
+        }
+        self
+    }
+
+    fn map_injected(
+        &mut self,
+        f: impl Fn(CodeAnalyzer) -> CodeAnalyzer,
+    ) -> Vec<(SupportedLanguage, CodeAnalyzer)> {
+        std::mem::take(&mut self.injected)
+            .into_iter()
+            .map(|(lang, analyzer)| (lang, f(analyzer)))
+            .collect()
+    }
+
+    pub fn parse(&self, source_code: &str, language: &Language) -> Result<Tree, ParseError> {
+        let _span = trace::span("parse").attr(
+            "language",
+            SupportedLanguage::from_tree_sitter(language).map_or("", |l| l.name()),
+        );
+        parse_tree_with_timeout(source_code, language, self.parse_timeout)
+    }
+
+    // Drops every rule below Error severity
+    pub fn errors_only(self) -> Self {
+        self.with_min_severity(&Severity::Error)
+    }
+
+    // Drops every rule below `min`, so its findings are never reported or scored
+    pub fn with_min_severity(mut self, min: &Severity) -> Self {
+        self.rules.retain(|rule| rule.severity.rank() >= min.rank());
+        if Severity::Style.rank() < min.rank() {
+            self.length_limits.max_line_length = None;
+            self.max_chain_length = None;
+            self.empty_bodies = false;
+            self.commented_code = false;
+            self.naming.clear();
+            self.nested_ternaries = false;
+            self.max_boolean_operators = None;
+        }
+        if Severity::Info.rank() < min.rank() {
+            self.length_limits.max_file_lines = None;
+        }
+        self.compiled_queries = OnceLock::new();
+        self
+    }
+
+    pub fn without_rule(mut self, name: &str) -> Self {
+        self.rules.retain(|rule| rule.name != name);
+        self
+    }
+
+    // Literal values (as written) that go_magic_number never reports
+    pub fn with_magic_number_allowlist(mut self, values: Vec<String>) -> Self {
+        self.magic_number_allowlist = values;
+        self
+    }
+
+    // Per-rule fix-time estimates; rules not listed use their severity's default
+    pub fn with_remediation_minutes(mut self, minutes: BTreeMap<String, u32>) -> Self {
+        self.remediation_minutes = minutes;
+        self
+    }
+
+    // Per-rule treatment of findings in test code
+    pub fn with_test_code_rules(mut self, rules: BTreeMap<String, TestCodePolicy>) -> Self {
+        self.test_code_rules = rules;
+        self
+    }
+
+    // The analyzed files are test files (see `language::is_test_file`)
+    pub fn with_test_file(mut self, test_file: bool) -> Self {
+        self.test_file = test_file;
+        self
+    }
+
+    // Score bands and labels used for `rating`
+    pub fn with_ratings(mut self, ratings: RatingScale) -> Self {
+        self.ratings = ratings;
+        self
+    }
+
+    pub fn ratings(&self) -> &RatingScale {
+        &self.ratings
+    }
+
+    pub fn remediation_minutes_for(&self, rule: &str, severity: &Severity) -> u32 {
+        self.remediation_minutes
+            .get(rule)
+            .copied()
+            .unwrap_or_else(|| severity.default_remediation_minutes())
+    }
+
+    // Stop matching once this many issues have been collected for a file
+    pub fn with_max_issues(mut self, max_issues: usize) -> Self {
+        self.max_issues = Some(max_issues);
+        self
+    }
+
+    pub fn max_issues(&self) -> Option<usize> {
+        self.max_issues
+    }
+
+    pub fn is_truncated(&self, results: &[AnalysisResult]) -> bool {
+        self.max_issues.is_some_and(|max| results.len() >= max)
+    }
+
+    pub fn rules(&self) -> &[AnalysisRule] {
+        &self.rules
     }
 
     pub fn add_rule(&mut self, rule: AnalysisRule) {
         self.rules.push(rule);
+        self.compiled_queries = OnceLock::new();
+    }
+
+    // Stable digest of the rule definitions, used to key cached results
+    pub fn rules_hash(&self) -> u64 {
+        let mut description = String::new();
+        for rule in &self.rules {
+            description.push_str(&format!(
+                "{}\0{}\0{:?}\0{}\0{:?}\0{}\0{:?}\0{}\0{:?}\0{:?}\n",
+                rule.name,
+                rule.query,
+                rule.severity,
+                rule.message_template,
+                rule.suggestion,
+                rule.weight_multiplier,
+                rule.capture,
+                rule.check.is_some(),
+                rule.script.as_ref().map(Script::source),
+                rule.category
+            ));
+        }
+        description.push_str(&format!(
+            "max_issues={:?};column_unit={:?};magic_numbers={:?};remediation={:?};ratings={:?};\
+             tests={:?}/{};macro_bodies={};fingerprints=1;lengths={:?};chains={:?};empty_bodies={};\
+             commented_code={};naming={:?};ternaries={};booleans={:?};locale={:?}",
+            self.max_issues,
+            self.column_unit,
+            self.magic_number_allowlist,
+            self.remediation_minutes,
+            self.ratings,
+            self.test_code_rules,
+            self.test_file,
+            self.macro_bodies,
+            self.length_limits,
+            self.max_chain_length,
+            self.empty_bodies,
+            self.commented_code,
+            self.naming
+                .iter()
+                .map(|rule| (rule.kind, rule.pattern.as_str()))
+                .collect::<Vec<_>>(),
+            self.nested_ternaries,
+            self.max_boolean_operators,
+            self.locale
+        ));
+        for (lang, analyzer) in &self.injected {
+            description.push_str(&format!(";injected {}={}", lang.name(), analyzer.rules_hash()));
+        }
+        stable_hash(description.as_bytes())
+    }
+
+    // Queries compiled for another grammar can't run on this one's trees: the React
+    // rules analyze both JSX and TSX files, so a second grammar goes to the shared cache
+    fn compiled_queries(&self, language: &Language) -> Result<Arc<Vec<Query>>, QueryError> {
+        if let Some((compiled_for, queries)) = self.compiled_queries.get() {
+            if compiled_for == language {
+                return Ok(Arc::clone(queries));
+            }
+        }
+
+        let queries = shared_queries(&self.rules, language)?;
+        let _ = self
+            .compiled_queries
+            .set((language.clone(), Arc::clone(&queries)));
+        Ok(queries)
+    }
+
+    // Compiles the rule queries now rather than on the first file
+    pub fn preload(&self, language: &Language) -> Result<(), QueryError> {
+        self.compiled_queries(language)?;
+        for (lang, analyzer) in &self.injected {
+            analyzer.preload(&lang.tree_sitter_language())?;
+        }
+        Ok(())
+    }
+
+    // Compiles the queries of every built-in analyzer, so servers pay for it at
+    // startup instead of on the first request for each language. Returns the
+    // number of analyzers warmed.
+    pub fn preload_all() -> Result<usize, QueryError> {
+        let mut warmed = 0;
+        for lang in SupportedLanguage::ALL {
+            if let Some(analyzer) = lang.analyzer() {
+                analyzer.preload(&lang.tree_sitter_language())?;
+                warmed += 1;
+            }
+        }
+        // `.jsx` files get the React rules on the JavaScript grammar
+        CodeAnalyzer::new_react_analyzer()
+            .preload(&SupportedLanguage::JavaScript.tree_sitter_language())?;
+        Ok(warmed + 1)
+    }
+
+    // `preload_all` for long-running modes, logging what it cost
+    pub fn warm_up() {
+        let started = Instant::now();
+        match CodeAnalyzer::preload_all() {
+            Ok(warmed) => logging::debug(
+                "analyzers warmed up",
+                &[
+                    ("analyzers", serde_json::json!(warmed)),
+                    ("elapsed_ms", logging::millis(started.elapsed())),
+                ],
+            ),
+            Err(e) => logging::warn(&format!("could not warm up analyzers: {}", e)),
+        }
     }
 
     pub fn analyze(
@@ -110,42 +816,209 @@ impl CodeAnalyzer {
         source_code: &str,
         language: &Language,
     ) -> Result<Vec<AnalysisResult>, Box<dyn std::error::Error>> {
-        let mut parser = Parser::new();
-        parser.set_language(language)?;
+        let tree = self.parse(source_code, language)?;
+        self.analyze_tree(&tree, source_code, language)
+    }
+
+    // Runs the rules over an already-parsed tree so other passes can share the parse
+    pub fn analyze_tree(
+        &self,
+        tree: &Tree,
+        source_code: &str,
+        language: &Language,
+    ) -> Result<Vec<AnalysisResult>, Box<dyn std::error::Error>> {
+        self.analyze_tree_with(tree, source_code, language, true)
+    }
 
-        let tree = parser.parse(source_code, None).unwrap();
+    // For code embedded in a host file (macro bodies, injections), whose lines are
+    // the host's and already checked against the length limits
+    pub(crate) fn analyze_embedded(
+        &self,
+        tree: &Tree,
+        source_code: &str,
+        language: &Language,
+    ) -> Result<Vec<AnalysisResult>, Box<dyn std::error::Error>> {
+        self.analyze_tree_with(tree, source_code, language, false)
+    }
+
+    fn analyze_tree_with(
+        &self,
+        tree: &Tree,
+        source_code: &str,
+        language: &Language,
+        text_rules: bool,
+    ) -> Result<Vec<AnalysisResult>, Box<dyn std::error::Error>> {
+        let queries = self.compiled_queries(language)?;
+        let max_issues = self.max_issues.unwrap_or(usize::MAX);
         let mut results = Vec::new();
+        let test_regions = if self.test_code_rules.is_empty() || self.test_file {
+            Vec::new()
+        } else {
+            rust_test_regions(tree, source_code)
+        };
 
-        for rule in &self.rules {
-            let query = Query::new(language, &rule.query)?;
+        for (rule, query) in self.rules.iter().zip(queries.iter()) {
+            let _span = trace::span("query").attr("rule", &rule.name);
+            let started = Instant::now();
+            let before = results.len();
+            let mut capped = false;
             let mut cursor = QueryCursor::new();
+            let only = rule
+                .capture
+                .as_deref()
+                .and_then(|name| query.capture_index_for_name(name));
 
-            let mut matches = cursor.matches(&query, tree.root_node(), source_code.as_bytes());
-            while let Some(match_) = matches.next() {
+            let mut matches = cursor.matches(query, tree.root_node(), source_code.as_bytes());
+            'matches: while let Some(match_) = matches.next() {
                 for capture in match_.captures {
+                    if only.is_some_and(|index| capture.index != index) {
+                        continue;
+                    }
+                    if results.len() >= max_issues {
+                        capped = true;
+                        break 'matches;
+                    }
                     let node = capture.node;
-                    let start = node.start_position();
+                    let (line, column) = self.column_unit.start_of(&node, source_code);
                     let text = node.utf8_text(source_code.as_bytes()).unwrap_or("");
 
-                    if self.should_report(&rule.name, &node, source_code) {
-                        let score_impact =
+                    let message = match &rule.script {
+                        Some(script) => match script.evaluate(&ScriptContext {
+                            node: &node,
+                            source: source_code,
+                            capture: query.capture_names()[capture.index as usize],
+                        }) {
+                            Verdict::Skip => continue,
+                            Verdict::Report => rule.message_template.clone(),
+                            Verdict::ReportWith(message) => message,
+                        },
+                        None => rule.message_template.clone(),
+                    };
+                    if self.should_report(&rule.name, &node, source_code)
+                        && rule.check.is_none_or(|check| run_check(check, &node, source_code))
+                    {
+                        let mut score_impact =
                             rule.severity.base_score_impact() * rule.weight_multiplier;
+                        let in_test = self.test_file
+                            || test_regions.iter().any(|r| r.contains(&node.start_byte()));
+                        match self.test_code_rules.get(&rule.name).filter(|_| in_test) {
+                            Some(TestCodePolicy::Off) => continue,
+                            Some(TestCodePolicy::Weight(weight)) => score_impact *= weight,
+                            None => {}
+                        }
 
                         results.push(AnalysisResult {
                             rule_name: rule.name.clone(),
                             severity: rule.severity.clone(),
-                            message: rule.message_template.clone(),
-                            line: start.row + 1,
-                            column: start.column + 1,
+                            message,
+                            line,
+                            column,
                             text: text.to_string(),
                             suggestion: rule.suggestion.clone(),
                             score_impact,
+                            category: rule.category.clone(),
+                            fingerprint: fingerprint(&rule.name, &node, text, source_code),
                         });
                     }
                 }
             }
+            if logging::enabled(Level::Trace) {
+                logging::trace(
+                    "rule",
+                    &[
+                        ("rule", serde_json::json!(rule.name)),
+                        ("matches", serde_json::json!(results.len() - before)),
+                        ("elapsed_ms", logging::millis(started.elapsed())),
+                    ],
+                );
+            }
+            if timings::enabled() {
+                let language = SupportedLanguage::from_tree_sitter(language).map_or("", |l| l.name());
+                timings::record(language, &rule.name, results.len() - before, started.elapsed());
+            }
+            if capped {
+                break;
+            }
         }
 
+        if self.macro_bodies
+            && SupportedLanguage::from_tree_sitter(language) == Some(SupportedLanguage::Rust)
+            && results.len() < max_issues
+        {
+            for (byte, mut result) in analyze_macro_bodies(tree, source_code, self, self.column_unit) {
+                if results.len() >= max_issues {
+                    break;
+                }
+                let in_test = self.test_file || test_regions.iter().any(|r| r.contains(&byte));
+                match self.test_code_rules.get(&result.rule_name).filter(|_| in_test) {
+                    Some(TestCodePolicy::Off) => continue,
+                    Some(TestCodePolicy::Weight(weight)) => result.score_impact *= weight,
+                    None => {}
+                }
+                results.push(result);
+            }
+        }
+
+        if !self.injected.is_empty() && results.len() < max_issues {
+            let injected = analyze_injections(tree, source_code, &self.injected, self.column_unit);
+            results.extend(injected.into_iter().take(max_issues - results.len()));
+        }
+
+        // Findings from passes over the whole tree or text rather than rule queries
+        let mut extra = Vec::new();
+        if let Some(max) = self.max_chain_length {
+            extra.extend(long_call_chains(tree, source_code, max, self.column_unit));
+        }
+        if self.empty_bodies {
+            extra.extend(empty_bodies(tree, source_code, self.column_unit));
+        }
+        // Macro bodies and injected code are inside the host's text, so their comments
+        // are seen from there
+        let host_language = SupportedLanguage::from_tree_sitter(language);
+        if let Some(lang) = host_language.filter(|_| text_rules && self.commented_code) {
+            extra.extend(commented_out_code(tree, source_code, lang, self.column_unit));
+        }
+        if !self.naming.is_empty() {
+            extra.extend(naming_findings(tree, source_code, &self.naming, self.column_unit));
+        }
+        if self.nested_ternaries || self.max_boolean_operators.is_some() {
+            extra.extend(complex_conditions(
+                tree,
+                source_code,
+                self.nested_ternaries,
+                self.max_boolean_operators,
+                self.column_unit,
+            ));
+        }
+        if text_rules && !self.length_limits.is_empty() {
+            extra.extend(length_findings(source_code, &self.length_limits, self.column_unit));
+        }
+        if !extra.is_empty() {
+            for (byte, mut result) in extra {
+                if results.len() >= max_issues {
+                    break;
+                }
+                let in_test = self.test_file || test_regions.iter().any(|r| r.contains(&byte));
+                match self.test_code_rules.get(&result.rule_name).filter(|_| in_test) {
+                    Some(TestCodePolicy::Off) => continue,
+                    Some(TestCodePolicy::Weight(weight)) => result.score_impact *= weight,
+                    None => {}
+                }
+                let translated = self.locale.as_deref().and_then(|locale| {
+                    bundled_translation(locale, &result.rule_name)?.suggestion
+                });
+                if let Some(suggestion) = translated {
+                    result.suggestion = Some(suggestion);
+                }
+                results.push(result);
+            }
+        }
+
+        // Report in source order regardless of rule registration or match order
+        results.sort_by(|a, b| {
+            (a.line, a.column, &a.rule_name).cmp(&(b.line, b.column, &b.rule_name))
+        });
+        disambiguate_fingerprints(&mut results);
         Ok(results)
     }
 
@@ -159,7 +1032,7 @@ impl CodeAnalyzer {
         Ok((results, score))
     }
 
-    fn calculate_score(&self, results: &[AnalysisResult], source_code: &str) -> CodeScore {
+    pub fn calculate_score(&self, results: &[AnalysisResult], source_code: &str) -> CodeScore {
         let base_score = 10.0;
         let line_count = source_code.lines().count();
 
@@ -176,7 +1049,9 @@ impl CodeAnalyzer {
         };
 
         // Count issues and calculate deductions
+        let mut remediation_minutes = 0;
         for result in results {
+            remediation_minutes += self.remediation_minutes_for(&result.rule_name, &result.severity);
             match result.severity {
                 Severity::Error => {
                     breakdown.errors += 1;
@@ -230,18 +1105,12 @@ impl CodeAnalyzer {
             breakdown,
             rating,
             summary,
+            remediation_minutes,
         }
     }
 
     fn get_rating_and_summary(&self, score: f64, breakdown: &ScoreBreakdown) -> (String, String) {
-        let rating = match score {
-            9.0..=10.0 => "Excellent",
-            7.5..=8.9 => "Good",
-            6.0..=7.4 => "Fair",
-            4.0..=5.9 => "Poor",
-            _ => "Critical",
-        }
-        .to_string();
+        let rating = self.ratings.rating_for(score).to_string();
 
         let summary = if breakdown.errors > 0 {
             format!(
@@ -252,12 +1121,13 @@ impl CodeAnalyzer {
             "Multiple warnings detected - consider addressing them".to_string()
         } else if breakdown.info_issues > 10 {
             "Many minor issues found - good opportunity for cleanup".to_string()
-        } else if score >= 9.0 {
-            "Excellent code quality with minimal issues".to_string()
-        } else if score >= 7.5 {
-            "Good code quality with room for minor improvements".to_string()
         } else {
-            "Code needs improvement in several areas".to_string()
+            // The top two bands of the configured scale, whatever their labels
+            match self.ratings.band_index(score) {
+                0 => "Excellent code quality with minimal issues".to_string(),
+                1 => "Good code quality with room for minor improvements".to_string(),
+                _ => "Code needs improvement in several areas".to_string(),
+            }
         };
 
         (rating, summary)
@@ -275,6 +1145,30 @@ impl CodeAnalyzer {
                 let line_count = node.end_position().row - node.start_position().row;
                 line_count > 40
             }
+            "react_missing_key" => returned_jsx(node)
+                .is_some_and(|element| !has_jsx_attribute(&element, "key", source_code)),
+            "react_large_component" => {
+                let line_count = node.end_position().row - node.start_position().row;
+                let name = node
+                    .child_by_field_name("name")
+                    .and_then(|name| name.utf8_text(source_code.as_bytes()).ok())
+                    .unwrap_or("");
+                // Components are capitalized by convention; anything else is a plain function
+                line_count > REACT_COMPONENT_MAX_LINES && name.starts_with(|c: char| c.is_uppercase())
+            }
+            "go_magic_number" => {
+                let text = node.utf8_text(source_code.as_bytes()).unwrap_or("");
+                !self.magic_number_allowlist.iter().any(|v| v == text)
+                    && !is_named_constant_context(node)
+            }
+            "go_loop_variable_capture" => captures_loop_variable(node, source_code),
+            "go_missing_waitgroup_wait" => !waitgroup_waited(node, source_code),
+            "go_sleep_synchronization" => sleeps_after_goroutine(node),
+            "js_unawaited_promise" => is_unawaited_async_call(node, source_code),
+            "js_then_without_catch" => node
+                .child_by_field_name("arguments")
+                .is_some_and(|args| args.named_child_count() < 2),
+            "js_async_promise_executor" => is_async_function(node),
             _ => true,
         }
     }
@@ -325,7 +1219,67 @@ impl CodeAnalyzer {
             .with_weight(1.2),
         ); // Slightly higher impact for maintainability
 
+        analyzer.add_rule(
+            AnalysisRule::new(
+                "discarded_result".to_string(),
+                r#"(let_declaration pattern: "_" value: [(call_expression) (await_expression)] @call) @let"#
+                    .to_string(),
+                Severity::Warning,
+                "Result of a call discarded with `let _ =`".to_string(),
+                Some("Handle or propagate the error with ?, or log why it can be ignored".to_string()),
+            )
+            .with_capture("let")
+            .with_category(ERROR_HANDLING),
+        );
+
+        analyzer.add_rule(
+            AnalysisRule::new(
+                "swallowed_error".to_string(),
+                r#"(expression_statement
+                    (call_expression
+                        function: (field_expression field: (field_identifier) @method)
+                        (#eq? @method "ok")) @call)"#
+                    .to_string(),
+                Severity::Warning,
+                "Error swallowed by an unused .ok()".to_string(),
+                Some("Handle or propagate the error instead of converting it to an ignored Option".to_string()),
+            )
+            .with_capture("call")
+            .with_category(ERROR_HANDLING),
+        );
+
+        analyzer.add_rule(
+            AnalysisRule::new(
+                "io_unwrap_or_default".to_string(),
+                format!(
+                    r#"(call_expression
+                        function: (field_expression
+                            value: (call_expression
+                                function: [
+                                    (identifier) @io
+                                    (scoped_identifier name: (identifier) @io)
+                                    (field_expression field: (field_identifier) @io)
+                                ])
+                            field: (field_identifier) @method)
+                        (#eq? @method "unwrap_or_default")
+                        (#match? @io "{}")) @call"#,
+                    RUST_IO_CALLS
+                ),
+                Severity::Warning,
+                "I/O error replaced with a default value".to_string(),
+                Some("A failed read silently becomes empty data; propagate or report the error".to_string()),
+            )
+            .with_capture("call")
+            .with_category(ERROR_HANDLING),
+        );
+
         analyzer
+            .with_max_chain_length(Some(DEFAULT_MAX_CHAIN_LENGTH))
+            .with_empty_body_checks(true)
+            .with_nested_ternary_checks(true)
+            .with_max_boolean_operators(Some(DEFAULT_MAX_BOOLEAN_OPERATORS))
+            .with_commented_code_checks(true)
+            .with_naming_rules(default_naming_rules(SupportedLanguage::Rust))
     }
 
     pub fn new_javascript_analyzer() -> Self {
@@ -353,7 +1307,7 @@ impl CodeAnalyzer {
         analyzer.add_rule(
             AnalysisRule::new(
                 "var_usage".to_string(),
-                "(variable_declaration kind: \"var\") @var".to_string(),
+                "(variable_declaration) @var".to_string(),
                 Severity::Warning,
                 "Use of 'var' keyword".to_string(),
                 Some("Use 'let' or 'const' instead".to_string()),
@@ -361,6 +1315,111 @@ impl CodeAnalyzer {
             .with_weight(1.3),
         ); // Higher impact - can lead to scoping issues
 
+        analyzer.add_rule(
+            AnalysisRule::new(
+                "js_unawaited_promise".to_string(),
+                r#"(expression_statement
+                    (call_expression
+                        function: [(identifier) (member_expression property: (property_identifier))]) @call)"#
+                    .to_string(),
+                Severity::Warning,
+                "Promise not awaited in async function".to_string(),
+                Some("Await the call, or return it, so failures reach the caller".to_string()),
+            )
+            .with_capture("call")
+            .with_weight(1.5)
+            .with_category(PROMISES),
+        );
+
+        analyzer.add_rule(
+            AnalysisRule::new(
+                "js_then_without_catch".to_string(),
+                r#"(expression_statement
+                    (call_expression
+                        function: (member_expression property: (property_identifier) @method)
+                        (#eq? @method "then")) @call)"#
+                    .to_string(),
+                Severity::Warning,
+                ".then() without .catch()".to_string(),
+                Some("Add .catch() (or a rejection handler) so errors are not silently dropped".to_string()),
+            )
+            .with_capture("call")
+            .with_weight(1.2)
+            .with_category(PROMISES),
+        );
+
+        analyzer.add_rule(
+            AnalysisRule::new(
+                "js_async_promise_executor".to_string(),
+                r#"(new_expression
+                    constructor: (identifier) @ctor
+                    arguments: (arguments . [(arrow_function) (function_expression)] @executor)
+                    (#eq? @ctor "Promise"))"#
+                    .to_string(),
+                Severity::Warning,
+                "Async function used as a Promise executor".to_string(),
+                Some("Errors thrown by an async executor never reject the promise; drop async or the wrapper".to_string()),
+            )
+            .with_capture("executor")
+            .with_weight(1.5)
+            .with_category(PROMISES),
+        );
+
+        analyzer
+            .with_max_chain_length(Some(DEFAULT_MAX_CHAIN_LENGTH))
+            .with_empty_body_checks(true)
+            .with_nested_ternary_checks(true)
+            .with_max_boolean_operators(Some(DEFAULT_MAX_BOOLEAN_OPERATORS))
+            .with_commented_code_checks(true)
+            .with_naming_rules(default_naming_rules(SupportedLanguage::JavaScript))
+    }
+
+    // The JavaScript rules plus React checks, for .jsx and .tsx files
+    pub fn new_react_analyzer() -> Self {
+        let mut analyzer = Self::new_javascript_analyzer();
+
+        analyzer.add_rule(
+            AnalysisRule::new(
+                "react_missing_key".to_string(),
+                r#"(call_expression
+                    function: (member_expression property: (property_identifier) @method)
+                    arguments: (arguments [(arrow_function body: (_) @body)
+                                           (function_expression body: (_) @body)])
+                    (#eq? @method "map"))"#
+                    .to_string(),
+                Severity::Warning,
+                "Element rendered in a list has no 'key' prop".to_string(),
+                Some("Give each element a stable, unique key".to_string()),
+            )
+            .with_capture("body"),
+        );
+
+        analyzer.add_rule(
+            AnalysisRule::new(
+                "react_inline_function_prop".to_string(),
+                "(jsx_attribute (jsx_expression [(arrow_function) (function_expression)] @fn))"
+                    .to_string(),
+                Severity::Info,
+                "Inline function passed as a prop".to_string(),
+                Some("Define the handler once (useCallback) so children don't re-render".to_string()),
+            )
+            .with_weight(0.5),
+        );
+
+        analyzer.add_rule(
+            AnalysisRule::new(
+                "react_large_component".to_string(),
+                r#"[(function_declaration name: (identifier)) @component
+                    (variable_declarator name: (identifier)
+                                         value: [(arrow_function) (function_expression)]) @component]"#
+                    .to_string(),
+                Severity::Style,
+                "Component is too large".to_string(),
+                Some("Split it into smaller components".to_string()),
+            )
+            .with_weight(1.2),
+        );
+
         analyzer
     }
 
@@ -455,20 +1514,9 @@ impl CodeAnalyzer {
             .with_weight(0.3),
         ); // Very low impact - often intentional
 
-        analyzer.add_rule(
-            AnalysisRule::new(
-                "go_empty_if_block".to_string(),
-                r#"(if_statement consequence: (block) @block (#eq? @block "{}"))"#.to_string(),
-                Severity::Style,
-                "Empty if block".to_string(),
-                Some("Remove empty if block or add implementation".to_string()),
-            )
-            .with_weight(1.0),
-        );
-
         analyzer.add_rule(AnalysisRule::new(
             "go_magic_number".to_string(),
-            r#"(int_literal) @number (#not-eq? @number "0") (#not-eq? @number "1") (#not-eq? @number "2")"#.to_string(),
+            "(int_literal) @number".to_string(),
             Severity::Style,
             "Magic number found".to_string(),
             Some("Consider using a named constant".to_string()),
@@ -482,73 +1530,858 @@ impl CodeAnalyzer {
             Some("Consider extracting nested logic into separate functions".to_string()),
         ).with_weight(1.4)); // Higher impact - affects readability significantly
 
+        analyzer.add_rule(
+            AnalysisRule::new(
+                "go_loop_variable_capture".to_string(),
+                "(go_statement (call_expression function: (func_literal))) @go".to_string(),
+                Severity::Warning,
+                "Goroutine closure captures the loop variable".to_string(),
+                Some("Pass the variable as an argument; before Go 1.22 every iteration shares it".to_string()),
+            )
+            .with_weight(1.5)
+            .with_category(CONCURRENCY),
+        );
+
+        analyzer.add_rule(
+            AnalysisRule::new(
+                "go_missing_waitgroup_wait".to_string(),
+                r#"(var_spec
+                    name: (identifier) @name
+                    type: (qualified_type package: (package_identifier) @pkg name: (type_identifier) @type)
+                    (#eq? @pkg "sync") (#eq? @type "WaitGroup"))
+                (short_var_declaration
+                    left: (expression_list . (identifier) @name)
+                    right: (expression_list
+                        [(composite_literal type: (qualified_type package: (package_identifier) @pkg name: (type_identifier) @type))
+                         (unary_expression operand: (composite_literal type: (qualified_type package: (package_identifier) @pkg name: (type_identifier) @type)))])
+                    (#eq? @pkg "sync") (#eq? @type "WaitGroup"))"#
+                    .to_string(),
+                Severity::Warning,
+                "sync.WaitGroup is never waited on".to_string(),
+                Some("Call Wait() before relying on the goroutines having finished".to_string()),
+            )
+            .with_capture("name")
+            .with_weight(1.5)
+            .with_category(CONCURRENCY),
+        );
+
+        analyzer.add_rule(
+            AnalysisRule::new(
+                "go_sleep_synchronization".to_string(),
+                r#"(call_expression
+                    function: (selector_expression operand: (identifier) @pkg field: (field_identifier) @func)
+                    (#eq? @pkg "time") (#eq? @func "Sleep")) @call"#
+                    .to_string(),
+                Severity::Warning,
+                "time.Sleep used to wait for a goroutine".to_string(),
+                Some("Synchronize with a sync.WaitGroup, a channel or a context instead".to_string()),
+            )
+            .with_capture("call")
+            .with_weight(1.2)
+            .with_category(CONCURRENCY),
+        );
+
         analyzer
+            .with_max_chain_length(Some(DEFAULT_MAX_CHAIN_LENGTH))
+            .with_empty_body_checks(true)
+            .with_nested_ternary_checks(true)
+            .with_max_boolean_operators(Some(DEFAULT_MAX_BOOLEAN_OPERATORS))
+            .with_commented_code_checks(true)
+            .with_naming_rules(default_naming_rules(SupportedLanguage::Go))
     }
 
     pub fn format_score_as_json(&self, results: &[AnalysisResult], score: &CodeScore) -> Value {
-        json!({
-            "score": score.overall_score,
-            "max_score": score.max_score,
-            "rating": score.rating,
-            "summary": score.summary,
-            "total_issues": score.total_issues,
-            "breakdown": {
-                "errors": score.breakdown.errors,
-                "warnings": score.breakdown.warnings,
-                "info_issues": score.breakdown.info_issues,
-                "style_issues": score.breakdown.style_issues,
-                "deductions": {
-                    "from_errors": score.breakdown.error_deduction,
-                    "from_warnings": score.breakdown.warning_deduction,
-                    "from_info": score.breakdown.info_deduction,
-                    "from_style": score.breakdown.style_deduction
-                },
-                "size_bonus": score.breakdown.size_bonus
-            },
-            "issues": results.iter().map(|r| json!({
-                "rule": r.rule_name,
-                "severity": format!("{:?}", r.severity),
-                "message": r.message,
-                "line": r.line,
-                "column": r.column,
-                "text": r.text,
-                "suggestion": r.suggestion,
-                "score_impact": r.score_impact
-            })).collect::<Vec<_>>()
-        })
-    }
-}
-
-
-pub fn analyze_code_with_analyzer(
-    file_path: *const c_char,
-    language: Language,
-    analyzer: CodeAnalyzer,
-) -> *mut c_char {
-    let c_str = unsafe { CStr::from_ptr(file_path) };
-    let file_path_str = match c_str.to_str() {
-        Ok(s) => s,
-        Err(_) => return std::ptr::null_mut(),
+        let report = AnalysisReport {
+            results,
+            score,
+            metrics: None,
+            metadata: None,
+            truncated: false,
+        };
+        serde_json::to_value(report).unwrap_or(Value::Null)
+    }
+}
+
+// Rating under the default bands
+pub fn rating_for_score(score: f64) -> &'static str {
+    DEFAULT_RATING_BANDS
+        .iter()
+        .find(|(min_score, _)| score >= *min_score)
+        .map_or("Critical", |(_, label)| label)
+}
+
+// (lowest score earning the label, label), best band first
+const DEFAULT_RATING_BANDS: &[(f64, &str)] = &[
+    (9.0, "Excellent"),
+    (7.5, "Good"),
+    (6.0, "Fair"),
+    (4.0, "Poor"),
+    (0.0, "Critical"),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RatingBand {
+    pub min_score: f64,
+    pub label: String,
+}
+
+// Score -> rating label mapping; scores below every band get the lowest band's label
+#[derive(Debug, Clone, PartialEq)]
+pub struct RatingScale {
+    bands: Vec<RatingBand>,
+}
+
+impl Default for RatingScale {
+    fn default() -> Self {
+        RatingScale {
+            bands: DEFAULT_RATING_BANDS
+                .iter()
+                .map(|(min_score, label)| RatingBand {
+                    min_score: *min_score,
+                    label: label.to_string(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl RatingScale {
+    // Bands are reordered best first; an empty list is rejected
+    pub fn new(mut bands: Vec<RatingBand>) -> Option<Self> {
+        if bands.is_empty() {
+            return None;
+        }
+        bands.sort_by(|a, b| b.min_score.total_cmp(&a.min_score));
+        Some(RatingScale { bands })
+    }
+
+    // `[{"min_score": 9.0, "label": "Excellent"}, ...]`
+    pub fn from_json(value: &Value) -> Result<Self, String> {
+        let entries = value
+            .as_array()
+            .ok_or("ratings must be an array of {min_score, label} objects")?;
+        let bands = entries
+            .iter()
+            .map(|entry| {
+                let min_score = entry.get("min_score").and_then(Value::as_f64);
+                let label = entry.get("label").and_then(Value::as_str);
+                match (min_score, label) {
+                    (Some(min_score), Some(label)) => Ok(RatingBand {
+                        min_score,
+                        label: label.to_string(),
+                    }),
+                    _ => Err(format!("invalid rating band {}: needs min_score and label", entry)),
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Self::new(bands).ok_or_else(|| "ratings must list at least one band".to_string())
+    }
+
+    pub fn to_json(&self) -> Value {
+        self.bands
+            .iter()
+            .map(|band| serde_json::json!({ "min_score": band.min_score, "label": band.label }))
+            .collect()
+    }
+
+    pub fn rating_for(&self, score: f64) -> &str {
+        self.bands
+            .get(self.band_index(score))
+            .map_or("", |band| band.label.as_str())
+    }
+
+    // Position of the band `score` falls in, 0 being the best
+    pub fn band_index(&self, score: f64) -> usize {
+        self.bands
+            .iter()
+            .position(|band| score >= band.min_score)
+            .unwrap_or(self.bands.len().saturating_sub(1))
+    }
+}
+
+// Byte ranges of `#[cfg(test)]` modules and `#[test]` functions (including
+// `#[tokio::test]` style attributes) at file or module level
+fn rust_test_regions(tree: &Tree, source_code: &str) -> Vec<Range<usize>> {
+    let mut regions = Vec::new();
+    let mut pending = vec![tree.root_node()];
+    while let Some(container) = pending.pop() {
+        let mut cursor = container.walk();
+        let mut test_attribute = false;
+        for child in container.named_children(&mut cursor) {
+            match child.kind() {
+                "attribute_item" => {
+                    let text = child.utf8_text(source_code.as_bytes()).unwrap_or("");
+                    let attribute: String = text.chars().filter(|c| !c.is_whitespace()).collect();
+                    test_attribute |= attribute == "#[cfg(test)]"
+                        || attribute == "#[test]"
+                        || attribute.ends_with("::test]")
+                        || attribute.starts_with("#[test(");
+                    continue;
+                }
+                "line_comment" | "block_comment" => continue,
+                "mod_item" | "function_item" if test_attribute => regions.push(child.byte_range()),
+                "mod_item" => pending.extend(child.child_by_field_name("body")),
+                _ => {}
+            }
+            test_attribute = false;
+        }
+    }
+    regions
+}
+
+const REACT_COMPONENT_MAX_LINES: usize = 100;
+
+// The JSX element (opening tag or self-closing element) a callback body evaluates to
+fn returned_jsx<'tree>(body: &tree_sitter::Node<'tree>) -> Option<tree_sitter::Node<'tree>> {
+    match body.kind() {
+        "parenthesized_expression" => returned_jsx(&body.named_child(0)?),
+        "jsx_element" => body.child_by_field_name("open_tag"),
+        "jsx_self_closing_element" => Some(*body),
+        "statement_block" => {
+            let mut cursor = body.walk();
+            let returned = body
+                .named_children(&mut cursor)
+                .find(|statement| statement.kind() == "return_statement")?
+                .named_child(0)?;
+            returned_jsx(&returned)
+        }
+        _ => None,
+    }
+}
+
+fn has_jsx_attribute(element: &tree_sitter::Node, name: &str, source_code: &str) -> bool {
+    let mut cursor = element.walk();
+    let found = element
+        .children_by_field_name("attribute", &mut cursor)
+        .filter(|attribute| attribute.kind() == "jsx_attribute")
+        .any(|attribute| {
+            attribute
+                .named_child(0)
+                .and_then(|n| n.utf8_text(source_code.as_bytes()).ok())
+                == Some(name)
+        });
+    found
+}
+
+// Whether `pred` holds for `node` or any named node beneath it
+fn any_descendant(node: &tree_sitter::Node, pred: &dyn Fn(&tree_sitter::Node) -> bool) -> bool {
+    if pred(node) {
+        return true;
+    }
+    let mut cursor = node.walk();
+    let found = node.named_children(&mut cursor).any(|child| any_descendant(&child, pred));
+    found
+}
+
+fn enclosing_go_function<'tree>(node: &tree_sitter::Node<'tree>) -> Option<tree_sitter::Node<'tree>> {
+    let mut current = node.parent();
+    while let Some(ancestor) = current {
+        match ancestor.kind() {
+            "function_declaration" | "method_declaration" | "func_literal" => return Some(ancestor),
+            _ => current = ancestor.parent(),
+        }
+    }
+    None
+}
+
+// Identifiers declared by the loops around `node` (`for i, v := range` and
+// `for i := 0; ...`), up to the enclosing function
+fn go_loop_variables<'a>(node: &tree_sitter::Node, source_code: &'a str) -> Vec<&'a str> {
+    let mut names = Vec::new();
+    let mut current = node.parent();
+    while let Some(ancestor) = current {
+        match ancestor.kind() {
+            "function_declaration" | "method_declaration" | "func_literal" => break,
+            "for_statement" => {
+                let mut cursor = ancestor.walk();
+                let left = ancestor.named_children(&mut cursor).find_map(|clause| match clause.kind() {
+                    "range_clause" => clause.child_by_field_name("left"),
+                    "for_clause" => clause.child_by_field_name("initializer")?.child_by_field_name("left"),
+                    _ => None,
+                });
+                if let Some(left) = left {
+                    let mut cursor = left.walk();
+                    names.extend(
+                        left.named_children(&mut cursor)
+                            .filter_map(|n| n.utf8_text(source_code.as_bytes()).ok())
+                            .filter(|name| *name != "_"),
+                    );
+                }
+            }
+            _ => {}
+        }
+        current = ancestor.parent();
+    }
+    names
+}
+
+// A `go func() { ... }()` inside a loop whose body uses a loop variable it doesn't
+// receive as a parameter
+fn captures_loop_variable(go: &tree_sitter::Node, source_code: &str) -> bool {
+    let Some(func) = go
+        .named_child(0)
+        .and_then(|call| call.child_by_field_name("function"))
+    else {
+        return false;
+    };
+    let text = |n: &tree_sitter::Node| n.utf8_text(source_code.as_bytes()).unwrap_or("");
+    let mut params = Vec::new();
+    if let Some(list) = func.child_by_field_name("parameters") {
+        let mut cursor = list.walk();
+        for decl in list.named_children(&mut cursor) {
+            let mut cursor = decl.walk();
+            params.extend(decl.children_by_field_name("name", &mut cursor).map(|n| text(&n)));
+        }
+    }
+    let captured: Vec<&str> = go_loop_variables(go, source_code)
+        .into_iter()
+        .filter(|name| !params.contains(name))
+        .collect();
+    match func.child_by_field_name("body") {
+        Some(body) if !captured.is_empty() => {
+            any_descendant(&body, &|n| n.kind() == "identifier" && captured.contains(&text(n)))
+        }
+        _ => false,
+    }
+}
+
+// A WaitGroup declared in a function counts as waited on when the function calls
+// `name.Wait()` or hands the group to another call that may wait on it
+fn waitgroup_waited(name: &tree_sitter::Node, source_code: &str) -> bool {
+    let Some(function) = enclosing_go_function(name) else {
+        return true;
+    };
+    let name = name.utf8_text(source_code.as_bytes()).unwrap_or("");
+    let text = |n: Option<tree_sitter::Node>| n.and_then(|n| n.utf8_text(source_code.as_bytes()).ok());
+    any_descendant(&function, &|n| match n.kind() {
+        "selector_expression" => {
+            text(n.child_by_field_name("operand")) == Some(name)
+                && text(n.child_by_field_name("field")) == Some("Wait")
+        }
+        "argument_list" => {
+            let mut cursor = n.walk();
+            let passed = n.named_children(&mut cursor).any(|arg| {
+                let arg = match arg.kind() {
+                    "unary_expression" => arg.child_by_field_name("operand"),
+                    _ => Some(arg),
+                };
+                text(arg) == Some(name)
+            });
+            passed
+        }
+        _ => false,
+    })
+}
+
+// time.Sleep in a function after it started a goroutine: the sleep is standing in for
+// synchronization. Sleeping inside the goroutine itself (pacing) is fine.
+fn sleeps_after_goroutine(sleep: &tree_sitter::Node) -> bool {
+    let Some(function) = enclosing_go_function(sleep) else {
+        return false;
+    };
+    any_descendant(&function, &|n| {
+        n.kind() == "go_statement"
+            && n.end_byte() <= sleep.start_byte()
+            && enclosing_go_function(n) == Some(function)
+    })
+}
+
+const JS_FUNCTION_KINDS: &[&str] = &[
+    "function_declaration",
+    "function_expression",
+    "arrow_function",
+    "method_definition",
+    "generator_function_declaration",
+];
+
+fn is_async_function(node: &tree_sitter::Node) -> bool {
+    let mut cursor = node.walk();
+    let found = node.children(&mut cursor).any(|child| child.kind() == "async");
+    found
+}
+
+// Whether `node` declares an async function or method called `name`
+fn declares_async(node: &tree_sitter::Node, name: &str, source_code: &str) -> bool {
+    let function = match node.kind() {
+        "function_declaration" | "method_definition" => Some(*node),
+        "variable_declarator" => node.child_by_field_name("value"),
+        _ => None,
+    };
+    function.is_some_and(|f| is_async_function(&f))
+        && node
+            .child_by_field_name("name")
+            .and_then(|n| n.utf8_text(source_code.as_bytes()).ok())
+            == Some(name)
+}
+
+// A bare call statement inside an async function to `fetch` or to an async function
+// of the same file: its promise (and any rejection) is dropped
+fn is_unawaited_async_call(call: &tree_sitter::Node, source_code: &str) -> bool {
+    let mut current = call.parent();
+    let function = loop {
+        match current {
+            Some(ancestor) if JS_FUNCTION_KINDS.contains(&ancestor.kind()) => break ancestor,
+            Some(ancestor) => current = ancestor.parent(),
+            None => return false,
+        }
     };
+    if !is_async_function(&function) {
+        return false;
+    }
+    let callee = call.child_by_field_name("function").and_then(|f| match f.kind() {
+        "member_expression" => f.child_by_field_name("property"),
+        _ => Some(f),
+    });
+    let Some(callee) = callee.and_then(|c| c.utf8_text(source_code.as_bytes()).ok()) else {
+        return false;
+    };
+    if callee == "fetch" {
+        return true;
+    }
+    let mut root = *call;
+    while let Some(parent) = root.parent() {
+        root = parent;
+    }
+    any_descendant(&root, &|n| declares_async(n, callee, source_code))
+}
+
+// Compiled queries keyed by grammar and rule queries. Analyzers are rebuilt per
+// file or document with the same rules, and compiling is far slower than matching.
+// Grammars are told apart by identity: most bundled ones have no name.
+fn shared_queries(
+    rules: &[AnalysisRule],
+    language: &Language,
+) -> Result<Arc<Vec<Query>>, QueryError> {
+    type Compiled = HashMap<(Language, String), Arc<Vec<Query>>>;
+    static COMPILED: OnceLock<Mutex<Compiled>> = OnceLock::new();
+    let mut queries = String::new();
+    for rule in rules {
+        queries.push('\0');
+        queries.push_str(&rule.query);
+    }
+    let key = (language.clone(), queries);
 
-    match run_analysis(file_path_str, language, analyzer) {
-        Ok(result) => match CString::new(result) {
-            Ok(c_string) => c_string.into_raw(),
-            Err(_) => std::ptr::null_mut(),
-        },
-        Err(_) => std::ptr::null_mut(),
+    let cache = COMPILED.get_or_init(Default::default);
+    if let Some(queries) = cache.lock().unwrap_or_else(|e| e.into_inner()).get(&key) {
+        return Ok(Arc::clone(queries));
     }
+    // Compiled outside the lock; a racing thread at worst compiles the same set twice
+    let queries = Arc::new(
+        rules
+            .iter()
+            .map(|rule| Query::new(language, &rule.query))
+            .collect::<Result<Vec<_>, _>>()?,
+    );
+    cache
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(key, Arc::clone(&queries));
+    Ok(queries)
 }
 
-fn run_analysis(
+// Hash of the rule, the whitespace-normalized snippet and the chain of named
+// declarations enclosing the node (`impl_item`/`function_item parse` and so on).
+// Line numbers are left out so baselines and trackers can follow an issue as
+// code above it moves.
+pub(crate) fn fingerprint(rule: &str, node: &tree_sitter::Node, text: &str, source_code: &str) -> String {
+    let mut scopes = Vec::new();
+    let mut current = node.parent();
+    while let Some(ancestor) = current {
+        if let Some(name) = ancestor
+            .child_by_field_name("name")
+            .and_then(|n| n.utf8_text(source_code.as_bytes()).ok())
+        {
+            scopes.push(format!("{} {}", ancestor.kind(), name));
+        }
+        current = ancestor.parent();
+    }
+    scopes.reverse();
+    let snippet = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    let key = format!("{}\0{}\0{}", rule, snippet, scopes.join("/"));
+    format!("{:016x}", stable_hash(key.as_bytes()))
+}
+
+// Identical findings in one scope (the same call twice in a function) are told
+// apart by their order within the file
+fn disambiguate_fingerprints(results: &mut [AnalysisResult]) {
+    let mut seen: BTreeMap<String, usize> = BTreeMap::new();
+    for result in results.iter_mut() {
+        let count = seen.entry(result.fingerprint.clone()).or_default();
+        *count += 1;
+        if *count > 1 {
+            let key = format!("{}#{}", result.fingerprint, count);
+            result.fingerprint = format!("{:016x}", stable_hash(key.as_bytes()));
+        }
+    }
+}
+
+// Literals that already name a value (const blocks) or size an array type
+fn is_named_constant_context(node: &tree_sitter::Node) -> bool {
+    if node.parent().is_some_and(|p| p.kind() == "array_type") {
+        return true;
+    }
+    let mut current = node.parent();
+    while let Some(ancestor) = current {
+        match ancestor.kind() {
+            "const_declaration" => return true,
+            "function_declaration" | "method_declaration" | "func_literal" => return false,
+            _ => current = ancestor.parent(),
+        }
+    }
+    false
+}
+
+pub fn run_analysis(
     file_path: &str,
     language: Language,
     analyzer: CodeAnalyzer,
 ) -> Result<String, Box<dyn std::error::Error>> {
-    let source_code = fs::read_to_string(file_path)?;
-    let (results, score) = analyzer.analyze_with_score(&source_code, &language)?;
+    let source_code = read_source(file_path, None)?;
+    analyze_source(&source_code, &language, &analyzer)
+}
 
-    // Use the new JSON formatting method
-    let output = analyzer.format_score_as_json(&results, &score);
-    Ok(serde_json::to_string_pretty(&output)?)
+pub fn analyze_source(
+    source_code: &str,
+    language: &Language,
+    analyzer: &CodeAnalyzer,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let mut output = Vec::new();
+    write_analysis(source_code, language, analyzer, &mut output)?;
+    Ok(String::from_utf8(output)?)
+}
+
+// Serializes the analysis report directly into `out`
+pub fn write_analysis<W: Write>(
+    source_code: &str,
+    language: &Language,
+    analyzer: &CodeAnalyzer,
+    out: W,
+) -> Result<(), Box<dyn std::error::Error>> {
+    with_report(source_code, language, analyzer, true, None, |report| {
+        Ok(report.write_pretty(out)?)
+    })
+}
+
+pub fn analyze_source_to_json(
+    source_code: &str,
+    language: &Language,
+    analyzer: &CodeAnalyzer,
+) -> Result<Value, Box<dyn std::error::Error>> {
+    with_report(source_code, language, analyzer, false, None, |report| {
+        Ok(serde_json::to_value(report)?)
+    })
+}
+
+// Like `analyze_source_to_json`, but only issues on `lines` (1-based) are reported
+// and scored, so a change is judged without the legacy issues around it
+pub fn analyze_lines_to_json(
+    source_code: &str,
+    language: &Language,
+    analyzer: &CodeAnalyzer,
+    lines: &BTreeSet<usize>,
+) -> Result<Value, Box<dyn std::error::Error>> {
+    with_report(source_code, language, analyzer, false, Some(lines), |report| {
+        Ok(serde_json::to_value(report)?)
+    })
+}
+
+fn with_report<T>(
+    source_code: &str,
+    language: &Language,
+    analyzer: &CodeAnalyzer,
+    // Directory scans report versions once for the whole run instead
+    with_metadata: bool,
+    only_lines: Option<&BTreeSet<usize>>,
+    render: impl FnOnce(&AnalysisReport) -> Result<T, Box<dyn std::error::Error>>,
+) -> Result<T, Box<dyn std::error::Error>> {
+    // Parse once; the rule engine and the metrics pass share the tree
+    let tree = analyzer.parse(source_code, language)?;
+    let mut results = analyzer.analyze_tree(&tree, source_code, language)?;
+    let truncated = analyzer.is_truncated(&results);
+    if let Some(lines) = only_lines {
+        results.retain(|result| lines.contains(&result.line));
+    }
+    let score = analyzer.calculate_score(&results, source_code);
+    let metrics = FileMetrics::compute(&tree, source_code);
+
+    let report = AnalysisReport {
+        results: &results,
+        score: &score,
+        metrics: Some(&metrics),
+        metadata: with_metadata.then(|| {
+            let mut metadata = version_metadata(language);
+            metadata["ratings"] = analyzer.ratings.to_json();
+            metadata
+        }),
+        truncated,
+    };
+    let _span = trace::span("serialize");
+    render(&report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_results_are_sorted_by_position_then_rule() {
+        let source = "package main\n\nvar limit = 42\n\nfunc main() {\n\tpanic(7)\n}\n";
+        let language: Language = tree_sitter_go::LANGUAGE.into();
+        let results = CodeAnalyzer::new_go_analyzer()
+            .analyze(source, &language)
+            .unwrap();
+
+        let positions: Vec<(usize, usize, &str)> = results
+            .iter()
+            .map(|r| (r.line, r.column, r.rule_name.as_str()))
+            .collect();
+        let mut sorted = positions.clone();
+        sorted.sort();
+        assert_eq!(positions, sorted);
+        assert!(positions.contains(&(6, 2, "go_panic_usage")));
+    }
+
+    #[test]
+    fn test_magic_numbers_skip_constants_and_allowlist() {
+        let source = "package main\n\nconst retries = 5\n\nvar buf [64]byte\n\n\
+                      func main() {\n\tsleep(30)\n\tsleep(10)\n}\n";
+        let language: Language = tree_sitter_go::LANGUAGE.into();
+        let magic = |analyzer: CodeAnalyzer| -> Vec<String> {
+            analyzer
+                .analyze(source, &language)
+                .unwrap()
+                .into_iter()
+                .filter(|r| r.rule_name == "go_magic_number")
+                .map(|r| r.text)
+                .collect()
+        };
+
+        assert_eq!(magic(CodeAnalyzer::new_go_analyzer()), vec!["30", "10"]);
+        let warnings_up = CodeAnalyzer::new_go_analyzer().with_min_severity(&Severity::Warning);
+        assert!(magic(warnings_up).is_empty());
+        let allowlist = vec!["10".to_string()];
+        assert_eq!(
+            magic(CodeAnalyzer::new_go_analyzer().with_magic_number_allowlist(allowlist)),
+            vec!["30"]
+        );
+    }
+
+    #[test]
+    fn test_remediation_minutes_use_overrides_then_severity_defaults() {
+        let source = "package main\n\nfunc main() {\n\tpanic(7)\n}\n";
+        let language: Language = tree_sitter_go::LANGUAGE.into();
+        let score = |analyzer: CodeAnalyzer| analyzer.analyze_with_score(source, &language).unwrap();
+
+        let (results, default) = score(CodeAnalyzer::new_go_analyzer());
+        let expected: u32 = results
+            .iter()
+            .map(|r| r.severity.default_remediation_minutes())
+            .sum();
+        assert_eq!(default.remediation_minutes, expected);
+
+        let panics = results.iter().filter(|r| r.rule_name == "go_panic_usage").count() as u32;
+        assert!(panics > 0);
+        let overrides = BTreeMap::from([("go_panic_usage".to_string(), 120)]);
+        let (_, tuned) = score(CodeAnalyzer::new_go_analyzer().with_remediation_minutes(overrides));
+        let panic_default = Severity::Warning.default_remediation_minutes();
+        assert_eq!(
+            tuned.remediation_minutes,
+            expected + panics * (120 - panic_default)
+        );
+    }
+
+    #[test]
+    fn test_rating_scale_from_config() {
+        let config = serde_json::json!({ "ratings": [
+            { "min_score": 5.0, "label": "Ship it" },
+            { "min_score": 8.0, "label": "Great" },
+            { "min_score": 0.0, "label": "Rework" },
+        ] });
+        let ratings = RuleConfig::from_json(&config).ratings;
+        assert_eq!(ratings.rating_for(9.5), "Great");
+        assert_eq!(ratings.rating_for(5.0), "Ship it");
+        assert_eq!(ratings.rating_for(-1.0), "Rework");
+        assert_eq!(ratings.to_json()[0]["label"], "Great");
+
+        assert_eq!(RatingScale::default().rating_for(7.5), rating_for_score(7.5));
+        assert!(RatingScale::from_json(&serde_json::json!([{ "label": "x" }])).is_err());
+        assert!(RatingScale::from_json(&serde_json::json!([])).is_err());
+    }
+
+    #[test]
+    fn test_summary_follows_rating_scale() {
+        let breakdown = ScoreBreakdown {
+            errors: 0,
+            warnings: 0,
+            info_issues: 0,
+            style_issues: 0,
+            error_deduction: 0.0,
+            warning_deduction: 0.0,
+            info_deduction: 0.0,
+            style_deduction: 0.0,
+            size_bonus: 0.0,
+        };
+        let summary = |analyzer: &CodeAnalyzer, score: f64| {
+            analyzer.get_rating_and_summary(score, &breakdown).1
+        };
+        let analyzer = CodeAnalyzer::new_rust_analyzer();
+        assert!(summary(&analyzer, 8.5).starts_with("Good code quality"));
+        assert!(summary(&analyzer, 7.0).starts_with("Code needs"));
+
+        let ratings = RatingScale::from_json(&serde_json::json!([
+            { "min_score": 8.0, "label": "A" },
+            { "min_score": 6.5, "label": "B" },
+            { "min_score": 0.0, "label": "C" },
+        ]))
+        .unwrap();
+        let analyzer = CodeAnalyzer::new_rust_analyzer().with_ratings(ratings);
+        assert_eq!(analyzer.get_rating_and_summary(8.5, &breakdown).0, "A");
+        assert!(summary(&analyzer, 8.5).starts_with("Excellent code quality"));
+        assert!(summary(&analyzer, 7.0).starts_with("Good code quality"));
+        assert!(summary(&analyzer, -1.0).starts_with("Code needs"));
+    }
+
+    #[test]
+    fn test_go_concurrency_rules() {
+        let source = "package main\n\nfunc run(items []int) {\n\tvar wg sync.WaitGroup\n\
+               \tfor _, item := range items {\n\t\twg.Add(1)\n\t\tgo func() {\n\
+               \t\t\tdefer wg.Done()\n\t\t\tuse(item)\n\t\t}()\n\t}\n\
+               \tfor i := 0; i < 3; i++ {\n\t\tgo func(i int) { use(i) }(i)\n\t}\n\
+               \tdone := &sync.WaitGroup{}\n\tgo work(done)\n\ttime.Sleep(time.Second)\n}\n";
+        let language: Language = tree_sitter_go::LANGUAGE.into();
+        let results = CodeAnalyzer::new_go_analyzer().analyze(source, &language).unwrap();
+        let found: Vec<(usize, &str)> = results
+            .iter()
+            .filter(|r| r.category.as_deref() == Some(CONCURRENCY))
+            .map(|r| (r.line, r.rule_name.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (4, "go_missing_waitgroup_wait"),
+                (7, "go_loop_variable_capture"),
+                (17, "go_sleep_synchronization"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_js_promise_rules() {
+        let source = "async function save(x) { return x; }\n\
+                      async function main() {\n  save(1);\n  await save(2);\n  log(3);\n\
+                      \x20 fetch('/a').then(r => r.json());\n  fetch('/b').then(ok, fail);\n}\n\
+                      new Promise(async (resolve) => resolve(1));\nnew Promise((resolve) => resolve(1));\n";
+        let language: Language = tree_sitter_javascript::LANGUAGE.into();
+        let results = CodeAnalyzer::new_javascript_analyzer().analyze(source, &language).unwrap();
+        let found: Vec<(usize, &str)> = results
+            .iter()
+            .filter(|r| r.category.as_deref() == Some(PROMISES))
+            .map(|r| (r.line, r.rule_name.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (3, "js_unawaited_promise"),
+                (6, "js_then_without_catch"),
+                (9, "js_async_promise_executor"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_preload_shares_compiled_queries() {
+        assert_eq!(CodeAnalyzer::preload_all().unwrap(), 5);
+        let language: Language = tree_sitter_go::LANGUAGE.into();
+        let first = CodeAnalyzer::new_go_analyzer();
+        let second = CodeAnalyzer::new_go_analyzer().with_max_issues(3);
+        assert!(Arc::ptr_eq(
+            &first.compiled_queries(&language).unwrap(),
+            &second.compiled_queries(&language).unwrap()
+        ));
+
+        // Same rules on different grammars (JSX and TSX files) compile separately
+        let javascript: Language = tree_sitter_javascript::LANGUAGE.into();
+        let tsx: Language = tree_sitter_typescript::LANGUAGE_TSX.into();
+        let on_javascript = CodeAnalyzer::new_react_analyzer();
+        let on_tsx = CodeAnalyzer::new_react_analyzer();
+        assert!(!Arc::ptr_eq(
+            &on_javascript.compiled_queries(&javascript).unwrap(),
+            &on_tsx.compiled_queries(&tsx).unwrap()
+        ));
+        // One analyzer used for both keeps each grammar's queries apart
+        let javascript_queries = on_javascript.compiled_queries(&javascript).unwrap();
+        assert!(Arc::ptr_eq(
+            &on_javascript.compiled_queries(&tsx).unwrap(),
+            &on_tsx.compiled_queries(&tsx).unwrap()
+        ));
+        assert!(Arc::ptr_eq(
+            &on_javascript.compiled_queries(&javascript).unwrap(),
+            &javascript_queries
+        ));
+    }
+
+    #[test]
+    fn test_fingerprints_ignore_line_shifts() {
+        let analyzer = CodeAnalyzer::new_rust_analyzer();
+        let language = tree_sitter_rust::LANGUAGE.into();
+        let fingerprints = |source: &str| -> Vec<String> {
+            let results = analyzer.analyze(source, &language).unwrap();
+            results
+                .into_iter()
+                .filter(|r| r.rule_name == "unwrap_usage")
+                .map(|r| r.fingerprint)
+                .collect()
+        };
+
+        let before = fingerprints("fn main() {\n    a.unwrap();\n    a.unwrap();\n}\n");
+        let after = fingerprints("// moved\n\nfn main() {\n    a.unwrap();\n    a.unwrap();\n}\n");
+        // Both captures of each call are reported, all four distinct
+        assert_eq!(before.iter().collect::<BTreeSet<_>>().len(), 4);
+        assert_eq!(before, after);
+        assert_ne!(before, fingerprints("fn other() {\n    a.unwrap();\n    a.unwrap();\n}\n"));
+    }
+
+    #[test]
+    fn test_rust_error_handling_rules() {
+        let source = "fn main() {\n    let _ = std::fs::remove_file(\"a\");\n    let _ = x;\n    \
+                      write_log().ok();\n    let v = parse().ok();\n    \
+                      let s = std::fs::read_to_string(\"a\").unwrap_or_default();\n    \
+                      let n = parse().unwrap_or_default();\n}\n";
+        let language: Language = tree_sitter_rust::LANGUAGE.into();
+        let results = CodeAnalyzer::new_rust_analyzer().analyze(source, &language).unwrap();
+        let found: Vec<(usize, &str, Option<&str>)> = results
+            .iter()
+            .map(|r| (r.line, r.rule_name.as_str(), r.category.as_deref()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (2, "discarded_result", Some(ERROR_HANDLING)),
+                (4, "swallowed_error", Some(ERROR_HANDLING)),
+                (6, "io_unwrap_or_default", Some(ERROR_HANDLING)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_react_rules() {
+        let source = "function List({ items }) {\n  return <ul>\n    \
+                      {items.map(item => <li key={item.id} onClick={() => go(item)} />)}\n    \
+                      {items.map((item) => (<Item {...item} />))}\n  </ul>;\n}\nvar x = 1;\n";
+        let language = crate::language::SupportedLanguage::Tsx.tree_sitter_language();
+        let found: Vec<(usize, String)> = CodeAnalyzer::new_react_analyzer()
+            .analyze(source, &language)
+            .unwrap()
+            .into_iter()
+            .map(|r| (r.line, r.rule_name))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (3, "react_inline_function_prop".to_string()),
+                (4, "react_missing_key".to_string()),
+                (7, "var_usage".to_string()),
+            ]
+        );
+    }
 }
@@ -0,0 +1,238 @@
+use crate::ast::parse_tree;
+use crate::doc_coverage::public_items;
+use crate::language::SupportedLanguage;
+use crate::source::read_source;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+// The public surface of `files` (see `public_items`), with paths relative to `root`
+// so two checkouts of the same package can be compared
+pub fn api_surface(root: &Path, files: &[PathBuf]) -> Value {
+    let mut entries = Vec::new();
+    let mut total = 0;
+    for path in files {
+        let Some(lang) = SupportedLanguage::detect(path) else {
+            continue;
+        };
+        let relative = match path.strip_prefix(root) {
+            Ok(rel) if !rel.as_os_str().is_empty() => rel,
+            // `root` is the file itself
+            _ => Path::new(path.file_name().unwrap_or_default()),
+        };
+        let relative = relative.display().to_string();
+        let items = read_source(path, None)
+            .map_err(|e| e.to_string())
+            .and_then(|source| {
+                let tree =
+                    parse_tree(&source, &lang.tree_sitter_language()).map_err(|e| e.to_string())?;
+                Ok(public_items(&tree, &source, lang))
+            });
+        match items {
+            Ok(items) => {
+                total += items.len();
+                let items: Vec<Value> = items
+                    .iter()
+                    .map(|item| {
+                        json!({
+                            "name": item.name,
+                            "kind": item.kind.as_str(),
+                            "line": item.line,
+                            "signature": item.signature,
+                        })
+                    })
+                    .collect();
+                entries.push(json!({ "path": relative, "language": lang.name(), "items": items }));
+            }
+            Err(e) => {
+                entries.push(json!({ "path": relative, "language": lang.name(), "error": e }))
+            }
+        }
+    }
+    json!({ "files": entries, "total": total })
+}
+
+type ItemKey = (String, String, String);
+
+// (path, kind, name) -> (path, signature). Comparing two single files matches items
+// regardless of the file names.
+fn keyed(surface: &Value, ignore_path: bool) -> BTreeMap<ItemKey, (String, String)> {
+    let mut items = BTreeMap::new();
+    for file in surface["files"].as_array().into_iter().flatten() {
+        let path = file["path"].as_str().unwrap_or("");
+        for item in file["items"].as_array().into_iter().flatten() {
+            let key = (
+                if ignore_path { "" } else { path }.to_string(),
+                item["kind"].as_str().unwrap_or("").to_string(),
+                item["name"].as_str().unwrap_or("").to_string(),
+            );
+            let signature = item["signature"].as_str().unwrap_or("").to_string();
+            items.insert(key, (path.to_string(), signature));
+        }
+    }
+    items
+}
+
+// Items added, removed or whose signature changed between two surfaces. Removed and
+// changed items are the ones that can break callers.
+pub fn api_changes(old: &Value, new: &Value) -> Value {
+    let single = |surface: &Value| surface["files"].as_array().is_some_and(|f| f.len() == 1);
+    let ignore_path = single(old) && single(new);
+    let (old, new) = (keyed(old, ignore_path), keyed(new, ignore_path));
+    let entry =
+        |(_, kind, name): &ItemKey, path: &str| json!({ "path": path, "kind": kind, "name": name });
+
+    let mut added = Vec::new();
+    let mut removed = Vec::new();
+    let mut changed = Vec::new();
+    for (key, (path, signature)) in &old {
+        match new.get(key) {
+            None => {
+                let mut item = entry(key, path);
+                item["signature"] = json!(signature);
+                removed.push(item);
+            }
+            Some((new_path, current)) if current != signature => {
+                let mut item = entry(key, new_path);
+                item["old"] = json!(signature);
+                item["new"] = json!(current);
+                changed.push(item);
+            }
+            Some(_) => {}
+        }
+    }
+    for (key, (path, signature)) in &new {
+        if !old.contains_key(key) {
+            let mut item = entry(key, path);
+            item["signature"] = json!(signature);
+            added.push(item);
+        }
+    }
+    let breaking = !removed.is_empty() || !changed.is_empty();
+    json!({ "added": added, "removed": removed, "changed": changed, "breaking": breaking })
+}
+
+pub fn format_api_text(surface: &Value) -> String {
+    let mut out = String::new();
+    for file in surface["files"].as_array().into_iter().flatten() {
+        for item in file["items"].as_array().into_iter().flatten() {
+            out.push_str(&format!(
+                "{}:{}  {:<9}  {}\n",
+                file["path"].as_str().unwrap_or(""),
+                item["line"],
+                item["kind"].as_str().unwrap_or(""),
+                item["signature"].as_str().unwrap_or("")
+            ));
+        }
+    }
+    out
+}
+
+pub fn format_changes_text(changes: &Value) -> String {
+    let text = |item: &Value, key: &str| item[key].as_str().unwrap_or("").to_string();
+    let mut out = String::new();
+    for item in changes["removed"].as_array().into_iter().flatten() {
+        out.push_str(&format!(
+            "- {}  {}\n",
+            text(item, "path"),
+            text(item, "signature")
+        ));
+    }
+    for item in changes["changed"].as_array().into_iter().flatten() {
+        out.push_str(&format!(
+            "~ {}  {}\n",
+            text(item, "path"),
+            text(item, "old")
+        ));
+        out.push_str(&format!(
+            "  {}  {}\n",
+            " ".repeat(text(item, "path").len()),
+            text(item, "new")
+        ));
+    }
+    for item in changes["added"].as_array().into_iter().flatten() {
+        out.push_str(&format!(
+            "+ {}  {}\n",
+            text(item, "path"),
+            text(item, "signature")
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    const OLD: &str =
+        "pub fn keep() {}\npub fn add(a: i32) -> i32 { a }\npub fn gone() {}\nfn private() {}\n";
+    const NEW: &str =
+        "pub fn keep() {}\npub fn add(a: i64) -> i64 { a }\npub struct New;\nfn private2() {}\n";
+
+    // The surface of a single file holding `source`
+    fn surface(dir: &TempDir, name: &str, source: &str) -> Value {
+        let path = dir.write(name, source);
+        api_surface(&path, std::slice::from_ref(&path))
+    }
+
+    #[test]
+    fn test_api_surface_lists_public_items() {
+        let dir = TempDir::new("api");
+        let files = vec![
+            dir.write("src/lib.rs", OLD),
+            dir.write("src/notes.txt", "pub fn not_code() {}"),
+        ];
+
+        let surface = api_surface(&dir, &files);
+        assert_eq!(surface["total"], 3);
+        assert_eq!(surface["files"].as_array().unwrap().len(), 1);
+        assert_eq!(surface["files"][0]["path"], "src/lib.rs");
+        assert_eq!(
+            surface["files"][0]["items"][1]["signature"],
+            "pub fn add(a: i32) -> i32"
+        );
+        assert!(format_api_text(&surface).starts_with("src/lib.rs:1  function   pub fn keep()\n"));
+    }
+
+    #[test]
+    fn test_unreadable_files_are_reported() {
+        let dir = TempDir::new("api");
+        let surface = api_surface(&dir, &[dir.join("missing.rs")]);
+        assert_eq!(surface["total"], 0);
+        assert_eq!(surface["files"][0]["path"], "missing.rs");
+        assert!(surface["files"][0]["error"].is_string());
+    }
+
+    #[test]
+    fn test_api_changes_between_versions() {
+        let dir = TempDir::new("api");
+        let old = surface(&dir, "old.rs", OLD);
+        let new = surface(&dir, "new.rs", NEW);
+
+        let changes = api_changes(&old, &new);
+        assert_eq!(changes["removed"][0]["name"], "gone");
+        assert_eq!(changes["changed"][0]["new"], "pub fn add(a: i64) -> i64");
+        assert_eq!(changes["added"][0]["signature"], "pub struct New;");
+        assert_eq!(changes["breaking"], true);
+        assert_eq!(
+            format_changes_text(&changes),
+            "- old.rs  pub fn gone()\n\
+             ~ new.rs  pub fn add(a: i32) -> i32\n\
+             \x20         pub fn add(a: i64) -> i64\n\
+             + new.rs  pub struct New;\n"
+        );
+    }
+
+    #[test]
+    fn test_additions_alone_are_not_breaking() {
+        let dir = TempDir::new("api");
+        let old = surface(&dir, "old.rs", "pub fn keep() {}\n");
+        let new = surface(&dir, "new.rs", "pub fn keep() {}\npub fn extra() {}\n");
+
+        let changes = api_changes(&old, &new);
+        assert_eq!(changes["added"][0]["name"], "extra");
+        assert_eq!(changes["breaking"], false);
+        assert_eq!(api_changes(&old, &old)["added"], json!([]));
+    }
+}
@@ -1,58 +1,495 @@
-use std::ffi::{c_char, CStr, CString};
-use std::fs;
-use tree_sitter::{Language, Parser};
-
-pub fn parse_ast(file_path: *const c_char, language: Language) -> *mut c_char {
-    let c_str = unsafe { CStr::from_ptr(file_path) };
-    let file_path_str = match c_str.to_str() {
-        Ok(s) => s,
-        Err(_) => return std::ptr::null_mut(),
-    };
+use crate::source::read_source;
+use std::borrow::Cow;
+use std::fmt;
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+use tree_sitter::{Language, LanguageError, Node, ParseOptions, ParseState, Parser, Point, Tree};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AstFormat {
+    Text,
+    Json,
+    // Byte-for-byte what `tree-sitter parse` prints: named nodes with fields and
+    // ranges, no leaf text
+    SexpCompat,
+}
+
+impl AstFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "text" => Some(AstFormat::Text),
+            "json" => Some(AstFormat::Json),
+            "sexp-compat" => Some(AstFormat::SexpCompat),
+            _ => None,
+        }
+    }
+}
+
+// Extra detail for `write_ast_with`; the defaults match `write_ast`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AstOptions {
+    // Text format: annotate every node with `[start_row,start_col - end_row,end_col]`
+    pub positions: bool,
+    // Byte offsets: `<start..end>` in text, `start_byte`/`end_byte` in JSON
+    pub bytes: bool,
+    // Omit anonymous nodes (punctuation, keywords); by default the full CST is written
+    pub named_only: bool,
+    // Nodes at this depth (the root is 0) are written without their children
+    pub max_depth: Option<usize>,
+}
 
-    match parse_file_with_language(file_path_str, language) {
-        Ok(result) => match CString::new(result) {
-            Ok(c_string) => c_string.into_raw(),
-            Err(_) => std::ptr::null_mut(),
-        },
-        Err(_) => std::ptr::null_mut(),
+impl AstOptions {
+    // Whether `node`'s children are written, or it is cut off by `max_depth`
+    fn expands(&self, node: &Node, depth: usize) -> bool {
+        node.child_count() > 0 && self.max_depth.is_none_or(|max| depth < max)
     }
 }
 
-fn parse_file_with_language(
+pub fn parse_file_with_language(
     file_path: &str,
     language: Language,
 ) -> Result<String, Box<dyn std::error::Error>> {
-    let source_code = fs::read_to_string(file_path)?;
+    let source_code = read_source(file_path, None)?;
+    parse_source(&source_code, &language)
+}
+
+pub fn parse_source(
+    source_code: &str,
+    language: &Language,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let tree = parse_tree(source_code, language)?;
+    let mut output = Vec::new();
+    write_ast(&tree, source_code, AstFormat::Text, &mut output)?;
+    Ok(String::from_utf8(output)?)
+}
+
+#[derive(Debug)]
+pub enum ParseError {
+    Language(LanguageError),
+    TimedOut(Duration),
+    // tree-sitter returned no tree without being cancelled
+    Failed,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Language(e) => write!(f, "Failed to load grammar: {}", e),
+            ParseError::TimedOut(timeout) => {
+                write!(f, "Parsing timed out after {} ms", timeout.as_millis())
+            }
+            ParseError::Failed => write!(f, "Parser did not produce a syntax tree"),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+pub fn parse_tree(
+    source_code: &str,
+    language: &Language,
+) -> Result<Tree, Box<dyn std::error::Error>> {
+    Ok(parse_tree_with_timeout(source_code, language, None)?)
+}
 
+// Parses `source_code`, giving up once `timeout` has elapsed
+pub fn parse_tree_with_timeout(
+    source_code: &str,
+    language: &Language,
+    timeout: Option<Duration>,
+) -> Result<Tree, ParseError> {
     let mut parser = Parser::new();
-    parser.set_language(&language)?;
+    parser
+        .set_language(language)
+        .map_err(ParseError::Language)?;
 
-    let tree = parser.parse(&source_code, None).unwrap();
-    let root_node = tree.root_node();
+    let bytes = source_code.as_bytes();
+    let mut read = |offset: usize, _: Point| &bytes[offset.min(bytes.len())..];
+    let started = Instant::now();
+    let mut timed_out = false;
+    let mut check_deadline = |_: &ParseState| {
+        // Returning true cancels the parse
+        timed_out = timeout.is_some_and(|limit| started.elapsed() >= limit);
+        timed_out
+    };
+    let options = ParseOptions::new().progress_callback(&mut check_deadline);
 
-    let ast_string = format_node(&root_node, &source_code, 0);
-    Ok(ast_string)
+    match parser.parse_with_options(&mut read, None, Some(options)) {
+        Some(tree) => Ok(tree),
+        None if timed_out => Err(ParseError::TimedOut(timeout.unwrap_or_default())),
+        None => Err(ParseError::Failed),
+    }
 }
 
-fn format_node(node: &tree_sitter::Node, source: &str, depth: usize) -> String {
-    let indent = "  ".repeat(depth);
-    let mut result = format!("{}({}", indent, node.kind());
+// Streams the tree into `out` without building the whole dump in memory
+pub fn write_ast<W: Write>(
+    tree: &Tree,
+    source: &str,
+    format: AstFormat,
+    out: &mut W,
+) -> io::Result<()> {
+    write_ast_with(tree, source, format, &AstOptions::default(), out)
+}
+
+pub fn write_ast_with<W: Write>(
+    tree: &Tree,
+    source: &str,
+    format: AstFormat,
+    options: &AstOptions,
+    out: &mut W,
+) -> io::Result<()> {
+    write_ast_node(tree.root_node(), source, format, options, out)
+}
+
+// Writes the subtree under `root`, e.g. the snippet inside a fragment's scaffolding
+pub fn write_ast_node<W: Write>(
+    root: Node,
+    source: &str,
+    format: AstFormat,
+    options: &AstOptions,
+    out: &mut W,
+) -> io::Result<()> {
+    let options = *options;
+    match format {
+        AstFormat::Text => walk(root, &mut TextWriter { out, source, options }),
+        AstFormat::Json => walk(
+            root,
+            &mut JsonWriter { out, source, options, needs_comma: false },
+        ),
+        AstFormat::SexpCompat => write_sexp_compat(root, out),
+    }
+}
 
-    if node.child_count() == 0 {
-        // Leaf node - include the text
-        let text = node.utf8_text(source.as_bytes()).unwrap_or("");
-        if !text.trim().is_empty() {
-            result.push_str(&format!(" \"{}\"", text.replace('\n', "\\n")));
+// Mirrors the tree-sitter CLI's printer, including where closing parens and
+// newlines go, so golden files written by `tree-sitter parse` compare equal
+fn write_sexp_compat<W: Write>(root: Node, out: &mut W) -> io::Result<()> {
+    let mut cursor = root.walk();
+    let mut needs_newline = false;
+    let mut indent = 0;
+    let mut visited_children = false;
+    loop {
+        let node = cursor.node();
+        let printed = node.is_named() || node.is_missing();
+        if visited_children {
+            if printed {
+                out.write_all(b")")?;
+                needs_newline = true;
+            }
+            if cursor.goto_next_sibling() {
+                visited_children = false;
+            } else if cursor.goto_parent() {
+                visited_children = true;
+                indent -= 1;
+            } else {
+                return Ok(());
+            }
+            continue;
+        }
+
+        if printed {
+            if needs_newline {
+                out.write_all(b"\n")?;
+            }
+            write!(out, "{}", "  ".repeat(indent))?;
+            if let Some(field) = cursor.field_name() {
+                write!(out, "{}: ", field)?;
+            }
+            match (node.is_missing(), node.is_named()) {
+                (true, true) => write!(out, "(MISSING {}", node.kind())?,
+                (true, false) => write!(out, "(MISSING \"{}\"", escape_text(node.kind()))?,
+                _ => write!(out, "({}", node.kind())?,
+            }
+            let start = node.start_position();
+            let end = node.end_position();
+            write!(
+                out,
+                " [{}, {}] - [{}, {}]",
+                start.row, start.column, end.row, end.column
+            )?;
+            needs_newline = true;
+        }
+        if cursor.goto_first_child() {
+            indent += 1;
+        } else {
+            visited_children = true;
         }
     }
-    result.push(')');
+}
+
+pub(crate) trait NodeVisitor {
+    fn enter(&mut self, node: &Node, depth: usize, first_sibling: bool) -> io::Result<()>;
+    fn leave(&mut self, node: &Node, depth: usize) -> io::Result<()>;
 
-    for i in 0..node.child_count() {
-        if let Some(child) = node.child(i) {
-            result.push('\n');
-            result.push_str(&format_node(&child, source, depth + 1));
+    // Returning false skips the node's subtree; `leave` is still called
+    fn descend(&self, _node: &Node, _depth: usize) -> bool {
+        true
+    }
+}
+
+// Pre-order traversal with a TreeCursor, so deeply nested trees can't overflow the stack
+pub(crate) fn walk<V: NodeVisitor>(root: Node, visitor: &mut V) -> io::Result<()> {
+    let mut cursor = root.walk();
+    let mut depth = 0;
+    let mut first_sibling = true;
+
+    loop {
+        let node = cursor.node();
+        visitor.enter(&node, depth, first_sibling)?;
+        if visitor.descend(&node, depth) && cursor.goto_first_child() {
+            depth += 1;
+            first_sibling = true;
+            continue;
+        }
+        visitor.leave(&node, depth)?;
+
+        loop {
+            if cursor.goto_next_sibling() {
+                first_sibling = false;
+                break;
+            }
+            if !cursor.goto_parent() {
+                return Ok(());
+            }
+            depth -= 1;
+            visitor.leave(&cursor.node(), depth)?;
         }
     }
+}
 
-    result
-}
\ No newline at end of file
+struct TextWriter<'a, W: Write> {
+    out: &'a mut W,
+    source: &'a str,
+    options: AstOptions,
+}
+
+impl<W: Write> NodeVisitor for TextWriter<'_, W> {
+    fn enter(&mut self, node: &Node, depth: usize, _first_sibling: bool) -> io::Result<()> {
+        // Anonymous nodes are tokens, so skipping one never orphans children
+        if self.options.named_only && !node.is_named() {
+            return Ok(());
+        }
+        if depth > 0 {
+            self.out.write_all(b"\n")?;
+        }
+        write!(self.out, "{}({}", "  ".repeat(depth), node.kind())?;
+        if self.options.positions {
+            let start = node.start_position();
+            let end = node.end_position();
+            write!(
+                self.out,
+                " [{},{} - {},{}]",
+                start.row, start.column, end.row, end.column
+            )?;
+        }
+        if self.options.bytes {
+            write!(self.out, " <{}..{}>", node.start_byte(), node.end_byte())?;
+        }
+
+        if node.child_count() == 0 {
+            // Leaf node - include the text
+            let text = node.utf8_text(self.source.as_bytes()).unwrap_or("");
+            if !text.trim().is_empty() {
+                write!(self.out, " \"{}\"", escape_text(text))?;
+            }
+        } else if !self.options.expands(node, depth) {
+            self.out.write_all(b" ...")?;
+        }
+        self.out.write_all(b")")
+    }
+
+    fn leave(&mut self, _node: &Node, _depth: usize) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn descend(&self, node: &Node, depth: usize) -> bool {
+        self.options.expands(node, depth)
+    }
+}
+
+// Escapes leaf text so every s-expression string literal is well formed
+pub fn escape_text(text: &str) -> Cow<'_, str> {
+    if !text.chars().any(|c| c == '"' || c == '\\' || c.is_control()) {
+        return Cow::Borrowed(text);
+    }
+
+    let mut escaped = String::with_capacity(text.len() + 8);
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{{{:04x}}}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    Cow::Owned(escaped)
+}
+
+struct JsonWriter<'a, W: Write> {
+    out: &'a mut W,
+    source: &'a str,
+    options: AstOptions,
+    // Set once an element has been written to the current children array
+    needs_comma: bool,
+}
+
+impl<W: Write> NodeVisitor for JsonWriter<'_, W> {
+    fn enter(&mut self, node: &Node, depth: usize, _first_sibling: bool) -> io::Result<()> {
+        if self.options.named_only && !node.is_named() {
+            return Ok(());
+        }
+        if self.needs_comma {
+            self.out.write_all(b",")?;
+        }
+        let start = node.start_position();
+        let end = node.end_position();
+        self.out.write_all(b"{\"kind\":")?;
+        serde_json::to_writer(&mut *self.out, node.kind())?;
+        write!(
+            self.out,
+            ",\"named\":{},\"start\":[{},{}],\"end\":[{},{}]",
+            node.is_named(),
+            start.row,
+            start.column,
+            end.row,
+            end.column
+        )?;
+        if self.options.bytes {
+            write!(
+                self.out,
+                ",\"start_byte\":{},\"end_byte\":{}",
+                node.start_byte(),
+                node.end_byte()
+            )?;
+        }
+
+        if node.child_count() == 0 {
+            let text = node.utf8_text(self.source.as_bytes()).unwrap_or("");
+            self.out.write_all(b",\"text\":")?;
+            serde_json::to_writer(&mut *self.out, text)?;
+            self.needs_comma = true;
+            self.out.write_all(b"}")
+        } else if !self.options.expands(node, depth) {
+            self.needs_comma = true;
+            self.out.write_all(b",\"truncated\":true}")
+        } else {
+            self.needs_comma = false;
+            self.out.write_all(b",\"children\":[")
+        }
+    }
+
+    fn leave(&mut self, node: &Node, depth: usize) -> io::Result<()> {
+        if self.options.expands(node, depth) {
+            self.needs_comma = true;
+            self.out.write_all(b"]}")?;
+        }
+        Ok(())
+    }
+
+    fn descend(&self, node: &Node, depth: usize) -> bool {
+        self.options.expands(node, depth)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_and_json_writers() {
+        let language: Language = tree_sitter_rust::LANGUAGE.into();
+        let source = "fn main() {}";
+
+        assert_eq!(
+            parse_source(source, &language).unwrap(),
+            "(source_file)\n  (function_item)\n    (fn \"fn\")\n    (identifier \"main\")\n    \
+             (parameters)\n      (( \"(\")\n      () \")\")\n    (block)\n      ({ \"{\")\n      (} \"}\")"
+        );
+
+        let tree = parse_tree(source, &language).unwrap();
+        let mut output = Vec::new();
+        write_ast(&tree, source, AstFormat::Json, &mut output).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&output).unwrap();
+        assert_eq!(json["kind"], "source_file");
+        assert_eq!(json["children"][0]["children"][1]["text"], "main");
+        assert_eq!(
+            json["children"][0]["children"][1]["start"],
+            serde_json::json!([0, 3])
+        );
+
+        let options = AstOptions {
+            positions: true,
+            bytes: true,
+            ..AstOptions::default()
+        };
+        let mut output = Vec::new();
+        write_ast_with(&tree, source, AstFormat::Text, &options, &mut output).unwrap();
+        let text = String::from_utf8(output).unwrap();
+        assert!(text.starts_with("(source_file [0,0 - 0,12] <0..12>)\n"));
+        assert!(text.contains("(identifier [0,3 - 0,7] <3..7> \"main\")"));
+
+        let named_only = AstOptions {
+            named_only: true,
+            ..AstOptions::default()
+        };
+        let mut output = Vec::new();
+        write_ast_with(&tree, source, AstFormat::Text, &named_only, &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "(source_file)\n  (function_item)\n    (identifier \"main\")\n    (parameters)\n    (block)"
+        );
+        let mut output = Vec::new();
+        write_ast_with(&tree, source, AstFormat::Json, &named_only, &mut output).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&output).unwrap();
+        assert_eq!(json["children"][0]["children"].as_array().unwrap().len(), 3);
+        assert_eq!(json["children"][0]["children"][1]["children"], serde_json::json!([]));
+
+        let shallow = AstOptions {
+            max_depth: Some(1),
+            ..AstOptions::default()
+        };
+        let mut output = Vec::new();
+        write_ast_with(&tree, source, AstFormat::Text, &shallow, &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "(source_file)\n  (function_item ...)"
+        );
+        let mut output = Vec::new();
+        write_ast_with(&tree, source, AstFormat::Json, &shallow, &mut output).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&output).unwrap();
+        assert_eq!(json["children"][0]["truncated"], true);
+
+        let mut output = Vec::new();
+        write_ast(&tree, source, AstFormat::SexpCompat, &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "(source_file [0, 0] - [0, 12]\n  (function_item [0, 0] - [0, 12]\n    \
+             name: (identifier [0, 3] - [0, 7])\n    parameters: (parameters [0, 7] - [0, 9])\n    \
+             body: (block [0, 10] - [0, 12])))"
+        );
+        assert!(json["children"][0].get("children").is_none());
+    }
+
+    #[test]
+    fn test_escape_text() {
+        assert!(matches!(escape_text("plain"), Cow::Borrowed("plain")));
+        assert_eq!(
+            escape_text("\"a\\b\"\r\n\t\u{1b}"),
+            "\\\"a\\\\b\\\"\\r\\n\\t\\u{001b}"
+        );
+    }
+
+    #[test]
+    fn test_parse_timeout_is_reported() {
+        let language: Language = tree_sitter_rust::LANGUAGE.into();
+        let source = "fn f() { let x = (1 + 2) * 3; }\n".repeat(20_000);
+
+        match parse_tree_with_timeout(&source, &language, Some(Duration::ZERO)) {
+            Err(ParseError::TimedOut(_)) => {}
+            other => panic!("expected a timeout, got {:?}", other.map(|_| ())),
+        }
+        assert!(parse_tree_with_timeout(&source, &language, Some(Duration::from_secs(60))).is_ok());
+    }
+}
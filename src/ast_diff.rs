@@ -0,0 +1,427 @@
+use crate::ast::{walk, NodeVisitor};
+use crate::cache::stable_hash;
+use crate::outline::{symbol_name, SymbolKind};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io;
+use tree_sitter::{Node, Tree};
+
+// Children lists longer than this (in old x new pairs) skip the LCS alignment and
+// are compared as one gap, so huge files can't blow up memory
+const MAX_ALIGNMENT_CELLS: usize = 1 << 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Modified,
+    // Identical subtree at a different place in the tree
+    Moved,
+}
+
+impl ChangeKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChangeKind::Added => "added",
+            ChangeKind::Removed => "removed",
+            ChangeKind::Modified => "modified",
+            ChangeKind::Moved => "moved",
+        }
+    }
+
+    fn marker(&self) -> char {
+        match self {
+            ChangeKind::Added => '+',
+            ChangeKind::Removed => '-',
+            ChangeKind::Modified => '~',
+            ChangeKind::Moved => '>',
+        }
+    }
+}
+
+// 1-based lines and byte columns, like analysis issues
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NodeRange {
+    pub start: (usize, usize),
+    pub end: (usize, usize),
+}
+
+impl NodeRange {
+    fn of(node: &Node) -> Self {
+        let start = node.start_position();
+        let end = node.end_position();
+        NodeRange {
+            start: (start.row + 1, start.column + 1),
+            end: (end.row + 1, end.column + 1),
+        }
+    }
+
+    fn to_json(self) -> Value {
+        json!({
+            "start": { "line": self.start.0, "column": self.start.1 },
+            "end": { "line": self.end.0, "column": self.end.1 },
+        })
+    }
+}
+
+impl std::fmt::Display for NodeRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}:{}-{}:{}",
+            self.start.0, self.start.1, self.end.0, self.end.1
+        )
+    }
+}
+
+// One named node that differs between the two trees. `old` is missing for added
+// nodes and `new` for removed ones.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeChange {
+    pub change: ChangeKind,
+    pub kind: String,
+    pub name: Option<String>,
+    pub old: Option<NodeRange>,
+    pub new: Option<NodeRange>,
+}
+
+// Structural diff of two parses of the same language. Identical subtrees are
+// matched by hash, the remaining named children of a changed node are paired by
+// kind and name, and each pair is compared in turn; a pair is reported as
+// modified once it is a leaf or its own tokens (operators, keywords) differ.
+pub fn diff_trees(old: &Tree, old_source: &str, new: &Tree, new_source: &str) -> Vec<NodeChange> {
+    let old_side = Side::new(old, old_source);
+    let new_side = Side::new(new, new_source);
+    let mut changes = Vec::new();
+    let mut removed = Vec::new();
+    let mut added = Vec::new();
+
+    let mut pending = vec![(old.root_node(), new.root_node())];
+    while let Some((old_node, new_node)) = pending.pop() {
+        if old_side.hash(&old_node) == new_side.hash(&new_node) {
+            continue;
+        }
+        if old_node.kind() != new_node.kind()
+            || old_node.named_child_count() == 0
+            || new_node.named_child_count() == 0
+            || own_tokens(&old_node) != own_tokens(&new_node)
+        {
+            changes.push(NodeChange {
+                change: ChangeKind::Modified,
+                kind: new_node.kind().to_string(),
+                name: new_side.name(&new_node),
+                old: Some(NodeRange::of(&old_node)),
+                new: Some(NodeRange::of(&new_node)),
+            });
+            continue;
+        }
+
+        let old_children = named_children(&old_node);
+        let new_children = named_children(&new_node);
+        // Pairing spans all gaps, so a declaration that changed and moved past
+        // an unchanged sibling is still compared with its old self
+        let gaps = align(&old_children, &new_children, &old_side, &new_side);
+        let mut unpaired: Vec<Option<Node>> = gaps
+            .iter()
+            .flat_map(|(_, new_gap)| new_gap.iter().copied().map(Some))
+            .collect();
+        for old_child in gaps.iter().flat_map(|(old_gap, _)| old_gap.iter()) {
+            let key = (old_child.kind(), old_side.name(old_child));
+            let partner = unpaired
+                .iter_mut()
+                .find(|candidate| candidate.is_some_and(|c| (c.kind(), new_side.name(&c)) == key));
+            match partner.and_then(Option::take) {
+                Some(new_child) if old_side.hash(old_child) == new_side.hash(&new_child) => {
+                    changes.push(moved(old_child, &new_child, &old_side))
+                }
+                Some(new_child) => pending.push((*old_child, new_child)),
+                None => removed.push(*old_child),
+            }
+        }
+        added.extend(unpaired.into_iter().flatten());
+    }
+
+    // Whole subtrees that disappeared in one place and reappeared in another
+    let mut moved_to: Vec<Option<Node>> = added.iter().copied().map(Some).collect();
+    for old_node in removed {
+        let hash = old_side.hash(&old_node);
+        let target = moved_to
+            .iter_mut()
+            .find(|candidate| candidate.is_some_and(|c| new_side.hash(&c) == hash));
+        changes.push(match target.and_then(Option::take) {
+            Some(new_node) => moved(&old_node, &new_node, &old_side),
+            None => NodeChange {
+                change: ChangeKind::Removed,
+                kind: old_node.kind().to_string(),
+                name: old_side.name(&old_node),
+                old: Some(NodeRange::of(&old_node)),
+                new: None,
+            },
+        });
+    }
+    for new_node in moved_to.into_iter().flatten() {
+        changes.push(NodeChange {
+            change: ChangeKind::Added,
+            kind: new_node.kind().to_string(),
+            name: new_side.name(&new_node),
+            old: None,
+            new: Some(NodeRange::of(&new_node)),
+        });
+    }
+
+    changes.sort_by_key(|change| {
+        let range = change.new.or(change.old).map(|range| range.start);
+        (range, change.change.as_str())
+    });
+    changes
+}
+
+fn moved(old_node: &Node, new_node: &Node, old_side: &Side) -> NodeChange {
+    NodeChange {
+        change: ChangeKind::Moved,
+        kind: old_node.kind().to_string(),
+        name: old_side.name(old_node),
+        old: Some(NodeRange::of(old_node)),
+        new: Some(NodeRange::of(new_node)),
+    }
+}
+
+pub fn diff_to_json(old_path: &str, new_path: &str, changes: &[NodeChange]) -> Value {
+    let count = |kind: ChangeKind| changes.iter().filter(|c| c.change == kind).count();
+    let changes: Vec<Value> = changes
+        .iter()
+        .map(|change| {
+            let mut value = json!({
+                "change": change.change.as_str(),
+                "kind": change.kind,
+                "old": change.old.map(NodeRange::to_json),
+                "new": change.new.map(NodeRange::to_json),
+            });
+            if let Some(name) = &change.name {
+                value["name"] = json!(name);
+            }
+            value
+        })
+        .collect();
+    json!({
+        "old": old_path,
+        "new": new_path,
+        "summary": {
+            "added": count(ChangeKind::Added),
+            "removed": count(ChangeKind::Removed),
+            "modified": count(ChangeKind::Modified),
+            "moved": count(ChangeKind::Moved),
+        },
+        "changes": changes,
+    })
+}
+
+// One line per change: `~ function_item area 3:1-5:2 -> 3:1-7:2`
+pub fn format_diff_text(changes: &[NodeChange]) -> String {
+    let mut text = String::new();
+    for change in changes {
+        text.push(change.change.marker());
+        text.push(' ');
+        text.push_str(&change.kind);
+        if let Some(name) = &change.name {
+            text.push(' ');
+            text.push_str(name);
+        }
+        match (change.old, change.new) {
+            (Some(old), Some(new)) => text.push_str(&format!(" {} -> {}", old, new)),
+            (Some(range), None) | (None, Some(range)) => text.push_str(&format!(" {}", range)),
+            (None, None) => {}
+        }
+        text.push('\n');
+    }
+    text
+}
+
+struct Side<'a> {
+    source: &'a str,
+    // Subtree hash of every node, by node id
+    hashes: HashMap<usize, u64>,
+}
+
+impl<'a> Side<'a> {
+    fn new(tree: &Tree, source: &'a str) -> Self {
+        let mut hasher = SubtreeHasher {
+            source,
+            hashes: HashMap::new(),
+        };
+        // The hasher never performs I/O
+        let _ = walk(tree.root_node(), &mut hasher);
+        Side {
+            source,
+            hashes: hasher.hashes,
+        }
+    }
+
+    fn hash(&self, node: &Node) -> u64 {
+        self.hashes.get(&node.id()).copied().unwrap_or(0)
+    }
+
+    // Declarations are identified by name so renamed ones show as removed + added
+    fn name(&self, node: &Node) -> Option<String> {
+        let name = if SymbolKind::from_node_kind(node.kind()).is_some() {
+            symbol_name(node)
+        } else {
+            node.child_by_field_name("name")
+        }?;
+        let text = name.utf8_text(self.source.as_bytes()).ok()?;
+        (!text.contains('\n')).then(|| text.to_string())
+    }
+}
+
+// Hashes subtrees bottom-up: leaves by kind and text, other nodes by kind and
+// their children's hashes
+struct SubtreeHasher<'a> {
+    source: &'a str,
+    hashes: HashMap<usize, u64>,
+}
+
+impl NodeVisitor for SubtreeHasher<'_> {
+    fn enter(&mut self, _node: &Node, _depth: usize, _first_sibling: bool) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn leave(&mut self, node: &Node, _depth: usize) -> io::Result<()> {
+        let mut key = node.kind().to_string();
+        if node.child_count() == 0 {
+            key.push('\0');
+            key.push_str(node.utf8_text(self.source.as_bytes()).unwrap_or(""));
+        } else {
+            let mut cursor = node.walk();
+            for child in node.children(&mut cursor) {
+                let hash = self.hashes.get(&child.id()).copied().unwrap_or(0);
+                key.push_str(&format!(":{:x}", hash));
+            }
+        }
+        self.hashes.insert(node.id(), stable_hash(key.as_bytes()));
+        Ok(())
+    }
+}
+
+fn named_children<'tree>(node: &Node<'tree>) -> Vec<Node<'tree>> {
+    let mut cursor = node.walk();
+    node.named_children(&mut cursor).collect()
+}
+
+// Anonymous children (operators, keywords, punctuation) belong to the node itself
+fn own_tokens<'tree>(node: &Node<'tree>) -> Vec<&'static str> {
+    let mut cursor = node.walk();
+    node.children(&mut cursor)
+        .filter(|child| !child.is_named())
+        .map(|child| child.kind())
+        .collect()
+}
+
+type Gap<'s, 'tree> = (&'s [Node<'tree>], &'s [Node<'tree>]);
+
+// Splits two children lists into the runs between identical subtrees: a common
+// prefix and suffix are trimmed, then the middle is aligned by LCS on hashes
+fn align<'s, 'tree>(
+    old: &'s [Node<'tree>],
+    new: &'s [Node<'tree>],
+    old_side: &Side,
+    new_side: &Side,
+) -> Vec<Gap<'s, 'tree>> {
+    let same = |i: usize, j: usize| old_side.hash(&old[i]) == new_side.hash(&new[j]);
+    let mut prefix = 0;
+    while prefix < old.len() && prefix < new.len() && same(prefix, prefix) {
+        prefix += 1;
+    }
+    let mut suffix = 0;
+    while suffix < old.len() - prefix
+        && suffix < new.len() - prefix
+        && same(old.len() - 1 - suffix, new.len() - 1 - suffix)
+    {
+        suffix += 1;
+    }
+    let old = &old[prefix..old.len() - suffix];
+    let new = &new[prefix..new.len() - suffix];
+    if old.is_empty() && new.is_empty() {
+        return Vec::new();
+    }
+    if old.len() * new.len() > MAX_ALIGNMENT_CELLS {
+        return vec![(old, new)];
+    }
+
+    let same = |i: usize, j: usize| old_side.hash(&old[i]) == new_side.hash(&new[j]);
+    // lengths[i][j] = LCS length of old[i..] and new[j..]
+    let width = new.len() + 1;
+    let mut lengths = vec![0u32; (old.len() + 1) * width];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lengths[i * width + j] = if same(i, j) {
+                lengths[(i + 1) * width + j + 1] + 1
+            } else {
+                lengths[(i + 1) * width + j].max(lengths[i * width + j + 1])
+            };
+        }
+    }
+
+    let mut gaps = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    let (mut gap_i, mut gap_j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if same(i, j) {
+            if gap_i < i || gap_j < j {
+                gaps.push((&old[gap_i..i], &new[gap_j..j]));
+            }
+            i += 1;
+            j += 1;
+            (gap_i, gap_j) = (i, j);
+        } else if lengths[(i + 1) * width + j] >= lengths[i * width + j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    if gap_i < old.len() || gap_j < new.len() {
+        gaps.push((&old[gap_i..], &new[gap_j..]));
+    }
+    gaps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::parse_tree;
+
+    #[test]
+    fn test_diff_reports_added_removed_modified_and_moved() {
+        let language = tree_sitter_rust::LANGUAGE.into();
+        let old = "fn a() { x + 1; }\nfn b() {}\nstruct S;\nstruct T;\n";
+        let new = "struct T;\nfn a() { x - 1; }\nstruct S;\nfn d() {}\n";
+        let old_tree = parse_tree(old, &language).unwrap();
+        let new_tree = parse_tree(new, &language).unwrap();
+
+        let changes = diff_trees(&old_tree, old, &new_tree, new);
+        let summary: Vec<(&str, &str, Option<&str>)> = changes
+            .iter()
+            .map(|c| (c.change.as_str(), c.kind.as_str(), c.name.as_deref()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("removed", "function_item", Some("b")),
+                ("modified", "binary_expression", None),
+                ("moved", "struct_item", Some("S")),
+                ("added", "function_item", Some("d")),
+            ]
+        );
+        assert_eq!(
+            changes[1].old,
+            Some(NodeRange {
+                start: (1, 10),
+                end: (1, 15)
+            })
+        );
+        assert!(format_diff_text(&changes).contains("- function_item b 2:1-2:10\n"));
+        assert_eq!(
+            diff_to_json("old.rs", "new.rs", &changes)["summary"]["moved"],
+            1
+        );
+    }
+}
@@ -0,0 +1,74 @@
+use crate::analyzer::rating_for_score;
+
+// shields.io palette, keyed by the rating the score falls in
+pub fn rating_color(rating: &str) -> &'static str {
+    match rating {
+        "Excellent" => "#4c1",
+        "Good" => "#97ca00",
+        "Fair" => "#dfb317",
+        "Poor" => "#fe7d37",
+        "Critical" => "#e05d44",
+        _ => "#9f9f9f",
+    }
+}
+
+// Flat badge reading `<label> | 8.7/10`, or `unknown` when nothing was analyzed
+pub fn render_badge(label: &str, score: Option<f64>) -> String {
+    let (value, color) = match score {
+        Some(score) => (
+            format!("{:.1}/10", score),
+            rating_color(rating_for_score(score)),
+        ),
+        None => ("unknown".to_string(), rating_color("")),
+    };
+    let label = escape_xml(label);
+    let label_width = text_width(&label);
+    let value_width = text_width(&value);
+    let width = label_width + value_width;
+
+    format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="20" role="img" aria-label="{label}: {value}">
+  <title>{label}: {value}</title>
+  <linearGradient id="s" x2="0" y2="100%"><stop offset="0" stop-color="#bbb" stop-opacity=".1"/><stop offset="1" stop-opacity=".1"/></linearGradient>
+  <clipPath id="r"><rect width="{width}" height="20" rx="3" fill="#fff"/></clipPath>
+  <g clip-path="url(#r)">
+    <rect width="{label_width}" height="20" fill="#555"/>
+    <rect x="{label_width}" width="{value_width}" height="20" fill="{color}"/>
+    <rect width="{width}" height="20" fill="url(#s)"/>
+  </g>
+  <g fill="#fff" text-anchor="middle" font-family="Verdana,Geneva,DejaVu Sans,sans-serif" font-size="11">
+    <text x="{label_x}" y="14">{label}</text>
+    <text x="{value_x}" y="14">{value}</text>
+  </g>
+</svg>
+"##,
+        label_x = label_width as f64 / 2.0,
+        value_x = label_width as f64 + value_width as f64 / 2.0,
+    )
+}
+
+// Verdana 11px averages about 7px per character; 10px of padding either side
+fn text_width(text: &str) -> usize {
+    text.chars().count() * 7 + 10
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_badge_color_follows_rating() {
+        let badge = render_badge("code quality", Some(7.54));
+        assert!(badge.contains("code quality: 7.5/10"));
+        assert!(badge.contains("fill=\"#97ca00\""));
+        assert!(render_badge("q", Some(3.0)).contains("#e05d44"));
+        assert!(render_badge("q", None).contains(">unknown<"));
+    }
+}
@@ -0,0 +1,281 @@
+use crate::scan::{language_breakdown, rollup_directories};
+use serde_json::{json, Value};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+
+pub const BASELINE_VERSION: u64 = 1;
+pub const DEFAULT_BASELINE_FILE: &str = ".treescan-baseline.json";
+
+// Known issues that directory scans stop reporting. Entries recorded from a treescan
+// report match on (path, fingerprint) so they survive code moving around them;
+// imported ones have no fingerprint and match on (path, rule, line).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Baseline {
+    pub issues: BTreeSet<BaselineIssue>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct BaselineIssue {
+    pub path: String,
+    pub rule: String,
+    pub line: u64,
+    pub fingerprint: Option<String>,
+}
+
+// What an import kept and what it had to drop for lack of a treescan rule
+#[derive(Debug, Default)]
+pub struct ImportSummary {
+    pub imported: usize,
+    pub unmapped: usize,
+}
+
+impl Baseline {
+    pub fn to_json(&self) -> Value {
+        let issues: Vec<Value> = self
+            .issues
+            .iter()
+            .map(|issue| {
+                let mut entry = json!({ "path": issue.path, "rule": issue.rule, "line": issue.line });
+                if let Some(fingerprint) = &issue.fingerprint {
+                    entry["fingerprint"] = json!(fingerprint);
+                }
+                entry
+            })
+            .collect();
+        json!({ "version": BASELINE_VERSION, "issues": issues })
+    }
+
+    pub fn from_json(value: &Value) -> Result<Self, Box<dyn std::error::Error>> {
+        if value["version"].as_u64() != Some(BASELINE_VERSION) {
+            return Err(format!("unsupported baseline version {}", value["version"]).into());
+        }
+        let mut baseline = Baseline::default();
+        for issue in value["issues"].as_array().into_iter().flatten() {
+            if let (Some(path), Some(rule), Some(line)) = (
+                issue["path"].as_str(),
+                issue["rule"].as_str(),
+                issue["line"].as_u64(),
+            ) {
+                baseline.insert(path, rule, line, issue["fingerprint"].as_str());
+            }
+        }
+        Ok(baseline)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let text = fs::read_to_string(path)?;
+        let value: Value = serde_json::from_str(&text)
+            .map_err(|e| format!("Invalid baseline '{}': {}", path.display(), e))?;
+        Self::from_json(&value)
+            .map_err(|e| format!("Invalid baseline '{}': {}", path.display(), e).into())
+    }
+
+    pub fn insert(&mut self, path: &str, rule: &str, line: u64, fingerprint: Option<&str>) {
+        self.issues.insert(BaselineIssue {
+            path: normalize_path(path),
+            rule: rule.to_string(),
+            line,
+            fingerprint: fingerprint.map(str::to_string),
+        });
+    }
+
+    // Every issue in a directory scan report (see `scan::scan_directory`)
+    pub fn from_report(report: &Value) -> Self {
+        let mut baseline = Baseline::default();
+        for file in report["files"].as_array().into_iter().flatten() {
+            let path = file["path"].as_str().unwrap_or("");
+            for issue in file["result"]["issues"].as_array().into_iter().flatten() {
+                if let (Some(rule), Some(line)) = (issue["rule"].as_str(), issue["line"].as_u64()) {
+                    baseline.insert(path, rule, line, issue["fingerprint"].as_str());
+                }
+            }
+        }
+        baseline
+    }
+
+    // ESLint's `--format json` output. Paths are made relative to `root` (where
+    // treescan will be run), and parse failures map onto `syntax_error`.
+    pub fn from_eslint(report: &Value, root: &Path) -> (Self, ImportSummary) {
+        let mut baseline = Baseline::default();
+        let mut summary = ImportSummary::default();
+        for file in report.as_array().into_iter().flatten() {
+            let path = relative_to(file["filePath"].as_str().unwrap_or(""), root);
+            for message in file["messages"].as_array().into_iter().flatten() {
+                let rule = match message["ruleId"].as_str() {
+                    Some(rule) => eslint_rule(rule),
+                    None if message["fatal"].as_bool() == Some(true) => Some("syntax_error"),
+                    None => None,
+                };
+                summary.record(&mut baseline, &path, rule, message["line"].as_u64());
+            }
+        }
+        (baseline, summary)
+    }
+
+    // golangci-lint's `--out-format json` output
+    pub fn from_golangci(report: &Value, root: &Path) -> (Self, ImportSummary) {
+        let mut baseline = Baseline::default();
+        let mut summary = ImportSummary::default();
+        for issue in report["Issues"].as_array().into_iter().flatten() {
+            let position = &issue["Pos"];
+            let path = relative_to(position["Filename"].as_str().unwrap_or(""), root);
+            let rule = issue["FromLinter"].as_str().and_then(golangci_rule);
+            summary.record(&mut baseline, &path, rule, position["Line"].as_u64());
+        }
+        (baseline, summary)
+    }
+
+    // Removes baselined issues from a scan report, counting them per file and in
+    // the summary as `suppressed`. Scores are left as analyzed.
+    pub fn apply(&self, report: &mut Value) -> usize {
+        let mut fingerprints = BTreeSet::new();
+        let mut lines = BTreeSet::new();
+        for issue in &self.issues {
+            match &issue.fingerprint {
+                Some(fingerprint) => fingerprints.insert((issue.path.as_str(), fingerprint.as_str())),
+                None => lines.insert((issue.path.as_str(), issue.rule.as_str(), issue.line)),
+            };
+        }
+
+        let mut suppressed = 0;
+        for file in report["files"].as_array_mut().into_iter().flatten() {
+            let path = normalize_path(file["path"].as_str().unwrap_or(""));
+            // Files that failed to analyze have no `result`
+            let Some(result) = file.get_mut("result") else {
+                continue;
+            };
+            let Some(issues) = result.get_mut("issues").and_then(Value::as_array_mut) else {
+                continue;
+            };
+            let before = issues.len();
+            issues.retain(|issue| {
+                let by_fingerprint = issue["fingerprint"]
+                    .as_str()
+                    .is_some_and(|fingerprint| fingerprints.contains(&(path.as_str(), fingerprint)));
+                let rule = issue["rule"].as_str().unwrap_or("");
+                let line = issue["line"].as_u64().unwrap_or(0);
+                !by_fingerprint && !lines.contains(&(path.as_str(), rule, line))
+            });
+            let removed = before - issues.len();
+            let remaining = issues.len();
+            result["total_issues"] = json!(remaining);
+            result["suppressed"] = json!(removed);
+            suppressed += removed;
+        }
+        if let Some(total) = report["summary"]["total_issues"].as_u64() {
+            report["summary"]["total_issues"] = json!(total.saturating_sub(suppressed as u64));
+        }
+        report["summary"]["suppressed"] = json!(suppressed);
+        rollup_directories(report);
+        language_breakdown(report);
+        suppressed
+    }
+}
+
+impl ImportSummary {
+    fn record(
+        &mut self,
+        baseline: &mut Baseline,
+        path: &str,
+        rule: Option<&str>,
+        line: Option<u64>,
+    ) {
+        match (rule, line) {
+            (Some(rule), Some(line)) => {
+                baseline.insert(path, rule, line, None);
+                self.imported += 1;
+            }
+            _ => self.unmapped += 1,
+        }
+    }
+}
+
+// ESLint core rules with a treescan equivalent
+fn eslint_rule(rule: &str) -> Option<&'static str> {
+    match rule {
+        "no-console" => Some("console_log"),
+        "no-var" => Some("var_usage"),
+        _ => None,
+    }
+}
+
+// golangci-lint linters with a treescan equivalent
+fn golangci_rule(linter: &str) -> Option<&'static str> {
+    match linter {
+        "errcheck" => Some("go_missing_error_check"),
+        "unused" | "ineffassign" => Some("go_unused_variable"),
+        "mnd" | "gomnd" => Some("go_magic_number"),
+        "godox" => Some("go_todo_comment"),
+        "funlen" => Some("go_large_function"),
+        "gochecknoglobals" => Some("go_global_variable"),
+        "nestif" => Some("go_deep_nesting"),
+        _ => None,
+    }
+}
+
+fn relative_to(path: &str, root: &Path) -> String {
+    Path::new(path)
+        .strip_prefix(root)
+        .map(|p| p.display().to_string())
+        .unwrap_or_else(|_| path.to_string())
+}
+
+fn normalize_path(path: &str) -> String {
+    let path = path.replace('\\', "/");
+    path.strip_prefix("./").unwrap_or(&path).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_import_eslint_and_golangci_then_apply() {
+        let eslint = json!([{ "filePath": "/repo/web/app.js", "messages": [
+            { "ruleId": "no-var", "line": 3 },
+            { "ruleId": "eqeqeq", "line": 4 },
+        ] }]);
+        let (mut baseline, summary) = Baseline::from_eslint(&eslint, Path::new("/repo"));
+        assert_eq!((summary.imported, summary.unmapped), (1, 1));
+
+        let golangci = json!({ "Issues": [
+            { "FromLinter": "errcheck", "Pos": { "Filename": "cmd/main.go", "Line": 9 } },
+        ] });
+        let (go, _) = Baseline::from_golangci(&golangci, Path::new("/repo"));
+        baseline.issues.extend(go.issues);
+        let baseline = Baseline::from_json(&baseline.to_json()).unwrap();
+        assert_eq!(baseline.issues.len(), 2);
+
+        let mut report = json!({
+            "summary": { "total_issues": 2 },
+            "files": [{ "path": "./web/app.js", "result": { "total_issues": 2, "issues": [
+                { "rule": "var_usage", "line": 3 },
+                { "rule": "var_usage", "line": 8 },
+            ] } }],
+        });
+        assert_eq!(baseline.apply(&mut report), 1);
+        assert_eq!(report["files"][0]["result"]["issues"][0]["line"], 8);
+        assert_eq!(report["summary"]["total_issues"], 1);
+        assert_eq!(report["summary"]["suppressed"], 1);
+    }
+
+    #[test]
+    fn test_fingerprinted_issues_survive_line_shifts() {
+        let scan = |line: u64| {
+            json!({
+                "summary": { "total_issues": 1 },
+                "files": [{ "path": "src/lib.rs", "result": { "total_issues": 1, "issues": [
+                    { "rule": "unwrap_usage", "line": line, "fingerprint": "abc" },
+                ] } }],
+            })
+        };
+        let baseline = Baseline::from_json(&Baseline::from_report(&scan(3)).to_json()).unwrap();
+
+        let mut moved = scan(7);
+        assert_eq!(baseline.apply(&mut moved), 1);
+        let mut changed = scan(3);
+        changed["files"][0]["result"]["issues"][0]["fingerprint"] = json!("def");
+        assert_eq!(baseline.apply(&mut changed), 0);
+    }
+}
@@ -0,0 +1,260 @@
+use crate::analyzer::{analyze_lines_to_json, analyze_source_to_json, CodeAnalyzer, RuleConfig};
+use crate::cache::ResultCache;
+use crate::git::ChangedLines;
+use crate::language::{AnalyzerKey, SupportedLanguage};
+use crate::position::ColumnUnit;
+use crate::sfc;
+use crate::source::read_source;
+use crate::trace;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Default)]
+pub struct BatchConfig {
+    // Defaults to the available parallelism
+    pub threads: Option<usize>,
+    pub max_issues_per_file: Option<usize>,
+    pub max_file_size: Option<u64>,
+    pub parse_timeout: Option<Duration>,
+    // Reuse results for unchanged files when set
+    pub cache_dir: Option<PathBuf>,
+    pub column_unit: ColumnUnit,
+    pub rules: RuleConfig,
+    // Diff-aware mode: only issues on these lines are reported and scored
+    pub changed_lines: Option<ChangedLines>,
+}
+
+impl BatchConfig {
+    pub fn from_json(config: &Value) -> Self {
+        let as_usize = |key: &str| config.get(key).and_then(Value::as_u64).map(|v| v as usize);
+        BatchConfig {
+            threads: as_usize("threads"),
+            max_issues_per_file: as_usize("max_issues_per_file"),
+            max_file_size: config.get("max_file_size").and_then(Value::as_u64),
+            parse_timeout: config
+                .get("parse_timeout_ms")
+                .and_then(Value::as_u64)
+                .map(Duration::from_millis),
+            cache_dir: config
+                .get("cache_dir")
+                .and_then(Value::as_str)
+                .map(PathBuf::from),
+            column_unit: config
+                .get("column_unit")
+                .and_then(Value::as_str)
+                .and_then(ColumnUnit::from_name)
+                .unwrap_or_default(),
+            rules: RuleConfig::from_json(config),
+            changed_lines: None,
+        }
+    }
+}
+
+// Analyzes every path on a pool of worker threads. Results come back in input
+// order, one entry per path, with either a `result` or an `error` field.
+pub fn analyze_many(paths: &[PathBuf], config: &BatchConfig) -> Value {
+    // One analyzer per language and file kind, shared by all workers so queries compile once
+    let mut analyzers: HashMap<AnalyzerKey, CodeAnalyzer> = HashMap::new();
+    for path in paths {
+        let Some(lang) = SupportedLanguage::detect(path).or_else(|| sfc::detect(path)) else {
+            continue;
+        };
+        let key = AnalyzerKey::new(lang, path, &config.rules);
+        if analyzers.contains_key(&key) {
+            continue;
+        }
+        if let Some(mut analyzer) = lang.analyzer_for_path(path, &config.rules) {
+            if let Some(max_issues) = config.max_issues_per_file {
+                analyzer = analyzer.with_max_issues(max_issues);
+            }
+            if let Some(timeout) = config.parse_timeout {
+                analyzer = analyzer.with_parse_timeout(timeout);
+            }
+            analyzer = analyzer.with_column_unit(config.column_unit);
+            analyzers.insert(key, analyzer);
+        }
+    }
+
+    let threads = config
+        .threads
+        .unwrap_or_else(|| thread::available_parallelism().map_or(1, |n| n.get()))
+        .clamp(1, paths.len().max(1));
+    let next = AtomicUsize::new(0);
+    let results: Mutex<Vec<Value>> = Mutex::new(vec![Value::Null; paths.len()]);
+
+    thread::scope(|scope| {
+        for _ in 0..threads {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                let Some(path) = paths.get(index) else {
+                    break;
+                };
+                let entry = analyze_path(path, &analyzers, config);
+                results.lock().unwrap()[index] = entry;
+            });
+        }
+    });
+
+    Value::Array(results.into_inner().unwrap())
+}
+
+fn analyze_path(
+    path: &PathBuf,
+    analyzers: &HashMap<AnalyzerKey, CodeAnalyzer>,
+    config: &BatchConfig,
+) -> Value {
+    let display = path.display().to_string();
+    let lang = match SupportedLanguage::detect(path).or_else(|| sfc::detect(path)) {
+        Some(lang) => lang,
+        None => return json!({ "path": display, "error": "Unsupported file extension" }),
+    };
+    let analyzer = match analyzers.get(&AnalyzerKey::new(lang, path, &config.rules)) {
+        Some(analyzer) => analyzer,
+        None => {
+            return json!({
+                "path": display,
+                "language": lang.name(),
+                "error": format!("Analysis not supported for language '{}'", lang.name()),
+            })
+        }
+    };
+
+    let _span = trace::span("analyze_file")
+        .attr("path", &display)
+        .attr("language", lang.name());
+    let outcome = read_source(path, config.max_file_size)
+        .map_err(|e| e.to_string())
+        .and_then(|source| {
+            // Component scripts are analyzed in place, the markup around them blanked
+            let view = sfc::is_component(path).then(|| sfc::script_view(&source));
+            let source = view.as_deref().unwrap_or(&source);
+            match &config.changed_lines {
+                Some(changed) => {
+                    let lines = fs::canonicalize(path)
+                        .ok()
+                        .and_then(|path| changed.get(&path).cloned())
+                        .unwrap_or_default();
+                    analyze_lines_to_json(source, &lang.tree_sitter_language(), analyzer, &lines)
+                        .map_err(|e| e.to_string())
+                }
+                None => analyze_cached(source, lang, analyzer, config),
+            }
+        });
+    match outcome {
+        Ok(result) => json!({ "path": display, "language": lang.name(), "result": result }),
+        Err(error) => json!({ "path": display, "language": lang.name(), "error": error }),
+    }
+}
+
+fn analyze_cached(
+    source: &str,
+    lang: SupportedLanguage,
+    analyzer: &CodeAnalyzer,
+    config: &BatchConfig,
+) -> Result<Value, String> {
+    let cache = config.cache_dir.as_ref().map(ResultCache::new);
+    let key = ResultCache::key(source, analyzer);
+    if let Some(cached) = cache.as_ref().and_then(|cache| cache.get(&key)) {
+        if let Ok(result) = serde_json::from_str(&cached) {
+            return Ok(result);
+        }
+    }
+
+    let result = analyze_source_to_json(source, &lang.tree_sitter_language(), analyzer)
+        .map_err(|e| e.to_string())?;
+    if let Some(cache) = &cache {
+        if let Ok(text) = serde_json::to_string_pretty(&result) {
+            // A failed cache write only costs the next run some time
+            let _ = cache.put(&key, &text);
+        }
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    #[test]
+    fn test_analyze_many_preserves_input_order() {
+        let dir = TempDir::new("batch");
+        let rust = dir.write("a.rs", "fn main() { x.unwrap(); }");
+        let go = dir.write("b.go", "package main\nfunc main() { panic(1) }");
+
+        let config = BatchConfig::from_json(&json!({ "threads": 3 }));
+        let results = analyze_many(&[go, rust], &config);
+
+        assert_eq!(results[0]["language"], "Go");
+        let go_rules: Vec<&Value> = results[0]["result"]["issues"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|issue| &issue["rule"])
+            .collect();
+        assert!(go_rules.contains(&&json!("go_panic_usage")));
+        assert_eq!(results[1]["language"], "Rust");
+        assert_eq!(results[1]["result"]["issues"][0]["rule"], "unwrap_usage");
+    }
+
+    #[test]
+    fn test_analyze_many_reports_failures_per_file() {
+        let dir = TempDir::new("batch");
+        let rust = dir.write("a.rs", "fn main() {}");
+        let paths = vec![
+            dir.join("missing.rs"),
+            dir.write("c.zig", "pub fn main() void {}"),
+            dir.write("notes.txt", "not source"),
+            rust.clone(),
+        ];
+
+        let results = analyze_many(&paths, &BatchConfig::default());
+        assert_eq!(results[0]["language"], "Rust");
+        assert!(results[0]["error"].is_string());
+        assert_eq!(
+            results[1]["error"],
+            "Analysis not supported for language 'Zig'"
+        );
+        assert_eq!(results[2]["error"], "Unsupported file extension");
+        assert_eq!(results[3]["path"], rust.display().to_string());
+        assert!(results[3]["result"]["score"].is_number());
+    }
+
+    #[test]
+    fn test_analyze_many_enforces_max_file_size() {
+        let dir = TempDir::new("batch");
+        let paths = vec![dir.write("a.rs", "fn main() { let x = 1; }")];
+
+        let config = BatchConfig::from_json(&json!({ "max_file_size": 4 }));
+        let results = analyze_many(&paths, &config);
+        let error = results[0]["error"].as_str().unwrap();
+        assert!(error.contains("exceeding the maximum file size of 4 bytes"));
+    }
+
+    #[test]
+    fn test_batch_config_from_json() {
+        let config = BatchConfig::from_json(&json!({
+            "threads": 2,
+            "max_issues_per_file": 5,
+            "parse_timeout_ms": 250,
+            "cache_dir": ".cache",
+            "column_unit": "utf16",
+        }));
+        assert_eq!(config.threads, Some(2));
+        assert_eq!(config.max_issues_per_file, Some(5));
+        assert_eq!(config.parse_timeout, Some(Duration::from_millis(250)));
+        assert_eq!(config.cache_dir, Some(PathBuf::from(".cache")));
+        assert_eq!(config.column_unit, ColumnUnit::Utf16);
+
+        // Mistyped values fall back to the defaults
+        let config = BatchConfig::from_json(&json!({ "threads": "two", "column_unit": "bytes" }));
+        assert_eq!(config.threads, None);
+        assert_eq!(config.column_unit, ColumnUnit::default());
+    }
+}
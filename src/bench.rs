@@ -0,0 +1,159 @@
+use crate::ast::{parse_tree, write_ast, AstFormat};
+use crate::language::SupportedLanguage;
+use crate::source::read_source;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::io;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Default)]
+struct LanguageTimings {
+    files: usize,
+    bytes: usize,
+    // Syntax tree nodes, counted once per file rather than per iteration
+    nodes: usize,
+    parse: Duration,
+    query: Duration,
+    serialize: Duration,
+}
+
+// Parses (and, where supported, analyzes) every file `iterations` times and reports
+// per-language timings so releases can be compared
+pub fn run_bench(
+    files: &[PathBuf],
+    iterations: usize,
+) -> Result<Value, Box<dyn std::error::Error>> {
+    let iterations = iterations.max(1);
+    let mut timings: BTreeMap<&'static str, LanguageTimings> = BTreeMap::new();
+
+    for path in files {
+        let lang = match SupportedLanguage::detect(path) {
+            Some(lang) => lang,
+            None => continue,
+        };
+        let source = read_source(path, None)?;
+        let language = lang.tree_sitter_language();
+        let analyzer = lang.analyzer();
+        let entry = timings.entry(lang.name()).or_default();
+        entry.files += 1;
+        entry.bytes += source.len();
+
+        for iteration in 0..iterations {
+            let start = Instant::now();
+            let tree = parse_tree(&source, &language)?;
+            entry.parse += start.elapsed();
+            if iteration == 0 {
+                entry.nodes += tree.root_node().descendant_count();
+            }
+
+            match &analyzer {
+                Some(analyzer) => {
+                    let start = Instant::now();
+                    let results = analyzer.analyze_tree(&tree, &source, &language)?;
+                    entry.query += start.elapsed();
+
+                    let start = Instant::now();
+                    let score = analyzer.calculate_score(&results, &source);
+                    let output = analyzer.format_score_as_json(&results, &score);
+                    serde_json::to_writer(io::sink(), &output)?;
+                    entry.serialize += start.elapsed();
+                }
+                None => {
+                    let start = Instant::now();
+                    write_ast(&tree, &source, AstFormat::Text, &mut io::sink())?;
+                    entry.serialize += start.elapsed();
+                }
+            }
+        }
+    }
+
+    let per_iteration_ms = |total: Duration| total.as_secs_f64() * 1000.0 / iterations as f64;
+    let languages: Vec<Value> = timings
+        .iter()
+        .map(|(name, t)| {
+            json!({
+                "language": name,
+                "files": t.files,
+                "bytes": t.bytes,
+                "nodes": t.nodes,
+                "parse_ms": per_iteration_ms(t.parse),
+                "query_ms": per_iteration_ms(t.query),
+                "serialize_ms": per_iteration_ms(t.serialize),
+            })
+        })
+        .collect();
+
+    Ok(json!({
+        "iterations": iterations,
+        "languages": languages,
+        "peak_rss_bytes": peak_rss_bytes(),
+    }))
+}
+
+#[cfg(unix)]
+fn peak_rss_bytes() -> Option<u64> {
+    let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+    if unsafe { libc::getrusage(libc::RUSAGE_SELF, &mut usage) } != 0 {
+        return None;
+    }
+    let max_rss = usage.ru_maxrss as u64;
+    // Linux reports kilobytes, macOS reports bytes
+    if cfg!(target_os = "macos") {
+        Some(max_rss)
+    } else {
+        Some(max_rss * 1024)
+    }
+}
+
+#[cfg(not(unix))]
+fn peak_rss_bytes() -> Option<u64> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    #[test]
+    fn test_bench_counts_files_and_nodes_per_language() {
+        let dir = TempDir::new("bench");
+        let files = vec![
+            dir.write("a.rs", "fn main() { x.unwrap(); }\n"),
+            dir.write("b.c", "int main(void) { return 0; }\n"),
+        ];
+
+        let report = run_bench(&files, 2).unwrap();
+        assert_eq!(report["iterations"], 2);
+        let languages = report["languages"].as_array().unwrap();
+        let names: Vec<&str> = languages
+            .iter()
+            .map(|l| l["language"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, ["C", "Rust"]);
+        for language in languages {
+            assert_eq!(language["files"], 1);
+            assert!(language["bytes"].as_u64().unwrap() > 0);
+            assert!(language["nodes"].as_u64().unwrap() > 0);
+        }
+    }
+
+    #[test]
+    fn test_bench_skips_unrecognized_files() {
+        let dir = TempDir::new("bench");
+        let files = vec![dir.write("notes.txt", "not source\n")];
+
+        let report = run_bench(&files, 0).unwrap();
+        assert_eq!(report["iterations"], 1);
+        assert_eq!(report["languages"], json!([]));
+    }
+
+    #[test]
+    fn test_bench_fails_on_unreadable_files() {
+        let dir = TempDir::new("bench");
+        assert!(run_bench(&[dir.join("missing.rs")], 1).is_err());
+        let binary = dir.write("binary.rs", [0xff, 0xfe]);
+        assert!(run_bench(&[binary], 1).is_err());
+    }
+}
@@ -0,0 +1,134 @@
+use crate::analyzer::CodeAnalyzer;
+use crate::version::grammar_fingerprint;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub const DEFAULT_CACHE_DIR: &str = ".treescan-cache";
+
+// FNV-1a; std's DefaultHasher is not guaranteed stable across Rust releases,
+// which would silently invalidate (or worse, alias) on-disk entries
+pub fn stable_hash(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in bytes {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+pub struct ResultCache {
+    dir: PathBuf,
+}
+
+impl ResultCache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    // Key covers the file content, the rule set, the grammar versions and the
+    // treescan version
+    pub fn key(source_code: &str, analyzer: &CodeAnalyzer) -> String {
+        format!(
+            "{:016x}-{:016x}-{:08x}-{}",
+            stable_hash(source_code.as_bytes()),
+            analyzer.rules_hash(),
+            stable_hash(grammar_fingerprint().as_bytes()) as u32,
+            env!("CARGO_PKG_VERSION")
+        )
+    }
+
+    pub fn get(&self, key: &str) -> Option<String> {
+        fs::read_to_string(self.entry_path(key)).ok()
+    }
+
+    pub fn put(&self, key: &str, output: &str) -> io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        // Write then rename so a concurrent reader never sees a partial entry
+        let tmp_path = self.dir.join(format!("{}.{}.tmp", key, std::process::id()));
+        fs::write(&tmp_path, output)?;
+        fs::rename(tmp_path, self.entry_path(key))
+    }
+
+    // Returns the number of entries removed
+    pub fn clear(&self) -> io::Result<usize> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e),
+        };
+
+        let mut removed = 0;
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                fs::remove_file(path)?;
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+
+    fn entry_path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    #[test]
+    fn test_key_covers_source_and_rules() {
+        let analyzer = CodeAnalyzer::new_rust_analyzer();
+        let key = ResultCache::key("fn main() {}", &analyzer);
+        assert_eq!(key, ResultCache::key("fn main() {}", &analyzer));
+        assert_ne!(key, ResultCache::key("fn main() { }", &analyzer));
+        assert_ne!(
+            key,
+            ResultCache::key("fn main() {}", &CodeAnalyzer::new_go_analyzer())
+        );
+    }
+
+    #[test]
+    fn test_put_then_get_round_trips() {
+        let dir = TempDir::new("cache");
+        let cache = ResultCache::new(dir.join("nested"));
+        let key = ResultCache::key("fn main() {}", &CodeAnalyzer::new_rust_analyzer());
+
+        assert_eq!(cache.get(&key), None);
+        cache.put(&key, "{}").unwrap();
+        assert_eq!(cache.get(&key).as_deref(), Some("{}"));
+    }
+
+    #[test]
+    fn test_clear_removes_only_entries() {
+        let dir = TempDir::new("cache");
+        let cache = ResultCache::new(dir.to_path_buf());
+        cache.put("a", "{}").unwrap();
+        cache.put("b", "{}").unwrap();
+        dir.write("notes.txt", "kept");
+
+        assert_eq!(cache.clear().unwrap(), 2);
+        assert_eq!(cache.get("a"), None);
+        assert!(dir.join("notes.txt").exists());
+    }
+
+    #[test]
+    fn test_clear_without_cache_dir_removes_nothing() {
+        let dir = TempDir::new("cache");
+        assert_eq!(ResultCache::new(dir.join("missing")).clear().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_put_fails_when_cache_dir_is_a_file() {
+        let dir = TempDir::new("cache");
+        let file = dir.write("cache", "not a directory");
+        assert!(ResultCache::new(file).put("a", "{}").is_err());
+    }
+}
@@ -0,0 +1,167 @@
+use crate::analyzer::{fingerprint, AnalysisResult, Severity};
+use crate::position::ColumnUnit;
+use std::collections::HashSet;
+use tree_sitter::{Node, Tree};
+
+pub const LONG_CALL_CHAIN: &str = "long_call_chain";
+// Stream and iterator pipelines commonly run to five or six calls
+pub const DEFAULT_MAX_CHAIN_LENGTH: usize = 6;
+
+// Node kinds are shared across the bundled grammars, so one table covers them all
+const CALL_KINDS: &[&str] = &["call_expression", "method_invocation"];
+const MEMBER_KINDS: &[&str] = &[
+    "field_expression",
+    "member_expression",
+    "selector_expression",
+];
+// Looked through when following a chain: `?`, `.await`, `!` and parentheses
+const WRAPPER_KINDS: &[&str] = &[
+    "try_expression",
+    "await_expression",
+    "non_null_expression",
+    "parenthesized_expression",
+];
+// The receiver of a member access, by grammar: Rust, JS/TS/Java/Zig, C/C++, Go
+const RECEIVER_FIELDS: &[&str] = &["value", "object", "argument", "operand"];
+
+fn receiver<'tree>(member: &Node<'tree>) -> Option<Node<'tree>> {
+    RECEIVER_FIELDS
+        .iter()
+        .find_map(|field| member.child_by_field_name(field))
+}
+
+// The receiver a call is made on, when the callee is a member access
+fn call_receiver<'tree>(call: &Node<'tree>) -> Option<Node<'tree>> {
+    if call.kind() == "method_invocation" {
+        return call.child_by_field_name("object");
+    }
+    let callee = call.child_by_field_name("function")?;
+    MEMBER_KINDS
+        .contains(&callee.kind())
+        .then(|| receiver(&callee))
+        .flatten()
+}
+
+// The calls making up the chain that ends in `call`, outermost first
+fn chain<'tree>(call: Node<'tree>) -> Vec<Node<'tree>> {
+    let mut links = vec![call];
+    let mut current = call_receiver(&call);
+    while let Some(node) = current {
+        current = if WRAPPER_KINDS.contains(&node.kind()) {
+            node.named_child(0)
+        } else if MEMBER_KINDS.contains(&node.kind()) {
+            // Plain field access between calls (`a.b().c.d()`)
+            receiver(&node)
+        } else if CALL_KINDS.contains(&node.kind()) {
+            match call_receiver(&node) {
+                Some(next) => {
+                    links.push(node);
+                    Some(next)
+                }
+                None => None,
+            }
+        } else {
+            None
+        };
+    }
+    links
+}
+
+// Chains of more than `max` method calls, reported once at the outermost call
+pub fn long_call_chains(
+    tree: &Tree,
+    source: &str,
+    max: usize,
+    unit: ColumnUnit,
+) -> Vec<(usize, AnalysisResult)> {
+    let mut results = Vec::new();
+    let mut seen = HashSet::new();
+    let mut stack = vec![tree.root_node()];
+    while let Some(node) = stack.pop() {
+        if CALL_KINDS.contains(&node.kind()) && !seen.contains(&node.id()) {
+            let links = chain(node);
+            seen.extend(links.iter().map(|link| link.id()));
+            if links.len() > max {
+                let text = node.utf8_text(source.as_bytes()).unwrap_or("");
+                let (line, column) = unit.start_of(&node, source);
+                let severity = Severity::Style;
+                results.push((
+                    node.start_byte(),
+                    AnalysisResult {
+                        rule_name: LONG_CALL_CHAIN.to_string(),
+                        score_impact: severity.base_score_impact(),
+                        severity,
+                        message: format!("Chain of {} method calls (limit {})", links.len(), max),
+                        line,
+                        column,
+                        text: text.to_string(),
+                        suggestion: Some(
+                            "Name intermediate results so each step can be read and debugged"
+                                .to_string(),
+                        ),
+                        category: None,
+                        fingerprint: fingerprint(LONG_CALL_CHAIN, &node, text, source),
+                    },
+                ));
+            }
+        }
+        let mut cursor = node.walk();
+        let children: Vec<Node> = node.named_children(&mut cursor).collect();
+        stack.extend(children.into_iter().rev());
+    }
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::parse_tree;
+    use crate::language::SupportedLanguage;
+
+    #[test]
+    fn test_chain_length_across_languages() {
+        let lengths = |source: &str, lang: SupportedLanguage| {
+            let tree = parse_tree(source, &lang.tree_sitter_language()).unwrap();
+            long_call_chains(&tree, source, 2, ColumnUnit::Byte)
+                .into_iter()
+                .map(|(_, r)| r.message)
+                .collect::<Vec<_>>()
+        };
+        let three = vec!["Chain of 3 method calls (limit 2)".to_string()];
+
+        assert_eq!(
+            lengths(
+                "async fn f() { a.b()?.c().await.d(); x.y(); }",
+                SupportedLanguage::Rust
+            ),
+            three
+        );
+        assert_eq!(
+            lengths("a?.b().c!.d().e(f.g().h());", SupportedLanguage::TypeScript),
+            three
+        );
+        assert_eq!(
+            lengths(
+                "package m\nfunc f() { a.B().C().D() }\n",
+                SupportedLanguage::Go
+            ),
+            three
+        );
+        assert_eq!(
+            lengths(
+                "class A { void f() { a.b().c().d(); } }",
+                SupportedLanguage::Java
+            ),
+            three
+        );
+        assert_eq!(
+            lengths("void f() { a.b().c()->d(); }", SupportedLanguage::Cpp),
+            three
+        );
+        // A chain passed as an argument is its own chain
+        assert_eq!(
+            lengths("a.b(x.c().d().e()).f();", SupportedLanguage::JavaScript),
+            three
+        );
+    }
+}
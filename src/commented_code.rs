@@ -0,0 +1,168 @@
+use crate::analyzer::{fingerprint, AnalysisResult, Severity};
+use crate::fragment::parse_fragment;
+use crate::language::SupportedLanguage;
+use crate::position::ColumnUnit;
+use tree_sitter::{Node, Tree};
+
+pub const COMMENTED_OUT_CODE: &str = "commented_out_code";
+// A single commented line is as often an example or a note as it is dead code
+pub const MIN_COMMENTED_LINES: usize = 2;
+
+const COMMENT_KINDS: &[&str] = &["comment", "line_comment", "block_comment"];
+// Doc comments hold examples on purpose
+const DOC_PREFIXES: &[&str] = &["///", "//!", "/**", "/*!"];
+// Prose that happens to parse (a run of bare words) has none of these
+const CODE_PUNCTUATION: &[char] = &[';', '{', '}', '(', ')', '='];
+
+// Comment blocks of at least `MIN_COMMENTED_LINES` lines that parse as code in `lang`.
+// Consecutive `//` lines on their own make one block; trailing comments are ignored.
+pub fn commented_out_code(
+    tree: &Tree,
+    source: &str,
+    lang: SupportedLanguage,
+    unit: ColumnUnit,
+) -> Vec<(usize, AnalysisResult)> {
+    let mut results = Vec::new();
+    for group in comment_groups(tree, source) {
+        let (first, last) = (group[0], group[group.len() - 1]);
+        let content = uncomment(&group, source);
+        let lines = content.lines().filter(|l| !l.trim().is_empty()).count();
+        if lines < MIN_COMMENTED_LINES || !content.contains(CODE_PUNCTUATION) {
+            continue;
+        }
+        let parses = parse_fragment(&content, lang, None)
+            .is_ok_and(|fragment| !fragment.tree.root_node().has_error());
+        if !parses {
+            continue;
+        }
+        let text = &source[first.start_byte()..last.end_byte()];
+        let (line, column) = unit.start_of(&first, source);
+        let severity = Severity::Style;
+        results.push((
+            first.start_byte(),
+            AnalysisResult {
+                rule_name: COMMENTED_OUT_CODE.to_string(),
+                score_impact: severity.base_score_impact(),
+                severity,
+                message: format!("{} lines of commented-out code", lines),
+                line,
+                column,
+                text: text.to_string(),
+                suggestion: Some("Delete it; version control keeps the old code".to_string()),
+                category: None,
+                fingerprint: fingerprint(COMMENTED_OUT_CODE, &first, text, source),
+            },
+        ));
+    }
+    results
+}
+
+// Non-doc comments that start their line, with runs of `//` comments on consecutive
+// lines merged into one group
+fn comment_groups<'tree>(tree: &'tree Tree, source: &str) -> Vec<Vec<Node<'tree>>> {
+    let mut groups: Vec<Vec<Node>> = Vec::new();
+    let mut stack = vec![tree.root_node()];
+    while let Some(node) = stack.pop() {
+        if COMMENT_KINDS.contains(&node.kind()) {
+            let text = node.utf8_text(source.as_bytes()).unwrap_or("");
+            let line_start = source[..node.start_byte()].rfind('\n').map_or(0, |i| i + 1);
+            let own_line = source[line_start..node.start_byte()].trim().is_empty();
+            if own_line && !DOC_PREFIXES.iter().any(|p| text.starts_with(p)) {
+                let row = node.start_position().row;
+                let joins = text.starts_with("//")
+                    && groups.last().and_then(|g| g.last()).is_some_and(|prev| {
+                        let prev_text = prev.utf8_text(source.as_bytes()).unwrap_or("");
+                        prev_text.starts_with("//") && prev.start_position().row + 1 == row
+                    });
+                match groups.last_mut() {
+                    Some(group) if joins => group.push(node),
+                    _ => groups.push(vec![node]),
+                }
+            }
+            continue;
+        }
+        let mut cursor = node.walk();
+        let children: Vec<Node> = node.children(&mut cursor).collect();
+        stack.extend(children.into_iter().rev());
+    }
+    groups
+}
+
+// The group's text with the comment markers taken off, line structure kept
+fn uncomment(group: &[Node], source: &str) -> String {
+    let text = |n: &Node| n.utf8_text(source.as_bytes()).unwrap_or("");
+    if let [block] = group {
+        let body = text(block);
+        if let Some(body) = body.strip_prefix("/*") {
+            let body = body.strip_suffix("*/").unwrap_or(body);
+            let lines: Vec<&str> = body.lines().collect();
+            // Leading `*` gutters, when every continuation line has one
+            let gutter = lines.len() > 1
+                && lines[1..]
+                    .iter()
+                    .all(|l| l.trim().is_empty() || l.trim_start().starts_with('*'));
+            let lines: Vec<&str> = lines
+                .into_iter()
+                .map(|l| match gutter {
+                    true => l.trim_start().strip_prefix('*').unwrap_or(l),
+                    false => l,
+                })
+                .collect();
+            return lines.join("\n");
+        }
+    }
+    let lines: Vec<&str> = group
+        .iter()
+        .map(|n| text(n).trim_end().strip_prefix("//").unwrap_or(""))
+        .collect();
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::parse_tree;
+
+    #[test]
+    fn test_commented_out_code_blocks() {
+        let found = |source: &str, lang: SupportedLanguage| {
+            let tree = parse_tree(source, &lang.tree_sitter_language()).unwrap();
+            commented_out_code(&tree, source, lang, ColumnUnit::Byte)
+                .into_iter()
+                .map(|(_, r)| (r.line, r.message))
+                .collect::<Vec<_>>()
+        };
+        let at = |line: usize, message: &str| (line, message.to_string());
+
+        assert_eq!(
+            found(
+                "fn f() {\n    // let x = g();\n    // x.unwrap()\n    h(); // h(1);\n    // Retry once, then give up\n    // (see the issue)\n}\n/// ```\n/// f();\n/// f();\n/// ```\nfn g() {}\n",
+                SupportedLanguage::Rust
+            ),
+            vec![at(2, "2 lines of commented-out code")]
+        );
+        assert_eq!(
+            found(
+                "package m\n/*\n * if err != nil {\n *     return err\n * }\n */\nfunc f() {}\n",
+                SupportedLanguage::Go
+            ),
+            vec![at(2, "3 lines of commented-out code")]
+        );
+        assert_eq!(
+            found(
+                "// Render\n// the list\nconst a = 1;\n// render(items);\n",
+                SupportedLanguage::JavaScript
+            ),
+            vec![]
+        );
+
+        // Wired into the analyzers of grammars without a name too
+        let results = crate::analyzer::CodeAnalyzer::new_javascript_analyzer()
+            .analyze(
+                "// const x = load();\n// render(x);\nrun();\n",
+                &SupportedLanguage::JavaScript.tree_sitter_language(),
+            )
+            .unwrap();
+        assert!(results.iter().any(|r| r.rule_name == COMMENTED_OUT_CODE));
+    }
+}
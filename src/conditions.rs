@@ -0,0 +1,195 @@
+use crate::analyzer::{fingerprint, AnalysisResult, Severity};
+use crate::position::ColumnUnit;
+use tree_sitter::{Node, Tree};
+
+pub const NESTED_TERNARY: &str = "nested_ternary";
+pub const COMPLEX_BOOLEAN_EXPRESSION: &str = "complex_boolean_expression";
+// Three operators is four conditions to keep in your head at once
+pub const DEFAULT_MAX_BOOLEAN_OPERATORS: usize = 3;
+
+// JS/TS/Java call it a ternary, C/C++ a conditional
+const TERNARY_KINDS: &[&str] = &["ternary_expression", "conditional_expression"];
+// Zig spells the operators out
+const BOOLEAN_OPERATORS: &[&str] = &["&&", "||", "and", "or"];
+// Looked through when measuring an expression: parentheses and negation
+const TRANSPARENT_KINDS: &[&str] = &[
+    "parenthesized_expression",
+    "unary_expression",
+    "not_expression",
+];
+
+fn is_ternary(node: &Node) -> bool {
+    TERNARY_KINDS.contains(&node.kind())
+}
+
+fn is_boolean(node: &Node) -> bool {
+    node.kind() == "binary_expression"
+        && node
+            .child_by_field_name("operator")
+            .is_some_and(|op| BOOLEAN_OPERATORS.contains(&op.kind()))
+}
+
+// The nearest ancestor that isn't parentheses or negation
+fn enclosing<'tree>(node: &Node<'tree>) -> Option<Node<'tree>> {
+    let mut parent = node.parent()?;
+    while TRANSPARENT_KINDS.contains(&parent.kind()) {
+        parent = parent.parent()?;
+    }
+    Some(parent)
+}
+
+// The operands of a ternary or boolean operator, with parentheses and negation
+// looked through
+fn operands<'tree>(node: &Node<'tree>) -> Vec<Node<'tree>> {
+    let mut cursor = node.walk();
+    let mut operands = Vec::new();
+    let mut pending: Vec<Node> = node.named_children(&mut cursor).collect();
+    while let Some(operand) = pending.pop() {
+        if TRANSPARENT_KINDS.contains(&operand.kind()) {
+            let mut cursor = operand.walk();
+            pending.extend(operand.named_children(&mut cursor));
+        } else {
+            operands.push(operand);
+        }
+    }
+    operands
+}
+
+fn boolean_operator_count(node: &Node) -> usize {
+    1 + operands(node)
+        .iter()
+        .filter(|operand| is_boolean(operand))
+        .map(boolean_operator_count)
+        .sum::<usize>()
+}
+
+fn finding(
+    rule: &str,
+    node: &Node,
+    source: &str,
+    message: String,
+    suggestion: &str,
+    unit: ColumnUnit,
+) -> (usize, AnalysisResult) {
+    let text = node.utf8_text(source.as_bytes()).unwrap_or("");
+    let (line, column) = unit.start_of(node, source);
+    let severity = Severity::Style;
+    (
+        node.start_byte(),
+        AnalysisResult {
+            rule_name: rule.to_string(),
+            score_impact: severity.base_score_impact(),
+            severity,
+            message,
+            line,
+            column,
+            text: text.to_string(),
+            suggestion: Some(suggestion.to_string()),
+            category: None,
+            fingerprint: fingerprint(rule, node, text, source),
+        },
+    )
+}
+
+// Ternaries with another ternary as an operand (with `ternaries`), reported once at the
+// outermost, and `&&`/`||` expressions with more than `max_operators` operators (when set)
+pub fn complex_conditions(
+    tree: &Tree,
+    source: &str,
+    ternaries: bool,
+    max_operators: Option<usize>,
+    unit: ColumnUnit,
+) -> Vec<(usize, AnalysisResult)> {
+    let mut results = Vec::new();
+    let mut stack = vec![tree.root_node()];
+    while let Some(node) = stack.pop() {
+        let outermost = |same: fn(&Node) -> bool| !enclosing(&node).is_some_and(|p| same(&p));
+        if ternaries && is_ternary(&node) && outermost(is_ternary) {
+            let depth = ternary_depth(&node);
+            if depth > 1 {
+                results.push(finding(
+                    NESTED_TERNARY,
+                    &node,
+                    source,
+                    format!("Ternary nested {} levels deep", depth),
+                    "Use if/else, a lookup table or a named helper instead",
+                    unit,
+                ));
+            }
+        }
+        if let Some(max) = max_operators.filter(|_| is_boolean(&node) && outermost(is_boolean)) {
+            let count = boolean_operator_count(&node);
+            if count > max {
+                results.push(finding(
+                    COMPLEX_BOOLEAN_EXPRESSION,
+                    &node,
+                    source,
+                    format!(
+                        "Boolean expression with {} operators (limit {})",
+                        count, max
+                    ),
+                    "Name parts of the condition with well-named variables or helper functions",
+                    unit,
+                ));
+            }
+        }
+        let mut cursor = node.walk();
+        let children: Vec<Node> = node.named_children(&mut cursor).collect();
+        stack.extend(children.into_iter().rev());
+    }
+    results
+}
+
+fn ternary_depth(node: &Node) -> usize {
+    1 + operands(node)
+        .iter()
+        .filter(|operand| is_ternary(operand))
+        .map(ternary_depth)
+        .max()
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::parse_tree;
+    use crate::language::SupportedLanguage;
+
+    #[test]
+    fn test_nested_ternaries_and_boolean_operator_counts() {
+        let found = |source: &str, lang: SupportedLanguage| {
+            let tree = parse_tree(source, &lang.tree_sitter_language()).unwrap();
+            complex_conditions(&tree, source, true, Some(2), ColumnUnit::Byte)
+                .into_iter()
+                .map(|(_, r)| (r.line, r.message))
+                .collect::<Vec<_>>()
+        };
+        let at = |line: usize, message: &str| (line, message.to_string());
+
+        assert_eq!(
+            found(
+                "const a = x ? 1 : y ? 2 : (z ? 3 : 4);\nconst b = x ? 1 : 2;\nconst c = f(x ? 1 : 2) ? 3 : 4;\n\
+                 if (a && !(b || c) && d) {}\nif (a && b || c) {}\n",
+                SupportedLanguage::TypeScript
+            ),
+            vec![
+                at(1, "Ternary nested 3 levels deep"),
+                at(4, "Boolean expression with 3 operators (limit 2)"),
+            ]
+        );
+        assert_eq!(
+            found(
+                "fn f() { if a && (b || c) && d.iter().any(|x| x.p && x.q) {} }",
+                SupportedLanguage::Rust
+            ),
+            vec![at(1, "Boolean expression with 3 operators (limit 2)")]
+        );
+        assert_eq!(
+            found(
+                "fn f() void { if (a and b or c and d) {} }",
+                SupportedLanguage::Zig
+            ),
+            vec![at(1, "Boolean expression with 3 operators (limit 2)")]
+        );
+    }
+}
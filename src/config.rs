@@ -0,0 +1,559 @@
+use crate::analyzer::{RatingScale, RuleConfig, Severity, TestCodePolicy};
+use crate::config_layers::{merge, EnvLayer};
+use crate::language::SupportedLanguage;
+use crate::naming::{NameKind, NamingRule};
+use crate::overrides::PathOverride;
+use crate::script::ScriptRule;
+use serde_json::{json, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Project settings file, looked up from the working directory (or an editor's
+// workspace root) towards the filesystem root
+pub const CONFIG_FILE_NAME: &str = ".treescan.json";
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProjectConfig {
+    pub rules: RuleConfig,
+    pub max_issues_per_file: Option<usize>,
+    // Findings of one rule shown per file before the rest are summarized (see `overflow`)
+    pub max_findings_per_rule: Option<usize>,
+    pub include_generated: bool,
+    // Forces `.h` files to "c" or "cpp" instead of guessing from their contents
+    pub header_language: Option<SupportedLanguage>,
+    // Template for the `license` command; `{year}` matches any year
+    pub license_header: Option<String>,
+    // Findings at these severities make `analyze` exit 1, whatever the score
+    pub fail_on: Vec<Severity>,
+}
+
+// Only C and C++ share the `.h` extension
+pub fn header_language(name: &str) -> Option<SupportedLanguage> {
+    SupportedLanguage::from_name(name)
+        .filter(|lang| matches!(lang, SupportedLanguage::C | SupportedLanguage::Cpp))
+}
+
+impl ProjectConfig {
+    pub fn from_json(config: &Value) -> Self {
+        ProjectConfig {
+            rules: RuleConfig {
+                overrides: config
+                    .get("overrides")
+                    .and_then(|overrides| PathOverride::list_from_json(overrides).ok())
+                    .unwrap_or_default(),
+                ..config
+                    .get("rules")
+                    .map(RuleConfig::from_json)
+                    .unwrap_or_default()
+            },
+            max_issues_per_file: config
+                .get("max_issues_per_file")
+                .and_then(Value::as_u64)
+                .map(|v| v as usize),
+            max_findings_per_rule: config
+                .get("max_findings_per_rule")
+                .and_then(Value::as_u64)
+                .map(|v| v as usize),
+            include_generated: config
+                .get("include_generated")
+                .and_then(Value::as_bool)
+                .unwrap_or(false),
+            header_language: config
+                .get("header_language")
+                .and_then(Value::as_str)
+                .and_then(header_language),
+            license_header: config
+                .get("license_header")
+                .and_then(Value::as_str)
+                .map(str::to_string),
+            fail_on: config
+                .get("fail_on")
+                .and_then(Value::as_array)
+                .map(|names| {
+                    names
+                        .iter()
+                        .filter_map(|name| name.as_str().and_then(Severity::from_name))
+                        .collect()
+                })
+                .unwrap_or_default(),
+        }
+    }
+
+    // `file`'s settings with `env`'s on top, checked as a whole. A problem with a
+    // setting the environment gave names the variable.
+    pub fn from_layers(file: &Value, env: &EnvLayer) -> Result<Self, String> {
+        let mut value = file.clone();
+        merge(&mut value, &env.settings);
+        if let Some(problem) = config_problems(&value).into_iter().next() {
+            return Err(match env.source_of(&problem.pointer) {
+                Some(var) => format!("{}: {}", var, problem.message),
+                None => problem.message,
+            });
+        }
+        Ok(Self::from_json(&value))
+    }
+
+    pub fn load(path: &Path, env: &EnvLayer) -> Result<Self, Box<dyn std::error::Error>> {
+        let value = read_config(path)?;
+        Ok(Self::from_layers(&value, env)
+            .map_err(|e| format!("Invalid config '{}': {}", path.display(), e))?)
+    }
+
+    // Loads the nearest config at or above `start` with the `TREESCAN_*` environment
+    // variables on top; no file means defaults
+    pub fn discover(start: &Path) -> Result<(Option<PathBuf>, Self), Box<dyn std::error::Error>> {
+        let env = EnvLayer::from_env()?;
+        match find_config_file(start) {
+            Some(path) => {
+                let config = Self::load(&path, &env)?;
+                Ok((Some(path), config))
+            }
+            None => Ok((None, Self::from_layers(&json!({}), &env)?)),
+        }
+    }
+}
+
+// A setting the config gets wrong, at a JSON pointer into the file (`/rules/min_severity`;
+// empty for the whole document)
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigProblem {
+    pub pointer: String,
+    pub message: String,
+}
+
+fn problem(pointer: &str, message: impl Into<String>) -> ConfigProblem {
+    ConfigProblem {
+        pointer: pointer.to_string(),
+        message: message.into(),
+    }
+}
+
+// `~` and `/` are escaped in JSON pointer segments
+pub fn pointer_segment(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+// The settings a config file can have, and those under `rules` (which overrides can
+// also set)
+const SETTINGS: &[&str] = &[
+    "max_issues_per_file",
+    "max_findings_per_rule",
+    "include_generated",
+    "header_language",
+    "license_header",
+    "fail_on",
+    "rules",
+    "overrides",
+];
+const RULE_SETTINGS: &[&str] = &[
+    "magic_number_allowlist",
+    "magic_numbers_in_tests",
+    "query_dirs",
+    "remediation_minutes",
+    "ratings",
+    "in_tests",
+    "injections",
+    "macro_bodies",
+    "plugins",
+    "scripts",
+    "packs",
+    "min_severity",
+    "max_line_length",
+    "max_file_lines",
+    "max_chain_length",
+    "max_boolean_operators",
+    "naming",
+    "locale",
+];
+
+// Everything `ProjectConfig::load` rejects a parsed config for, not only the first
+pub fn config_problems(value: &Value) -> Vec<ConfigProblem> {
+    let Some(settings) = value.as_object() else {
+        return vec![problem("", "expected a JSON object")];
+    };
+    let mut problems = Vec::new();
+    // A rules setting written at the top level is suggested under `rules`
+    let known: Vec<(&str, String)> = SETTINGS
+        .iter()
+        .map(|key| (*key, key.to_string()))
+        .chain(
+            RULE_SETTINGS
+                .iter()
+                .map(|key| (*key, format!("rules.{}", key))),
+        )
+        .collect();
+    for key in settings.keys() {
+        if !SETTINGS.contains(&key.as_str()) {
+            problems.push(unknown_setting("", key, key, &known));
+        }
+    }
+    if value
+        .get("max_issues_per_file")
+        .is_some_and(|max| max.as_u64().is_none())
+    {
+        problems.push(problem(
+            "/max_issues_per_file",
+            "max_issues_per_file must be a whole number",
+        ));
+    }
+    if let Some(max) = value.get("max_findings_per_rule") {
+        if max.as_u64().is_none_or(|max| max == 0) {
+            problems.push(problem(
+                "/max_findings_per_rule",
+                "max_findings_per_rule must be a positive integer",
+            ));
+        }
+    }
+    if value
+        .get("include_generated")
+        .is_some_and(|v| !v.is_boolean())
+    {
+        problems.push(problem(
+            "/include_generated",
+            "include_generated must be true or false",
+        ));
+    }
+    if let Some(lang) = value.get("header_language") {
+        if lang.as_str().and_then(header_language).is_none() {
+            problems.push(problem(
+                "/header_language",
+                "header_language must be c or cpp",
+            ));
+        }
+    }
+    if let Some(header) = value.get("license_header") {
+        if header.as_str().is_none_or(|h| h.trim().is_empty()) {
+            problems.push(problem(
+                "/license_header",
+                "license_header must be a non-empty string",
+            ));
+        }
+    }
+    if let Some(fail_on) = value.get("fail_on") {
+        let valid = fail_on.as_array().is_some_and(|names| {
+            names
+                .iter()
+                .all(|name| name.as_str().and_then(Severity::from_name).is_some())
+        });
+        if !valid {
+            problems.push(problem(
+                "/fail_on",
+                "fail_on must be a list of error, warning, info or style",
+            ));
+        }
+    }
+    if let Some(rules) = value.get("rules") {
+        match rules.as_object() {
+            Some(_) => problems.extend(rule_problems(rules, "/rules", "rules.")),
+            None => problems.push(problem("/rules", "rules must be an object")),
+        }
+    }
+    if let Some(overrides) = value.get("overrides") {
+        match PathOverride::list_from_json(overrides) {
+            Ok(overrides) => {
+                for path_override in &overrides {
+                    let pointer = format!("/overrides/{}", pointer_segment(&path_override.pattern));
+                    for mut found in rule_problems(&path_override.rules, &pointer, "") {
+                        found.message =
+                            format!("override '{}': {}", path_override.pattern, found.message);
+                        problems.push(found);
+                    }
+                }
+            }
+            Err(e) => problems.push(problem("/overrides", e)),
+        }
+    }
+    problems
+}
+
+// The problems with an object of rule settings at `pointer`: `rules`, or an override.
+// `names` is what setting names are shown under ("rules." or nothing).
+fn rule_problems(rules: &Value, pointer: &str, names: &str) -> Vec<ConfigProblem> {
+    let mut problems = Vec::new();
+    let at = |key: &str| format!("{}/{}", pointer, key);
+    let known: Vec<(&str, String)> = RULE_SETTINGS
+        .iter()
+        .map(|key| (*key, format!("{}{}", names, key)))
+        .collect();
+    for key in rules.as_object().into_iter().flat_map(|rules| rules.keys()) {
+        if !RULE_SETTINGS.contains(&key.as_str()) {
+            problems.push(unknown_setting(
+                pointer,
+                key,
+                &format!("{}{}", names, key),
+                &known,
+            ));
+        }
+    }
+    if let Some(ratings) = rules.get("ratings") {
+        if let Err(e) = RatingScale::from_json(ratings) {
+            problems.push(problem(&at("ratings"), e));
+        }
+    }
+    if let Some(locale) = rules.get("locale") {
+        if locale.as_str().is_none_or(|l| l.trim().is_empty()) {
+            problems.push(problem(&at("locale"), "locale must be a non-empty string"));
+        }
+    }
+    if let Some(min) = rules.get("min_severity") {
+        if min.as_str().and_then(Severity::from_name).is_none() {
+            problems.push(problem(
+                &at("min_severity"),
+                "min_severity must be error, warning, info or style",
+            ));
+        }
+    }
+    for key in ["max_line_length", "max_file_lines"] {
+        if let Some(max) = rules.get(key) {
+            if max.as_u64().is_none_or(|n| n == 0) {
+                problems.push(problem(
+                    &at(key),
+                    format!("{} must be a positive integer", key),
+                ));
+            }
+        }
+    }
+    // 0 turns these off
+    for key in ["max_chain_length", "max_boolean_operators"] {
+        if rules.get(key).is_some_and(|max| max.as_u64().is_none()) {
+            problems.push(problem(&at(key), format!("{} must be a whole number", key)));
+        }
+    }
+    for key in ["magic_numbers_in_tests", "injections", "macro_bodies"] {
+        if rules.get(key).is_some_and(|flag| !flag.is_boolean()) {
+            problems.push(problem(&at(key), format!("{} must be true or false", key)));
+        }
+    }
+    for key in ["query_dirs", "packs", "plugins"] {
+        if let Some(list) = rules.get(key) {
+            let valid = list
+                .as_array()
+                .is_some_and(|items| items.iter().all(Value::is_string));
+            if !valid {
+                problems.push(problem(
+                    &at(key),
+                    format!("{} must be a list of strings", key),
+                ));
+            }
+        }
+    }
+    if let Some(allowlist) = rules.get("magic_number_allowlist") {
+        let valid = allowlist
+            .as_array()
+            .is_some_and(|items| items.iter().all(|v| v.is_number() || v.is_string()));
+        if !valid {
+            problems.push(problem(
+                &at("magic_number_allowlist"),
+                "magic_number_allowlist must be a list of numbers",
+            ));
+        }
+    }
+    if let Some(minutes) = rules.get("remediation_minutes") {
+        match minutes.as_object() {
+            Some(minutes) => {
+                for (rule, m) in minutes {
+                    if m.as_u64().and_then(|m| u32::try_from(m).ok()).is_none() {
+                        problems.push(problem(
+                            &format!("{}/{}", at("remediation_minutes"), pointer_segment(rule)),
+                            format!(
+                                "remediation_minutes.{} must be a whole number of minutes",
+                                rule
+                            ),
+                        ));
+                    }
+                }
+            }
+            None => problems.push(problem(
+                &at("remediation_minutes"),
+                "remediation_minutes must map rules to minutes",
+            )),
+        }
+    }
+    if let Some(in_tests) = rules.get("in_tests") {
+        match in_tests.as_object() {
+            Some(in_tests) => {
+                for (rule, policy) in in_tests {
+                    let valid = match TestCodePolicy::from_json(policy) {
+                        Some(TestCodePolicy::Weight(weight)) => weight >= 0.0,
+                        other => other.is_some(),
+                    };
+                    if !valid {
+                        problems.push(problem(
+                            &format!("{}/{}", at("in_tests"), pointer_segment(rule)),
+                            format!(
+                                "in_tests.{} must be \"off\", \"on\" or a weight of 0 or more",
+                                rule
+                            ),
+                        ));
+                    }
+                }
+            }
+            None => problems.push(problem(
+                &at("in_tests"),
+                "in_tests must map rules to \"off\", \"on\" or a weight",
+            )),
+        }
+    }
+    if let Some(naming) = rules.get("naming") {
+        if let Err(e) = check_naming(naming) {
+            problems.push(problem(&at("naming"), e));
+        }
+    }
+    if let Some(scripts) = rules.get("scripts") {
+        match scripts.as_array() {
+            Some(scripts) => {
+                for (i, script) in scripts.iter().enumerate() {
+                    if let Err(e) = ScriptRule::from_json(script) {
+                        problems.push(problem(&format!("{}/{}", at("scripts"), i), e));
+                    }
+                }
+            }
+            None => problems.push(problem(&at("scripts"), "scripts must be a list")),
+        }
+    }
+    problems
+}
+
+// "unknown setting 'name'", suggesting the `known` setting (key, name as shown) whose
+// key is closest to `key`, if it is close enough to be a typo or a setting at the
+// wrong level
+fn unknown_setting(
+    pointer: &str,
+    key: &str,
+    name: &str,
+    known: &[(&str, String)],
+) -> ConfigProblem {
+    let closest = known
+        .iter()
+        .map(|(candidate, shown)| (edit_distance(key, candidate), shown))
+        .filter(|(distance, _)| *distance <= (key.chars().count() / 3).max(1))
+        .min_by_key(|(distance, _)| *distance);
+    problem(
+        &format!("{}/{}", pointer, pointer_segment(key)),
+        match closest {
+            Some((_, shown)) => format!("unknown setting '{}' (did you mean '{}'?)", name, shown),
+            None => format!("unknown setting '{}'", name),
+        },
+    )
+}
+
+// Levenshtein distance, counting characters
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = (diagonal + usize::from(ca != *cb))
+                .min(row[j] + 1)
+                .min(above + 1);
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
+
+// `naming` maps language names to {kind: pattern} objects
+fn check_naming(naming: &Value) -> Result<(), String> {
+    let languages = naming.as_object().ok_or("naming must be an object")?;
+    for (lang, kinds) in languages {
+        SupportedLanguage::from_name(lang)
+            .ok_or_else(|| format!("naming: unknown language '{}'", lang))?;
+        let kinds = kinds
+            .as_object()
+            .ok_or_else(|| format!("naming.{} must be an object", lang))?;
+        for (kind, pattern) in kinds {
+            let kind = NameKind::from_name(kind).ok_or_else(|| {
+                format!(
+                    "naming.{}: unknown kind '{}' (expected function, type or constant)",
+                    lang, kind
+                )
+            })?;
+            match pattern {
+                Value::Null => {}
+                Value::String(p) if p.is_empty() => {}
+                Value::String(p) => {
+                    NamingRule::new(kind, p)
+                        .map_err(|e| format!("naming.{}.{}: {}", lang, kind.as_str(), e))?;
+                }
+                _ => return Err(format!("naming.{}.{} must be a string", lang, kind.as_str())),
+            }
+        }
+    }
+    Ok(())
+}
+
+// The config file as written, before any checks
+pub fn read_config(path: &Path) -> Result<Value, Box<dyn std::error::Error>> {
+    let text = fs::read_to_string(path)?;
+    Ok(serde_json::from_str(&text)
+        .map_err(|e| format!("Invalid config '{}': {}", path.display(), e))?)
+}
+
+pub fn find_config_file(start: &Path) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .map(|dir| dir.join(CONFIG_FILE_NAME))
+        .find(|candidate| candidate.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    #[test]
+    fn test_discover_nearest_config() {
+        let root = TempDir::new("config");
+        let config =
+            json!({ "max_issues_per_file": 5, "rules": { "magic_number_allowlist": [0, 1, 60] } });
+        let path = root.write(CONFIG_FILE_NAME, config.to_string());
+        let nested = root.join("src/pkg");
+        fs::create_dir_all(&nested).unwrap();
+
+        let (found, config) = ProjectConfig::discover(&nested).unwrap();
+        assert_eq!(found, Some(path));
+        assert_eq!(config.max_issues_per_file, Some(5));
+        assert_eq!(config.rules.magic_number_allowlist, vec!["0", "1", "60"]);
+        assert!(!config.rules.magic_numbers_in_tests);
+    }
+
+    #[test]
+    fn test_inner_config_wins() {
+        let root = TempDir::new("config");
+        root.write(CONFIG_FILE_NAME, r#"{ "max_issues_per_file": 5 }"#);
+        let inner = root.write(
+            &format!("src/{}", CONFIG_FILE_NAME),
+            r#"{ "max_issues_per_file": 9 }"#,
+        );
+
+        let (found, config) = ProjectConfig::discover(&root.join("src")).unwrap();
+        assert_eq!(found, Some(inner));
+        assert_eq!(config.max_issues_per_file, Some(9));
+    }
+
+    #[test]
+    fn test_load_rejects_invalid_configs() {
+        let root = TempDir::new("config");
+        let load = |text: &str| {
+            let path = root.write(CONFIG_FILE_NAME, text);
+            ProjectConfig::load(&path, &EnvLayer::default())
+                .err()
+                .map(|e| e.to_string())
+                .unwrap_or_default()
+        };
+        assert!(load("[]").starts_with("Invalid config"));
+        assert!(load("{ \"max_issues_per_file\": ").starts_with("Invalid config"));
+        assert!(load(r#"{ "max_issues_per_file": "five" }"#).contains("max_issues_per_file"));
+        assert!(load(r#"{ "max_isues_per_file": 5 }"#).contains("max_issues_per_file"));
+    }
+
+    #[test]
+    fn test_read_config_reports_missing_files() {
+        let root = TempDir::new("config");
+        assert!(read_config(&root.join(CONFIG_FILE_NAME)).is_err());
+        assert_eq!(find_config_file(&root.join(CONFIG_FILE_NAME)), None);
+    }
+}
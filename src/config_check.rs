@@ -0,0 +1,314 @@
+use crate::config::{config_problems, ConfigProblem};
+use crate::language::SupportedLanguage;
+use crate::plugin::load_plugin;
+use crate::query_rules::load_query_rules;
+use crate::rule_pack::load_installed;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// A config problem with the 1-based line and column of the setting at fault
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocatedProblem {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+// Everything wrong with the config at `path`, each at its place in the file: what
+// `ProjectConfig::load` checks plus the query directories (every language's lints file
+// is compiled against its grammar), rule packs and plugins it refers to. Err when the
+// file can't be read.
+pub fn check_config(path: &Path) -> Result<Vec<LocatedProblem>, String> {
+    let text = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let value: Value = match serde_json::from_str(&text) {
+        Ok(value) => value,
+        Err(e) => {
+            return Ok(vec![LocatedProblem {
+                line: e.line(),
+                column: e.column(),
+                message: e.to_string(),
+            }])
+        }
+    };
+    let mut problems = config_problems(&value);
+    problems.extend(referenced_file_problems(&value));
+    let offsets = value_offsets(&text);
+    Ok(problems
+        .into_iter()
+        .map(|problem| {
+            let (line, column) = locate(&text, &offsets, &problem.pointer);
+            LocatedProblem {
+                line,
+                column,
+                message: problem.message,
+            }
+        })
+        .collect())
+}
+
+fn referenced_file_problems(value: &Value) -> Vec<ConfigProblem> {
+    let strings = |pointer: &str| -> Vec<(String, String)> {
+        value
+            .pointer(pointer)
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .enumerate()
+            .filter_map(|(i, v)| Some((format!("{}/{}", pointer, i), v.as_str()?.to_string())))
+            .collect()
+    };
+    let mut problems = Vec::new();
+    let mut problem =
+        |pointer: String, message: String| problems.push(ConfigProblem { pointer, message });
+    for (pointer, dir) in strings("/rules/query_dirs") {
+        let dir = PathBuf::from(dir);
+        if !dir.is_dir() {
+            problem(
+                pointer,
+                format!("query directory '{}' does not exist", dir.display()),
+            );
+            continue;
+        }
+        for language in SupportedLanguage::ALL {
+            if let Err(e) = load_query_rules(&dir, language) {
+                problem(pointer.clone(), e.to_string());
+            }
+        }
+    }
+    for (pointer, name) in strings("/rules/packs") {
+        if let Err(e) = load_installed(&name) {
+            problem(pointer, e.to_string());
+        }
+    }
+    for (pointer, plugin) in strings("/rules/plugins") {
+        if let Err(e) = load_plugin(Path::new(&plugin)) {
+            problem(pointer, e.to_string());
+        }
+    }
+    problems
+}
+
+// Line and column of the setting `pointer` names, or of its closest ancestor that is
+// in the file
+fn locate(text: &str, offsets: &HashMap<String, usize>, pointer: &str) -> (usize, usize) {
+    let mut pointer = pointer;
+    let offset = loop {
+        if let Some(offset) = offsets.get(pointer) {
+            break *offset;
+        }
+        match pointer.rfind('/') {
+            Some(i) => pointer = &pointer[..i],
+            None => break 0,
+        }
+    };
+    let before = &text[..offset];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    (
+        before.matches('\n').count() + 1,
+        text[line_start..offset].chars().count() + 1,
+    )
+}
+
+// Byte offset of every value in `text`, which must be valid JSON, keyed by JSON
+// pointer. Object members point at their key, where an editor would look.
+fn value_offsets(text: &str) -> HashMap<String, usize> {
+    let mut scanner = Scanner {
+        bytes: text.as_bytes(),
+        pos: 0,
+        offsets: HashMap::new(),
+    };
+    scanner.value(String::new());
+    scanner.offsets
+}
+
+struct Scanner<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    offsets: HashMap<String, usize>,
+}
+
+impl Scanner<'_> {
+    fn peek(&mut self) -> Option<u8> {
+        while self
+            .bytes
+            .get(self.pos)
+            .is_some_and(u8::is_ascii_whitespace)
+        {
+            self.pos += 1;
+        }
+        self.bytes.get(self.pos).copied()
+    }
+
+    fn value(&mut self, pointer: String) {
+        let Some(first) = self.peek() else {
+            return;
+        };
+        self.offsets.entry(pointer.clone()).or_insert(self.pos);
+        match first {
+            b'{' => {
+                self.pos += 1;
+                while let Some(b'"') = self.peek() {
+                    let key_start = self.pos;
+                    let key = self.string();
+                    let member = format!("{}/{}", pointer, crate::config::pointer_segment(&key));
+                    self.offsets.insert(member.clone(), key_start);
+                    if self.peek() == Some(b':') {
+                        self.pos += 1;
+                    }
+                    self.value(member);
+                    if self.peek() == Some(b',') {
+                        self.pos += 1;
+                    }
+                }
+                self.pos += 1;
+            }
+            b'[' => {
+                self.pos += 1;
+                let mut index = 0;
+                while !matches!(self.peek(), Some(b']') | None) {
+                    self.value(format!("{}/{}", pointer, index));
+                    index += 1;
+                    if self.peek() == Some(b',') {
+                        self.pos += 1;
+                    }
+                }
+                self.pos += 1;
+            }
+            b'"' => {
+                self.string();
+            }
+            _ => {
+                while self
+                    .bytes
+                    .get(self.pos)
+                    .is_some_and(|b| !b",]} \t\r\n".contains(b))
+                {
+                    self.pos += 1;
+                }
+            }
+        }
+    }
+
+    // At an opening quote; returns the unescaped string
+    fn string(&mut self) -> String {
+        let start = self.pos;
+        self.pos += 1;
+        while let Some(&b) = self.bytes.get(self.pos) {
+            self.pos += if b == b'\\' { 2 } else { 1 };
+            if b == b'"' {
+                break;
+            }
+        }
+        let end = self.pos.min(self.bytes.len());
+        serde_json::from_slice(&self.bytes[start..end]).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    // The problems check_config finds in a config holding `text`
+    fn check(dir: &TempDir, text: &str) -> Vec<LocatedProblem> {
+        check_config(&dir.write(".treescan.json", text)).unwrap()
+    }
+
+    #[test]
+    fn test_every_problem_reported_at_its_line() {
+        let dir = TempDir::new("check");
+        dir.write("queries/rust/lints.scm", "(no_such_node) @warning");
+        let queries = dir.join("queries").display().to_string();
+        let config = format!(
+            r#"{{
+  "max_issues_per_file": 100,
+  "rules": {{
+    "min_severity": "severe",
+    "in_tests": {{ "unwrap_usage": "sometimes", "large_function": 0.5 }},
+    "query_dirs": [{:?}],
+    "scripts": [
+      {{ "language": "rust", "name": "x", "query": "(nope) @x", "script": "true" }}
+    ]
+  }},
+  "overrides": {{ "src/a/**": {{ "min_severity": "loud" }} }}
+}}
+"#,
+            queries
+        );
+
+        let problems = check(&dir, &config);
+        let at: Vec<(usize, usize)> = problems.iter().map(|p| (p.line, p.column)).collect();
+        assert_eq!(at, [(4, 5), (5, 19), (8, 7), (11, 32), (6, 20)]);
+        assert!(problems[0].message.starts_with("min_severity must be"));
+        assert!(problems[1].message.starts_with("in_tests.unwrap_usage"));
+        assert!(problems[2].message.contains("invalid query"));
+        assert!(problems[3].message.starts_with("override 'src/a/**'"));
+        assert!(problems[4].message.contains("lints.scm"));
+    }
+
+    #[test]
+    fn test_syntax_errors_reported_where_parsing_stopped() {
+        let dir = TempDir::new("check");
+        let syntax = check(
+            &dir,
+            "{\n  \"rules\": {\n    \"min_severity\": \"error\",\n  }\n}\n",
+        );
+        assert_eq!(syntax.len(), 1);
+        assert_eq!((syntax[0].line, syntax[0].column), (4, 3));
+    }
+
+    #[test]
+    fn test_misplaced_settings_and_wrong_types() {
+        let dir = TempDir::new("check");
+        let problems = check(
+            &dir,
+            r#"{
+  "min_severity": "bogus",
+  "max_chain_length": "x",
+  "include_generated": "yes",
+  "rules": { "unwrap_usage": { "severity": "eror" }, "max_line_lenght": 80, "injections": 1 }
+}"#,
+        );
+        let messages: Vec<String> = problems
+            .into_iter()
+            .map(|p| format!("{}:{} {}", p.line, p.column, p.message))
+            .collect();
+        assert_eq!(
+            messages,
+            [
+                "3:3 unknown setting 'max_chain_length' (did you mean 'rules.max_chain_length'?)",
+                "2:3 unknown setting 'min_severity' (did you mean 'rules.min_severity'?)",
+                "4:3 include_generated must be true or false",
+                "5:54 unknown setting 'rules.max_line_lenght' (did you mean 'rules.max_line_length'?)",
+                "5:14 unknown setting 'rules.unwrap_usage'",
+                "5:77 injections must be true or false",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_valid_config_has_no_problems() {
+        let dir = TempDir::new("check");
+        assert!(check(&dir, "{ \"rules\": { \"min_severity\": \"error\" } }").is_empty());
+    }
+
+    #[test]
+    fn test_missing_query_dir_is_a_problem() {
+        let dir = TempDir::new("check");
+        let missing = dir.join("no-queries").display().to_string();
+        let problems = check(
+            &dir,
+            &format!("{{ \"rules\": {{ \"query_dirs\": [{:?}] }} }}", missing),
+        );
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].message.contains("no-queries"), "{:?}", problems);
+    }
+
+    #[test]
+    fn test_missing_config_is_an_error() {
+        let dir = TempDir::new("check");
+        assert!(check_config(&dir.join(".treescan.json")).is_err());
+    }
+}
@@ -0,0 +1,332 @@
+use crate::analyzer::{RuleConfig, TestCodePolicy};
+use serde_json::{json, Map, Value};
+use std::path::Path;
+
+// How an environment variable's text becomes a setting
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum EnvKind {
+    Count,
+    Flag,
+    Text,
+    // Comma-separated
+    List,
+    // Comma-separated, or "none" to clear the config file's list
+    Severities,
+}
+
+// Environment variables and the settings they replace. Settings that are whole
+// objects (`naming`, `overrides`, `scripts`, ...) only come from the config file.
+const ENV_VARS: &[(&str, &str, EnvKind)] = &[
+    (
+        "TREESCAN_MAX_ISSUES_PER_FILE",
+        "/max_issues_per_file",
+        EnvKind::Count,
+    ),
+    (
+        "TREESCAN_MAX_FINDINGS_PER_RULE",
+        "/max_findings_per_rule",
+        EnvKind::Count,
+    ),
+    (
+        "TREESCAN_INCLUDE_GENERATED",
+        "/include_generated",
+        EnvKind::Flag,
+    ),
+    (
+        "TREESCAN_HEADER_LANGUAGE",
+        "/header_language",
+        EnvKind::Text,
+    ),
+    ("TREESCAN_LICENSE_HEADER", "/license_header", EnvKind::Text),
+    ("TREESCAN_FAIL_ON", "/fail_on", EnvKind::Severities),
+    (
+        "TREESCAN_MIN_SEVERITY",
+        "/rules/min_severity",
+        EnvKind::Text,
+    ),
+    ("TREESCAN_LOCALE", "/rules/locale", EnvKind::Text),
+    (
+        "TREESCAN_MAX_LINE_LENGTH",
+        "/rules/max_line_length",
+        EnvKind::Count,
+    ),
+    (
+        "TREESCAN_MAX_FILE_LINES",
+        "/rules/max_file_lines",
+        EnvKind::Count,
+    ),
+    (
+        "TREESCAN_MAX_CHAIN_LENGTH",
+        "/rules/max_chain_length",
+        EnvKind::Count,
+    ),
+    (
+        "TREESCAN_MAX_BOOLEAN_OPERATORS",
+        "/rules/max_boolean_operators",
+        EnvKind::Count,
+    ),
+    (
+        "TREESCAN_MAGIC_NUMBER_ALLOWLIST",
+        "/rules/magic_number_allowlist",
+        EnvKind::List,
+    ),
+    (
+        "TREESCAN_MAGIC_NUMBERS_IN_TESTS",
+        "/rules/magic_numbers_in_tests",
+        EnvKind::Flag,
+    ),
+    ("TREESCAN_INJECTIONS", "/rules/injections", EnvKind::Flag),
+    (
+        "TREESCAN_MACRO_BODIES",
+        "/rules/macro_bodies",
+        EnvKind::Flag,
+    ),
+    ("TREESCAN_QUERY_DIRS", "/rules/query_dirs", EnvKind::List),
+    ("TREESCAN_PACKS", "/rules/packs", EnvKind::List),
+    ("TREESCAN_PLUGINS", "/rules/plugins", EnvKind::List),
+];
+
+// Settings from `TREESCAN_*` environment variables, layered over the config file's
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EnvLayer {
+    // Shaped like a config file
+    pub settings: Value,
+    // (setting pointer, variable) for each variable that was set
+    pub sources: Vec<(String, String)>,
+}
+
+impl EnvLayer {
+    pub fn from_env() -> Result<Self, String> {
+        Self::from_vars(std::env::vars())
+    }
+
+    // Unknown variables are ignored and empty ones count as unset, so CI can blank a
+    // variable it inherits
+    pub fn from_vars(vars: impl IntoIterator<Item = (String, String)>) -> Result<Self, String> {
+        let mut layer = EnvLayer {
+            settings: json!({}),
+            sources: Vec::new(),
+        };
+        let mut vars: Vec<(String, String)> = vars.into_iter().collect();
+        vars.sort();
+        for (name, text) in vars {
+            let Some(&(_, pointer, kind)) = ENV_VARS.iter().find(|(var, _, _)| *var == name) else {
+                continue;
+            };
+            let text = text.trim();
+            if text.is_empty() {
+                continue;
+            }
+            let list = |text: &str| -> Value {
+                text.split(',')
+                    .map(str::trim)
+                    .filter(|item| !item.is_empty())
+                    .collect::<Vec<_>>()
+                    .into()
+            };
+            let value = match kind {
+                EnvKind::Count => json!(text
+                    .parse::<u64>()
+                    .map_err(|_| format!("{} must be a whole number", name))?),
+                EnvKind::Flag => match text {
+                    "true" | "1" => json!(true),
+                    "false" | "0" => json!(false),
+                    _ => return Err(format!("{} must be true or false", name)),
+                },
+                EnvKind::Text => json!(text),
+                EnvKind::List => list(text),
+                EnvKind::Severities if text == "none" => json!([]),
+                EnvKind::Severities => list(text),
+            };
+            set_pointer(&mut layer.settings, pointer, value);
+            layer.sources.push((pointer.to_string(), name));
+        }
+        Ok(layer)
+    }
+
+    // The variable that set the setting at `pointer`, or the one holding it
+    pub fn source_of(&self, pointer: &str) -> Option<&str> {
+        self.sources
+            .iter()
+            .find(|(set, _)| pointer == set || pointer.starts_with(&format!("{}/", set)))
+            .map(|(_, var)| var.as_str())
+    }
+}
+
+// `/rules/min_severity` -> {"rules": {"min_severity": value}}, keeping what's there
+fn set_pointer(root: &mut Value, pointer: &str, value: Value) {
+    let (parents, last) = pointer.rsplit_once('/').unwrap_or(("", pointer));
+    let mut target = root;
+    for segment in parents.split('/').filter(|segment| !segment.is_empty()) {
+        target = &mut target[segment];
+    }
+    target[last] = value;
+}
+
+// `layer`'s settings over `base`'s: objects are merged key by key, anything else is
+// replaced. A base that isn't an object is left for `config_problems` to report.
+pub fn merge(base: &mut Value, layer: &Value) {
+    let (Some(base), Some(layer)) = (base.as_object_mut(), layer.as_object()) else {
+        return;
+    };
+    for (key, value) in layer {
+        match base.get_mut(key) {
+            Some(existing) if existing.is_object() && value.is_object() => merge(existing, value),
+            _ => {
+                base.insert(key.clone(), value.clone());
+            }
+        }
+    }
+}
+
+// Every setting at its built-in default, shaped like a config file
+pub fn defaults_json() -> Value {
+    let rules = RuleConfig::default();
+    let in_tests: Map<String, Value> = rules
+        .in_tests
+        .iter()
+        .map(|(rule, policy)| {
+            let policy = match policy {
+                TestCodePolicy::Off => json!("off"),
+                TestCodePolicy::Weight(weight) => json!(weight),
+            };
+            (rule.clone(), policy)
+        })
+        .collect();
+    json!({
+        "max_issues_per_file": null,
+        "max_findings_per_rule": null,
+        "include_generated": false,
+        "header_language": null,
+        "license_header": null,
+        "fail_on": [],
+        "rules": {
+            "magic_number_allowlist": rules.magic_number_allowlist,
+            "magic_numbers_in_tests": rules.magic_numbers_in_tests,
+            "query_dirs": [],
+            "remediation_minutes": {},
+            "ratings": rules.ratings.to_json(),
+            "in_tests": in_tests,
+            "injections": rules.injections,
+            "macro_bodies": rules.macro_bodies,
+            "plugins": [],
+            "scripts": [],
+            "packs": [],
+            "min_severity": null,
+            "max_line_length": rules.length_limits.max_line_length,
+            "max_file_lines": rules.length_limits.max_file_lines,
+            // 0 turns these off
+            "max_chain_length": rules.max_chain_length.unwrap_or(0),
+            "max_boolean_operators": rules.max_boolean_operators.unwrap_or(0),
+            "naming": {},
+            "locale": null,
+        },
+        "overrides": {},
+    })
+}
+
+// What `config show` prints: the config file's settings with the environment's on top
+// (over every default too, with `resolved`), and where each setting that isn't a
+// default came from, keyed by pointer: the file's path or the variable's name
+pub fn layered_settings(file: Option<(&Path, &Value)>, env: &EnvLayer, resolved: bool) -> Value {
+    let mut settings = if resolved { defaults_json() } else { json!({}) };
+    let mut sources = Map::new();
+    if let Some((path, value)) = file {
+        merge(&mut settings, value);
+        for pointer in setting_pointers(value) {
+            sources.insert(pointer, json!(path.display().to_string()));
+        }
+    }
+    merge(&mut settings, &env.settings);
+    for (pointer, var) in &env.sources {
+        sources.insert(pointer.clone(), json!(var));
+    }
+    json!({
+        "config_file": file.map(|(path, _)| path.display().to_string()),
+        "settings": settings,
+        "sources": sources,
+    })
+}
+
+// `/max_issues_per_file`, `/rules/min_severity`, ...: the top-level settings and those
+// under `rules`
+fn setting_pointers(value: &Value) -> Vec<String> {
+    let mut pointers = Vec::new();
+    for (key, setting) in value.as_object().into_iter().flatten() {
+        match setting.as_object().filter(|_| key == "rules") {
+            Some(rules) => pointers.extend(rules.keys().map(|rule| format!("/rules/{}", rule))),
+            None => pointers.push(format!("/{}", key)),
+        }
+    }
+    pointers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::Severity;
+    use crate::config::ProjectConfig;
+
+    fn vars(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_environment_layered_over_config_file() {
+        let file = json!({
+            "max_issues_per_file": 5,
+            "fail_on": ["error"],
+            "rules": { "min_severity": "info", "locale": "ja" }
+        });
+        let env = EnvLayer::from_vars(vars(&[
+            ("TREESCAN_MIN_SEVERITY", "error"),
+            ("TREESCAN_FAIL_ON", "none"),
+            ("TREESCAN_QUERY_DIRS", "ci/queries, shared/queries"),
+            ("TREESCAN_LOCALE", ""),
+            ("TREESCAN_PACKS_DIR", "/opt/packs"),
+            ("HOME", "/root"),
+        ]))
+        .unwrap();
+        let config = ProjectConfig::from_layers(&file, &env).unwrap();
+        assert_eq!(config.max_issues_per_file, Some(5));
+        assert_eq!(config.rules.min_severity, Some(Severity::Error));
+        assert!(config.fail_on.is_empty());
+        assert_eq!(config.rules.query_dirs.len(), 2);
+        // Blank variables don't override
+        assert_eq!(config.rules.locale.as_deref(), Some("ja"));
+
+        let shown = layered_settings(Some((Path::new(".treescan.json"), &file)), &env, true);
+        assert_eq!(shown["settings"]["rules"]["min_severity"], "error");
+        assert_eq!(shown["settings"]["rules"]["injections"], true);
+        assert_eq!(
+            shown["sources"]["/rules/min_severity"],
+            "TREESCAN_MIN_SEVERITY"
+        );
+        assert_eq!(shown["sources"]["/rules/locale"], ".treescan.json");
+        assert!(shown["sources"].get("/rules/injections").is_none());
+        let unresolved = layered_settings(None, &env, false);
+        assert!(unresolved["settings"]["rules"].get("injections").is_none());
+        assert!(unresolved["config_file"].is_null());
+
+        // Bad values name the variable
+        let bad = EnvLayer::from_vars(vars(&[("TREESCAN_MIN_SEVERITY", "loud")])).unwrap();
+        let err = ProjectConfig::from_layers(&file, &bad).unwrap_err();
+        assert!(err.starts_with("TREESCAN_MIN_SEVERITY: min_severity must be"));
+        assert_eq!(
+            EnvLayer::from_vars(vars(&[("TREESCAN_MAX_ISSUES_PER_FILE", "lots")])).unwrap_err(),
+            "TREESCAN_MAX_ISSUES_PER_FILE must be a whole number"
+        );
+        // Unknown settings fail rather than being shown as resolved
+        let unknown = json!({ "min_severity": "bogus", "rules": { "unwrap_usage": {} } });
+        let err = ProjectConfig::from_layers(&unknown, &env).unwrap_err();
+        assert!(err.starts_with("unknown setting 'min_severity'"));
+        // The defaults document reads back as the default settings
+        assert_eq!(
+            ProjectConfig::from_json(&defaults_json()),
+            ProjectConfig::default()
+        );
+    }
+}
@@ -0,0 +1,461 @@
+use crate::analyzer::{analyze_source_to_json, CodeAnalyzer};
+use crate::ast::{parse_tree, write_ast_node, AstFormat, AstOptions};
+use crate::config::ProjectConfig;
+use crate::diagnostics::collect_syntax_errors;
+use crate::fragment::parse_fragment;
+use crate::language::{supported_languages_json, AnalyzerKey, SupportedLanguage};
+use crate::lsp::{read_message, write_message};
+use crate::outline::extract_symbols;
+use crate::position::ColumnUnit;
+use crate::prom::{Exposition, SEVERITIES};
+use crate::query::query_captures;
+use crate::source::read_source;
+use crate::version::version_metadata;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, BufRead, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+use std::time::Duration;
+use tree_sitter::Query;
+
+pub(crate) const PARSE_ERROR: i64 = -32700;
+pub(crate) const METHOD_NOT_FOUND: i64 = -32601;
+pub(crate) const INVALID_PARAMS: i64 = -32602;
+// Reading, parsing or analysis of the requested source failed
+const REQUEST_FAILED: i64 = -32000;
+
+// Long-running JSON-RPC 2.0 server (`Content-Length` framing, as in LSP) that
+// keeps analyzers and compiled queries alive between requests.
+//
+// Methods: `parse`, `analyze`, `query`, `symbols`, `languages`, `metrics`, `shutdown`; the `exit`
+// notification ends the session. Sources are given either as `path` or as
+// `source` plus `language`.
+//
+// With `metrics_addr`, Prometheus metrics for the session are also served over
+// HTTP at `/metrics`.
+pub fn run_stdio(config: ProjectConfig, metrics_addr: Option<&str>) -> io::Result<()> {
+    CodeAnalyzer::warm_up();
+    let daemon = Arc::new(Mutex::new(Daemon::new(config)));
+    if let Some(addr) = metrics_addr {
+        serve_metrics(addr, Arc::clone(&daemon), METRICS_REQUEST_TIMEOUT)?;
+    }
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    serve_shared(&daemon, &mut stdin.lock(), &mut stdout.lock())
+}
+
+pub fn serve<R: BufRead, W: Write>(
+    config: ProjectConfig,
+    input: &mut R,
+    output: &mut W,
+) -> io::Result<()> {
+    serve_shared(&Mutex::new(Daemon::new(config)), input, output)
+}
+
+fn serve_shared<R: BufRead, W: Write>(
+    daemon: &Mutex<Daemon>,
+    input: &mut R,
+    output: &mut W,
+) -> io::Result<()> {
+    loop {
+        let message = match read_message(input) {
+            Ok(Some(message)) => message,
+            Ok(None) => return Ok(()),
+            Err(e) if e.kind() == io::ErrorKind::InvalidData => {
+                write_message(
+                    output,
+                    &error_response(Value::Null, PARSE_ERROR, e.to_string()),
+                )?;
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+
+        let method = message["method"].as_str().unwrap_or("");
+        if method == "exit" {
+            return Ok(());
+        }
+        // Notifications get no reply
+        let Some(id) = message.get("id").cloned() else {
+            continue;
+        };
+        let outcome = lock(daemon).handle(method, &message["params"]);
+        let reply = match outcome {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err((code, error)) => error_response(id, code, error),
+        };
+        write_message(output, &reply)?;
+    }
+}
+
+fn lock(daemon: &Mutex<Daemon>) -> MutexGuard<'_, Daemon> {
+    // A panicking request leaves the caches usable
+    daemon
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+// How long a metrics client gets to send its request line
+const METRICS_REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+// Longest request line read; anything longer is answered with 404
+const MAX_REQUEST_LINE: u64 = 8 * 1024;
+
+// Answers `GET /metrics` on a background thread; returns the bound address
+fn serve_metrics(
+    addr: &str,
+    daemon: Arc<Mutex<Daemon>>,
+    timeout: Duration,
+) -> io::Result<SocketAddr> {
+    let listener = TcpListener::bind(addr)?;
+    let local = listener.local_addr()?;
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            // A client that hangs up or stalls mid-request only affects itself; requests
+            // are answered one at a time, so a silent one is cut off after `timeout`
+            let _ = stream
+                .set_read_timeout(Some(timeout))
+                .and_then(|_| stream.set_write_timeout(Some(timeout)))
+                .and_then(|_| answer_metrics_request(stream, &daemon));
+        }
+    });
+    Ok(local)
+}
+
+fn answer_metrics_request(mut stream: TcpStream, daemon: &Mutex<Daemon>) -> io::Result<()> {
+    let mut request_line = String::new();
+    io::BufReader::new((&stream).take(MAX_REQUEST_LINE)).read_line(&mut request_line)?;
+    let (status, body) = if request_line.starts_with("GET /metrics ") {
+        ("200 OK", lock(daemon).metrics())
+    } else {
+        ("404 Not Found", "Not found\n".to_string())
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )
+}
+
+pub(crate) fn error_response(id: Value, code: i64, message: String) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+pub(crate) type RpcResult = Result<Value, (i64, String)>;
+
+// Request handling shared by the stdio daemon and the MCP server
+pub(crate) struct Daemon {
+    config: ProjectConfig,
+    analyzers: HashMap<AnalyzerKey, CodeAnalyzer>,
+    queries: HashMap<(SupportedLanguage, String), Query>,
+    stats: SessionStats,
+}
+
+// What `/metrics` reports: request counts and the latest result per analyzed path
+#[derive(Default)]
+struct SessionStats {
+    requests: BTreeMap<String, u64>,
+    scores: BTreeMap<String, f64>,
+    issues: BTreeMap<String, u64>,
+}
+
+impl Daemon {
+    pub(crate) fn new(config: ProjectConfig) -> Self {
+        Daemon {
+            config,
+            analyzers: HashMap::new(),
+            queries: HashMap::new(),
+            stats: SessionStats::default(),
+        }
+    }
+
+    pub(crate) fn handle(&mut self, method: &str, params: &Value) -> RpcResult {
+        *self.stats.requests.entry(method.to_string()).or_default() += 1;
+        match method {
+            "parse" => self.parse(params),
+            "analyze" => self.analyze(params),
+            "query" => self.query(params),
+            "symbols" => self.symbols(params),
+            "languages" => Ok(supported_languages_json()),
+            "metrics" => Ok(Value::String(self.metrics())),
+            "shutdown" => Ok(Value::Null),
+            _ => Err((METHOD_NOT_FOUND, format!("Method not found: {}", method))),
+        }
+    }
+
+    fn parse(&mut self, params: &Value) -> RpcResult {
+        let (source, lang) = resolve_source(params)?;
+        let format = match params["format"].as_str() {
+            Some(name) => AstFormat::from_name(name)
+                .ok_or_else(|| (INVALID_PARAMS, format!("Unknown format '{}'", name)))?,
+            None => AstFormat::Json,
+        };
+        let options = AstOptions {
+            positions: params["positions"].as_bool().unwrap_or(false),
+            bytes: params["bytes"].as_bool().unwrap_or(false),
+            named_only: params["named_only"].as_bool().unwrap_or(false),
+            max_depth: params["max_depth"].as_u64().map(|depth| depth as usize),
+        };
+        // A fragment is written from the snippet's node rather than the scaffolding
+        let (fragment, whole);
+        let (tree, root) = if params["fragment"].as_bool().unwrap_or(false) {
+            fragment = parse_fragment(&source, lang, None).map_err(failed)?;
+            (&fragment.tree, fragment.root())
+        } else {
+            whole = parse_tree(&source, &lang.tree_sitter_language()).map_err(failed)?;
+            (&whole, whole.root_node())
+        };
+
+        let mut output = Vec::new();
+        write_ast_node(root, &source, format, &options, &mut output).map_err(failed)?;
+        let tree_value = match format {
+            AstFormat::Json => serde_json::from_slice(&output).map_err(failed)?,
+            AstFormat::Text | AstFormat::SexpCompat => {
+                Value::String(String::from_utf8_lossy(&output).into_owned())
+            }
+        };
+        let diagnostics = collect_syntax_errors(tree, &source, ColumnUnit::Byte);
+        Ok(json!({
+            "language": lang.name(),
+            "tree": tree_value,
+            "diagnostics": serde_json::to_value(diagnostics).map_err(failed)?,
+            "metadata": version_metadata(&tree.language()),
+        }))
+    }
+
+    fn analyze(&mut self, params: &Value) -> RpcResult {
+        let (source, lang) = resolve_source(params)?;
+        let path = Path::new(params["path"].as_str().unwrap_or(""));
+        let key = AnalyzerKey::new(lang, path, &self.config.rules);
+        if !self.analyzers.contains_key(&key) {
+            let analyzer = lang
+                .analyzer_for_path(path, &self.config.rules)
+                .ok_or_else(|| {
+                    (
+                        INVALID_PARAMS,
+                        format!("Analysis not supported for language '{}'", lang.name()),
+                    )
+                })?;
+            let analyzer = match self.config.max_issues_per_file {
+                Some(max) => analyzer.with_max_issues(max),
+                None => analyzer,
+            };
+            self.analyzers.insert(key, analyzer);
+        }
+        let analyzer = &self.analyzers[&key];
+        let report = analyze_source_to_json(&source, &lang.tree_sitter_language(), analyzer)
+            .map_err(failed)?;
+
+        for issue in report["issues"].as_array().into_iter().flatten() {
+            if let Some(severity) = issue["severity"].as_str() {
+                *self.stats.issues.entry(severity.to_string()).or_default() += 1;
+            }
+        }
+        if let (Some(path), Some(score)) = (params["path"].as_str(), report["score"].as_f64()) {
+            self.stats.scores.insert(path.to_string(), score);
+        }
+        Ok(report)
+    }
+
+    pub(crate) fn metrics(&self) -> String {
+        let stats = &self.stats;
+        Exposition::new()
+            .counter(
+                "treescan_requests_total",
+                "Requests handled, by method",
+                stats
+                    .requests
+                    .iter()
+                    .map(|(method, n)| (vec![("method", method.clone())], *n as f64)),
+            )
+            .gauge(
+                "treescan_score",
+                "Latest quality score of an analyzed file (0-10)",
+                stats
+                    .scores
+                    .iter()
+                    .map(|(file, score)| (vec![("file", file.clone())], *score)),
+            )
+            .counter(
+                "treescan_issues_total",
+                "Issues reported by analyze requests, by severity",
+                SEVERITIES.iter().map(|severity| {
+                    let count = stats.issues.get(*severity).copied().unwrap_or(0);
+                    (vec![("severity", severity.to_string())], count as f64)
+                }),
+            )
+            .finish()
+    }
+
+    fn query(&mut self, params: &Value) -> RpcResult {
+        let (source, lang) = resolve_source(params)?;
+        let query_text = params["query"]
+            .as_str()
+            .ok_or_else(|| (INVALID_PARAMS, "Missing 'query'".to_string()))?;
+        let language = lang.tree_sitter_language();
+
+        let key = (lang, query_text.to_string());
+        if !self.queries.contains_key(&key) {
+            let query = Query::new(&language, query_text)
+                .map_err(|e| (INVALID_PARAMS, format!("Invalid query: {}", e)))?;
+            self.queries.insert(key.clone(), query);
+        }
+        let tree = parse_tree(&source, &language).map_err(failed)?;
+        Ok(query_captures(&tree, &source, &self.queries[&key]))
+    }
+
+    fn symbols(&mut self, params: &Value) -> RpcResult {
+        let (source, lang) = resolve_source(params)?;
+        let tree = parse_tree(&source, &lang.tree_sitter_language()).map_err(failed)?;
+        let symbols = extract_symbols(&tree, &source, ColumnUnit::Byte);
+        serde_json::to_value(symbols).map_err(failed)
+    }
+}
+
+fn failed(e: impl std::fmt::Display) -> (i64, String) {
+    (REQUEST_FAILED, e.to_string())
+}
+
+// Inline `source` wins over `path`; the language comes from `language` or the path
+fn resolve_source(params: &Value) -> Result<(String, SupportedLanguage), (i64, String)> {
+    let path = params["path"].as_str();
+    let lang = match params["language"].as_str() {
+        Some(name) => SupportedLanguage::from_name(name)
+            .ok_or_else(|| (INVALID_PARAMS, format!("Unsupported language '{}'", name)))?,
+        None => path
+            .and_then(|p| SupportedLanguage::detect(Path::new(p)))
+            .ok_or_else(|| {
+                (
+                    INVALID_PARAMS,
+                    "Missing 'language' and no supported 'path'".to_string(),
+                )
+            })?,
+    };
+
+    let source = match (params["source"].as_str(), path) {
+        (Some(source), _) => source.to_string(),
+        (None, Some(path)) => read_source(path, None).map_err(failed)?.to_string(),
+        (None, None) => {
+            return Err((INVALID_PARAMS, "Expected 'source' or 'path'".to_string()));
+        }
+    };
+    Ok((source, lang))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(message: Value) -> String {
+        let body = message.to_string();
+        format!("Content-Length: {}\r\n\r\n{}", body.len(), body)
+    }
+
+    #[test]
+    fn test_requests_share_one_session() {
+        let query = "(function_item name: (identifier) @name)";
+        let mut input = String::new();
+        for (id, method, params) in [
+            (
+                1,
+                "analyze",
+                json!({ "source": "fn main() { x.unwrap(); }", "language": "rust" }),
+            ),
+            (
+                2,
+                "query",
+                json!({ "source": "fn a() {}", "language": "rust", "query": query }),
+            ),
+            (
+                3,
+                "query",
+                json!({ "source": "fn b() {}", "language": "rust", "query": query }),
+            ),
+            (
+                4,
+                "parse",
+                json!({ "source": "fn", "language": "rust", "format": "text" }),
+            ),
+            (5, "analyze", json!({ "source": "x", "language": "java" })),
+            (6, "nope", json!({})),
+        ] {
+            input.push_str(&frame(
+                json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }),
+            ));
+        }
+        input.push_str("Content-Length: 3\r\n\r\n{x}");
+        input.push_str(&frame(json!({ "jsonrpc": "2.0", "method": "exit" })));
+
+        let mut output = Vec::new();
+        serve(ProjectConfig::default(), &mut input.as_bytes(), &mut output).unwrap();
+        let mut reader = output.as_slice();
+        let mut replies = Vec::new();
+        while let Some(reply) = read_message(&mut reader).unwrap() {
+            replies.push(reply);
+        }
+
+        assert_eq!(replies.len(), 7);
+        assert_eq!(replies[0]["result"]["issues"][0]["rule"], "unwrap_usage");
+        assert_eq!(replies[1]["result"][0]["text"], "a");
+        assert_eq!(replies[2]["result"][0]["text"], "b");
+        assert!(replies[3]["result"]["tree"]
+            .as_str()
+            .unwrap()
+            .starts_with("(source_file"));
+        assert!(replies[3]["result"]["diagnostics"]
+            .as_array()
+            .is_some_and(|d| !d.is_empty()));
+        assert_eq!(replies[4]["error"]["code"], INVALID_PARAMS);
+        assert_eq!(replies[5]["error"]["code"], METHOD_NOT_FOUND);
+        assert_eq!(replies[6]["error"]["code"], PARSE_ERROR);
+    }
+
+    #[test]
+    fn test_metrics_endpoint_reports_session() {
+        let daemon = Arc::new(Mutex::new(Daemon::new(ProjectConfig::default())));
+        let params = json!({ "path": "src/a.rs", "source": "fn f() { x.unwrap(); }" });
+        lock(&daemon).handle("analyze", &params).unwrap();
+        let addr =
+            serve_metrics("127.0.0.1:0", Arc::clone(&daemon), METRICS_REQUEST_TIMEOUT).unwrap();
+
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream
+            .write_all(b"GET /metrics HTTP/1.1\r\nHost: x\r\n\r\n")
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.contains("treescan_requests_total{method=\"analyze\"} 1\n"));
+        assert!(response.contains("treescan_score{file=\"src/a.rs\"} "));
+        assert!(response.contains("treescan_issues_total{severity=\"Error\"} 0\n"));
+        assert!(!response.contains("treescan_issues_total{severity=\"Warning\"} 0\n"));
+    }
+
+    #[test]
+    fn test_metrics_endpoint_times_out_silent_clients() {
+        let daemon = Arc::new(Mutex::new(Daemon::new(ProjectConfig::default())));
+        let addr = serve_metrics("127.0.0.1:0", daemon, Duration::from_millis(100)).unwrap();
+
+        // Connects but never sends its request line
+        let _silent = TcpStream::connect(addr).unwrap();
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(10)))
+            .unwrap();
+        stream.write_all(b"GET /metrics HTTP/1.1\r\n\r\n").unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.write_all(b"GET /other HTTP/1.1\r\n\r\n").unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 404 Not Found"));
+    }
+}
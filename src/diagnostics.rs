@@ -0,0 +1,193 @@
+use crate::position::ColumnUnit;
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use std::fmt;
+use tree_sitter::{Node, Tree};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyntaxErrorKind {
+    // Input tree-sitter could not fit into the grammar
+    Error,
+    // A token the parser inserted to recover, e.g. a missing ';'
+    Missing,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxError {
+    pub kind: SyntaxErrorKind,
+    pub start_line: usize,
+    pub start_column: usize,
+    pub end_line: usize,
+    pub end_column: usize,
+    pub start_byte: usize,
+    pub end_byte: usize,
+    // For MISSING nodes, the token the parser expected
+    pub missing: Option<String>,
+    // For ERROR nodes, the literal tokens (punctuation, keywords) the grammar allowed
+    // where the error starts
+    pub expected: Vec<String>,
+}
+
+impl SyntaxError {
+    fn from_node(node: &Node, source: &str, unit: ColumnUnit) -> Self {
+        let (start_line, start_column) = unit.start_of(node, source);
+        let (end_line, end_column) = unit.end_of(node, source);
+        let missing = node.is_missing().then(|| node.kind().to_string());
+        let expected = if node.is_error() {
+            expected_tokens(node)
+        } else {
+            Vec::new()
+        };
+        SyntaxError {
+            kind: if node.is_missing() {
+                SyntaxErrorKind::Missing
+            } else {
+                SyntaxErrorKind::Error
+            },
+            start_line,
+            start_column,
+            end_line,
+            end_column,
+            start_byte: node.start_byte(),
+            end_byte: node.end_byte(),
+            missing,
+            expected,
+        }
+    }
+}
+
+// Replays the parse state after the last token before `node` and lists the visible
+// anonymous tokens the grammar accepts there. Named tokens such as `identifier` are
+// left out since the lookahead also yields rule names that can't be told apart.
+fn expected_tokens(node: &Node) -> Vec<String> {
+    let language = node.language();
+    let state = match previous_leaf(node) {
+        Some(leaf) => language.next_state(leaf.parse_state(), leaf.grammar_id()),
+        None => 1,
+    };
+    let Some(lookahead) = language.lookahead_iterator(state) else {
+        return Vec::new();
+    };
+    let mut tokens: Vec<String> = lookahead
+        .filter(|&symbol| {
+            symbol != 0
+                && language.node_kind_is_visible(symbol)
+                && !language.node_kind_is_named(symbol)
+        })
+        .filter_map(|symbol| language.node_kind_for_id(symbol).map(str::to_string))
+        .collect();
+    tokens.sort();
+    tokens.dedup();
+    tokens
+}
+
+fn previous_leaf<'a>(node: &Node<'a>) -> Option<Node<'a>> {
+    let mut current = *node;
+    let mut leaf = loop {
+        match current.prev_sibling() {
+            Some(sibling) => break sibling,
+            None => current = current.parent()?,
+        }
+    };
+    while let Some(last) = leaf.child(leaf.child_count().checked_sub(1)?) {
+        leaf = last;
+    }
+    Some(leaf)
+}
+
+impl fmt::Display for SyntaxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.missing {
+            Some(token) => write!(f, "MISSING \"{}\"", token)?,
+            None => write!(f, "ERROR")?,
+        }
+        write!(
+            f,
+            " at {}:{}-{}:{}",
+            self.start_line, self.start_column, self.end_line, self.end_column
+        )
+    }
+}
+
+impl Serialize for SyntaxError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("SyntaxError", 6)?;
+        let kind = match self.kind {
+            SyntaxErrorKind::Error => "error",
+            SyntaxErrorKind::Missing => "missing",
+        };
+        state.serialize_field("kind", kind)?;
+        state.serialize_field("start", &[self.start_line, self.start_column])?;
+        state.serialize_field("end", &[self.end_line, self.end_column])?;
+        state.serialize_field("bytes", &[self.start_byte, self.end_byte])?;
+        state.serialize_field("missing", &self.missing)?;
+        state.serialize_field("expected", &self.expected)?;
+        state.end()
+    }
+}
+
+// Collects every ERROR and MISSING node, in source order. Only subtrees that
+// contain errors are visited, so clean trees cost a single flag check.
+pub fn collect_syntax_errors(tree: &Tree, source: &str, unit: ColumnUnit) -> Vec<SyntaxError> {
+    let mut errors = Vec::new();
+    let root = tree.root_node();
+    if !root.has_error() {
+        return errors;
+    }
+
+    let mut cursor = root.walk();
+    loop {
+        let node = cursor.node();
+        if node.is_error() || node.is_missing() {
+            errors.push(SyntaxError::from_node(&node, source, unit));
+        }
+        // ERROR nodes are reported whole rather than descended into
+        if !node.is_error() && node.has_error() && cursor.goto_first_child() {
+            continue;
+        }
+        while !cursor.goto_next_sibling() {
+            if !cursor.goto_parent() {
+                return errors;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::parse_tree;
+
+    #[test]
+    fn test_collects_error_and_missing_nodes() {
+        let language = tree_sitter_rust::LANGUAGE.into();
+        let clean = parse_tree("fn main() {}", &language).unwrap();
+        assert!(collect_syntax_errors(&clean, "fn main() {}", ColumnUnit::Byte).is_empty());
+
+        let source = "fn main() { let x = 1 }";
+        let tree = parse_tree(source, &language).unwrap();
+        let errors = collect_syntax_errors(&tree, source, ColumnUnit::Byte);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].kind, SyntaxErrorKind::Missing);
+        assert!(errors[0].expected.is_empty());
+        assert_eq!(errors[0].to_string(), "MISSING \";\" at 1:22-1:22");
+
+        let source = "fn main() { @@@ }";
+        let tree = parse_tree(source, &language).unwrap();
+        let errors = collect_syntax_errors(&tree, source, ColumnUnit::Byte);
+        assert_eq!(errors[0].kind, SyntaxErrorKind::Error);
+
+        // After `let` comes a pattern, so `=` is not among the expected tokens
+        let source = "fn main() { let = 5; }";
+        let errors = crate::syntax_errors(source, "rust").unwrap();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].start_byte, 16);
+        for token in ["(", "&", "["] {
+            assert!(errors[0].expected.contains(&token.to_string()), "{}", token);
+        }
+        assert!(!errors[0].expected.contains(&"=".to_string()));
+        assert!(crate::syntax_errors("fn main() {}", "rust")
+            .unwrap()
+            .is_empty());
+        assert!(crate::syntax_errors("x", "cobol").is_err());
+    }
+}
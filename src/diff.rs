@@ -0,0 +1,92 @@
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+
+// Lines added or modified in one file of a unified diff, by their line number
+// in the new version of the file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileDiff {
+    pub path: PathBuf,
+    pub added_lines: BTreeSet<usize>,
+}
+
+// Parses `git diff` / `gh pr diff` output. Deleted files are dropped; renamed
+// files are reported under their new path.
+pub fn parse_unified_diff(diff: &str) -> Vec<FileDiff> {
+    let mut files: Vec<FileDiff> = Vec::new();
+    let mut current: Option<FileDiff> = None;
+    let mut new_line = 0;
+
+    for line in diff.lines() {
+        if let Some(target) = line.strip_prefix("+++ ") {
+            files.extend(current.take());
+            let target = target.split('\t').next().unwrap_or(target);
+            current = (target != "/dev/null").then(|| FileDiff {
+                path: PathBuf::from(target.strip_prefix("b/").unwrap_or(target)),
+                added_lines: BTreeSet::new(),
+            });
+        } else if line.starts_with("diff --git ") {
+            files.extend(current.take());
+        } else if let Some(hunk) = line.strip_prefix("@@ ") {
+            new_line = hunk_new_start(hunk).unwrap_or(0);
+        } else if let Some(file) = current.as_mut() {
+            if new_line == 0 {
+                continue;
+            }
+            match line.as_bytes().first() {
+                Some(b'+') => {
+                    file.added_lines.insert(new_line);
+                    new_line += 1;
+                }
+                Some(b' ') => new_line += 1,
+                // Removed lines and "\ No newline at end of file" don't advance
+                _ => {}
+            }
+        }
+    }
+    files.extend(current);
+    files
+}
+
+// "-12,3 +14,5 @@ fn x()" -> 14
+fn hunk_new_start(hunk: &str) -> Option<usize> {
+    let new_range = hunk.split_whitespace().find(|part| part.starts_with('+'))?;
+    new_range[1..].split(',').next()?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_unified_diff() {
+        let diff = "\
+diff --git a/src/lib.rs b/src/lib.rs
+index 1111111..2222222 100644
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -1,3 +1,4 @@
+ fn a() {}
+-fn b() {}
++fn b() { x.unwrap(); }
++fn c() {}
+ fn d() {}
+@@ -10,2 +11,3 @@ fn e() {}
+ fn f() {}
++fn g() {}
+\\ No newline at end of file
+diff --git a/old.rs b/old.rs
+deleted file mode 100644
+--- a/old.rs
++++ /dev/null
+@@ -1 +0,0 @@
+-fn gone() {}
+";
+        let files = parse_unified_diff(diff);
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].path, PathBuf::from("src/lib.rs"));
+        assert_eq!(
+            files[0].added_lines.iter().copied().collect::<Vec<_>>(),
+            vec![2, 3, 12]
+        );
+    }
+}
@@ -0,0 +1,334 @@
+use crate::ast::{parse_tree, walk, NodeVisitor};
+use crate::language::SupportedLanguage;
+use crate::outline::{symbol_name, SymbolKind};
+use crate::source::read_source;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::io;
+use std::path::PathBuf;
+use tree_sitter::{Node, Tree};
+
+// A public function or type and whether a doc comment precedes it. Lines are 1-based.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DocItem {
+    pub name: String,
+    pub kind: SymbolKind,
+    pub line: usize,
+    pub documented: bool,
+    // Declaration without its body (see `signature`)
+    pub signature: String,
+}
+
+// Public/exported functions, methods and types of a file: `pub` in Rust and Zig,
+// capitalized names in Go, `public` in Java, `export`ed in JS/TS and non-static
+// file-scope declarations in C/C++
+pub fn public_items(tree: &Tree, source: &str, lang: SupportedLanguage) -> Vec<DocItem> {
+    let mut collector = DocCollector {
+        source,
+        lang,
+        items: Vec::new(),
+    };
+    // The collector never performs I/O
+    let _ = walk(tree.root_node(), &mut collector);
+    collector.items
+}
+
+struct DocCollector<'a> {
+    source: &'a str,
+    lang: SupportedLanguage,
+    items: Vec<DocItem>,
+}
+
+impl NodeVisitor for DocCollector<'_> {
+    fn enter(&mut self, node: &Node, _depth: usize, _first_sibling: bool) -> io::Result<()> {
+        let Some((kind, name)) = item_kind(node, self.lang) else {
+            return Ok(());
+        };
+        if !is_public(node, &name, self.lang, self.source) {
+            return Ok(());
+        }
+        self.items.push(DocItem {
+            name: name
+                .utf8_text(self.source.as_bytes())
+                .unwrap_or("")
+                .to_string(),
+            kind,
+            line: node.start_position().row + 1,
+            documented: has_doc_comment(node, self.lang, self.source),
+            signature: signature(node, kind, self.source),
+        });
+        Ok(())
+    }
+
+    fn leave(&mut self, _node: &Node, _depth: usize) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn item_kind<'tree>(
+    node: &Node<'tree>,
+    lang: SupportedLanguage,
+) -> Option<(SymbolKind, Node<'tree>)> {
+    // Zig types are constants bound to a container: `pub const Point = struct { ... };`
+    if lang == SupportedLanguage::Zig && node.kind() == "variable_declaration" {
+        let mut cursor = node.walk();
+        let kind = node
+            .named_children(&mut cursor)
+            .find_map(|child| match child.kind() {
+                "struct_declaration" | "union_declaration" | "opaque_declaration" => {
+                    Some(SymbolKind::Struct)
+                }
+                "enum_declaration" => Some(SymbolKind::Enum),
+                _ => None,
+            })?;
+        return Some((kind, node.named_child(0)?));
+    }
+    let kind = match SymbolKind::from_node_kind(node.kind())? {
+        SymbolKind::Module | SymbolKind::Constant => return None,
+        // impl blocks group methods; they are not documented themselves
+        SymbolKind::Class if node.kind() == "impl_item" => return None,
+        // `struct foo *p;` only names a type declared elsewhere
+        SymbolKind::Class | SymbolKind::Struct | SymbolKind::Enum
+            if node.kind().ends_with("_specifier")
+                && node.child_by_field_name("body").is_none() =>
+        {
+            return None
+        }
+        kind => kind,
+    };
+    Some((kind, symbol_name(node)?))
+}
+
+// Functions and classes stop at their body; other types keep it, since their fields
+// are part of the API. Whitespace is collapsed: `pub fn add(a: i32) -> i32`
+fn signature(node: &Node, kind: SymbolKind, source: &str) -> String {
+    let end = match kind {
+        SymbolKind::Function | SymbolKind::Method | SymbolKind::Class => node
+            .child_by_field_name("body")
+            .map_or(node.end_byte(), |body| body.start_byte()),
+        _ => node.end_byte(),
+    };
+    source[node.start_byte()..end]
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn is_public(node: &Node, name: &Node, lang: SupportedLanguage, source: &str) -> bool {
+    let text = |n: &Node| n.utf8_text(source.as_bytes()).unwrap_or("").to_string();
+    let mut cursor = node.walk();
+    let mut children = node.children(&mut cursor);
+    match lang {
+        SupportedLanguage::Rust => {
+            children.any(|c| c.kind() == "visibility_modifier" && text(&c) == "pub")
+        }
+        SupportedLanguage::Zig => children.next().is_some_and(|c| c.kind() == "pub"),
+        SupportedLanguage::Go => text(name).starts_with(|c: char| c.is_uppercase()),
+        SupportedLanguage::Java => {
+            children
+                .find(|c| c.kind() == "modifiers")
+                .is_some_and(|modifiers| {
+                    let mut cursor = modifiers.walk();
+                    let public = modifiers
+                        .children(&mut cursor)
+                        .any(|m| m.kind() == "public");
+                    public
+                })
+        }
+        SupportedLanguage::JavaScript | SupportedLanguage::TypeScript | SupportedLanguage::Tsx => {
+            if node.kind() != "method_definition" {
+                return node
+                    .parent()
+                    .is_some_and(|p| p.kind() == "export_statement");
+            }
+            let class = node.parent().and_then(|body| body.parent());
+            let exported = class
+                .and_then(|c| c.parent())
+                .is_some_and(|p| p.kind() == "export_statement");
+            let hidden =
+                children.any(|c| c.kind() == "accessibility_modifier" && text(&c) != "public");
+            exported && !hidden && !text(name).starts_with('#')
+        }
+        SupportedLanguage::C | SupportedLanguage::Cpp => {
+            // Members follow access specifiers, which this does not track
+            let file_scope = node
+                .parent()
+                .is_none_or(|p| p.kind() != "field_declaration_list");
+            file_scope
+                && !children.any(|c| c.kind() == "storage_class_specifier" && text(&c) == "static")
+        }
+    }
+}
+
+fn has_doc_comment(node: &Node, lang: SupportedLanguage, source: &str) -> bool {
+    // Comments sit before the wrapper, not the declaration inside it
+    let mut anchor = *node;
+    while let Some(parent) = anchor.parent().filter(|p| {
+        matches!(
+            p.kind(),
+            "export_statement" | "type_declaration" | "template_declaration"
+        )
+    }) {
+        anchor = parent;
+    }
+
+    let mut next_row = anchor.start_position().row;
+    let mut sibling = anchor.prev_sibling();
+    while let Some(prev) = sibling {
+        // A blank line separates the comment from the declaration
+        if prev.end_position().row + 1 < next_row {
+            return false;
+        }
+        if matches!(prev.kind(), "attribute_item" | "decorator") {
+            next_row = prev.start_position().row;
+            sibling = prev.prev_sibling();
+            continue;
+        }
+        if !prev.kind().contains("comment") {
+            return false;
+        }
+        let text = prev.utf8_text(source.as_bytes()).unwrap_or("");
+        return match lang {
+            SupportedLanguage::Rust => {
+                (text.starts_with("///") && !text.starts_with("////"))
+                    || (text.starts_with("/**") && !text.starts_with("/**/"))
+            }
+            SupportedLanguage::Zig => text.starts_with("///"),
+            SupportedLanguage::Java
+            | SupportedLanguage::JavaScript
+            | SupportedLanguage::TypeScript
+            | SupportedLanguage::Tsx => text.starts_with("/**") && !text.starts_with("/**/"),
+            // No dedicated doc syntax: any comment directly above counts
+            SupportedLanguage::Go | SupportedLanguage::C | SupportedLanguage::Cpp => true,
+        };
+    }
+    false
+}
+
+// Percentage rounded to one decimal, or null when there is nothing to document
+fn coverage(documented: usize, total: usize) -> Value {
+    if total == 0 {
+        return Value::Null;
+    }
+    json!((documented as f64 / total as f64 * 1000.0).round() / 10.0)
+}
+
+// Per-file, per-language and overall doc-comment coverage of public items
+pub fn doc_coverage(files: &[PathBuf]) -> Value {
+    // language -> (files, documented, total)
+    let mut languages: BTreeMap<&'static str, (usize, usize, usize)> = BTreeMap::new();
+    let mut entries = Vec::new();
+
+    for path in files {
+        let Some(lang) = SupportedLanguage::detect(path) else {
+            continue;
+        };
+        let items = read_source(path, None)
+            .map_err(|e| e.to_string())
+            .and_then(|source| {
+                let tree =
+                    parse_tree(&source, &lang.tree_sitter_language()).map_err(|e| e.to_string())?;
+                Ok(public_items(&tree, &source, lang))
+            });
+        let path = path.display().to_string();
+        let items = match items {
+            Ok(items) => items,
+            Err(e) => {
+                entries.push(json!({ "path": path, "language": lang.name(), "error": e }));
+                continue;
+            }
+        };
+        let documented = items.iter().filter(|item| item.documented).count();
+        let totals = languages.entry(lang.name()).or_default();
+        totals.0 += 1;
+        totals.1 += documented;
+        totals.2 += items.len();
+        let undocumented: Vec<Value> = items
+            .iter()
+            .filter(|item| !item.documented)
+            .map(|item| json!({ "name": item.name, "kind": item.kind.as_str(), "line": item.line }))
+            .collect();
+        entries.push(json!({
+            "path": path,
+            "language": lang.name(),
+            "documented": documented,
+            "total": items.len(),
+            "coverage": coverage(documented, items.len()),
+            "undocumented": undocumented,
+        }));
+    }
+
+    let (documented, total) = languages
+        .values()
+        .fold((0, 0), |(d, t), (_, documented, total)| {
+            (d + documented, t + total)
+        });
+    let languages: BTreeMap<&str, Value> = languages
+        .into_iter()
+        .map(|(name, (files, documented, total))| {
+            (
+                name,
+                json!({
+                    "files": files,
+                    "documented": documented,
+                    "total": total,
+                    "coverage": coverage(documented, total),
+                }),
+            )
+        })
+        .collect();
+
+    json!({
+        "files": entries,
+        "languages": languages,
+        "total": {
+            "documented": documented,
+            "total": total,
+            "coverage": coverage(documented, total),
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn items(source: &str, lang: SupportedLanguage) -> Vec<(String, bool)> {
+        let tree = parse_tree(source, &lang.tree_sitter_language()).unwrap();
+        public_items(&tree, source, lang)
+            .into_iter()
+            .map(|item| (item.name, item.documented))
+            .collect()
+    }
+
+    #[test]
+    fn test_public_items_and_doc_comments() {
+        let rust = "/// Doc\n#[inline]\npub fn a() {}\npub(crate) fn b() {}\n\
+                    // plain\npub struct S;\nimpl S {\n    /** m */\n    pub fn m(&self) {}\n}\n";
+        assert_eq!(
+            items(rust, SupportedLanguage::Rust),
+            vec![("a".into(), true), ("S".into(), false), ("m".into(), true)]
+        );
+
+        let go = "package p\n\n// T is documented\ntype T struct{}\n\n// F doc\n\nfunc F() {}\n\
+                  func (t T) M() {}\nfunc g() {}\n";
+        assert_eq!(
+            items(go, SupportedLanguage::Go),
+            vec![("T".into(), true), ("F".into(), false), ("M".into(), false)]
+        );
+
+        let ts = "/** doc */\nexport function f() {}\nfunction g() {}\n\
+                  export class C { m() {} private p() {} }\n";
+        assert_eq!(
+            items(ts, SupportedLanguage::TypeScript),
+            vec![("f".into(), true), ("C".into(), false), ("m".into(), false)]
+        );
+
+        let zig = "/// Adds.\npub fn add(a: i32) i32 { return a; }\nfn hidden() void {}\n\
+                   pub const Point = struct { x: i32 };\n";
+        assert_eq!(
+            items(zig, SupportedLanguage::Zig),
+            vec![("add".into(), true), ("Point".into(), false)]
+        );
+    }
+}
@@ -0,0 +1,147 @@
+use crate::analyzer::{fingerprint, AnalysisResult, Severity};
+use crate::position::ColumnUnit;
+use tree_sitter::{Node, Tree};
+
+pub const EMPTY_BLOCK: &str = "empty_block";
+pub const EMPTY_FUNCTION: &str = "empty_function";
+
+const BLOCK_KINDS: &[&str] = &["block", "statement_block", "compound_statement"];
+const IF_KINDS: &[&str] = &["if_statement", "if_expression"];
+// Named declarations only; empty closures and arrow functions are usually deliberate
+// no-op callbacks
+const FUNCTION_KINDS: &[&str] = &[
+    "function_item",
+    "function_declaration",
+    "generator_function_declaration",
+    "method_declaration",
+    "method_definition",
+];
+
+// A body with nothing in it, not even a comment explaining why
+fn is_empty_block(node: &Node) -> bool {
+    BLOCK_KINDS.contains(&node.kind()) && node.named_child_count() == 0
+}
+
+// Empty bodies of if/else branches, catch clauses and named functions, whatever
+// their formatting
+pub fn empty_bodies(tree: &Tree, source: &str, unit: ColumnUnit) -> Vec<(usize, AnalysisResult)> {
+    let mut results = Vec::new();
+    let mut report = |rule: &str, message: &str, at: Node, body: Node| {
+        let (line, column) = unit.start_of(&at, source);
+        let text = at.utf8_text(source.as_bytes()).unwrap_or("");
+        let suggestion = match rule {
+            EMPTY_FUNCTION => "Implement the function, or comment on why it does nothing",
+            _ => "Remove the block, or comment on why it is empty",
+        };
+        let severity = Severity::Style;
+        results.push((
+            body.start_byte(),
+            AnalysisResult {
+                rule_name: rule.to_string(),
+                score_impact: severity.base_score_impact(),
+                severity,
+                message: message.to_string(),
+                line,
+                column,
+                text: text.to_string(),
+                suggestion: Some(suggestion.to_string()),
+                category: None,
+                fingerprint: fingerprint(rule, &at, text, source),
+            },
+        ));
+    };
+
+    let mut stack = vec![tree.root_node()];
+    while let Some(node) = stack.pop() {
+        let kind = node.kind();
+        if IF_KINDS.contains(&kind) {
+            if let Some(block) = node
+                .child_by_field_name("consequence")
+                .filter(is_empty_block)
+            {
+                report(EMPTY_BLOCK, "Empty if block", block, block);
+            }
+            // Go and Java put the block straight in `alternative`, others wrap it in
+            // an else clause; `else if` is an if of its own
+            let alternative = node.child_by_field_name("alternative");
+            let alternative = match alternative {
+                Some(clause) if clause.kind() == "else_clause" => clause.named_child(0),
+                other => other,
+            };
+            if let Some(block) = alternative.filter(is_empty_block) {
+                report(EMPTY_BLOCK, "Empty else block", block, block);
+            }
+        } else if kind == "catch_clause" {
+            if let Some(block) = node.child_by_field_name("body").filter(is_empty_block) {
+                report(EMPTY_BLOCK, "Empty catch block", block, block);
+            }
+        } else if FUNCTION_KINDS.contains(&kind) && !in_trait_impl(&node) {
+            if let Some(block) = node.child_by_field_name("body").filter(is_empty_block) {
+                let name = node.child_by_field_name("name").unwrap_or(node);
+                report(EMPTY_FUNCTION, "Empty function body", name, block);
+            }
+        }
+        let mut cursor = node.walk();
+        let children: Vec<Node> = node.named_children(&mut cursor).collect();
+        stack.extend(children.into_iter().rev());
+    }
+    results
+}
+
+// Trait methods and their default bodies are often no-ops on purpose (`Drop`,
+// visitor hooks)
+fn in_trait_impl(function: &Node) -> bool {
+    let Some(list) = function.parent() else {
+        return false;
+    };
+    list.kind() == "declaration_list"
+        && list.parent().is_some_and(|owner| {
+            owner.kind() == "trait_item"
+                || owner.kind() == "impl_item" && owner.child_by_field_name("trait").is_some()
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::parse_tree;
+    use crate::language::SupportedLanguage;
+
+    #[test]
+    fn test_empty_bodies_regardless_of_formatting() {
+        let found = |source: &str, lang: SupportedLanguage| {
+            let tree = parse_tree(source, &lang.tree_sitter_language()).unwrap();
+            empty_bodies(&tree, source, ColumnUnit::Byte)
+                .into_iter()
+                .map(|(_, r)| (r.line, r.message))
+                .collect::<Vec<_>>()
+        };
+        let at = |line: usize, message: &str| (line, message.to_string());
+
+        assert_eq!(
+            found(
+                "package m\nfunc f() {}\nfunc g() {\n\tif x {\n\t} else {\n\t}\n}\n",
+                SupportedLanguage::Go
+            ),
+            vec![
+                at(2, "Empty function body"),
+                at(4, "Empty if block"),
+                at(5, "Empty else block"),
+            ]
+        );
+        assert_eq!(
+            found(
+                "impl Drop for A { fn drop(&mut self) {} }\ntrait T { fn hook(&self) {} }\nfn g() { if x { // later\n} else {} }\n",
+                SupportedLanguage::Rust
+            ),
+            vec![at(4, "Empty else block")]
+        );
+        assert_eq!(
+            found(
+                "try { x() } catch (e) {}\nif (a) { b() } else if (c) {} \nconst noop = () => {};\n",
+                SupportedLanguage::JavaScript
+            ),
+            vec![at(1, "Empty catch block"), at(2, "Empty if block")]
+        );
+    }
+}
@@ -0,0 +1,568 @@
+use crate::analyzer::{RuleConfig, Severity};
+use crate::chains::LONG_CALL_CHAIN;
+use crate::commented_code::COMMENTED_OUT_CODE;
+use crate::conditions::{COMPLEX_BOOLEAN_EXPRESSION, NESTED_TERNARY};
+use crate::empty_bodies::{EMPTY_BLOCK, EMPTY_FUNCTION};
+use crate::language::SupportedLanguage;
+use crate::length::{FILE_TOO_LONG, LINE_TOO_LONG};
+use crate::naming::NAMING_CONVENTION;
+use serde_json::{json, Value};
+use std::path::Path;
+
+// What a rule looks for, with code it flags and the same code fixed
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RuleDoc {
+    pub description: Option<String>,
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+impl RuleDoc {
+    // {"description": "...", "example": {"before": "...", "after": "..."}}, as found on
+    // a script rule
+    pub fn from_json(value: &Value) -> Result<Self, String> {
+        let text = |value: Option<&Value>, key: &str| match value {
+            None => Ok(None),
+            Some(Value::String(s)) => Ok(Some(s.clone())),
+            Some(_) => Err(format!("\"{}\" must be a string", key)),
+        };
+        let example = value.get("example");
+        if example.is_some_and(|e| !e.is_object()) {
+            return Err("\"example\" must be {\"before\", \"after\"}".to_string());
+        }
+        Ok(RuleDoc {
+            description: text(value.get("description"), "description")?,
+            before: text(example.and_then(|e| e.get("before")), "example.before")?,
+            after: text(example.and_then(|e| e.get("after")), "example.after")?,
+        })
+    }
+}
+
+// Built-in checks that walk the tree in code rather than running a query
+const CHECKS: &[(&str, Severity)] = &[
+    (COMMENTED_OUT_CODE, Severity::Style),
+    (NAMING_CONVENTION, Severity::Style),
+    (NESTED_TERNARY, Severity::Style),
+    (COMPLEX_BOOLEAN_EXPRESSION, Severity::Style),
+    (LONG_CALL_CHAIN, Severity::Style),
+    (EMPTY_BLOCK, Severity::Style),
+    (EMPTY_FUNCTION, Severity::Style),
+    (LINE_TOO_LONG, Severity::Style),
+    (FILE_TOO_LONG, Severity::Info),
+];
+
+// (rule, language of the example, description, before, after)
+const DOCS: &[(&str, SupportedLanguage, &str, &str, &str)] = &[
+    (
+        "syntax_error",
+        SupportedLanguage::Rust,
+        "Code the grammar could not parse. Findings near it may be off, since the tree \
+         around an error is a best guess.",
+        "fn main() {\n    let total = ;\n}",
+        "fn main() {\n    let total = 0;\n}",
+    ),
+    (
+        "unwrap_usage",
+        SupportedLanguage::Rust,
+        "Calls to .unwrap(), which panic on None or Err with a message that says nothing \
+         about what went wrong.",
+        "fn port() -> u16 {\n    env::var(\"PORT\").unwrap().parse().unwrap()\n}",
+        "fn port() -> Result<u16, Box<dyn Error>> {\n    Ok(env::var(\"PORT\")?.parse()?)\n}",
+    ),
+    (
+        "large_function",
+        SupportedLanguage::Rust,
+        "Functions longer than 50 lines, which are hard to read and test as a unit.",
+        "fn handle(request: Request) -> Response {\n    // 80 lines of parsing, checks and rendering\n}",
+        "fn handle(request: Request) -> Response {\n    let order = parse(&request)?;\n    validate(&order)?;\n    render(&order)\n}",
+    ),
+    (
+        "discarded_result",
+        SupportedLanguage::Rust,
+        "`let _ = call()`, which throws away the Result and any error in it.",
+        "fn cleanup(path: &Path) {\n    let _ = fs::remove_file(path);\n}",
+        "fn cleanup(path: &Path) -> io::Result<()> {\n    fs::remove_file(path)\n}",
+    ),
+    (
+        "swallowed_error",
+        SupportedLanguage::Rust,
+        "A statement ending in .ok(), which turns an error into a None nobody looks at.",
+        "fn flush(file: &File) {\n    file.sync_all().ok();\n}",
+        "fn flush(file: &File) -> io::Result<()> {\n    file.sync_all()\n}",
+    ),
+    (
+        "io_unwrap_or_default",
+        SupportedLanguage::Rust,
+        "I/O calls whose error becomes a default value, so a failed read looks like an \
+         empty file.",
+        "fn load(path: &Path) -> String {\n    fs::read_to_string(path).unwrap_or_default()\n}",
+        "fn load(path: &Path) -> io::Result<String> {\n    fs::read_to_string(path)\n}",
+    ),
+    (
+        "console_log",
+        SupportedLanguage::JavaScript,
+        "console.log calls, usually debugging output left behind.",
+        "function save(user) {\n  console.log(user);\n  return db.put(user);\n}",
+        "function save(user) {\n  return db.put(user);\n}",
+    ),
+    (
+        "var_usage",
+        SupportedLanguage::JavaScript,
+        "`var` declarations, which are function-scoped and hoisted.",
+        "var count = 0;",
+        "let count = 0;",
+    ),
+    (
+        "js_unawaited_promise",
+        SupportedLanguage::JavaScript,
+        "Calls to async functions (and fetch) in an async function that are neither awaited \
+         nor returned, so their failures are lost.",
+        "async function save(user) {\n  fetch(\"/users\", { method: \"POST\" });\n}",
+        "async function save(user) {\n  await fetch(\"/users\", { method: \"POST\" });\n}",
+    ),
+    (
+        "js_then_without_catch",
+        SupportedLanguage::JavaScript,
+        ".then() chains with no rejection handler.",
+        "loadUser(id).then(render);",
+        "loadUser(id).then(render).catch(showError);",
+    ),
+    (
+        "js_async_promise_executor",
+        SupportedLanguage::JavaScript,
+        "`new Promise(async ...)`: an error thrown in an async executor never rejects the \
+         promise.",
+        "const data = new Promise(async (resolve) => {\n  resolve(await load());\n});",
+        "const data = load();",
+    ),
+    (
+        "react_missing_key",
+        SupportedLanguage::Tsx,
+        "Elements rendered from .map() without a `key` prop, which React needs to track \
+         list items.",
+        "const List = ({ items }) => <ul>{items.map((item) => <li>{item.name}</li>)}</ul>;",
+        "const List = ({ items }) => (\n  <ul>{items.map((item) => <li key={item.id}>{item.name}</li>)}</ul>\n);",
+    ),
+    (
+        "react_inline_function_prop",
+        SupportedLanguage::Tsx,
+        "Functions created inline in a JSX prop, a new value on every render.",
+        "const Row = ({ id }) => <Button onClick={() => remove(id)} />;",
+        "const Row = ({ id }) => {\n  const onClick = useCallback(() => remove(id), [id]);\n  return <Button onClick={onClick} />;\n};",
+    ),
+    (
+        "react_large_component",
+        SupportedLanguage::Tsx,
+        "Capitalized function components longer than the component line limit.",
+        "function Checkout() {\n  // 200 lines of state, effects and markup\n}",
+        "function Checkout() {\n  return (\n    <>\n      <Cart />\n      <Payment />\n    </>\n  );\n}",
+    ),
+    (
+        "go_missing_error_check",
+        SupportedLanguage::Go,
+        "Assignments to `err` not followed by an `if err != nil` check.",
+        "func open(path string) (f *os.File, err error) {\n\tf, err = os.Open(path)\n\treturn f, nil\n}",
+        "func open(path string) (f *os.File, err error) {\n\tf, err = os.Open(path)\n\tif err != nil {\n\t\treturn nil, err\n\t}\n\treturn f, nil\n}",
+    ),
+    (
+        "go_unused_variable",
+        SupportedLanguage::Go,
+        "Short variable declarations, which the compiler rejects when unused; `_` marks a \
+         value as deliberately ignored.",
+        "func run() {\n\tresult := compute()\n}",
+        "func run() {\n\t_ = compute()\n}",
+    ),
+    (
+        "go_panic_usage",
+        SupportedLanguage::Go,
+        "Calls to panic(), which crash the program unless something recovers.",
+        "func load() Config {\n\tpanic(\"config missing\")\n}",
+        "func load() (Config, error) {\n\treturn Config{}, errors.New(\"config missing\")\n}",
+    ),
+    (
+        "go_large_function",
+        SupportedLanguage::Go,
+        "Functions longer than 40 lines.",
+        "func handle(w http.ResponseWriter, r *http.Request) {\n\t// 70 lines of parsing, checks and rendering\n}",
+        "func handle(w http.ResponseWriter, r *http.Request) {\n\torder, err := parse(r)\n\tif err != nil {\n\t\thttp.Error(w, err.Error(), 400)\n\t\treturn\n\t}\n\trender(w, order)\n}",
+    ),
+    (
+        "go_too_many_parameters",
+        SupportedLanguage::Go,
+        "Functions with six or more parameters.",
+        "func Connect(host string, port int, user string, password string, db string, tls bool) {}",
+        "func Connect(config ConnConfig) {}",
+    ),
+    (
+        "go_global_variable",
+        SupportedLanguage::Go,
+        "Package-level `var` declarations, shared mutable state.",
+        "var cache = map[string]int{}",
+        "type Server struct {\n\tcache map[string]int\n}",
+    ),
+    (
+        "go_missing_package_doc",
+        SupportedLanguage::Go,
+        "Package clauses without a doc comment.",
+        "package store",
+        "// Package store persists orders.\npackage store",
+    ),
+    (
+        "go_todo_comment",
+        SupportedLanguage::Go,
+        "TODO, FIXME, XXX and HACK comments.",
+        "// TODO: retry on timeout\nfunc fetch() {}",
+        "// fetch retries on timeout.\nfunc fetch() {}",
+    ),
+    (
+        "go_magic_number",
+        SupportedLanguage::Go,
+        "Integer literals outside constant declarations; `magic_number_allowlist` lists \
+         accepted values.",
+        "func retry(n int) bool {\n\treturn n < 5\n}",
+        "const maxRetries = 5\n\nfunc retry(n int) bool {\n\treturn n < maxRetries\n}",
+    ),
+    (
+        "go_deep_nesting",
+        SupportedLanguage::Go,
+        "if statements nested four or more levels deep.",
+        "func f(a, b, c, d bool) {\n\tif a {\n\t\tif b {\n\t\t\tif c {\n\t\t\t\tif d {\n\t\t\t\t\trun()\n\t\t\t\t}\n\t\t\t}\n\t\t}\n\t}\n}",
+        "func f(a, b, c, d bool) {\n\tif !a || !b || !c || !d {\n\t\treturn\n\t}\n\trun()\n}",
+    ),
+    (
+        "go_loop_variable_capture",
+        SupportedLanguage::Go,
+        "Goroutine closures that use the loop variable. Before Go 1.22 every iteration \
+         shares it.",
+        "func start(jobs []Job) {\n\tfor _, job := range jobs {\n\t\tgo func() {\n\t\t\trun(job)\n\t\t}()\n\t}\n}",
+        "func start(jobs []Job) {\n\tfor _, job := range jobs {\n\t\tgo func(job Job) {\n\t\t\trun(job)\n\t\t}(job)\n\t}\n}",
+    ),
+    (
+        "go_missing_waitgroup_wait",
+        SupportedLanguage::Go,
+        "A sync.WaitGroup declared in a function that never calls Wait() on it or passes \
+         it on.",
+        "func start() {\n\tvar wg sync.WaitGroup\n\twg.Add(1)\n\tgo func() {\n\t\tdefer wg.Done()\n\t}()\n}",
+        "func start() {\n\tvar wg sync.WaitGroup\n\twg.Add(1)\n\tgo func() {\n\t\tdefer wg.Done()\n\t}()\n\twg.Wait()\n}",
+    ),
+    (
+        "go_sleep_synchronization",
+        SupportedLanguage::Go,
+        "time.Sleep after starting a goroutine, guessing how long it takes.",
+        "func start() {\n\tgo work()\n\ttime.Sleep(time.Second)\n}",
+        "func start() {\n\tdone := make(chan struct{})\n\tgo work(done)\n\t<-done\n}",
+    ),
+    (
+        COMMENTED_OUT_CODE,
+        SupportedLanguage::Rust,
+        "Runs of two or more comment lines that parse as code.",
+        "fn total(items: &[u32]) -> u32 {\n    // let total: u32 = items.iter().sum();\n    // println!(\"{}\", total);\n    items.iter().sum()\n}",
+        "fn total(items: &[u32]) -> u32 {\n    items.iter().sum()\n}",
+    ),
+    (
+        NAMING_CONVENTION,
+        SupportedLanguage::Rust,
+        "Functions, types and constants not following the language's naming convention; \
+         `naming` in the config replaces the patterns.",
+        "fn parseHeader(line: &str) -> usize {\n    line.len()\n}",
+        "fn parse_header(line: &str) -> usize {\n    line.len()\n}",
+    ),
+    (
+        NESTED_TERNARY,
+        SupportedLanguage::JavaScript,
+        "Conditional expressions with another conditional as an operand.",
+        "const size = n > 100 ? \"large\" : n > 10 ? \"medium\" : \"small\";",
+        "function sizeOf(n) {\n  if (n > 100) return \"large\";\n  if (n > 10) return \"medium\";\n  return \"small\";\n}",
+    ),
+    (
+        COMPLEX_BOOLEAN_EXPRESSION,
+        SupportedLanguage::JavaScript,
+        "Conditions with more `&&`/`||` operators than `max_boolean_operators` (3 by default).",
+        "if ((a && b) || (c && !d) || e) {\n  run();\n}",
+        "const ready = (a && b) || (c && !d);\nif (ready || e) {\n  run();\n}",
+    ),
+    (
+        LONG_CALL_CHAIN,
+        SupportedLanguage::JavaScript,
+        "Method chains longer than `max_chain_length` (6 by default), hard to debug one \
+         step at a time.",
+        "const names = users.filter(active).map(name).filter(Boolean).map(trim).sort().slice(0, 10).join(\", \");",
+        "const active = users.filter(isActive).map(name);\nconst names = active.filter(Boolean).map(trim).sort();\nconst list = names.slice(0, 10).join(\", \");",
+    ),
+    (
+        EMPTY_BLOCK,
+        SupportedLanguage::JavaScript,
+        "Empty if, else and catch blocks with no comment saying why.",
+        "try {\n  save();\n} catch (e) {\n}",
+        "try {\n  save();\n} catch (e) {\n  // Saving is best effort; the next sync retries it\n}",
+    ),
+    (
+        EMPTY_FUNCTION,
+        SupportedLanguage::Rust,
+        "Named functions with an empty body and no comment saying why.",
+        "fn on_shutdown() {}",
+        "fn on_shutdown() {\n    // Nothing to release: every handle closes on drop\n}",
+    ),
+    (
+        LINE_TOO_LONG,
+        SupportedLanguage::Rust,
+        "Lines longer than `max_line_length` characters (off unless set).",
+        "",
+        "",
+    ),
+    (
+        FILE_TOO_LONG,
+        SupportedLanguage::Rust,
+        "Files longer than `max_file_lines` lines (off unless set).",
+        "",
+        "",
+    ),
+];
+
+// The bundled documentation of a built-in rule
+pub fn bundled_doc(rule: &str) -> Option<RuleDoc> {
+    let text = |s: &str| (!s.is_empty()).then(|| s.to_string());
+    DOCS.iter()
+        .find(|(name, ..)| *name == rule)
+        .map(|(_, _, description, before, after)| RuleDoc {
+            description: text(description),
+            before: text(before),
+            after: text(after),
+        })
+}
+
+// One rule as configured, with the languages it is defined for. A rule shared by
+// several languages (e.g. JavaScript's rules in the React analyzer) is listed once.
+#[derive(Debug, Clone)]
+pub struct RuleExplanation {
+    pub name: String,
+    pub languages: Vec<SupportedLanguage>,
+    pub severity: Severity,
+    pub category: Option<String>,
+    // None for checks computed in code, whose messages depend on the finding, and
+    // which have no query
+    pub message: Option<String>,
+    pub suggestion: Option<String>,
+    pub query: Option<String>,
+    pub doc: RuleDoc,
+}
+
+impl RuleExplanation {
+    pub fn to_json(&self) -> Value {
+        let languages: Vec<&str> = self.languages.iter().map(|l| l.name()).collect();
+        json!({
+            "name": self.name,
+            "languages": languages,
+            "severity": self.severity.as_str(),
+            "category": self.category,
+            "message": self.message,
+            "suggestion": self.suggestion,
+            "query": self.query,
+            "description": self.doc.description,
+            "example": {"before": self.doc.before, "after": self.doc.after},
+        })
+    }
+
+    pub fn format_text(&self) -> String {
+        let mut out = self.name.clone();
+        if !self.languages.is_empty() {
+            let languages: Vec<&str> = self.languages.iter().map(|l| l.name()).collect();
+            out.push_str(&format!(" ({})", languages.join(", ")));
+        }
+        out.push_str(&format!("\nSeverity: {}", self.severity.as_str()));
+        if let Some(category) = &self.category {
+            out.push_str(&format!("\nCategory: {}", category));
+        }
+        if let Some(description) = &self.doc.description {
+            out.push_str(&format!("\n\n{}", description));
+        }
+        let lines = [("Message", &self.message), ("Suggestion", &self.suggestion)];
+        let mut lines = lines
+            .iter()
+            .filter_map(|(label, text)| Some(format!("{}: {}", label, text.as_deref()?)))
+            .peekable();
+        if lines.peek().is_some() {
+            out.push_str(&format!("\n\n{}", lines.collect::<Vec<_>>().join("\n")));
+        }
+        let block = |title: &str, text: &str| format!("\n\n{}:\n{}", title, indent(text));
+        match &self.query {
+            Some(query) => out.push_str(&block("Query", &dedent_query(query))),
+            None => out.push_str("\n\nQuery: none, computed from the syntax tree"),
+        }
+        if let Some(before) = &self.doc.before {
+            out.push_str(&block("Before", before));
+        }
+        if let Some(after) = &self.doc.after {
+            out.push_str(&block("After", after));
+        }
+        out.push('\n');
+        out
+    }
+}
+
+// Built-in queries are written inside Rust string literals: their first line starts
+// at the literal and the rest carry the source indentation
+fn dedent_query(query: &str) -> String {
+    let mut lines = query.lines();
+    let first = lines.next().unwrap_or("").trim();
+    let rest: Vec<&str> = lines.collect();
+    let margin = rest
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    std::iter::once(first.to_string())
+        .chain(
+            rest.iter()
+                .map(|line| format!("  {}", line.get(margin..).unwrap_or(""))),
+        )
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn indent(text: &str) -> String {
+    text.lines()
+        .map(|line| format!("    {}", line.trim_end()))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// Every rule named `name` that `rules` enables in some language, built-in or custom.
+// Empty when no language has it.
+pub fn explain(name: &str, rules: &RuleConfig) -> Vec<RuleExplanation> {
+    let mut explanations: Vec<RuleExplanation> = Vec::new();
+    for language in SupportedLanguage::ALL {
+        let Some(analyzer) = language.analyzer_for_path(Path::new(""), rules) else {
+            continue;
+        };
+        for rule in analyzer.rules().iter().filter(|rule| rule.name == name) {
+            let same = explanations.iter_mut().find(|e| {
+                e.query.as_deref() == Some(rule.query.as_str()) && e.severity == rule.severity
+            });
+            if let Some(existing) = same {
+                existing.languages.push(language);
+                continue;
+            }
+            explanations.push(RuleExplanation {
+                name: rule.name.clone(),
+                languages: vec![language],
+                severity: rule.severity.clone(),
+                category: rule.category.clone(),
+                message: Some(rule.message_template.clone()),
+                suggestion: rule.suggestion.clone(),
+                query: Some(rule.query.clone()),
+                doc: rule
+                    .doc
+                    .clone()
+                    .or_else(|| bundled_doc(&rule.name))
+                    .unwrap_or_default(),
+            });
+        }
+    }
+    if explanations.is_empty() {
+        if let Some((_, severity)) = CHECKS.iter().find(|(check, _)| *check == name) {
+            let doc = bundled_doc(name).unwrap_or_default();
+            explanations.push(RuleExplanation {
+                name: name.to_string(),
+                languages: Vec::new(),
+                severity: severity.clone(),
+                category: None,
+                message: None,
+                suggestion: None,
+                query: None,
+                doc,
+            });
+        }
+    }
+    explanations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::CodeAnalyzer;
+
+    #[test]
+    fn test_built_in_rules_explained_with_working_examples() {
+        // Every built-in rule is documented ...
+        for language in SupportedLanguage::ALL {
+            for rule in language.analyzer().iter().flat_map(CodeAnalyzer::rules) {
+                assert!(bundled_doc(&rule.name).is_some(), "{}", rule.name);
+            }
+        }
+        // ... and its example is flagged before the fix and not after it. Size rules
+        // only sketch the long function, and these queries also match the fixed code.
+        let sketched = [
+            "large_function",
+            "go_large_function",
+            "react_large_component",
+        ];
+        let over_matching = [
+            "go_missing_error_check",
+            "go_missing_package_doc",
+            "go_todo_comment",
+        ];
+        for (rule, language, _, before, after) in DOCS {
+            if sketched.contains(rule) || before.is_empty() {
+                continue;
+            }
+            let analyzer = language.analyzer().unwrap();
+            let fires = |source: &str| {
+                analyzer
+                    .analyze(source, &language.tree_sitter_language())
+                    .unwrap()
+                    .iter()
+                    .any(|r| r.rule_name == *rule)
+            };
+            assert!(fires(before), "{} before", rule);
+            assert!(
+                over_matching.contains(rule) || !fires(after),
+                "{} after",
+                rule
+            );
+        }
+
+        let unwrap = explain("unwrap_usage", &RuleConfig::default());
+        assert_eq!(unwrap.len(), 1);
+        assert_eq!(unwrap[0].languages, [SupportedLanguage::Rust]);
+        let text = unwrap[0].format_text();
+        assert!(text.starts_with("unwrap_usage (Rust)\nSeverity: Warning"));
+        assert!(text.contains("\nQuery:\n    (call_expression"));
+        assert!(text.contains("\nAfter:\n    fn port() -> Result<u16"));
+
+        let console = explain("console_log", &RuleConfig::default());
+        assert_eq!(
+            console[0].languages,
+            [SupportedLanguage::JavaScript, SupportedLanguage::Tsx]
+        );
+        assert_eq!(
+            explain("nested_ternary", &RuleConfig::default())[0].query,
+            None
+        );
+        let discarded = explain("discarded_result", &RuleConfig::default())[0].format_text();
+        assert!(discarded.contains("\n    (let_declaration pattern: \"_\""));
+        assert!(explain("no_such_rule", &RuleConfig::default()).is_empty());
+
+        let script = crate::script::ScriptRule::from_json(&json!({
+            "language": "go",
+            "name": "no_println",
+            "query": "(call_expression function: (selector_expression) @call)",
+            "script": "text == \"fmt.Println\"",
+            "description": "Printing from library code",
+            "example": {"before": "fmt.Println(x)", "after": "log.Debug(x)"}
+        }))
+        .unwrap();
+        let rules = RuleConfig {
+            scripts: vec![script],
+            ..RuleConfig::default()
+        };
+        let custom = &explain("no_println", &rules)[0];
+        assert_eq!(custom.languages, [SupportedLanguage::Go]);
+        assert_eq!(
+            custom.doc.description.as_deref(),
+            Some("Printing from library code")
+        );
+        assert_eq!(custom.doc.after.as_deref(), Some("log.Debug(x)"));
+    }
+}
@@ -0,0 +1,378 @@
+use crate::analyzer::{run_analysis, CodeAnalyzer};
+use crate::ast::parse_file_with_language;
+use crate::batch::{analyze_many, BatchConfig};
+use crate::source::read_source;
+use std::ffi::{c_char, CStr, CString};
+use std::path::{Path, PathBuf};
+use tree_sitter::Language;
+
+/// # Safety
+///
+/// This function needs to be exported so strings can be derefenced for FFI;
+#[no_mangle]
+pub unsafe extern "C" fn free_string(s: *mut c_char) {
+    if !s.is_null() {
+        let _ = CString::from_raw(s);
+    }
+}
+
+fn c_path_to_str<'a>(file_path: *const c_char) -> Option<&'a str> {
+    if file_path.is_null() {
+        return None;
+    }
+    let c_str = unsafe { CStr::from_ptr(file_path) };
+    c_str.to_str().ok()
+}
+
+unsafe fn utf16_to_string(data: *const u16, len: usize) -> Option<String> {
+    if data.is_null() {
+        return None;
+    }
+    let units = std::slice::from_raw_parts(data, len);
+    // Reject unpaired surrogates rather than silently substituting U+FFFD
+    String::from_utf16(units).ok()
+}
+
+// Windows paths are UTF-16 already, so they are taken as-is, unpaired surrogates and all
+#[cfg(windows)]
+unsafe fn utf16_to_path(data: *const u16, len: usize) -> Option<PathBuf> {
+    use std::os::windows::ffi::OsStringExt;
+    if data.is_null() {
+        return None;
+    }
+    let units = std::slice::from_raw_parts(data, len);
+    Some(std::ffi::OsString::from_wide(units).into())
+}
+
+// Elsewhere a path must be valid UTF-16 to be converted
+#[cfg(not(windows))]
+unsafe fn utf16_to_path(data: *const u16, len: usize) -> Option<PathBuf> {
+    utf16_to_string(data, len).map(PathBuf::from)
+}
+
+fn into_c_string<E>(result: Result<String, E>) -> *mut c_char {
+    match result {
+        Ok(result) => match CString::new(result) {
+            Ok(c_string) => c_string.into_raw(),
+            Err(_) => std::ptr::null_mut(),
+        },
+        Err(_) => std::ptr::null_mut(),
+    }
+}
+
+pub fn parse_ast(file_path: *const c_char, language: Language) -> *mut c_char {
+    let file_path_str = match c_path_to_str(file_path) {
+        Some(s) => s,
+        None => return std::ptr::null_mut(),
+    };
+
+    into_c_string(parse_file_with_language(file_path_str, language))
+}
+
+pub fn analyze_code_with_analyzer(
+    file_path: *const c_char,
+    language: Language,
+    analyzer: CodeAnalyzer,
+) -> *mut c_char {
+    let file_path_str = match c_path_to_str(file_path) {
+        Some(s) => s,
+        None => return std::ptr::null_mut(),
+    };
+
+    into_c_string(run_analysis(file_path_str, language, analyzer))
+}
+
+// Functions exported for FFF
+#[no_mangle]
+pub extern "C" fn parse_rust_ast(file_path: *const c_char) -> *mut c_char {
+    let language = tree_sitter_rust::LANGUAGE;
+    parse_ast(file_path, language.into())
+}
+
+#[no_mangle]
+pub extern "C" fn parse_java_ast(file_path: *const c_char) -> *mut c_char {
+    let language = tree_sitter_java::LANGUAGE;
+    parse_ast(file_path, language.into())
+}
+
+#[no_mangle]
+pub extern "C" fn parse_zig_ast(file_path: *const c_char) -> *mut c_char {
+    let language = tree_sitter_zig::LANGUAGE;
+    parse_ast(file_path, language.into())
+}
+
+#[no_mangle]
+pub extern "C" fn parse_c_ast(file_path: *const c_char) -> *mut c_char {
+    let language = tree_sitter_c::LANGUAGE;
+    parse_ast(file_path, language.into())
+}
+
+#[no_mangle]
+pub extern "C" fn parse_js_ast(file_path: *const c_char) -> *mut c_char {
+    let language = tree_sitter_javascript::LANGUAGE;
+    parse_ast(file_path, language.into())
+}
+
+#[no_mangle]
+pub extern "C" fn parse_ts_ast(file_path: *const c_char) -> *mut c_char {
+    // .tsx needs the JSX-aware grammar, which misreads `<T>value` casts in plain .ts
+    let language = match c_path_to_str(file_path) {
+        Some(path) if path.to_lowercase().ends_with(".tsx") => tree_sitter_typescript::LANGUAGE_TSX,
+        _ => tree_sitter_typescript::LANGUAGE_TYPESCRIPT,
+    };
+    parse_ast(file_path, language.into())
+}
+
+#[no_mangle]
+pub extern "C" fn parse_cpp_ast(file_path: *const c_char) -> *mut c_char {
+    let language = tree_sitter_cpp::LANGUAGE;
+    parse_ast(file_path, language.into())
+}
+#[no_mangle]
+pub extern "C" fn analyze_rust_code(file_path: *const c_char) -> *mut c_char {
+    let language = tree_sitter_rust::LANGUAGE;
+    let analyzer = CodeAnalyzer::new_rust_analyzer();
+    analyze_code_with_analyzer(file_path, language.into(), analyzer)
+}
+
+#[no_mangle]
+pub extern "C" fn analyze_go_code(file_path: *const c_char) -> *mut c_char {
+    let language = tree_sitter_go::LANGUAGE;
+    let analyzer = CodeAnalyzer::new_go_analyzer();
+    analyze_code_with_analyzer(file_path, language.into(), analyzer)
+}
+
+#[no_mangle]
+pub extern "C" fn analyze_js_code(file_path: *const c_char) -> *mut c_char {
+    let language = tree_sitter_javascript::LANGUAGE;
+    let analyzer = CodeAnalyzer::new_javascript_analyzer();
+    analyze_code_with_analyzer(file_path, language.into(), analyzer)
+}
+
+fn parse_file_by_name(file_path: &Path, language: &str) -> Result<String, String> {
+    let source_code = read_source(file_path, None).map_err(|e| e.to_string())?;
+    crate::parse(&source_code, language)
+}
+
+fn analyze_file_by_name(file_path: &Path, language: &str) -> Result<String, String> {
+    let source_code = read_source(file_path, None).map_err(|e| e.to_string())?;
+    crate::analyze(&source_code, language)
+}
+
+/// # Safety
+///
+/// `file_path` must point to `len` UTF-16 code units and `language` to a
+/// NUL-terminated language id such as "rust" or "cpp". On Windows the path is used
+/// as-is; elsewhere a path with unpaired surrogates is rejected and NULL returned.
+#[no_mangle]
+pub unsafe extern "C" fn treescan_parse_file_utf16(
+    file_path: *const u16,
+    len: usize,
+    language: *const c_char,
+) -> *mut c_char {
+    match (utf16_to_path(file_path, len), c_path_to_str(language)) {
+        (Some(path), Some(language)) => into_c_string(parse_file_by_name(&path, language)),
+        _ => std::ptr::null_mut(),
+    }
+}
+
+/// # Safety
+///
+/// `source` must point to `len` UTF-16 code units and `language` to a
+/// NUL-terminated language id such as "rust" or "cpp".
+#[no_mangle]
+pub unsafe extern "C" fn treescan_parse_source_utf16(
+    source: *const u16,
+    len: usize,
+    language: *const c_char,
+) -> *mut c_char {
+    match (utf16_to_string(source, len), c_path_to_str(language)) {
+        (Some(source), Some(language)) => into_c_string(crate::parse(&source, language)),
+        _ => std::ptr::null_mut(),
+    }
+}
+
+/// # Safety
+///
+/// `file_path` must point to `len` UTF-16 code units and `language` to a
+/// NUL-terminated language id such as "rust" or "go". On Windows the path is used
+/// as-is; elsewhere a path with unpaired surrogates is rejected and NULL returned.
+#[no_mangle]
+pub unsafe extern "C" fn treescan_analyze_file_utf16(
+    file_path: *const u16,
+    len: usize,
+    language: *const c_char,
+) -> *mut c_char {
+    match (utf16_to_path(file_path, len), c_path_to_str(language)) {
+        (Some(path), Some(language)) => into_c_string(analyze_file_by_name(&path, language)),
+        _ => std::ptr::null_mut(),
+    }
+}
+
+/// # Safety
+///
+/// `source` must point to `len` UTF-16 code units and `language` to a
+/// NUL-terminated language id such as "rust" or "go".
+#[no_mangle]
+pub unsafe extern "C" fn treescan_analyze_source_utf16(
+    source: *const u16,
+    len: usize,
+    language: *const c_char,
+) -> *mut c_char {
+    match (utf16_to_string(source, len), c_path_to_str(language)) {
+        (Some(source), Some(language)) => into_c_string(crate::analyze(&source, language)),
+        _ => std::ptr::null_mut(),
+    }
+}
+
+/// # Safety
+///
+/// All arguments must be NUL-terminated strings. `path_or_source` is read as a
+/// file when it names an existing path and is otherwise treated as source text.
+#[no_mangle]
+pub unsafe extern "C" fn treescan_query(
+    path_or_source: *const c_char,
+    language: *const c_char,
+    query_text: *const c_char,
+) -> *mut c_char {
+    let (input, language, query_text) = match (
+        c_path_to_str(path_or_source),
+        c_path_to_str(language),
+        c_path_to_str(query_text),
+    ) {
+        (Some(input), Some(language), Some(query_text)) => (input, language, query_text),
+        _ => return std::ptr::null_mut(),
+    };
+
+    if std::path::Path::new(input).is_file() {
+        match read_source(input, None) {
+            Ok(source) => into_c_string(crate::query(&source, language, query_text)),
+            Err(_) => std::ptr::null_mut(),
+        }
+    } else {
+        into_c_string(crate::query(input, language, query_text))
+    }
+}
+
+// Returns a JSON array of {name, extensions, parse, analyze} for every built-in grammar
+#[no_mangle]
+pub extern "C" fn treescan_supported_languages() -> *mut c_char {
+    let languages = crate::language::supported_languages_json();
+    into_c_string(serde_json::to_string(&languages))
+}
+
+// Compiles every built-in analyzer's queries up front, so a host can pay that cost
+// at startup rather than on its first request per language. Returns 0 on success.
+#[no_mangle]
+pub extern "C" fn treescan_warmup() -> i32 {
+    match CodeAnalyzer::preload_all() {
+        Ok(_) => 0,
+        Err(_) => -1,
+    }
+}
+
+/// # Safety
+///
+/// `paths_json` must be a NUL-terminated JSON array of file paths and
+/// `config_json` a NUL-terminated JSON object (or NULL for defaults) with optional
+/// `threads`, `max_issues_per_file`, `max_file_size`, `parse_timeout_ms`,
+/// `cache_dir` and `column_unit` keys.
+#[no_mangle]
+pub unsafe extern "C" fn treescan_analyze_many(
+    paths_json: *const c_char,
+    config_json: *const c_char,
+) -> *mut c_char {
+    let paths: Vec<PathBuf> =
+        match c_path_to_str(paths_json).map(serde_json::from_str::<Vec<String>>) {
+            Some(Ok(paths)) => paths.into_iter().map(PathBuf::from).collect(),
+            _ => return std::ptr::null_mut(),
+        };
+    let config = if config_json.is_null() {
+        BatchConfig::default()
+    } else {
+        match c_path_to_str(config_json).map(serde_json::from_str::<serde_json::Value>) {
+            Some(Ok(config)) => BatchConfig::from_json(&config),
+            _ => return std::ptr::null_mut(),
+        }
+    };
+
+    into_c_string(serde_json::to_string(&analyze_many(&paths, &config)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn take_string(ptr: *mut c_char) -> String {
+        assert!(!ptr.is_null());
+        let result = unsafe { CStr::from_ptr(ptr) }.to_str().unwrap().to_string();
+        unsafe { free_string(ptr) };
+        result
+    }
+
+    #[test]
+    fn test_utf16_source_entry_points() {
+        let source: Vec<u16> = "fn größe() { x.unwrap(); }".encode_utf16().collect();
+        let language = CString::new("rust").unwrap();
+
+        let ast = unsafe {
+            treescan_parse_source_utf16(source.as_ptr(), source.len(), language.as_ptr())
+        };
+        assert!(take_string(ast).contains("\"größe\""));
+
+        let report = unsafe {
+            treescan_analyze_source_utf16(source.as_ptr(), source.len(), language.as_ptr())
+        };
+        assert!(take_string(report).contains("unwrap_usage"));
+
+        // An unpaired surrogate is rejected instead of being converted lossily
+        let invalid = [0xD800u16];
+        let result = unsafe {
+            treescan_parse_source_utf16(invalid.as_ptr(), invalid.len(), language.as_ptr())
+        };
+        assert!(result.is_null());
+    }
+
+    #[test]
+    fn test_utf16_file_entry_points() {
+        let path: Vec<u16> = concat!(env!("CARGO_MANIFEST_DIR"), "/src/lib.rs")
+            .encode_utf16()
+            .collect();
+        let language = CString::new("rust").unwrap();
+
+        let ast =
+            unsafe { treescan_parse_file_utf16(path.as_ptr(), path.len(), language.as_ptr()) };
+        assert!(take_string(ast).contains("source_file"));
+        let report =
+            unsafe { treescan_analyze_file_utf16(path.as_ptr(), path.len(), language.as_ptr()) };
+        assert!(take_string(report).contains("\"score\""));
+
+        let missing: Vec<u16> = "/nonexistent/main.rs".encode_utf16().collect();
+        let result = unsafe {
+            treescan_parse_file_utf16(missing.as_ptr(), missing.len(), language.as_ptr())
+        };
+        assert!(result.is_null());
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_utf16_file_path_rejects_unpaired_surrogates() {
+        let mut path: Vec<u16> = "a.rs".encode_utf16().collect();
+        path.insert(1, 0xDC00);
+        let language = CString::new("rust").unwrap();
+        let result =
+            unsafe { treescan_parse_file_utf16(path.as_ptr(), path.len(), language.as_ptr()) };
+        assert!(result.is_null());
+    }
+
+    #[test]
+    fn test_query_entry_point() {
+        let source = CString::new("package main\nfunc main() { panic(1) }").unwrap();
+        let language = CString::new("go").unwrap();
+        let query = CString::new("(call_expression function: (identifier) @func)").unwrap();
+
+        let result = unsafe { treescan_query(source.as_ptr(), language.as_ptr(), query.as_ptr()) };
+        let captures: serde_json::Value = serde_json::from_str(&take_string(result)).unwrap();
+        assert_eq!(captures[0]["text"], "panic");
+    }
+}
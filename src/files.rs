@@ -0,0 +1,279 @@
+use crate::generated::is_generated_file;
+use crate::language::{unsupported_language_name, SupportedLanguage};
+use crate::sfc;
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Directories that never contain first-party sources worth scanning
+const SKIPPED_DIRS: &[&str] = &["target", "node_modules", "vendor"];
+
+#[derive(Debug, Clone, Default)]
+pub struct WalkOptions {
+    pub follow_symlinks: bool,
+    // Generated sources are skipped unless set
+    pub include_generated: bool,
+}
+
+// An entry the walker could not or would not descend into
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkippedEntry {
+    pub path: PathBuf,
+    pub reason: String,
+}
+
+impl SkippedEntry {
+    fn new(path: &Path, reason: impl Into<String>) -> Self {
+        SkippedEntry {
+            path: path.to_path_buf(),
+            reason: reason.into(),
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({ "path": self.path.display().to_string(), "reason": self.reason })
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct WalkResult {
+    pub files: Vec<PathBuf>,
+    pub skipped: Vec<SkippedEntry>,
+}
+
+// Expands a file or directory argument into the supported source files beneath it,
+// in a stable (sorted) order. Unreadable directories, special files, symlinks
+// (unless followed) and symlink cycles are recorded in `skipped` instead of
+// aborting the walk.
+pub fn walk_source_files(path: &Path, options: &WalkOptions) -> WalkResult {
+    let mut result = WalkResult::default();
+    if path.is_dir() {
+        let mut visited = HashSet::new();
+        if let Ok(canonical) = fs::canonicalize(path) {
+            visited.insert(canonical);
+        }
+        visit_dir(path, options, &mut visited, &mut result);
+    } else {
+        result.files.push(path.to_path_buf());
+    }
+    result
+}
+
+fn visit_dir(
+    dir: &Path,
+    options: &WalkOptions,
+    visited: &mut HashSet<PathBuf>,
+    result: &mut WalkResult,
+) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            result.skipped.push(SkippedEntry::new(dir, e.to_string()));
+            return;
+        }
+    };
+    let mut paths = Vec::new();
+    for entry in entries {
+        match entry {
+            Ok(entry) => paths.push(entry.path()),
+            Err(e) => result.skipped.push(SkippedEntry::new(dir, e.to_string())),
+        }
+    }
+    paths.sort();
+
+    for path in paths {
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+        let metadata = match fs::symlink_metadata(&path) {
+            Ok(metadata) => metadata,
+            Err(e) => {
+                result.skipped.push(SkippedEntry::new(&path, e.to_string()));
+                continue;
+            }
+        };
+
+        let file_type = if metadata.file_type().is_symlink() {
+            if !options.follow_symlinks {
+                if is_candidate(&path) || path.is_dir() {
+                    result.skipped.push(SkippedEntry::new(
+                        &path,
+                        "symbolic link (use --follow-symlinks to include)",
+                    ));
+                }
+                continue;
+            }
+            match fs::metadata(&path) {
+                Ok(target) => target.file_type(),
+                Err(e) => {
+                    result.skipped.push(SkippedEntry::new(
+                        &path,
+                        format!("broken symbolic link: {}", e),
+                    ));
+                    continue;
+                }
+            }
+        } else {
+            metadata.file_type()
+        };
+
+        if file_type.is_dir() {
+            if name.starts_with('.') || SKIPPED_DIRS.contains(&name) {
+                continue;
+            }
+            // Canonical paths catch both symlink loops and links back into the tree
+            match fs::canonicalize(&path) {
+                Ok(canonical) => {
+                    if visited.insert(canonical) {
+                        visit_dir(&path, options, visited, result);
+                    } else {
+                        result.skipped.push(SkippedEntry::new(
+                            &path,
+                            "directory already visited (symbolic link cycle)",
+                        ));
+                    }
+                }
+                Err(e) => result.skipped.push(SkippedEntry::new(&path, e.to_string())),
+            }
+        } else if !is_candidate(&path) {
+            if let Some(language) = unsupported_language_name(&path) {
+                result.skipped.push(SkippedEntry::new(
+                    &path,
+                    format!("no grammar available for {}", language),
+                ));
+            }
+            continue;
+        } else if file_type.is_file() {
+            if !options.include_generated && is_generated_file(&path) {
+                result.skipped.push(SkippedEntry::new(
+                    &path,
+                    "generated file (use --include-generated to include)",
+                ));
+                continue;
+            }
+            result.files.push(path);
+        } else {
+            result.skipped.push(SkippedEntry::new(
+                &path,
+                "not a regular file (socket, FIFO or device)",
+            ));
+        }
+    }
+}
+
+fn is_candidate(path: &Path) -> bool {
+    SupportedLanguage::detect(path).is_some() || sfc::is_component(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+    #[cfg(unix)]
+    use std::os::unix::fs::symlink;
+
+    fn reasons(result: &WalkResult) -> Vec<&str> {
+        result.skipped.iter().map(|s| s.reason.as_str()).collect()
+    }
+
+    #[test]
+    fn test_walk_lists_supported_files_in_order() {
+        let root = TempDir::new("walk");
+        let lib = root.write("src/lib.rs", "");
+        let main = root.write("src/bin/main.go", "package main");
+        root.write("src/notes.txt", "");
+        root.write("target/debug/build.rs", "");
+        root.write(".git/hooks/hook.js", "");
+
+        let result = walk_source_files(&root, &WalkOptions::default());
+        assert_eq!(result.files, vec![main, lib]);
+        assert!(result.skipped.is_empty());
+    }
+
+    #[test]
+    fn test_walk_reports_files_without_a_grammar() {
+        let root = TempDir::new("walk");
+        root.write("Makefile", "all:\n");
+
+        let result = walk_source_files(&root, &WalkOptions::default());
+        assert!(result.files.is_empty());
+        assert_eq!(reasons(&result), ["no grammar available for Makefile"]);
+    }
+
+    #[test]
+    fn test_walk_skips_generated_files_unless_included() {
+        let root = TempDir::new("walk");
+        let generated = root.write("api.pb.go", "package api");
+
+        let result = walk_source_files(&root, &WalkOptions::default());
+        assert!(result.files.is_empty());
+        assert!(reasons(&result)[0].starts_with("generated file"));
+
+        let options = WalkOptions {
+            include_generated: true,
+            ..WalkOptions::default()
+        };
+        assert_eq!(walk_source_files(&root, &options).files, vec![generated]);
+    }
+
+    #[test]
+    fn test_walk_passes_file_arguments_through() {
+        let root = TempDir::new("walk");
+        // Missing paths too: reading them reports the error per file
+        let missing = root.join("missing.rs");
+        let result = walk_source_files(&missing, &WalkOptions::default());
+        assert_eq!(result.files, vec![missing]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_walk_skips_symlinks_unless_followed() {
+        let root = TempDir::new("walk");
+        let main = root.write("src/main.rs", "fn main() {}");
+        symlink(&main, root.join("link.rs")).unwrap();
+
+        let result = walk_source_files(&root, &WalkOptions::default());
+        assert_eq!(result.files, vec![main.clone()]);
+        assert!(reasons(&result)[0].starts_with("symbolic link"));
+
+        let followed = WalkOptions {
+            follow_symlinks: true,
+            ..WalkOptions::default()
+        };
+        let result = walk_source_files(&root, &followed);
+        assert_eq!(result.files, vec![root.join("link.rs"), main]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_walk_skips_symlink_cycles() {
+        let root = TempDir::new("walk");
+        let main = root.write("src/main.rs", "fn main() {}");
+        symlink(&*root, root.join("src/loop")).unwrap();
+
+        let followed = WalkOptions {
+            follow_symlinks: true,
+            ..WalkOptions::default()
+        };
+        let result = walk_source_files(&root, &followed);
+        assert_eq!(result.files, vec![main]);
+        assert_eq!(
+            reasons(&result),
+            ["directory already visited (symbolic link cycle)"]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_walk_reports_broken_symlinks() {
+        let root = TempDir::new("walk");
+        symlink(root.join("gone.rs"), root.join("link.rs")).unwrap();
+
+        let followed = WalkOptions {
+            follow_symlinks: true,
+            ..WalkOptions::default()
+        };
+        let result = walk_source_files(&root, &followed);
+        assert!(result.files.is_empty());
+        assert!(reasons(&result)[0].starts_with("broken symbolic link"));
+    }
+}
@@ -0,0 +1,148 @@
+use crate::ast::{parse_tree_with_timeout, ParseError};
+use crate::diagnostics::collect_syntax_errors;
+use crate::language::SupportedLanguage;
+use crate::position::ColumnUnit;
+use std::time::Duration;
+use tree_sitter::{InputEdit, Node, Point, Tree};
+
+// Snippets are tried bare first, then inside each wrapper in turn. Prefixes end
+// and suffixes start with a newline so the snippet's columns are untouched.
+fn scaffolds(lang: SupportedLanguage) -> &'static [(&'static str, &'static str)] {
+    match lang {
+        SupportedLanguage::Rust => &[
+            ("", ""),
+            ("fn __fragment() {\n", "\n}"),
+            ("fn __fragment() { match () {\n", "\n} }"),
+            ("impl __Fragment {\n", "\n}"),
+        ],
+        SupportedLanguage::Java => &[
+            ("", ""),
+            ("class __Fragment {\n", "\n}"),
+            ("class __Fragment { void __fragment() {\n", "\n} }"),
+        ],
+        SupportedLanguage::Zig => &[("", ""), ("fn __fragment() void {\n", "\n}")],
+        SupportedLanguage::C | SupportedLanguage::Cpp => {
+            &[("", ""), ("void __fragment(void) {\n", "\n}")]
+        }
+        SupportedLanguage::JavaScript | SupportedLanguage::TypeScript | SupportedLanguage::Tsx => {
+            &[
+                ("", ""),
+                ("class __Fragment {\n", "\n}"),
+                ("function __fragment() {\n", "\n}"),
+            ]
+        }
+        SupportedLanguage::Go => &[
+            ("package __fragment\n", ""),
+            ("package __fragment\nfunc __fragment() {\n", "\n}"),
+            ("package __fragment\ntype __Fragment struct {\n", "\n}"),
+        ],
+    }
+}
+
+// A snippet parsed inside whatever scaffolding it needed. The tree has been
+// edited so every position and byte offset is relative to the snippet itself.
+pub struct Fragment {
+    pub tree: Tree,
+    start_byte: usize,
+    end_byte: usize,
+}
+
+impl Fragment {
+    // The innermost node spanning the whole snippet: the statement itself for a
+    // single statement, otherwise the block (or file) the scaffolding put it in
+    pub fn root(&self) -> Node<'_> {
+        let root = self.tree.root_node();
+        root.descendant_for_byte_range(self.start_byte, self.end_byte)
+            .unwrap_or(root)
+    }
+}
+
+// Parses a code fragment (statements, an expression, class members, match arms, ...)
+// that isn't a valid file on its own. The first scaffolding that parses without
+// errors wins; if none does, the one with the fewest syntax errors is kept.
+pub fn parse_fragment(
+    source: &str,
+    lang: SupportedLanguage,
+    timeout: Option<Duration>,
+) -> Result<Fragment, ParseError> {
+    let language = lang.tree_sitter_language();
+    let mut best: Option<(usize, Tree, &str, &str)> = None;
+    for &(prefix, suffix) in scaffolds(lang) {
+        let wrapped = format!("{}{}{}", prefix, source, suffix);
+        let tree = parse_tree_with_timeout(&wrapped, &language, timeout)?;
+        let errors = collect_syntax_errors(&tree, &wrapped, ColumnUnit::Byte).len();
+        if best.as_ref().is_none_or(|(fewest, ..)| errors < *fewest) {
+            best = Some((errors, tree, prefix, suffix));
+        }
+        if errors == 0 {
+            break;
+        }
+    }
+    let (_, mut tree, prefix, suffix) = best.ok_or(ParseError::Failed)?;
+
+    // Cut the scaffolding out of the tree's coordinates
+    tree.edit(&InputEdit {
+        start_byte: 0,
+        old_end_byte: prefix.len(),
+        new_end_byte: 0,
+        start_position: Point::new(0, 0),
+        old_end_position: end_point(prefix),
+        new_end_position: Point::new(0, 0),
+    });
+    let source_end = end_point(source);
+    tree.edit(&InputEdit {
+        start_byte: source.len(),
+        old_end_byte: source.len() + suffix.len(),
+        new_end_byte: source.len(),
+        start_position: source_end,
+        old_end_position: end_point(&format!("{}{}", source, suffix)),
+        new_end_position: source_end,
+    });
+
+    let trimmed = source.trim_start();
+    let start_byte = source.len() - trimmed.len();
+    let end_byte = start_byte + trimmed.trim_end().len();
+    Ok(Fragment {
+        tree,
+        start_byte,
+        end_byte,
+    })
+}
+
+fn end_point(text: &str) -> Point {
+    match text.rfind('\n') {
+        Some(newline) => Point::new(text.matches('\n').count(), text.len() - newline - 1),
+        None => Point::new(0, text.len()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fragments_parse_with_snippet_positions() {
+        let fragment =
+            parse_fragment("let x = 1;\nx.unwrap()", SupportedLanguage::Rust, None).unwrap();
+        assert!(!fragment.tree.root_node().has_error());
+        let root = fragment.root();
+        assert_eq!(root.kind(), "block");
+        let call = root.named_child(1).unwrap();
+        assert_eq!(call.kind(), "call_expression");
+        assert_eq!(call.start_position(), Point::new(1, 0));
+        assert_eq!(call.start_byte(), 11);
+
+        let fragment = parse_fragment("  x := f()\n", SupportedLanguage::Go, None).unwrap();
+        assert!(!fragment.tree.root_node().has_error());
+        assert_eq!(fragment.root().kind(), "short_var_declaration");
+        assert_eq!(fragment.root().start_position(), Point::new(0, 2));
+
+        let fragment = parse_fragment(
+            "public int area() { return w * h; }",
+            SupportedLanguage::Java,
+            None,
+        )
+        .unwrap();
+        assert_eq!(fragment.root().kind(), "method_declaration");
+    }
+}
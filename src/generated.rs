@@ -0,0 +1,68 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+// Markers are only honoured near the top of a file, where generators put them
+const HEADER_BYTES: usize = 1024;
+
+const GENERATED_MARKERS: &[&str] = &["DO NOT EDIT", "@generated", "<auto-generated"];
+
+// File name suffixes produced by protobuf/gRPC, mock generators and bundlers
+const GENERATED_SUFFIXES: &[&str] = &[
+    ".pb.go",
+    "_grpc.pb.go",
+    ".pb.gw.go",
+    "_mock.go",
+    ".pb.cc",
+    ".pb.h",
+    "_pb.js",
+    "_pb.d.ts",
+    ".generated.ts",
+    ".generated.js",
+    ".min.js",
+];
+
+const GENERATED_PREFIXES: &[&str] = &["mock_", "zz_generated"];
+
+pub fn is_generated_path(path: &Path) -> bool {
+    let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+        return false;
+    };
+    GENERATED_SUFFIXES.iter().any(|suffix| name.ends_with(suffix))
+        || GENERATED_PREFIXES.iter().any(|prefix| name.starts_with(prefix))
+}
+
+pub fn has_generated_marker(header: &str) -> bool {
+    GENERATED_MARKERS.iter().any(|marker| header.contains(marker))
+}
+
+// Checks the file name first, then the first kilobyte for a generator marker
+pub fn is_generated_file(path: &Path) -> bool {
+    if is_generated_path(path) {
+        return true;
+    }
+    let mut header = [0u8; HEADER_BYTES];
+    let read = match File::open(path).and_then(|mut file| file.read(&mut header)) {
+        Ok(read) => read,
+        Err(_) => return false,
+    };
+    has_generated_marker(&String::from_utf8_lossy(&header[..read]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generated_detection() {
+        assert!(is_generated_path(Path::new("api/service.pb.go")));
+        assert!(is_generated_path(Path::new("mock_store.go")));
+        assert!(!is_generated_path(Path::new("store.go")));
+
+        assert!(has_generated_marker(
+            "// Code generated by protoc-gen-go. DO NOT EDIT.\npackage api\n"
+        ));
+        assert!(has_generated_marker("/* @generated */\nexport {};\n"));
+        assert!(!has_generated_marker("package main\n"));
+    }
+}
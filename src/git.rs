@@ -0,0 +1,118 @@
+use crate::diff::parse_unified_diff;
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+// Added or modified line numbers per file, keyed by absolute path
+pub type ChangedLines = HashMap<PathBuf, BTreeSet<usize>>;
+
+// Runs `git -C <dir> <args>` and returns its stdout. There is no git crate in the
+// dependency tree, so history is read through the `git` CLI.
+pub fn git(dir: &Path, args: &[&str]) -> Result<String, Box<dyn std::error::Error>> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .map_err(|e| format!("could not run git: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "git {} failed: {}",
+            args[0],
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+        .into());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+// Root of the work tree containing `dir`; git prints paths relative to it
+pub fn toplevel(dir: &Path) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    Ok(PathBuf::from(
+        git(dir, &["rev-parse", "--show-toplevel"])?.trim(),
+    ))
+}
+
+// Lines under `dir` that differ between `base` and the working tree (committed or
+// not). Untracked files are not part of any diff and so have no changed lines.
+pub fn changed_lines(dir: &Path, base: &str) -> Result<ChangedLines, Box<dyn std::error::Error>> {
+    let toplevel = toplevel(dir)?;
+    let diff = git(
+        dir,
+        &[
+            "diff",
+            "--unified=0",
+            "--no-color",
+            "--no-ext-diff",
+            base,
+            "--",
+            ".",
+        ],
+    )?;
+    Ok(parse_unified_diff(&diff)
+        .into_iter()
+        .filter(|file| !file.added_lines.is_empty())
+        .map(|file| (toplevel.join(file.path), file.added_lines))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    // A repository with one commit holding a.rs and same.rs
+    fn repository() -> TempDir {
+        let dir = TempDir::new("git");
+        let run = |args: &[&str]| git(&dir, args).unwrap();
+        run(&["init", "--quiet"]);
+        dir.write("a.rs", "fn a() {}\nfn b() {}\n");
+        dir.write("same.rs", "fn c() {}\n");
+        run(&["add", "."]);
+        run(&[
+            "-c",
+            "user.name=t",
+            "-c",
+            "user.email=t@t",
+            "commit",
+            "--quiet",
+            "-m",
+            "base",
+        ]);
+        dir
+    }
+
+    #[test]
+    fn test_changed_lines_since_base() {
+        let dir = repository();
+        dir.write("a.rs", "fn a() {}\nfn b() { x.unwrap(); }\nfn d() {}\n");
+
+        let changed = changed_lines(&dir, "HEAD").unwrap();
+        let a = toplevel(&dir).unwrap().join("a.rs");
+        assert_eq!(changed.len(), 1);
+        assert_eq!(changed[&a], BTreeSet::from([2, 3]));
+    }
+
+    #[test]
+    fn test_untracked_and_deleted_lines_are_not_changes() {
+        let dir = repository();
+        dir.write("new.rs", "fn e() {}\n");
+        dir.write("a.rs", "fn a() {}\n");
+
+        assert!(changed_lines(&dir, "HEAD").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_unknown_base_is_an_error() {
+        let dir = repository();
+        let error = changed_lines(&dir, "no-such-rev").unwrap_err().to_string();
+        assert!(error.starts_with("git diff failed"));
+    }
+
+    #[test]
+    fn test_outside_a_repository_is_an_error() {
+        let dir = TempDir::new("git");
+        let error = changed_lines(&dir, "HEAD").unwrap_err().to_string();
+        assert!(error.starts_with("git rev-parse failed"));
+    }
+}
@@ -0,0 +1,104 @@
+use std::path::Path;
+use tree_sitter::{Language, LANGUAGE_VERSION, MIN_COMPATIBLE_LANGUAGE_VERSION};
+
+// Symbol a grammar library exports for `name`, as generated by `tree-sitter generate`
+pub fn language_symbol(name: &str) -> String {
+    format!("tree_sitter_{}", name.replace('-', "_"))
+}
+
+// Opens the shared library at `path` and looks up `symbol`. `kind` names the
+// library in errors ("grammar", "plugin"). The library is never closed.
+#[cfg(unix)]
+pub(crate) fn library_symbol(
+    path: &Path,
+    symbol: &str,
+    kind: &str,
+) -> Result<*mut libc::c_void, Box<dyn std::error::Error>> {
+    use std::ffi::{CStr, CString};
+    use std::os::unix::ffi::OsStrExt;
+
+    let filename = CString::new(path.as_os_str().as_bytes())?;
+    let symbol = CString::new(symbol)?;
+    let dl_error = || unsafe {
+        let message = libc::dlerror();
+        if message.is_null() {
+            "unknown error".to_string()
+        } else {
+            CStr::from_ptr(message).to_string_lossy().into_owned()
+        }
+    };
+
+    let handle = unsafe { libc::dlopen(filename.as_ptr(), libc::RTLD_NOW | libc::RTLD_LOCAL) };
+    if handle.is_null() {
+        return Err(format!(
+            "Failed to load {} '{}': {}",
+            kind,
+            path.display(),
+            dl_error()
+        )
+        .into());
+    }
+    let function = unsafe { libc::dlsym(handle, symbol.as_ptr()) };
+    if function.is_null() {
+        let mut kind = kind.to_string();
+        kind[..1].make_ascii_uppercase();
+        return Err(format!(
+            "{} '{}' does not export {}",
+            kind,
+            path.display(),
+            symbol.to_string_lossy()
+        )
+        .into());
+    }
+    Ok(function)
+}
+
+// Loads an external grammar from a shared library (`libtree-sitter-foo.so`), like
+// the tree-sitter CLI does. The library stays loaded for the life of the process,
+// since trees and queries keep pointers into it.
+#[cfg(unix)]
+pub fn load_grammar(path: &Path, name: &str) -> Result<Language, Box<dyn std::error::Error>> {
+    use tree_sitter_language::LanguageFn;
+
+    let function = library_symbol(path, &language_symbol(name), "grammar")?;
+    let language_fn = unsafe {
+        let function: unsafe extern "C" fn() -> *const () = std::mem::transmute(function);
+        LanguageFn::from_raw(function)
+    };
+    let language = Language::new(language_fn);
+    let abi = language.abi_version();
+    if !(MIN_COMPATIBLE_LANGUAGE_VERSION..=LANGUAGE_VERSION).contains(&abi) {
+        return Err(format!(
+            "Grammar '{}' has ABI version {}, expected {} to {}",
+            path.display(),
+            abi,
+            MIN_COMPATIBLE_LANGUAGE_VERSION,
+            LANGUAGE_VERSION
+        )
+        .into());
+    }
+    Ok(language)
+}
+
+#[cfg(not(unix))]
+pub fn load_grammar(path: &Path, _name: &str) -> Result<Language, Box<dyn std::error::Error>> {
+    Err(format!(
+        "Cannot load grammar '{}': dynamic grammars are only supported on Unix",
+        path.display()
+    )
+    .into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_library_and_symbol_name() {
+        assert_eq!(language_symbol("c-sharp"), "tree_sitter_c_sharp");
+        let error = load_grammar(Path::new("/nonexistent/libtree-sitter-foo.so"), "foo")
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("libtree-sitter-foo.so"));
+    }
+}
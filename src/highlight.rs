@@ -0,0 +1,204 @@
+use crate::ast::parse_tree;
+use crate::language::SupportedLanguage;
+use std::fmt::Write;
+use tree_sitter::{Query, QueryCursor, StreamingIterator, Tree};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HighlightFormat {
+    Html,
+    Ansi,
+}
+
+impl HighlightFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "html" => Some(HighlightFormat::Html),
+            "ansi" => Some(HighlightFormat::Ansi),
+            _ => None,
+        }
+    }
+}
+
+// The grammars' bundled highlights.scm, combined the way their tree-sitter.json
+// does (C++ and TypeScript build on the C and JavaScript queries)
+pub fn highlights_query(language: SupportedLanguage) -> String {
+    match language {
+        SupportedLanguage::Rust => tree_sitter_rust::HIGHLIGHTS_QUERY.to_string(),
+        SupportedLanguage::Java => tree_sitter_java::HIGHLIGHTS_QUERY.to_string(),
+        SupportedLanguage::Zig => tree_sitter_zig::HIGHLIGHTS_QUERY.to_string(),
+        SupportedLanguage::C => tree_sitter_c::HIGHLIGHT_QUERY.to_string(),
+        SupportedLanguage::Cpp => [
+            tree_sitter_c::HIGHLIGHT_QUERY,
+            tree_sitter_cpp::HIGHLIGHT_QUERY,
+        ]
+        .join("\n"),
+        SupportedLanguage::JavaScript => [
+            tree_sitter_javascript::HIGHLIGHT_QUERY,
+            tree_sitter_javascript::JSX_HIGHLIGHT_QUERY,
+        ]
+        .join("\n"),
+        SupportedLanguage::TypeScript => [
+            tree_sitter_typescript::HIGHLIGHTS_QUERY,
+            tree_sitter_javascript::HIGHLIGHT_QUERY,
+        ]
+        .join("\n"),
+        SupportedLanguage::Tsx => [
+            tree_sitter_typescript::HIGHLIGHTS_QUERY,
+            tree_sitter_javascript::HIGHLIGHT_QUERY,
+            tree_sitter_javascript::JSX_HIGHLIGHT_QUERY,
+        ]
+        .join("\n"),
+        SupportedLanguage::Go => tree_sitter_go::HIGHLIGHTS_QUERY.to_string(),
+    }
+}
+
+pub fn highlight(
+    source: &str,
+    language: SupportedLanguage,
+    format: HighlightFormat,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let ts_language = language.tree_sitter_language();
+    let tree = parse_tree(source, &ts_language)?;
+    let query = Query::new(&ts_language, &highlights_query(language))?;
+    let spans = highlight_spans(&tree, source, &query);
+    Ok(match format {
+        HighlightFormat::Html => render_html(source, &spans),
+        HighlightFormat::Ansi => render_ansi(source, &spans),
+    })
+}
+
+// Splits `source` into consecutive (start, end, capture) runs. Where captures
+// overlap the innermost node wins, and between equal nodes the earlier pattern
+// does, as in tree-sitter-highlight.
+pub fn highlight_spans<'q>(
+    tree: &Tree,
+    source: &str,
+    query: &'q Query,
+) -> Vec<(usize, usize, Option<&'q str>)> {
+    let names = query.capture_names();
+    let mut captures = Vec::new();
+    let mut cursor = QueryCursor::new();
+    let mut matches = cursor.captures(query, tree.root_node(), source.as_bytes());
+    while let Some((match_, index)) = matches.next() {
+        let capture = match_.captures[*index];
+        // `@_name` captures only feed predicates
+        if names[capture.index as usize].starts_with('_') {
+            continue;
+        }
+        let range = capture.node.byte_range();
+        captures.push((range, match_.pattern_index, capture.index));
+    }
+
+    // Paint outer and later captures first so inner and earlier ones overwrite them
+    captures.sort_by(|(a, a_pattern, _), (b, b_pattern, _)| {
+        (b.len(), b_pattern).cmp(&(a.len(), a_pattern))
+    });
+    let mut painted: Vec<Option<u32>> = vec![None; source.len()];
+    for (range, _, index) in captures {
+        for slot in &mut painted[range] {
+            *slot = Some(index);
+        }
+    }
+
+    let mut spans = Vec::new();
+    let mut start = 0;
+    for end in 1..=painted.len() {
+        if end == painted.len() || painted[end] != painted[start] {
+            let name = painted[start].map(|index| names[index as usize]);
+            spans.push((start, end, name));
+            start = end;
+        }
+    }
+    spans
+}
+
+// `<span class="function method">` for `@function.method`; styling is left to CSS
+pub fn render_html(source: &str, spans: &[(usize, usize, Option<&str>)]) -> String {
+    let mut html = String::from("<pre class=\"treescan-highlight\"><code>");
+    for (start, end, name) in spans {
+        let text = escape_html(&source[*start..*end]);
+        match name {
+            Some(name) => {
+                let _ = write!(
+                    html,
+                    "<span class=\"{}\">{}</span>",
+                    name.replace('.', " "),
+                    text
+                );
+            }
+            None => html.push_str(&text),
+        }
+    }
+    html.push_str("</code></pre>\n");
+    html
+}
+
+pub fn render_ansi(source: &str, spans: &[(usize, usize, Option<&str>)]) -> String {
+    let mut out = String::new();
+    for (start, end, name) in spans {
+        let text = &source[*start..*end];
+        match name.and_then(ansi_color) {
+            // Reset before each newline so colors never bleed into a pager's next line
+            Some(color) => {
+                for (i, line) in text.split('\n').enumerate() {
+                    if i > 0 {
+                        out.push('\n');
+                    }
+                    if !line.is_empty() {
+                        let _ = write!(out, "\x1b[{}m{}\x1b[0m", color, line);
+                    }
+                }
+            }
+            None => out.push_str(text),
+        }
+    }
+    out
+}
+
+// SGR code for the capture's most specific recognized scope
+fn ansi_color(name: &str) -> Option<&'static str> {
+    if name.ends_with(".builtin") {
+        return Some("31");
+    }
+    match name.split('.').next()? {
+        "keyword" | "conditional" | "repeat" | "include" | "storageclass" => Some("35"),
+        "string" | "character" | "escape" => Some("32"),
+        "comment" => Some("90"),
+        "number" | "float" | "boolean" | "constant" | "attribute" | "label" => Some("33"),
+        "function" | "method" | "constructor" => Some("34"),
+        "type" | "tag" | "module" | "namespace" => Some("36"),
+        _ => None,
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_highlight_queries_compile_and_render() {
+        for language in SupportedLanguage::ALL {
+            let query = Query::new(
+                &language.tree_sitter_language(),
+                &highlights_query(language),
+            );
+            assert!(query.is_ok(), "{}: {:?}", language.name(), query.err());
+        }
+
+        let source = "fn main() { let s = \"<a>\"; } // done\n";
+        let html = highlight(source, SupportedLanguage::Rust, HighlightFormat::Html).unwrap();
+        assert!(html.contains("<span class=\"keyword\">fn</span>"));
+        assert!(html.contains("<span class=\"string\">&quot;&lt;a&gt;&quot;</span>"));
+        assert!(html.contains("<span class=\"comment\">// done</span>"));
+
+        let ansi = highlight(source, SupportedLanguage::Rust, HighlightFormat::Ansi).unwrap();
+        assert!(ansi.starts_with("\x1b[35mfn\x1b[0m "));
+    }
+}
@@ -0,0 +1,132 @@
+use crate::git::{git, toplevel};
+use serde_json::{json, Value};
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+// Number of commits touching each file under `dir`, keyed by absolute path
+pub fn commit_counts(
+    dir: &Path,
+    since: Option<&str>,
+) -> Result<BTreeMap<PathBuf, usize>, Box<dyn std::error::Error>> {
+    let toplevel = toplevel(dir)?;
+
+    let mut args = vec![
+        "log".to_string(),
+        "--format=".to_string(),
+        "--name-only".to_string(),
+    ];
+    if let Some(since) = since {
+        args.push(format!("--since={}", since));
+    }
+    args.extend(["--".to_string(), ".".to_string()]);
+    let log = git(dir, &args.iter().map(String::as_str).collect::<Vec<_>>())?;
+
+    Ok(count_log_paths(&log)
+        .into_iter()
+        .map(|(path, commits)| (toplevel.join(path), commits))
+        .collect())
+}
+
+// `git log --format= --name-only` prints the files of each commit, one per line
+fn count_log_paths(log: &str) -> BTreeMap<&str, usize> {
+    let mut counts = BTreeMap::new();
+    for path in log.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        *counts.entry(path).or_default() += 1;
+    }
+    counts
+}
+
+// Ranks the analyzed files of a directory report by churn x complexity: code that
+// is both complicated and edited often is where refactoring pays off first.
+// `commits` maps each report path to its commit count.
+pub fn rank_hotspots(report: &Value, commits: &BTreeMap<String, usize>) -> Value {
+    let mut hotspots: Vec<Value> = report["files"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|file| file["result"]["score"].is_number())
+        .filter_map(|file| {
+            let path = file["path"].as_str()?;
+            let commits = commits.get(path).copied().unwrap_or(0);
+            let result = &file["result"];
+            let complexity = result["metrics"]["cyclomatic_complexity"]
+                .as_u64()
+                .unwrap_or(0);
+            Some(json!({
+                "path": path,
+                "commits": commits,
+                "complexity": complexity,
+                "score": result["score"],
+                "hotspot": commits as u64 * complexity,
+            }))
+        })
+        .collect();
+    hotspots.sort_by_key(|h| Reverse(h["hotspot"].as_u64().unwrap_or(0)));
+    json!(hotspots)
+}
+
+pub fn format_hotspots_table(hotspots: &Value) -> String {
+    let mut out = format!(
+        "{:>4}  {:>8}  {:>7}  {:>10}  {:>5}  {}\n",
+        "rank", "hotspot", "commits", "complexity", "score", "path"
+    );
+    for (rank, hotspot) in hotspots.as_array().into_iter().flatten().enumerate() {
+        out.push_str(&format!(
+            "{:>4}  {:>8}  {:>7}  {:>10}  {:>5.1}  {}\n",
+            rank + 1,
+            hotspot["hotspot"].as_u64().unwrap_or(0),
+            hotspot["commits"].as_u64().unwrap_or(0),
+            hotspot["complexity"].as_u64().unwrap_or(0),
+            hotspot["score"].as_f64().unwrap_or(0.0),
+            hotspot["path"].as_str().unwrap_or(""),
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hotspots_rank_churn_times_complexity() {
+        let log = "src/a.rs\nsrc/b.rs\n\nsrc/a.rs\n\nsrc/a.rs\nREADME.md\n";
+        let counts = count_log_paths(log);
+        assert_eq!(counts["src/a.rs"], 3);
+        assert_eq!(counts["src/b.rs"], 1);
+
+        let file = |path: &str, complexity: u64| {
+            json!({ "path": path, "result": {
+                "score": 8.0, "metrics": { "cyclomatic_complexity": complexity },
+            } })
+        };
+        let report = json!({ "files": [
+            file("src/a.rs", 4),
+            file("src/b.rs", 20),
+            file("src/c.rs", 50),
+            { "path": "src/d.rs", "error": "unreadable" },
+        ] });
+        let commits: BTreeMap<String, usize> = counts
+            .into_iter()
+            .map(|(path, count)| (path.to_string(), count))
+            .collect();
+
+        let ranked = rank_hotspots(&report, &commits);
+        let order: Vec<(&str, u64)> = ranked
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|h| (h["path"].as_str().unwrap(), h["hotspot"].as_u64().unwrap()))
+            .collect();
+        assert_eq!(
+            order,
+            vec![("src/b.rs", 20), ("src/a.rs", 12), ("src/c.rs", 0)]
+        );
+        assert!(format_hotspots_table(&ranked)
+            .lines()
+            .nth(1)
+            .unwrap()
+            .ends_with("src/b.rs"));
+    }
+}
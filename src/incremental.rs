@@ -0,0 +1,134 @@
+use tree_sitter::{InputEdit, Language, Parser, Point, Tree};
+
+// Keeps the previous tree around so small edits only re-parse the changed region
+pub struct IncrementalParser {
+    parser: Parser,
+    tree: Option<Tree>,
+    source: String,
+}
+
+impl IncrementalParser {
+    pub fn new(language: &Language) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut parser = Parser::new();
+        parser.set_language(language)?;
+        Ok(Self {
+            parser,
+            tree: None,
+            source: String::new(),
+        })
+    }
+
+    pub fn tree(&self) -> Option<&Tree> {
+        self.tree.as_ref()
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    // Full parse, discarding any previous tree
+    pub fn parse(&mut self, source: &str) -> Result<&Tree, Box<dyn std::error::Error>> {
+        let tree = self
+            .parser
+            .parse(source, None)
+            .ok_or("Parser did not produce a tree")?;
+        self.source = source.to_string();
+        Ok(self.tree.insert(tree))
+    }
+
+    // Applies `edits` (expressed against the previous source) to the old tree, then
+    // re-parses `new_source` reusing every subtree the edits did not touch
+    pub fn edit(
+        &mut self,
+        edits: &[InputEdit],
+        new_source: &str,
+    ) -> Result<&Tree, Box<dyn std::error::Error>> {
+        let mut old_tree = match self.tree.take() {
+            Some(tree) => tree,
+            None => return self.parse(new_source),
+        };
+        for edit in edits {
+            old_tree.edit(edit);
+        }
+
+        let tree = self
+            .parser
+            .parse(new_source, Some(&old_tree))
+            .ok_or("Parser did not produce a tree")?;
+        self.source = new_source.to_string();
+        Ok(self.tree.insert(tree))
+    }
+
+    // Convenience for the common single-range replacement case
+    pub fn replace_range(
+        &mut self,
+        start_byte: usize,
+        old_end_byte: usize,
+        new_text: &str,
+    ) -> Result<&Tree, Box<dyn std::error::Error>> {
+        if start_byte > old_end_byte
+            || old_end_byte > self.source.len()
+            || !self.source.is_char_boundary(start_byte)
+            || !self.source.is_char_boundary(old_end_byte)
+        {
+            return Err("Edit range is out of bounds".into());
+        }
+
+        let mut new_source = String::with_capacity(self.source.len() + new_text.len());
+        new_source.push_str(&self.source[..start_byte]);
+        new_source.push_str(new_text);
+        new_source.push_str(&self.source[old_end_byte..]);
+
+        let new_end_byte = start_byte + new_text.len();
+        let edit = InputEdit {
+            start_byte,
+            old_end_byte,
+            new_end_byte,
+            start_position: point_at(&self.source, start_byte),
+            old_end_position: point_at(&self.source, old_end_byte),
+            new_end_position: point_at(&new_source, new_end_byte),
+        };
+        self.edit(&[edit], &new_source)
+    }
+}
+
+fn point_at(source: &str, byte: usize) -> Point {
+    let before = &source.as_bytes()[..byte];
+    let row = before.iter().filter(|&&b| b == b'\n').count();
+    let column = match before.iter().rposition(|&b| b == b'\n') {
+        Some(newline) => byte - newline - 1,
+        None => byte,
+    };
+    Point::new(row, column)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replace_range_matches_full_parse() {
+        let language: Language = tree_sitter_rust::LANGUAGE.into();
+        let mut incremental = IncrementalParser::new(&language).unwrap();
+        incremental
+            .parse("fn main() {\n    let a = 1;\n}\n")
+            .unwrap();
+
+        let start = incremental.source().find('1').unwrap();
+        let tree = incremental
+            .replace_range(start, start + 1, "foo(2)")
+            .unwrap();
+        let incremental_sexp = tree.root_node().to_sexp();
+
+        let mut parser = Parser::new();
+        parser.set_language(&language).unwrap();
+        let full = parser.parse(incremental.source(), None).unwrap();
+
+        assert_eq!(
+            incremental.source(),
+            "fn main() {\n    let a = foo(2);\n}\n"
+        );
+        assert_eq!(incremental_sexp, full.root_node().to_sexp());
+        assert!(incremental.replace_range(5, 1000, "x").is_err());
+    }
+}
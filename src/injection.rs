@@ -0,0 +1,225 @@
+use crate::analyzer::{AnalysisResult, CodeAnalyzer};
+use crate::language::SupportedLanguage;
+use crate::position::ColumnUnit;
+use std::ops::Range;
+use tree_sitter::{Node, Tree};
+
+// Source of another language embedded in a string literal of the host file
+#[derive(Debug, Clone, PartialEq)]
+pub struct Injection {
+    pub language: SupportedLanguage,
+    // Byte range of the embedded source in the host file
+    pub range: Range<usize>,
+}
+
+// String and template literal kinds across the bundled grammars
+const LITERAL_KINDS: &[&str] = &[
+    "string",
+    "template_string",
+    "string_literal",
+    "raw_string_literal",
+    "interpreted_string_literal",
+];
+
+// Finds embedded code in string literals:
+// - a `language=<name>` comment on the line above (or before it on the same line)
+// - JS/TS tagged templates named after a language (js`...`, ts`...`)
+// - `<script>` blocks, as JavaScript or, with a TypeScript `type`/`lang`, TypeScript
+// Escape sequences are not decoded; the literal text is parsed as written.
+pub fn find_injections(tree: &Tree, source: &str) -> Vec<Injection> {
+    let mut injections = Vec::new();
+    let mut pending = vec![tree.root_node()];
+    while let Some(node) = pending.pop() {
+        if LITERAL_KINDS.contains(&node.kind()) {
+            if let Some(content) = literal_content(&node) {
+                injections.extend(literal_injections(&node, content, source));
+            }
+            continue;
+        }
+        let mut cursor = node.walk();
+        pending.extend(node.named_children(&mut cursor));
+    }
+    injections.sort_by_key(|injection| injection.range.start);
+    injections
+}
+
+// The text between a literal's delimiters; None for templates with substitutions
+fn literal_content(node: &Node) -> Option<Range<usize>> {
+    let mut cursor = node.walk();
+    if node
+        .named_children(&mut cursor)
+        .any(|child| child.kind() == "template_substitution")
+    {
+        return None;
+    }
+    let open = node.child(0).filter(|child| !child.is_named())?;
+    let close = node
+        .child(node.child_count().checked_sub(1)?)
+        .filter(|child| !child.is_named() && child.id() != open.id())?;
+    (open.end_byte() < close.start_byte()).then(|| open.end_byte()..close.start_byte())
+}
+
+fn literal_injections(node: &Node, content: Range<usize>, source: &str) -> Vec<Injection> {
+    if let Some(language) = directive_language(node, source).or_else(|| tag_language(node, source))
+    {
+        return vec![Injection {
+            language,
+            range: content,
+        }];
+    }
+    script_blocks(&source[content.clone()])
+        .into_iter()
+        .map(|(language, range)| Injection {
+            language,
+            range: content.start + range.start..content.start + range.end,
+        })
+        .collect()
+}
+
+// `// language=js` (or `/* language=js */`) on the line above the literal or
+// earlier on its own line
+fn directive_language(node: &Node, source: &str) -> Option<SupportedLanguage> {
+    let line_start = source[..node.start_byte()].rfind('\n').map_or(0, |i| i + 1);
+    let previous_start = source[..line_start.saturating_sub(1)]
+        .rfind('\n')
+        .map_or(0, |i| i + 1);
+    let prefix = &source[previous_start..node.start_byte()];
+    let at = prefix.rfind("language=")?;
+    if !prefix[..at].contains("//") && !prefix[..at].contains("/*") {
+        return None;
+    }
+    let name: String = prefix[at + "language=".len()..]
+        .chars()
+        .take_while(|c| c.is_alphanumeric() || *c == '+')
+        .collect();
+    SupportedLanguage::from_name(&name)
+}
+
+// js`...`: a template passed straight to a tag named after a language
+fn tag_language(node: &Node, source: &str) -> Option<SupportedLanguage> {
+    let call = node.parent().filter(|p| p.kind() == "call_expression")?;
+    if call.child_by_field_name("arguments")?.id() != node.id() {
+        return None;
+    }
+    let tag = call.child_by_field_name("function")?;
+    SupportedLanguage::from_name(tag.utf8_text(source.as_bytes()).ok()?)
+}
+
+// `<script ...>` bodies in `text`, with ranges relative to it
+pub(crate) fn script_blocks(text: &str) -> Vec<(SupportedLanguage, Range<usize>)> {
+    let mut blocks = Vec::new();
+    let mut offset = 0;
+    while let Some(open) = text[offset..].find("<script").map(|i| offset + i) {
+        let Some(tag_end) = text[open..].find('>').map(|i| open + i + 1) else {
+            break;
+        };
+        let Some(close) = text[tag_end..].find("</script").map(|i| tag_end + i) else {
+            break;
+        };
+        let tag = &text[open..tag_end];
+        let language = if tag.contains("typescript") || tag.contains("\"ts\"") || tag.contains("'ts'") {
+            SupportedLanguage::TypeScript
+        } else {
+            SupportedLanguage::JavaScript
+        };
+        if tag_end < close {
+            blocks.push((language, tag_end..close));
+        }
+        offset = close;
+    }
+    blocks
+}
+
+// Runs the analyzer for each injected language over its embedded code and maps the
+// findings back to host positions. Languages without an analyzer are skipped.
+pub fn analyze_injections(
+    tree: &Tree,
+    source: &str,
+    analyzers: &[(SupportedLanguage, CodeAnalyzer)],
+    unit: ColumnUnit,
+) -> Vec<AnalysisResult> {
+    let mut results = Vec::new();
+    for injection in find_injections(tree, source) {
+        let Some((_, analyzer)) = analyzers.iter().find(|(l, _)| *l == injection.language) else {
+            continue;
+        };
+        let embedded = &source[injection.range.clone()];
+        let language = injection.language.tree_sitter_language();
+        let Ok(tree) = analyzer.parse(embedded, &language) else {
+            continue;
+        };
+        let Ok(found) = analyzer.analyze_embedded(&tree, embedded, &language) else {
+            continue;
+        };
+        for mut result in found {
+            let Some(offset) = unit.byte_offset(embedded, result.line, result.column) else {
+                continue;
+            };
+            let (line, column) = host_position(source, injection.range.start + offset, unit);
+            result.line = line;
+            result.column = column;
+            result.message = format!(
+                "{} (embedded {})",
+                result.message,
+                injection.language.name()
+            );
+            results.push(result);
+        }
+    }
+    results
+}
+
+// 1-based (line, column) of `byte` in `source`
+pub(crate) fn host_position(source: &str, byte: usize, unit: ColumnUnit) -> (usize, usize) {
+    let before = &source[..byte];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    let line = before.matches('\n').count() + 1;
+    (line, unit.convert(source, byte, byte - line_start) + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::parse_tree;
+
+    #[test]
+    fn test_injections_are_found_and_mapped_to_host_positions() {
+        let source = "// language=go\nconst a = `package main\nfunc main() { panic(1) }`;\n\
+                      const h = \"<p><script>console.log(1)</script>\";\n\
+                      const t = ts`let x = 1;`;\nconst plain = \"console.log(2)\";\n";
+        let tree = parse_tree(
+            source,
+            &SupportedLanguage::JavaScript.tree_sitter_language(),
+        )
+        .unwrap();
+        let found: Vec<(SupportedLanguage, &str)> = find_injections(&tree, source)
+            .into_iter()
+            .map(|i| (i.language, &source[i.range]))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (
+                    SupportedLanguage::Go,
+                    "package main\nfunc main() { panic(1) }"
+                ),
+                (SupportedLanguage::JavaScript, "console.log(1)"),
+                (SupportedLanguage::TypeScript, "let x = 1;"),
+            ]
+        );
+
+        let go = SupportedLanguage::Go.analyzer().unwrap();
+        let results = analyze_injections(
+            &tree,
+            source,
+            &[(SupportedLanguage::Go, go)],
+            ColumnUnit::Byte,
+        );
+        let panic = results
+            .iter()
+            .find(|r| r.rule_name == "go_panic_usage")
+            .unwrap();
+        assert_eq!((panic.line, panic.column), (3, 15));
+        assert_eq!(panic.message, "Use of panic() (embedded Go)");
+    }
+}
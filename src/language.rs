@@ -0,0 +1,689 @@
+use crate::analyzer::{CodeAnalyzer, RuleConfig};
+use crate::plugin::load_plugin;
+use crate::query_rules::load_query_rules;
+use crate::rule_pack::load_installed;
+use serde_json::{json, Value};
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use std::sync::atomic::{AtomicU8, Ordering};
+use tree_sitter::Language;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum SupportedLanguage {
+    Rust,
+    Java,
+    Zig,
+    C,
+    Cpp,
+    JavaScript,
+    TypeScript,
+    Tsx,
+    Go,
+}
+
+impl SupportedLanguage {
+    pub const ALL: [SupportedLanguage; 9] = [
+        SupportedLanguage::Rust,
+        SupportedLanguage::Java,
+        SupportedLanguage::Zig,
+        SupportedLanguage::C,
+        SupportedLanguage::Cpp,
+        SupportedLanguage::JavaScript,
+        SupportedLanguage::TypeScript,
+        SupportedLanguage::Tsx,
+        SupportedLanguage::Go,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SupportedLanguage::Rust => "Rust",
+            SupportedLanguage::Java => "Java",
+            SupportedLanguage::Zig => "Zig",
+            SupportedLanguage::C => "C",
+            SupportedLanguage::Cpp => "C++",
+            SupportedLanguage::JavaScript => "JavaScript",
+            SupportedLanguage::TypeScript => "TypeScript",
+            SupportedLanguage::Tsx => "TSX",
+            SupportedLanguage::Go => "Go",
+        }
+    }
+
+    // Accepts display names as well as the short ids used by bindings ("rust", "js", "cpp")
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "rust" | "rs" => Some(SupportedLanguage::Rust),
+            "java" => Some(SupportedLanguage::Java),
+            "zig" => Some(SupportedLanguage::Zig),
+            "c" => Some(SupportedLanguage::C),
+            "c++" | "cpp" => Some(SupportedLanguage::Cpp),
+            "javascript" | "js" => Some(SupportedLanguage::JavaScript),
+            "typescript" | "ts" => Some(SupportedLanguage::TypeScript),
+            "tsx" => Some(SupportedLanguage::Tsx),
+            "go" => Some(SupportedLanguage::Go),
+            _ => None,
+        }
+    }
+
+    pub fn extensions(&self) -> &'static [&'static str] {
+        match self {
+            SupportedLanguage::Rust => &["rs"],
+            SupportedLanguage::Java => &["java"],
+            SupportedLanguage::Zig => &["zig"],
+            SupportedLanguage::C => &["c", "h"],
+            SupportedLanguage::Cpp => &["cpp", "cc", "cxx", "hpp", "hxx"],
+            SupportedLanguage::JavaScript => &["js", "jsx", "mjs", "cjs"],
+            SupportedLanguage::TypeScript => &["ts", "mts", "cts"],
+            SupportedLanguage::Tsx => &["tsx"],
+            SupportedLanguage::Go => &["go"],
+        }
+    }
+
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        let extension = extension.to_lowercase();
+        Self::ALL
+            .into_iter()
+            .find(|lang| lang.extensions().contains(&extension.as_str()))
+    }
+
+    pub fn from_path(path: &Path) -> Option<Self> {
+        Self::from_extension(path.extension()?.to_str()?)
+    }
+
+    // Like `from_path`, but falls back to the `#!` line for extension-less scripts and
+    // tells C++ headers from C ones by their contents
+    pub fn detect(path: &Path) -> Option<Self> {
+        if path.extension().is_some() {
+            let lang = Self::from_path(path)?;
+            if is_c_header(path) {
+                return Some(Self::header_language(&read_head(path).unwrap_or_default()));
+            }
+            return Some(lang);
+        }
+        Self::from_shebang(&read_first_line(path)?)
+    }
+
+    // `detect` for a document whose text is already in memory (editors)
+    pub fn detect_source(path: &Path, source: &str) -> Option<Self> {
+        let lang = Self::from_path(path)?;
+        Some(if is_c_header(path) { Self::header_language(source) } else { lang })
+    }
+
+    // The language of a `.h` file: the `--header-lang` override, else C++ when the
+    // header uses C++-only constructs, else C
+    pub fn header_language(source: &str) -> Self {
+        match HEADER_LANGUAGE.load(Ordering::Relaxed) {
+            HEADER_C => SupportedLanguage::C,
+            HEADER_CPP => SupportedLanguage::Cpp,
+            _ if looks_like_cpp(source) => SupportedLanguage::Cpp,
+            _ => SupportedLanguage::C,
+        }
+    }
+
+    // Forces every `.h` file to C or C++; `None` restores detection from the contents
+    pub fn set_header_language(lang: Option<Self>) {
+        let value = match lang {
+            Some(SupportedLanguage::C) => HEADER_C,
+            Some(SupportedLanguage::Cpp) => HEADER_CPP,
+            _ => HEADER_DETECT,
+        };
+        HEADER_LANGUAGE.store(value, Ordering::Relaxed);
+    }
+
+    // Maps the interpreter named on a `#!` line, including `/usr/bin/env [-S] <name>` forms
+    pub fn from_shebang(line: &str) -> Option<Self> {
+        let interpreter = shebang_interpreter(line)?;
+        match interpreter {
+            "node" | "nodejs" | "bun" | "qjs" => Some(SupportedLanguage::JavaScript),
+            "deno" | "ts-node" | "tsx" => Some(SupportedLanguage::TypeScript),
+            "rust-script" | "cargo" => Some(SupportedLanguage::Rust),
+            _ => None,
+        }
+    }
+
+    pub fn tree_sitter_language(&self) -> Language {
+        match self {
+            SupportedLanguage::Rust => tree_sitter_rust::LANGUAGE.into(),
+            SupportedLanguage::Java => tree_sitter_java::LANGUAGE.into(),
+            SupportedLanguage::Zig => tree_sitter_zig::LANGUAGE.into(),
+            SupportedLanguage::C => tree_sitter_c::LANGUAGE.into(),
+            SupportedLanguage::Cpp => tree_sitter_cpp::LANGUAGE.into(),
+            SupportedLanguage::JavaScript => tree_sitter_javascript::LANGUAGE.into(),
+            SupportedLanguage::TypeScript => tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
+            SupportedLanguage::Tsx => tree_sitter_typescript::LANGUAGE_TSX.into(),
+            SupportedLanguage::Go => tree_sitter_go::LANGUAGE.into(),
+        }
+    }
+
+    // The bundled grammar `language` is; most have no name to go by
+    pub fn from_tree_sitter(language: &Language) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|lang| lang.tree_sitter_language() == *language)
+    }
+
+    pub fn supports_parse(&self) -> bool {
+        !matches!(self, SupportedLanguage::Go)
+    }
+
+    pub fn supports_analyze(&self) -> bool {
+        matches!(
+            self,
+            SupportedLanguage::Rust
+                | SupportedLanguage::JavaScript
+                | SupportedLanguage::Tsx
+                | SupportedLanguage::Go
+        )
+    }
+
+    pub fn analyzer(&self) -> Option<CodeAnalyzer> {
+        match self {
+            SupportedLanguage::Rust => Some(CodeAnalyzer::new_rust_analyzer()),
+            SupportedLanguage::JavaScript => Some(CodeAnalyzer::new_javascript_analyzer()),
+            SupportedLanguage::Tsx => Some(CodeAnalyzer::new_react_analyzer()),
+            SupportedLanguage::Go => Some(CodeAnalyzer::new_go_analyzer()),
+            _ => None,
+        }
+    }
+
+    // Applies the rule tuning, the overrides matching `path` and any per-file-kind
+    // relaxations for it
+    pub fn analyzer_for_path(&self, path: &Path, rules: &RuleConfig) -> Option<CodeAnalyzer> {
+        let rules = &rules.for_path(path);
+        let analyzer = self.configured_analyzer(path, rules)?;
+        if !rules.injections {
+            return Some(analyzer);
+        }
+        // Embedded code is held to its own language's rules, tuned the same way
+        let injected = SupportedLanguage::ALL
+            .into_iter()
+            .filter_map(|lang| Some((lang, lang.configured_analyzer(path, rules)?)))
+            .collect();
+        Some(analyzer.with_injected_analyzers(injected))
+    }
+
+    fn configured_analyzer(&self, path: &Path, rules: &RuleConfig) -> Option<CodeAnalyzer> {
+        let base = if *self == SupportedLanguage::JavaScript && is_jsx_file(path) {
+            CodeAnalyzer::new_react_analyzer()
+        } else {
+            self.analyzer()?
+        };
+        let mut analyzer = base
+            .with_magic_number_allowlist(rules.magic_number_allowlist.clone())
+            .with_remediation_minutes(rules.remediation_minutes.clone())
+            .with_ratings(rules.ratings.clone())
+            .with_test_code_rules(rules.in_tests.clone())
+            .with_test_file(is_test_file(path))
+            .with_macro_bodies(rules.macro_bodies)
+            .with_length_limits(rules.length_limits)
+            .with_max_chain_length(rules.max_chain_length)
+            .with_max_boolean_operators(rules.max_boolean_operators);
+        // JSX and TSX files are held to the JavaScript conventions
+        let naming_language = match self {
+            SupportedLanguage::Tsx => SupportedLanguage::JavaScript,
+            other => *other,
+        };
+        if let Some(overrides) = rules.naming.get(&naming_language) {
+            analyzer = analyzer.with_naming_overrides(overrides);
+        }
+        // Declaration files only describe types, so they are held to the error rules alone
+        if is_declaration_file(path) {
+            analyzer = analyzer.errors_only();
+        }
+        if is_go_test_file(path) && !rules.magic_numbers_in_tests {
+            analyzer = analyzer.without_rule("go_magic_number");
+        }
+        // Directories are checked up front (see `validate_query_dirs`)
+        for dir in &rules.query_dirs {
+            for rule in load_query_rules(dir, *self).unwrap_or_default() {
+                analyzer.add_rule(rule);
+            }
+        }
+        for script in rules.scripts.iter().filter(|script| script.language == *self) {
+            analyzer.add_rule(script.to_analysis_rule());
+        }
+        // So are rule packs (see `validate_packs`)
+        for name in &rules.packs {
+            for rule in load_installed(name).iter().flat_map(|pack| pack.rules(*self)) {
+                analyzer.add_rule(rule);
+            }
+        }
+        // Plugins are loaded and checked up front too (see `validate_plugins`)
+        for path in &rules.plugins {
+            for plugin_rule in load_plugin(path).iter().flat_map(|rules| rules.iter()) {
+                if plugin_rule.language == *self {
+                    analyzer.add_rule(plugin_rule.rule.clone());
+                }
+            }
+        }
+        if let Some(min) = &rules.min_severity {
+            analyzer = analyzer.with_min_severity(min);
+        }
+        // Last, so rules from queries, scripts, packs and plugins are translated too
+        Some(analyzer.with_locale(rules.locale.as_deref()))
+    }
+}
+
+// Files we recognize but have no grammar for, so scans can report them instead of
+// dropping them silently
+pub fn unsupported_language_name(path: &Path) -> Option<&'static str> {
+    let name = path.file_name()?.to_str()?;
+    match name {
+        "Makefile" | "makefile" | "GNUmakefile" => return Some("Makefile"),
+        "Dockerfile" | "Containerfile" => return Some("Dockerfile"),
+        "Jenkinsfile" => return Some("Groovy"),
+        _ if name.starts_with("Dockerfile.") => return Some("Dockerfile"),
+        _ => {}
+    }
+    if path.extension().is_some() {
+        return None;
+    }
+    let line = read_first_line(path)?;
+    match shebang_interpreter(&line)? {
+        "sh" | "bash" | "zsh" | "dash" | "ksh" => Some("Shell"),
+        "python" | "python2" | "python3" => Some("Python"),
+        "ruby" => Some("Ruby"),
+        "perl" => Some("Perl"),
+        _ => None,
+    }
+}
+
+fn shebang_interpreter(line: &str) -> Option<&str> {
+    let mut words = line.strip_prefix("#!")?.split_whitespace();
+    let mut program = words.next()?.rsplit('/').next()?;
+    if program == "env" {
+        program = words.find(|word| !word.starts_with('-'))?;
+    }
+    Some(program)
+}
+
+const HEADER_DETECT: u8 = 0;
+const HEADER_C: u8 = 1;
+const HEADER_CPP: u8 = 2;
+static HEADER_LANGUAGE: AtomicU8 = AtomicU8::new(HEADER_DETECT);
+
+fn is_c_header(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("h"))
+}
+
+// Enough of a header to find its first declarations
+fn read_head(path: &Path) -> Option<String> {
+    let mut head = Vec::new();
+    File::open(path).ok()?.take(64 * 1024).read_to_end(&mut head).ok()?;
+    Some(String::from_utf8_lossy(&head).into_owned())
+}
+
+// Looks for `class Name`, `template <` and `namespace name`/`namespace {` outside
+// comments, strings and preprocessor lines, so the `#ifdef __cplusplus` guard of a C
+// header doesn't count
+pub fn looks_like_cpp(source: &str) -> bool {
+    let identifier = |token: &str| token.starts_with(|c: char| c.is_alphabetic() || c == '_');
+    strip_comments_and_strings(source)
+        .lines()
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .any(|line| {
+            tokens(line).windows(2).any(|pair| match (pair[0], pair[1]) {
+                ("class", next) => identifier(next),
+                ("namespace", next) => identifier(next) || next == "{",
+                ("template", next) => next == "<",
+                _ => false,
+            })
+        })
+}
+
+// Words and single punctuation characters
+fn tokens(line: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut word_start = None;
+    for (i, c) in line.char_indices() {
+        let word = c.is_alphanumeric() || c == '_';
+        match word_start {
+            None if word => word_start = Some(i),
+            Some(start) if !word => {
+                tokens.push(&line[start..i]);
+                word_start = None;
+            }
+            _ => {}
+        }
+        if !word && !c.is_whitespace() {
+            tokens.push(&line[i..i + c.len_utf8()]);
+        }
+    }
+    if let Some(start) = word_start {
+        tokens.push(&line[start..]);
+    }
+    tokens
+}
+
+// Blanks out comments and string/char literals, keeping line breaks
+fn strip_comments_and_strings(source: &str) -> String {
+    let mut out = String::with_capacity(source.len());
+    let mut chars = source.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '/' if chars.peek() == Some(&'/') => {
+                while chars.peek().is_some_and(|&c| c != '\n') {
+                    chars.next();
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut prev = ' ';
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        out.push('\n');
+                    }
+                    if prev == '*' && c == '/' {
+                        break;
+                    }
+                    prev = c;
+                }
+                out.push(' ');
+            }
+            '"' | '\'' => {
+                while let Some(next) = chars.next() {
+                    match next {
+                        '\\' => {
+                            chars.next();
+                        }
+                        '\n' => {
+                            out.push('\n');
+                            break;
+                        }
+                        _ if next == c => break,
+                        _ => {}
+                    }
+                }
+                out.push(' ');
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+fn read_first_line(path: &Path) -> Option<String> {
+    let mut head = [0u8; 256];
+    let read = File::open(path).ok()?.read(&mut head).ok()?;
+    let head = &head[..read];
+    if !head.starts_with(b"#!") {
+        return None;
+    }
+    let end = head.iter().position(|&b| b == b'\n').unwrap_or(head.len());
+    String::from_utf8(head[..end].to_vec()).ok()
+}
+
+// Files of the same language share an analyzer unless their path changes the rule set
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AnalyzerKey {
+    pub language: SupportedLanguage,
+    declaration: bool,
+    go_test: bool,
+    test: bool,
+    jsx: bool,
+    overrides: u64,
+}
+
+impl AnalyzerKey {
+    pub fn new(language: SupportedLanguage, path: &Path, rules: &RuleConfig) -> Self {
+        AnalyzerKey {
+            language,
+            declaration: is_declaration_file(path),
+            go_test: is_go_test_file(path),
+            test: is_test_file(path),
+            jsx: is_jsx_file(path),
+            overrides: rules.override_mask(path),
+        }
+    }
+}
+
+pub fn is_go_test_file(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|n| n.to_str())
+        .is_some_and(|name| name.ends_with("_test.go"))
+}
+
+pub fn is_jsx_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("jsx"))
+}
+
+// Test sources by naming convention (`_test.go`, `*.spec.ts`, `FooTest.java`,
+// `foo_test.cc`, ...) or location (`tests/`, `test/`, `__tests__/`)
+pub fn is_test_file(path: &Path) -> bool {
+    let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+        return false;
+    };
+    let in_test_dir = path.parent().is_some_and(|dir| {
+        dir.components().any(|c| {
+            matches!(c.as_os_str().to_str(), Some("tests" | "test" | "__tests__"))
+        })
+    });
+    let stem = name.split('.').next().unwrap_or(name);
+    in_test_dir
+        || name.contains(".spec.")
+        || name.contains(".test.")
+        || stem.ends_with("_test")
+        || stem.ends_with("_unittest")
+        || (name.ends_with(".java") && (stem.ends_with("Test") || stem.ends_with("Tests")))
+}
+
+// `.d.ts`, `.d.mts` and `.d.cts` files
+pub fn is_declaration_file(path: &Path) -> bool {
+    let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+        return false;
+    };
+    let name = name.to_lowercase();
+    [".d.ts", ".d.mts", ".d.cts"]
+        .iter()
+        .any(|suffix| name.len() > suffix.len() && name.ends_with(suffix))
+}
+
+pub fn supported_languages_json() -> Value {
+    Value::Array(
+        SupportedLanguage::ALL
+            .iter()
+            .map(|lang| {
+                json!({
+                    "name": lang.name(),
+                    "extensions": lang.extensions(),
+                    "parse": lang.supports_parse(),
+                    "analyze": lang.supports_analyze(),
+                })
+            })
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    #[test]
+    fn test_modern_js_and_ts_extensions() {
+        let lang = |path: &str| SupportedLanguage::from_path(Path::new(path));
+        assert_eq!(lang("index.mjs"), Some(SupportedLanguage::JavaScript));
+        assert_eq!(lang("index.cjs"), Some(SupportedLanguage::JavaScript));
+        assert_eq!(lang("index.mts"), Some(SupportedLanguage::TypeScript));
+        assert_eq!(lang("index.cts"), Some(SupportedLanguage::TypeScript));
+        assert_eq!(lang("types.d.ts"), Some(SupportedLanguage::TypeScript));
+        assert_eq!(lang("App.tsx"), Some(SupportedLanguage::Tsx));
+
+        assert!(is_declaration_file(Path::new("src/types.d.ts")));
+        assert!(is_declaration_file(Path::new("index.d.mts")));
+        assert!(!is_declaration_file(Path::new("index.ts")));
+        assert!(!is_declaration_file(Path::new(".d.ts")));
+    }
+
+    #[test]
+    fn test_shebang_detection() {
+        let from = SupportedLanguage::from_shebang;
+        assert_eq!(
+            from("#!/usr/bin/env node"),
+            Some(SupportedLanguage::JavaScript)
+        );
+        assert_eq!(
+            from("#!/usr/local/bin/node --harmony"),
+            Some(SupportedLanguage::JavaScript)
+        );
+        assert_eq!(
+            from("#!/usr/bin/env -S deno run"),
+            Some(SupportedLanguage::TypeScript)
+        );
+        assert_eq!(from("#!/usr/bin/env python3"), None);
+        assert_eq!(from("// not a shebang"), None);
+    }
+
+    #[test]
+    fn test_detect_reads_extensionless_scripts() {
+        let dir = TempDir::new("shebang");
+        let deploy = dir.write("deploy", "#!/usr/bin/env node\nconsole.log(1);\n");
+        let build = dir.write("build", "#!/bin/bash\necho hi\n");
+
+        assert_eq!(
+            SupportedLanguage::detect(&deploy),
+            Some(SupportedLanguage::JavaScript)
+        );
+        assert_eq!(SupportedLanguage::detect(&build), None);
+        assert_eq!(unsupported_language_name(&build), Some("Shell"));
+    }
+
+    #[test]
+    fn test_unreadable_scripts_are_not_detected() {
+        let dir = TempDir::new("shebang");
+        let missing = dir.join("deploy");
+        assert_eq!(SupportedLanguage::detect(&missing), None);
+        assert_eq!(unsupported_language_name(&missing), None);
+        let empty = dir.write("empty", "");
+        assert_eq!(SupportedLanguage::detect(&empty), None);
+        let binary = dir.write("binary", [0xff, 0xfe, 0x00]);
+        assert_eq!(SupportedLanguage::detect(&binary), None);
+    }
+
+    #[test]
+    fn test_unsupported_language_names() {
+        let name = |path: &str| unsupported_language_name(Path::new(path));
+        assert_eq!(name("Dockerfile.dev"), Some("Dockerfile"));
+        assert_eq!(name("src/Makefile"), Some("Makefile"));
+        assert_eq!(name("Jenkinsfile"), Some("Groovy"));
+        assert_eq!(name("notes.txt"), None);
+    }
+
+    #[test]
+    fn test_header_language_detection() {
+        let c = "#ifndef UTIL_H\n#define UTIL_H\n#ifdef __cplusplus\nextern \"C\" {\n#endif\n\
+                 /* a class of helpers, see namespace docs */\nint class_count(const char *s);\n";
+        assert!(!looks_like_cpp(c));
+        assert!(looks_like_cpp("#pragma once\nnamespace util {\nint f();\n}\n"));
+        assert!(looks_like_cpp("template <typename T>\nT max(T a, T b);\n"));
+        assert!(looks_like_cpp("class Widget : public Base {};\n"));
+
+        let path = Path::new("include/widget.h");
+        assert_eq!(SupportedLanguage::detect_source(path, c), Some(SupportedLanguage::C));
+        assert_eq!(
+            SupportedLanguage::detect_source(path, "class Widget;\n"),
+            Some(SupportedLanguage::Cpp)
+        );
+        SupportedLanguage::set_header_language(Some(SupportedLanguage::C));
+        assert_eq!(
+            SupportedLanguage::detect_source(path, "class Widget;\n"),
+            Some(SupportedLanguage::C)
+        );
+        SupportedLanguage::set_header_language(None);
+    }
+
+    #[test]
+    fn test_plain_ts_grammar_rejects_jsx() {
+        let source = "const el = <div>{x}</div>;";
+        let ts = crate::ast::parse_tree(
+            source,
+            &SupportedLanguage::TypeScript.tree_sitter_language(),
+        )
+        .unwrap();
+        let tsx =
+            crate::ast::parse_tree(source, &SupportedLanguage::Tsx.tree_sitter_language()).unwrap();
+        assert!(ts.root_node().has_error());
+        assert!(!tsx.root_node().has_error());
+
+        // Type assertions in angle brackets only parse with the plain grammar
+        let cast = "let n = <number>value;";
+        let ts =
+            crate::ast::parse_tree(cast, &SupportedLanguage::TypeScript.tree_sitter_language())
+                .unwrap();
+        assert!(!ts.root_node().has_error());
+    }
+
+    #[test]
+    fn test_unwrap_is_ignored_in_test_code() {
+        for (path, test) in [
+            ("src/lib.rs", false),
+            ("tests/api.rs", true),
+            ("web/app.spec.ts", true),
+            ("web/app.test.jsx", true),
+            ("src/main/java/FooTest.java", true),
+            ("pkg/server_test.go", true),
+            ("src/contest.rs", false),
+        ] {
+            assert_eq!(is_test_file(Path::new(path)), test, "{}", path);
+        }
+
+        let source = "fn f() { a.unwrap(); }
+
+#[cfg(test)]
+mod tests {
+    \
+                      #[test]
+    fn t() { b.unwrap(); }
+}
+";
+        let language = SupportedLanguage::Rust.tree_sitter_language();
+        let unwrap_lines = |path: &str, rules: &RuleConfig| -> Vec<usize> {
+            let analyzer = SupportedLanguage::Rust
+                .analyzer_for_path(Path::new(path), rules)
+                .unwrap();
+            let mut lines: Vec<usize> = analyzer
+                .analyze(source, &language)
+                .unwrap()
+                .iter()
+                .filter(|r| r.rule_name == "unwrap_usage")
+                .map(|r| r.line)
+                .collect();
+            lines.dedup();
+            lines
+        };
+        let defaults = RuleConfig::default();
+        assert_eq!(unwrap_lines("src/lib.rs", &defaults), vec![1]);
+        assert!(unwrap_lines("tests/lib.rs", &defaults).is_empty());
+        let reported =
+            RuleConfig::from_json(&serde_json::json!({ "in_tests": { "unwrap_usage": "on" } }));
+        assert_eq!(unwrap_lines("src/lib.rs", &reported), vec![1, 6]);
+    }
+
+    #[test]
+    fn test_go_test_files_skip_magic_numbers_by_default() {
+        let has_rule = |path: &str, rules: &RuleConfig| {
+            let analyzer = SupportedLanguage::Go
+                .analyzer_for_path(Path::new(path), rules)
+                .unwrap();
+            let source = "package main\nfunc f() { g(42) }\n";
+            let language = SupportedLanguage::Go.tree_sitter_language();
+            analyzer
+                .analyze(source, &language)
+                .unwrap()
+                .iter()
+                .any(|r| r.rule_name == "go_magic_number")
+        };
+        let defaults = RuleConfig::default();
+        assert!(has_rule("main.go", &defaults));
+        assert!(!has_rule("main_test.go", &defaults));
+        let in_tests = RuleConfig {
+            magic_numbers_in_tests: true,
+            ..RuleConfig::default()
+        };
+        assert!(has_rule("main_test.go", &in_tests));
+    }
+}
@@ -0,0 +1,139 @@
+use crate::analyzer::{AnalysisResult, Severity};
+use crate::cache::stable_hash;
+use crate::position::ColumnUnit;
+
+pub const LINE_TOO_LONG: &str = "line_too_long";
+pub const FILE_TOO_LONG: &str = "file_too_long";
+
+// Text-level limits checked alongside the query rules; both are off unless set
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LengthLimits {
+    // In characters, so wide Unicode text counts the way editors show it
+    pub max_line_length: Option<usize>,
+    pub max_file_lines: Option<usize>,
+}
+
+impl LengthLimits {
+    pub fn is_empty(&self) -> bool {
+        self.max_line_length.is_none() && self.max_file_lines.is_none()
+    }
+}
+
+// Findings for lines and files over the limits, each with the byte offset it points at.
+// Long lines are reported at the first character past the limit, a long file at its
+// first line past the limit.
+pub fn length_findings(
+    source: &str,
+    limits: &LengthLimits,
+    unit: ColumnUnit,
+) -> Vec<(usize, AnalysisResult)> {
+    let mut results = Vec::new();
+    let mut line_start = 0;
+    let mut first_extra_line = 0;
+    for (index, raw) in source.split('\n').enumerate() {
+        let line = raw.strip_suffix('\r').unwrap_or(raw);
+        if limits.max_file_lines == Some(index) {
+            first_extra_line = line_start;
+        }
+        if let Some(max) = limits.max_line_length {
+            let length = line.chars().count();
+            if let Some((column, _)) = line.char_indices().nth(max) {
+                let byte = line_start + column;
+                results.push((
+                    byte,
+                    finding(
+                        LINE_TOO_LONG,
+                        Severity::Style,
+                        format!("Line is {} characters long (limit {})", length, max),
+                        "Break the line up, or let the formatter wrap it",
+                        (index + 1, unit.convert(source, byte, column) + 1),
+                        line.trim(),
+                    ),
+                ));
+            }
+        }
+        line_start += raw.len() + 1;
+    }
+
+    let total = source.lines().count();
+    if let Some(max) = limits.max_file_lines.filter(|max| total > *max) {
+        results.push((
+            first_extra_line,
+            finding(
+                FILE_TOO_LONG,
+                Severity::Info,
+                format!("File has {} lines (limit {})", total, max),
+                "Split the file into smaller modules",
+                (max + 1, 1),
+                "",
+            ),
+        ));
+    }
+    results
+}
+
+fn finding(
+    rule: &str,
+    severity: Severity,
+    message: String,
+    suggestion: &str,
+    (line, column): (usize, usize),
+    text: &str,
+) -> AnalysisResult {
+    // The line's own text rather than its number, so the finding survives edits above it
+    let key = format!(
+        "{}\0{}",
+        rule,
+        text.split_whitespace().collect::<Vec<_>>().join(" ")
+    );
+    AnalysisResult {
+        rule_name: rule.to_string(),
+        score_impact: severity.base_score_impact(),
+        severity,
+        message,
+        line,
+        column,
+        text: text.to_string(),
+        suggestion: Some(suggestion.to_string()),
+        category: None,
+        fingerprint: format!("{:016x}", stable_hash(key.as_bytes())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::CodeAnalyzer;
+
+    #[test]
+    fn test_long_lines_and_files() {
+        let limits = LengthLimits {
+            max_line_length: Some(10),
+            max_file_lines: Some(2),
+        };
+        let source = "fn a() {}\r\nlet größe_x = 1;\r\n\r\n";
+        let found = length_findings(source, &limits, ColumnUnit::Char);
+        let summary: Vec<(&str, usize, usize, usize)> = found
+            .iter()
+            .map(|(byte, r)| (r.rule_name.as_str(), r.line, r.column, *byte))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![(LINE_TOO_LONG, 2, 11, 23), (FILE_TOO_LONG, 3, 1, 31)]
+        );
+        assert_eq!(found[0].1.message, "Line is 16 characters long (limit 10)");
+
+        // Macro bodies are host lines, so they are not checked a second time
+        let analyzer = CodeAnalyzer::new_rust_analyzer()
+            .with_macro_bodies(true)
+            .with_length_limits(limits);
+        let results = analyzer
+            .analyze(
+                "fn main() {\n    println!(\"{}\", some_value);\n}\n",
+                &tree_sitter_rust::LANGUAGE.into(),
+            )
+            .unwrap();
+        let rules: Vec<&str> = results.iter().map(|r| r.rule_name.as_str()).collect();
+        assert_eq!(rules, vec![LINE_TOO_LONG, LINE_TOO_LONG, FILE_TOO_LONG]);
+    }
+}
@@ -1,79 +1,157 @@
-mod analyzer;
-mod ast;
-use crate::analyzer::{analyze_code_with_analyzer, CodeAnalyzer};
-use libc::c_char;
-use std::ffi::CString;
-use crate::ast::parse_ast;
+pub mod analyzer;
+pub mod api;
+pub mod ast;
+pub mod ast_diff;
+pub mod badge;
+pub mod baseline;
+pub mod batch;
+pub mod bench;
+pub mod cache;
+pub mod chains;
+pub mod commented_code;
+pub mod conditions;
+pub mod config;
+pub mod config_check;
+pub mod config_layers;
+pub mod daemon;
+pub mod diagnostics;
+pub mod diff;
+pub mod doc_coverage;
+pub mod empty_bodies;
+pub mod explain;
+#[cfg(feature = "ffi")]
+mod ffi;
+pub mod files;
+pub mod fragment;
+pub mod generated;
+pub mod git;
+pub mod grammar;
+pub mod highlight;
+pub mod hotspots;
+pub mod incremental;
+pub mod injection;
+pub mod language;
+pub mod length;
+pub mod license;
+pub mod locale;
+pub mod logging;
+pub mod lsp;
+pub mod macros;
+pub mod mcp;
+pub mod metrics;
+pub mod naming;
+pub mod node_at;
+pub mod outline;
+pub mod overflow;
+pub mod overrides;
+pub mod pipeline;
+pub mod plugin;
+pub mod position;
+pub mod prom;
+pub mod query;
+pub mod query_rules;
+pub mod report;
+pub mod review;
+pub mod rule_pack;
+pub mod rule_trial;
+pub mod run_info;
+pub mod scan;
+pub mod script;
+pub mod sfc;
+pub mod snapshot;
+pub mod source;
+pub mod stats;
+pub mod store;
+pub mod strings;
+pub mod summary;
+#[cfg(test)]
+mod test_support;
+pub mod timings;
+pub mod trace;
+pub mod tui;
+pub mod version;
 
-/// # Safety
-///
-/// This function needs to be exported so strings can be derefenced for FFI;
-#[no_mangle]
-pub unsafe extern "C" fn free_string(s: *mut c_char) {
-    if !s.is_null() {
-        let _ = CString::from_raw(s);
-    }
-}
+#[cfg(feature = "ffi")]
+pub use crate::ffi::*;
+use crate::language::SupportedLanguage;
 
-// Functions exported for FFF
-#[no_mangle]
-pub extern "C" fn parse_rust_ast(file_path: *const c_char) -> *mut c_char {
-    let language = tree_sitter_rust::LANGUAGE;
-    parse_ast(file_path, language.into())
+// Safe entry points working on in-memory source, for Rust callers and builds without
+// the C exports
+pub fn parse(source: &str, language: &str) -> Result<String, String> {
+    let lang = SupportedLanguage::from_name(language)
+        .ok_or_else(|| format!("Unsupported language '{}'", language))?;
+    ast::parse_source(source, &lang.tree_sitter_language()).map_err(|e| e.to_string())
 }
 
-#[no_mangle]
-pub extern "C" fn parse_java_ast(file_path: *const c_char) -> *mut c_char {
-    let language = tree_sitter_java::LANGUAGE;
-    parse_ast(file_path, language.into())
+// Parses a snippet that isn't a whole file (see `fragment::parse_fragment`)
+pub fn parse_fragment(source: &str, language: &str) -> Result<String, String> {
+    let lang = SupportedLanguage::from_name(language)
+        .ok_or_else(|| format!("Unsupported language '{}'", language))?;
+    let fragment = fragment::parse_fragment(source, lang, None).map_err(|e| e.to_string())?;
+    let mut output = Vec::new();
+    ast::write_ast_node(
+        fragment.root(),
+        source,
+        ast::AstFormat::Text,
+        &ast::AstOptions::default(),
+        &mut output,
+    )
+    .map_err(|e| e.to_string())?;
+    String::from_utf8(output).map_err(|e| e.to_string())
 }
 
-#[no_mangle]
-pub extern "C" fn parse_zig_ast(file_path: *const c_char) -> *mut c_char {
-    let language = tree_sitter_zig::LANGUAGE;
-    parse_ast(file_path, language.into())
+pub fn analyze(source: &str, language: &str) -> Result<String, String> {
+    let lang = SupportedLanguage::from_name(language)
+        .ok_or_else(|| format!("Unsupported language '{}'", language))?;
+    let analyzer = lang
+        .analyzer()
+        .ok_or_else(|| format!("Analysis not supported for language '{}'", lang.name()))?;
+    analyzer::analyze_source(source, &lang.tree_sitter_language(), &analyzer)
+        .map_err(|e| e.to_string())
 }
 
-#[no_mangle]
-pub extern "C" fn parse_c_ast(file_path: *const c_char) -> *mut c_char {
-    let language = tree_sitter_c::LANGUAGE;
-    parse_ast(file_path, language.into())
+// Syntax errors only, without running any rules: a cheap check for editors and
+// pre-commit hooks. Positions are in bytes.
+pub fn syntax_errors(
+    source: &str,
+    language: &str,
+) -> Result<Vec<diagnostics::SyntaxError>, String> {
+    let lang = SupportedLanguage::from_name(language)
+        .ok_or_else(|| format!("Unsupported language '{}'", language))?;
+    let tree = ast::parse_tree(source, &lang.tree_sitter_language()).map_err(|e| e.to_string())?;
+    Ok(diagnostics::collect_syntax_errors(
+        &tree,
+        source,
+        position::ColumnUnit::Byte,
+    ))
 }
 
-#[no_mangle]
-pub extern "C" fn parse_js_ast(file_path: *const c_char) -> *mut c_char {
-    let language = tree_sitter_javascript::LANGUAGE;
-    parse_ast(file_path, language.into())
+// Runs a tree-sitter query against in-memory source, returning captures as JSON
+pub fn query(source: &str, language: &str, query_text: &str) -> Result<String, String> {
+    let lang = SupportedLanguage::from_name(language)
+        .ok_or_else(|| format!("Unsupported language '{}'", language))?;
+    let captures = query::run_query(source, &lang.tree_sitter_language(), query_text)
+        .map_err(|e| e.to_string())?;
+    serde_json::to_string_pretty(&captures).map_err(|e| e.to_string())
 }
 
-#[no_mangle]
-pub extern "C" fn parse_ts_ast(file_path: *const c_char) -> *mut c_char {
-    let language = tree_sitter_typescript::LANGUAGE_TSX;
-    parse_ast(file_path, language.into())
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-#[no_mangle]
-pub extern "C" fn parse_cpp_ast(file_path: *const c_char) -> *mut c_char {
-    let language = tree_sitter_cpp::LANGUAGE;
-    parse_ast(file_path, language.into())
-}
-#[no_mangle]
-pub extern "C" fn analyze_rust_code(file_path: *const c_char) -> *mut c_char {
-    let language = tree_sitter_rust::LANGUAGE;
-    let analyzer = CodeAnalyzer::new_rust_analyzer();
-    analyze_code_with_analyzer(file_path, language.into(), analyzer)
-}
+    #[test]
+    fn test_parse_and_analyze_in_memory_source() {
+        let ast = parse("fn main() {}", "rust").unwrap();
+        assert!(ast.starts_with("(source_file)"));
+        assert!(ast.contains("(function_item)"));
 
-#[no_mangle]
-pub extern "C" fn analyze_go_code(file_path: *const c_char) -> *mut c_char {
-    let language = tree_sitter_go::LANGUAGE;
-    let analyzer = CodeAnalyzer::new_go_analyzer();
-    analyze_code_with_analyzer(file_path, language.into(), analyzer)
-}
+        let report = analyze("fn main() { x.unwrap(); }", "Rust").unwrap();
+        assert!(report.contains("unwrap_usage"));
+
+        let fragment = parse_fragment("x.unwrap()", "rust").unwrap();
+        assert!(fragment.starts_with("(call_expression)\n  (field_expression)"));
 
-#[no_mangle]
-pub extern "C" fn analyze_js_code(file_path: *const c_char) -> *mut c_char {
-    let language = tree_sitter_javascript::LANGUAGE;
-    let analyzer = CodeAnalyzer::new_javascript_analyzer();
-    analyze_code_with_analyzer(file_path, language.into(), analyzer)
-}
\ No newline at end of file
+        assert!(parse("x", "cobol").is_err());
+        assert!(analyze("int main() {}", "c").is_err());
+    }
+}
@@ -0,0 +1,345 @@
+use crate::language::SupportedLanguage;
+use crate::source::read_source;
+use regex::Regex;
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Any year or year range (`2019`, `2019-2024`, `2019, 2021`) stands in for `{year}`,
+// so headers don't all need touching each January
+const YEAR_PLACEHOLDER: &str = "{year}";
+const YEAR_PATTERN: &str = r"\d{4}(?:\s*[-,]\s*\d{4})*";
+
+// The header every file should start with, compared line by line with comment
+// markers and surrounding whitespace ignored
+#[derive(Debug, Clone)]
+pub struct HeaderTemplate {
+    lines: Vec<String>,
+    pattern: Regex,
+}
+
+impl HeaderTemplate {
+    // `text` is the bare header, or the header already written as a comment
+    pub fn new(text: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let lines = match leading_comment(text).1 {
+            Some(comment) if text.trim_start().starts_with('/') => comment.lines,
+            _ => normalize(text.lines()),
+        };
+        if lines.is_empty() {
+            return Err("License header template is empty".into());
+        }
+        let escaped: Vec<String> = lines
+            .iter()
+            .map(|line| {
+                let parts: Vec<String> = line.split(YEAR_PLACEHOLDER).map(regex::escape).collect();
+                parts.join(YEAR_PATTERN)
+            })
+            .collect();
+        // Text may follow the header in the same comment
+        let pattern = Regex::new(&format!("^{}(?:\n|$)", escaped.join("\n")))?;
+        Ok(HeaderTemplate { lines, pattern })
+    }
+
+    fn matches(&self, lines: &[String]) -> bool {
+        self.pattern.is_match(&lines.join("\n"))
+    }
+
+    // The header as `//` comments, which every supported language accepts
+    pub fn render(&self, year: i64, newline: &str) -> String {
+        let lines: Vec<String> = self
+            .lines
+            .iter()
+            .map(|line| line.replace(YEAR_PLACEHOLDER, &year.to_string()))
+            .map(|line| match line.is_empty() {
+                true => "//".to_string(),
+                false => format!("// {}", line),
+            })
+            .collect();
+        lines.join(newline)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeaderStatus {
+    Ok,
+    // The file doesn't start with a comment that looks like a license
+    Missing,
+    // It does, but not the one in the template
+    Mismatched,
+}
+
+impl HeaderStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HeaderStatus::Ok => "ok",
+            HeaderStatus::Missing => "missing",
+            HeaderStatus::Mismatched => "mismatched",
+        }
+    }
+}
+
+// The first comment of a file, after any shebang and blank lines
+struct LeadingComment {
+    start: usize,
+    // Up to the end of its last line, not including the line break
+    end: usize,
+    // Without comment markers, trimmed, blank lines at either end dropped
+    lines: Vec<String>,
+}
+
+impl LeadingComment {
+    fn looks_like_license(&self) -> bool {
+        let text = self.lines.join("\n").to_lowercase();
+        ["copyright", "license", "licence", "spdx-license-identifier"]
+            .iter()
+            .any(|word| text.contains(word))
+    }
+}
+
+fn normalize<'a>(lines: impl Iterator<Item = &'a str>) -> Vec<String> {
+    let mut lines: Vec<String> = lines.map(|l| l.trim().to_string()).collect();
+    while lines.last().is_some_and(String::is_empty) {
+        lines.pop();
+    }
+    let leading = lines.iter().take_while(|l| l.is_empty()).count();
+    lines.split_off(leading)
+}
+
+// Where a header goes (past a shebang) and the comment already there, if any
+fn leading_comment(source: &str) -> (usize, Option<LeadingComment>) {
+    let mut lines = Vec::new();
+    let mut offset = 0;
+    for line in source.split_inclusive('\n') {
+        lines.push((offset, line));
+        offset += line.len();
+    }
+    let insert_at = match lines.first() {
+        Some((_, first)) if first.starts_with("#!") => first.len(),
+        _ => 0,
+    };
+    let mut rest = lines.iter().skip_while(|(at, _)| *at < insert_at);
+    let Some(&(start, first)) = rest.by_ref().find(|(_, line)| !line.trim().is_empty()) else {
+        return (insert_at, None);
+    };
+    let start = start + (first.len() - first.trim_start().len());
+    let line_end = |at: usize, line: &str| at + line.trim_end_matches(['\n', '\r']).len();
+
+    if first.trim_start().starts_with("//") {
+        let mut end = line_end(start, first.trim_start());
+        let mut body = vec![first.trim_start()];
+        for &(at, line) in rest.take_while(|(_, line)| line.trim_start().starts_with("//")) {
+            end = line_end(at, line);
+            body.push(line);
+        }
+        let text = body
+            .iter()
+            .map(|line| line.trim().trim_start_matches('/').trim_start_matches('!'));
+        let comment = LeadingComment {
+            start,
+            end,
+            lines: normalize(text),
+        };
+        return (insert_at, Some(comment));
+    }
+    if source[start..].starts_with("/*") {
+        let Some(close) = source[start..].find("*/") else {
+            return (insert_at, None);
+        };
+        let end = start + close + 2;
+        let inner = source[start + 2..end - 2].trim_start_matches(['*', '!']);
+        // Leading `*` gutters are decoration
+        let text = inner
+            .lines()
+            .map(|l| l.trim_start().trim_start_matches('*'));
+        let comment = LeadingComment {
+            start,
+            end,
+            lines: normalize(text),
+        };
+        return (insert_at, Some(comment));
+    }
+    (insert_at, None)
+}
+
+pub fn check_header(source: &str, template: &HeaderTemplate) -> HeaderStatus {
+    match leading_comment(source).1 {
+        Some(comment) if template.matches(&comment.lines) => HeaderStatus::Ok,
+        Some(comment) if comment.looks_like_license() => HeaderStatus::Mismatched,
+        _ => HeaderStatus::Missing,
+    }
+}
+
+// The source with the template's header inserted, or in place of a mismatched
+// license comment; None when the header is already right
+pub fn fix_header(source: &str, template: &HeaderTemplate, year: i64) -> Option<String> {
+    let newline = if source.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+    let header = template.render(year, newline);
+    let (insert_at, comment) = leading_comment(source);
+    match comment {
+        Some(comment) if template.matches(&comment.lines) => None,
+        Some(comment) if comment.looks_like_license() => Some(format!(
+            "{}{}{}",
+            &source[..comment.start],
+            header,
+            &source[comment.end..]
+        )),
+        _ => {
+            let rest = &source[insert_at..];
+            let separator = match rest.is_empty() {
+                true => newline.to_string(),
+                false => format!("{}{}", newline, newline),
+            };
+            Some(format!(
+                "{}{}{}{}",
+                &source[..insert_at],
+                header,
+                separator,
+                rest
+            ))
+        }
+    }
+}
+
+// The current UTC calendar year, for filling in `{year}`
+pub fn current_year() -> i64 {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64);
+    // Days since 1970-01-01 to a proleptic Gregorian year, counting from 0000-03-01
+    let days = seconds.div_euclid(86_400) + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    // January and February belong to the next year
+    year_of_era + era * 400 + i64::from(month_index >= 10)
+}
+
+// The header status of every source file in `files`, with paths relative to `root`.
+// With `fix`, missing and mismatched headers are rewritten in place.
+pub fn license_report(
+    root: &Path,
+    files: &[PathBuf],
+    template: &HeaderTemplate,
+    fix: bool,
+) -> Value {
+    let year = current_year();
+    let mut entries = Vec::new();
+    let mut counts = [0usize; 3];
+    for path in files {
+        if SupportedLanguage::detect(path).is_none() {
+            continue;
+        }
+        let relative = match path.strip_prefix(root) {
+            Ok(rel) if !rel.as_os_str().is_empty() => rel,
+            _ => path.as_path(),
+        };
+        let relative = relative.display().to_string();
+        let source = match read_source(path, None) {
+            Ok(source) => source,
+            Err(e) => {
+                entries.push(json!({ "path": relative, "error": e.to_string() }));
+                continue;
+            }
+        };
+        let status = check_header(&source, template);
+        counts[status as usize] += 1;
+        let mut entry = json!({ "path": relative, "status": status.as_str() });
+        if fix && status != HeaderStatus::Ok {
+            let written = fix_header(&source, template, year)
+                .map_or(Ok(()), |fixed| std::fs::write(path, fixed));
+            match written {
+                Ok(()) => entry["fixed"] = json!(true),
+                Err(e) => entry["error"] = json!(e.to_string()),
+            }
+        }
+        entries.push(entry);
+    }
+    json!({
+        "files": entries,
+        "ok": counts[HeaderStatus::Ok as usize],
+        "missing": counts[HeaderStatus::Missing as usize],
+        "mismatched": counts[HeaderStatus::Mismatched as usize],
+    })
+}
+
+// Files needing attention, then a one-line summary
+pub fn format_license_text(report: &Value) -> String {
+    let mut out = String::new();
+    for entry in report["files"].as_array().into_iter().flatten() {
+        let path = entry["path"].as_str().unwrap_or("");
+        if let Some(error) = entry["error"].as_str() {
+            out.push_str(&format!("{}: error: {}\n", path, error));
+            continue;
+        }
+        let status = entry["status"].as_str().unwrap_or("");
+        if status == HeaderStatus::Ok.as_str() {
+            continue;
+        }
+        let fixed = if entry["fixed"] == true {
+            " (fixed)"
+        } else {
+            ""
+        };
+        out.push_str(&format!("{}: {} license header{}\n", path, status, fixed));
+    }
+    out.push_str(&format!(
+        "{} ok, {} missing, {} mismatched\n",
+        report["ok"], report["missing"], report["mismatched"]
+    ));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_license_headers_checked_and_fixed() {
+        let template =
+            HeaderTemplate::new("Copyright {year} Acme Inc.\nSPDX-License-Identifier: MIT\n")
+                .unwrap();
+        let status = |source: &str| check_header(source, &template);
+
+        assert_eq!(
+            status("// Copyright 2019-2024 Acme Inc.\n// SPDX-License-Identifier: MIT\n\nfn main() {}\n"),
+            HeaderStatus::Ok
+        );
+        assert_eq!(
+            status("#!/usr/bin/env node\n/*\n * Copyright 2021 Acme Inc.\n * SPDX-License-Identifier: MIT\n * Extra notes.\n */\n"),
+            HeaderStatus::Ok
+        );
+        assert_eq!(
+            status("// Copyright 2020 Other Corp\n// SPDX-License-Identifier: MIT\npackage m\n"),
+            HeaderStatus::Mismatched
+        );
+        assert_eq!(
+            status("//! Crate docs\nfn main() {}\n"),
+            HeaderStatus::Missing
+        );
+
+        assert_eq!(
+            fix_header("#!/bin/sh\n//! Crate docs\r\n", &template, 2025).unwrap(),
+            "#!/bin/sh\n// Copyright 2025 Acme Inc.\r\n// SPDX-License-Identifier: MIT\r\n\r\n//! Crate docs\r\n"
+        );
+        let fixed = fix_header(
+            "/* Copyright 2020 Other Corp */\npackage m\n",
+            &template,
+            2025,
+        )
+        .unwrap();
+        assert_eq!(
+            fixed,
+            "// Copyright 2025 Acme Inc.\n// SPDX-License-Identifier: MIT\npackage m\n"
+        );
+        assert_eq!(status(&fixed), HeaderStatus::Ok);
+        assert_eq!(fix_header(&fixed, &template, 2025), None);
+        assert!(current_year() >= 2024);
+    }
+}
@@ -0,0 +1,275 @@
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+// Rule text in another language, replacing the English message and suggestion.
+// Either may be missing, in which case the English one stays.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Translation {
+    pub message: Option<String>,
+    pub suggestion: Option<String>,
+}
+
+impl Translation {
+    // {"ja": {"message": "...", "suggestion": "..."}}
+    pub fn from_json(value: &Value) -> Result<BTreeMap<String, Translation>, String> {
+        let locales = value
+            .as_object()
+            .ok_or("\"translations\" must map locales to {\"message\", \"suggestion\"}")?;
+        locales
+            .iter()
+            .map(|(locale, text)| {
+                let field = |key: &str| match text.get(key) {
+                    None => Ok(None),
+                    Some(Value::String(s)) => Ok(Some(s.clone())),
+                    Some(_) => Err(format!(
+                        "translation '{}': \"{}\" must be a string",
+                        locale, key
+                    )),
+                };
+                Ok((
+                    locale.clone(),
+                    Translation {
+                        message: field("message")?,
+                        suggestion: field("suggestion")?,
+                    },
+                ))
+            })
+            .collect()
+    }
+}
+
+// Locales with a bundled catalog for the built-in rules
+pub const BUNDLED_LOCALES: &[&str] = &["en", "ja"];
+
+// Messages computed from the code (counts, names, limits) are not listed, only their
+// suggestions
+const JA: &[(&str, Option<&str>, Option<&str>)] = &[
+    ("syntax_error", Some("構文エラー"), None),
+    (
+        "unwrap_usage",
+        Some(".unwrap() の使用はパニックの原因になります"),
+        Some("メッセージ付きの .expect() か、適切なエラー処理を検討してください"),
+    ),
+    (
+        "large_function",
+        Some("関数が大きすぎる可能性があります"),
+        Some("小さな関数に分割することを検討してください"),
+    ),
+    (
+        "discarded_result",
+        Some("`let _ =` で呼び出しの結果が破棄されています"),
+        Some("? でエラーを処理・伝播するか、無視してよい理由をログに残してください"),
+    ),
+    (
+        "swallowed_error",
+        Some("未使用の .ok() でエラーが握りつぶされています"),
+        Some("無視される Option に変換せず、エラーを処理または伝播してください"),
+    ),
+    (
+        "io_unwrap_or_default",
+        Some("I/O エラーがデフォルト値に置き換えられています"),
+        Some("読み込みの失敗が空データになってしまいます。エラーを伝播または報告してください"),
+    ),
+    (
+        "console_log",
+        Some("console.log 文が見つかりました"),
+        Some("本番前に削除してください"),
+    ),
+    (
+        "var_usage",
+        Some("'var' キーワードが使われています"),
+        Some("代わりに 'let' か 'const' を使ってください"),
+    ),
+    (
+        "js_unawaited_promise",
+        Some("async 関数内で Promise が await されていません"),
+        Some("失敗が呼び出し元に届くよう、await するか return してください"),
+    ),
+    (
+        "js_then_without_catch",
+        Some(".catch() のない .then()"),
+        Some("エラーが黙って捨てられないよう .catch()（または reject ハンドラ）を追加してください"),
+    ),
+    (
+        "js_async_promise_executor",
+        Some("Promise の executor に async 関数が使われています"),
+        Some("async executor 内の例外は Promise を reject しません。async かラッパーを外してください"),
+    ),
+    (
+        "react_missing_key",
+        Some("リストで描画される要素に 'key' プロパティがありません"),
+        Some("各要素に安定した一意の key を指定してください"),
+    ),
+    (
+        "react_inline_function_prop",
+        Some("インライン関数がプロパティとして渡されています"),
+        Some("子の再描画を避けるため、ハンドラは一度だけ定義してください（useCallback）"),
+    ),
+    (
+        "react_large_component",
+        Some("コンポーネントが大きすぎます"),
+        Some("小さなコンポーネントに分割してください"),
+    ),
+    (
+        "go_missing_error_check",
+        Some("エラーが確認されていない可能性があります"),
+        Some("この代入の後で 'if err != nil' を確認してください"),
+    ),
+    (
+        "go_unused_variable",
+        Some("未使用の可能性がある変数"),
+        Some("意図的に使わない場合は _ を使ってください"),
+    ),
+    (
+        "go_panic_usage",
+        Some("panic() が使われています"),
+        Some("panic せずにエラーを返すことを検討してください"),
+    ),
+    (
+        "go_large_function",
+        Some("関数が大きすぎる可能性があります"),
+        Some("小さな関数に分割することを検討してください"),
+    ),
+    (
+        "go_too_many_parameters",
+        Some("関数の引数が多すぎます"),
+        Some("構造体を使うか、引数を減らすことを検討してください"),
+    ),
+    (
+        "go_global_variable",
+        Some("グローバル変数の宣言"),
+        Some("このグローバル変数が本当に必要か検討してください"),
+    ),
+    (
+        "go_missing_package_doc",
+        Some("パッケージのドキュメントがありません"),
+        Some("パッケージのドキュメントコメントを追加してください"),
+    ),
+    (
+        "go_todo_comment",
+        Some("TODO コメントが見つかりました"),
+        Some("この TODO への対応を検討してください"),
+    ),
+    (
+        "go_magic_number",
+        Some("マジックナンバーが見つかりました"),
+        Some("名前付き定数の使用を検討してください"),
+    ),
+    (
+        "go_deep_nesting",
+        Some("深いネストを検出しました（4 段以上）"),
+        Some("ネストしたロジックを別の関数に切り出すことを検討してください"),
+    ),
+    (
+        "go_loop_variable_capture",
+        Some("goroutine のクロージャがループ変数をキャプチャしています"),
+        Some("変数は引数として渡してください。Go 1.22 より前は全イテレーションで共有されます"),
+    ),
+    (
+        "go_missing_waitgroup_wait",
+        Some("sync.WaitGroup が一度も Wait されていません"),
+        Some("goroutine の完了を前提にする前に Wait() を呼んでください"),
+    ),
+    (
+        "go_sleep_synchronization",
+        Some("goroutine を待つために time.Sleep が使われています"),
+        Some("sync.WaitGroup、チャネル、context で同期してください"),
+    ),
+    (
+        "commented_out_code",
+        None,
+        Some("削除してください。古いコードはバージョン管理に残っています"),
+    ),
+    (
+        "naming_convention",
+        None,
+        Some("プロジェクトの命名規則に従って名前を変更してください"),
+    ),
+    (
+        "nested_ternary",
+        None,
+        Some("if/else、ルックアップテーブル、名前付きヘルパーを使ってください"),
+    ),
+    (
+        "complex_boolean_expression",
+        None,
+        Some("条件の一部を分かりやすい名前の変数やヘルパー関数にしてください"),
+    ),
+];
+
+// The bundled translation of a built-in rule, if `locale` has one
+pub fn bundled_translation(locale: &str, rule: &str) -> Option<Translation> {
+    let catalog = match locale {
+        "ja" => JA,
+        _ => return None,
+    };
+    catalog
+        .iter()
+        .find(|(name, _, _)| *name == rule)
+        .map(|(_, message, suggestion)| Translation {
+            message: message.map(str::to_string),
+            suggestion: suggestion.map(str::to_string),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::analyzer::CodeAnalyzer;
+    use crate::language::SupportedLanguage;
+    use crate::script::ScriptRule;
+    use serde_json::json;
+
+    #[test]
+    fn test_findings_in_the_chosen_locale() {
+        let script = ScriptRule::from_json(&json!({
+            "language": "javascript",
+            "name": "no_alert",
+            "query": "(call_expression function: (identifier) @f (#eq? @f \"alert\"))",
+            "script": "len(text) > 0",
+            "message": "alert() blocks the page",
+            "translations": {"ja": {"message": "alert() はページをブロックします"}}
+        }))
+        .unwrap();
+        let analyzer = |locale: Option<&str>| {
+            let mut analyzer = CodeAnalyzer::new_javascript_analyzer();
+            analyzer.add_rule(script.to_analysis_rule());
+            analyzer.with_locale(locale)
+        };
+        let source = "var a = 1;\nalert(a);\nconst f = (x) => x ? 1 : x ? 2 : 3;\n";
+        let language = SupportedLanguage::JavaScript.tree_sitter_language();
+        let found = |analyzer: &CodeAnalyzer| {
+            analyzer
+                .analyze(source, &language)
+                .unwrap()
+                .into_iter()
+                .map(|r| (r.rule_name, r.message, r.suggestion))
+                .collect::<Vec<_>>()
+        };
+
+        let english = found(&analyzer(None));
+        let japanese = found(&analyzer(Some("ja")));
+        let text = |rule: &str, results: &[(String, String, Option<String>)]| {
+            results.iter().find(|r| r.0 == rule).cloned().unwrap()
+        };
+        assert_eq!(text("var_usage", &english).1, "Use of 'var' keyword");
+        assert_eq!(
+            text("var_usage", &japanese),
+            (
+                "var_usage".to_string(),
+                "'var' キーワードが使われています".to_string(),
+                Some("代わりに 'let' か 'const' を使ってください".to_string())
+            )
+        );
+        assert_eq!(
+            text("no_alert", &japanese).1,
+            "alert() はページをブロックします"
+        );
+        // Computed messages stay in English, their suggestions are translated
+        let ternary = text("nested_ternary", &japanese);
+        assert_eq!(ternary.1, "Ternary nested 2 levels deep");
+        assert!(ternary.2.unwrap().starts_with("if/else"));
+
+        // Locales without a catalog leave everything in English
+        assert_eq!(found(&analyzer(Some("fr"))), english);
+    }
+}
@@ -0,0 +1,178 @@
+use serde_json::{json, Map, Value};
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Diagnostics go to stderr so stdout carries only the command's payload.
+// `--quiet` keeps errors only, the default adds warnings and progress, `-v` adds
+// timings and `-vv` per-rule diagnostics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Error = 0,
+    Warn = 1,
+    Info = 2,
+    Debug = 3,
+    Trace = 4,
+}
+
+impl Level {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Level::Error => "error",
+            Level::Warn => "warn",
+            Level::Info => "info",
+            Level::Debug => "debug",
+            Level::Trace => "trace",
+        }
+    }
+
+    fn from_u8(value: u8) -> Self {
+        match value {
+            0 => Level::Error,
+            1 => Level::Warn,
+            2 => Level::Info,
+            3 => Level::Debug,
+            _ => Level::Trace,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    Text,
+    // One JSON object per line, for CI log processors
+    Json,
+}
+
+impl LogFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "text" => Some(LogFormat::Text),
+            "json" => Some(LogFormat::Json),
+            _ => None,
+        }
+    }
+}
+
+static LEVEL: AtomicU8 = AtomicU8::new(Level::Info as u8);
+static FORMAT: AtomicU8 = AtomicU8::new(0);
+
+pub fn init(level: Level, format: LogFormat) {
+    LEVEL.store(level as u8, Ordering::Relaxed);
+    FORMAT.store((format == LogFormat::Json) as u8, Ordering::Relaxed);
+}
+
+pub fn level() -> Level {
+    Level::from_u8(LEVEL.load(Ordering::Relaxed))
+}
+
+fn format() -> LogFormat {
+    if FORMAT.load(Ordering::Relaxed) == 1 {
+        LogFormat::Json
+    } else {
+        LogFormat::Text
+    }
+}
+
+// Lets callers skip building expensive diagnostics nobody will see
+pub fn enabled(level: Level) -> bool {
+    level <= self::level()
+}
+
+// Text records keep the CLI's existing prefixes ("Error: ", "Warning: ") and
+// append fields as key=value; JSON records carry them as keys
+pub fn format_record(
+    level: Level,
+    format: LogFormat,
+    message: &str,
+    fields: &[(&str, Value)],
+) -> String {
+    match format {
+        LogFormat::Text => {
+            let prefix = match level {
+                Level::Error => "Error: ",
+                Level::Warn => "Warning: ",
+                Level::Info => "",
+                Level::Debug => "debug: ",
+                Level::Trace => "trace: ",
+            };
+            let mut line = format!("{}{}", prefix, message);
+            for (key, value) in fields {
+                match value {
+                    Value::String(s) => line.push_str(&format!(" {}={}", key, s)),
+                    other => line.push_str(&format!(" {}={}", key, other)),
+                }
+            }
+            line
+        }
+        LogFormat::Json => {
+            let mut record = Map::new();
+            let timestamp = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0.0, |d| d.as_secs_f64());
+            record.insert("timestamp".to_string(), json!(timestamp));
+            record.insert("level".to_string(), json!(level.as_str()));
+            record.insert("message".to_string(), json!(message));
+            for (key, value) in fields {
+                record.insert(key.to_string(), value.clone());
+            }
+            Value::Object(record).to_string()
+        }
+    }
+}
+
+pub fn log(level: Level, message: &str, fields: &[(&str, Value)]) {
+    if enabled(level) {
+        eprintln!("{}", format_record(level, format(), message, fields));
+    }
+}
+
+pub fn error(message: &str) {
+    log(Level::Error, message, &[]);
+}
+
+pub fn warn(message: &str) {
+    log(Level::Warn, message, &[]);
+}
+
+pub fn info(message: &str) {
+    log(Level::Info, message, &[]);
+}
+
+pub fn debug(message: &str, fields: &[(&str, Value)]) {
+    log(Level::Debug, message, fields);
+}
+
+pub fn trace(message: &str, fields: &[(&str, Value)]) {
+    log(Level::Trace, message, fields);
+}
+
+// Milliseconds with microsecond precision, for timing fields
+pub fn millis(elapsed: std::time::Duration) -> Value {
+    json!((elapsed.as_secs_f64() * 1_000_000.0).round() / 1000.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_formats() {
+        let fields = [("file", json!("a.rs")), ("elapsed_ms", json!(1.5))];
+        assert_eq!(
+            format_record(Level::Debug, LogFormat::Text, "analyzed", &fields),
+            "debug: analyzed file=a.rs elapsed_ms=1.5"
+        );
+        assert_eq!(
+            format_record(Level::Warn, LogFormat::Text, "slow", &[]),
+            "Warning: slow"
+        );
+        let record: Value =
+            serde_json::from_str(&format_record(Level::Info, LogFormat::Json, "hi", &fields))
+                .unwrap();
+        assert_eq!(record["level"], "info");
+        assert_eq!(record["message"], "hi");
+        assert_eq!(record["file"], "a.rs");
+        assert!(record["timestamp"].as_f64().unwrap() > 0.0);
+        assert!(Level::Error < Level::Trace);
+    }
+}
@@ -0,0 +1,430 @@
+use crate::analyzer::{AnalysisResult, CodeAnalyzer, Severity};
+use crate::ast::parse_tree;
+use crate::config::ProjectConfig;
+use crate::diagnostics::collect_syntax_errors;
+use crate::generated::{has_generated_marker, is_generated_path};
+use crate::language::SupportedLanguage;
+use crate::outline::{extract_symbols, Symbol, SymbolKind};
+use crate::position::ColumnUnit;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+
+// Minimal language server: full-document sync, diagnostics from the analyzer
+// (or syntax errors for parse-only languages) and a document outline.
+// Positions are LSP's 0-based line and UTF-16 character offsets.
+pub fn run_stdio() -> io::Result<()> {
+    CodeAnalyzer::warm_up();
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    serve(&mut stdin.lock(), &mut stdout.lock())
+}
+
+pub fn serve<R: BufRead, W: Write>(input: &mut R, output: &mut W) -> io::Result<()> {
+    let mut server = Server::default();
+    while let Some(message) = read_message(input)? {
+        let Some(outgoing) = server.handle(&message) else {
+            return Ok(());
+        };
+        for reply in outgoing {
+            write_message(output, &reply)?;
+        }
+    }
+    Ok(())
+}
+
+// Reads one `Content-Length`-framed JSON message; None at end of input
+pub fn read_message<R: BufRead>(input: &mut R) -> io::Result<Option<Value>> {
+    let mut content_length = None;
+    loop {
+        let mut header = String::new();
+        if input.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+
+    let length = content_length.ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidData, "missing Content-Length header")
+    })?;
+    let mut body = vec![0; length];
+    input.read_exact(&mut body)?;
+    serde_json::from_slice(&body)
+        .map(Some)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+pub fn write_message<W: Write>(output: &mut W, message: &Value) -> io::Result<()> {
+    let body = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    output.flush()
+}
+
+struct Document {
+    language: SupportedLanguage,
+    text: String,
+    // None for languages that only get syntax diagnostics
+    analyzer: Option<CodeAnalyzer>,
+    generated: bool,
+}
+
+#[derive(Default)]
+struct Server {
+    documents: HashMap<String, Document>,
+}
+
+impl Server {
+    // Returns the messages to send back, or None once the client asked to exit
+    fn handle(&mut self, message: &Value) -> Option<Vec<Value>> {
+        let method = message["method"].as_str().unwrap_or("");
+        let params = &message["params"];
+        let id = message.get("id").cloned();
+
+        let mut outgoing = Vec::new();
+        match method {
+            "initialize" => outgoing.push(response(id, initialize_result())),
+            "shutdown" => outgoing.push(response(id, Value::Null)),
+            "exit" => return None,
+            "textDocument/didOpen" => {
+                let document = &params["textDocument"];
+                let uri = document["uri"].as_str().unwrap_or("").to_string();
+                let text = document["text"].as_str().unwrap_or("").to_string();
+                let language_id = document["languageId"].as_str().unwrap_or("");
+                match self.open(&uri, language_id, text) {
+                    Ok(warning) => {
+                        outgoing.extend(warning.as_deref().map(show_message));
+                        outgoing.push(self.publish_diagnostics(&uri));
+                    }
+                    Err(message) => outgoing.push(show_message(&message)),
+                }
+            }
+            "textDocument/didChange" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or("");
+                // Full sync: the last change holds the whole document
+                let text = params["contentChanges"]
+                    .as_array()
+                    .and_then(|changes| changes.last())
+                    .and_then(|change| change["text"].as_str());
+                if let (Some(document), Some(text)) = (self.documents.get_mut(uri), text) {
+                    document.text = text.to_string();
+                    outgoing.push(self.publish_diagnostics(uri));
+                }
+            }
+            "textDocument/didSave" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or("");
+                if let Some(document) = self.documents.get_mut(uri) {
+                    if let Some(text) = params["text"].as_str() {
+                        document.text = text.to_string();
+                    }
+                    outgoing.push(self.publish_diagnostics(uri));
+                }
+            }
+            "textDocument/didClose" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or("");
+                if self.documents.remove(uri).is_some() {
+                    outgoing.push(diagnostics_notification(uri, Vec::new()));
+                }
+            }
+            "textDocument/documentSymbol" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or("");
+                let symbols = self.document_symbols(uri);
+                outgoing.push(response(id, symbols));
+            }
+            _ => {
+                // Requests must be answered; unknown notifications are ignored
+                if let Some(id) = id {
+                    outgoing.push(json!({
+                        "jsonrpc": "2.0",
+                        "id": id,
+                        "error": { "code": -32601, "message": format!("Method not found: {}", method) },
+                    }));
+                }
+            }
+        }
+        Some(outgoing)
+    }
+
+    // Returns a warning to surface when the project config could not be used
+    fn open(
+        &mut self,
+        uri: &str,
+        language_id: &str,
+        text: String,
+    ) -> Result<Option<String>, String> {
+        let path = uri_to_path(uri);
+        let language = SupportedLanguage::detect_source(&path, &text)
+            .or_else(|| SupportedLanguage::from_name(language_id))
+            .ok_or_else(|| format!("treescan: unsupported document '{}'", uri))?;
+
+        let dir = path.parent().unwrap_or(Path::new("."));
+        let (config, warning) = match ProjectConfig::discover(dir) {
+            Ok((_, config)) => (config, None),
+            Err(e) => (ProjectConfig::default(), Some(e.to_string())),
+        };
+        let analyzer = language
+            .analyzer_for_path(&path, &config.rules)
+            .map(|analyzer| {
+                let analyzer = analyzer.with_column_unit(ColumnUnit::Utf16);
+                match config.max_issues_per_file {
+                    Some(max) => analyzer.with_max_issues(max),
+                    None => analyzer,
+                }
+            });
+        let generated = !config.include_generated
+            && (is_generated_path(&path) || has_generated_marker(header(&text)));
+
+        self.documents.insert(
+            uri.to_string(),
+            Document {
+                language,
+                text,
+                analyzer,
+                generated,
+            },
+        );
+        Ok(warning.map(|warning| format!("treescan: using default settings: {}", warning)))
+    }
+
+    fn publish_diagnostics(&self, uri: &str) -> Value {
+        let Some(document) = self.documents.get(uri) else {
+            return diagnostics_notification(uri, Vec::new());
+        };
+        if document.generated {
+            return diagnostics_notification(uri, Vec::new());
+        }
+
+        let language = document.language.tree_sitter_language();
+        let diagnostics = match &document.analyzer {
+            Some(analyzer) => analyzer
+                .analyze(&document.text, &language)
+                .map(|results| results.iter().map(result_to_diagnostic).collect())
+                .unwrap_or_default(),
+            None => parse_tree(&document.text, &language)
+                .map(|tree| {
+                    collect_syntax_errors(&tree, &document.text, ColumnUnit::Utf16)
+                        .iter()
+                        .map(|error| {
+                            json!({
+                                "range": range(
+                                    (error.start_line, error.start_column),
+                                    (error.end_line, error.end_column),
+                                ),
+                                "severity": 1,
+                                "source": "treescan",
+                                "code": "syntax_error",
+                                "message": error.to_string(),
+                            })
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
+        };
+        diagnostics_notification(uri, diagnostics)
+    }
+
+    fn document_symbols(&self, uri: &str) -> Value {
+        let Some(document) = self.documents.get(uri) else {
+            return Value::Null;
+        };
+        let language = document.language.tree_sitter_language();
+        match parse_tree(&document.text, &language) {
+            Ok(tree) => Value::Array(
+                extract_symbols(&tree, &document.text, ColumnUnit::Utf16)
+                    .iter()
+                    .map(document_symbol)
+                    .collect(),
+            ),
+            Err(_) => Value::Null,
+        }
+    }
+}
+
+fn initialize_result() -> Value {
+    json!({
+        "capabilities": {
+            "textDocumentSync": { "openClose": true, "change": 1, "save": { "includeText": true } },
+            "documentSymbolProvider": true,
+        },
+        "serverInfo": { "name": "treescan", "version": env!("CARGO_PKG_VERSION") },
+    })
+}
+
+fn response(id: Option<Value>, result: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id.unwrap_or(Value::Null), "result": result })
+}
+
+fn show_message(message: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "window/showMessage",
+        "params": { "type": 2, "message": message },
+    })
+}
+
+fn diagnostics_notification(uri: &str, diagnostics: Vec<Value>) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/publishDiagnostics",
+        "params": { "uri": uri, "diagnostics": diagnostics },
+    })
+}
+
+// Converts 1-based (line, column) pairs into an LSP range
+fn range(start: (usize, usize), end: (usize, usize)) -> Value {
+    json!({
+        "start": { "line": start.0 - 1, "character": start.1 - 1 },
+        "end": { "line": end.0 - 1, "character": end.1 - 1 },
+    })
+}
+
+fn result_to_diagnostic(result: &AnalysisResult) -> Value {
+    // Results only carry their start, so the end is recovered from the matched text
+    let end = match result.text.rsplit_once('\n') {
+        Some((before, last)) => (
+            result.line + before.matches('\n').count() + 1,
+            last.encode_utf16().count() + 1,
+        ),
+        None => (
+            result.line,
+            result.column + result.text.encode_utf16().count(),
+        ),
+    };
+    let severity = match result.severity {
+        Severity::Error => 1,
+        Severity::Warning => 2,
+        Severity::Info => 3,
+        Severity::Style => 4,
+    };
+    let message = match &result.suggestion {
+        Some(suggestion) => format!("{}. {}", result.message, suggestion),
+        None => result.message.clone(),
+    };
+    json!({
+        "range": range((result.line, result.column), end),
+        "severity": severity,
+        "source": "treescan",
+        "code": result.rule_name,
+        "message": message,
+        "data": { "fingerprint": result.fingerprint },
+    })
+}
+
+fn document_symbol(symbol: &Symbol) -> Value {
+    let kind = match symbol.kind {
+        SymbolKind::Module => 2,
+        SymbolKind::Class => 5,
+        SymbolKind::Method => 6,
+        SymbolKind::Enum => 10,
+        SymbolKind::Interface => 11,
+        SymbolKind::Function => 12,
+        SymbolKind::Constant => 14,
+        SymbolKind::Struct => 23,
+        SymbolKind::TypeAlias => 26,
+    };
+    json!({
+        "name": symbol.name,
+        "kind": kind,
+        "range": range(symbol.start, symbol.end),
+        "selectionRange": range(symbol.name_start, symbol.name_end),
+        "children": symbol.children.iter().map(document_symbol).collect::<Vec<_>>(),
+    })
+}
+
+fn header(text: &str) -> &str {
+    let mut end = text.len().min(1024);
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    &text[..end]
+}
+
+// Decodes `file://` URIs; anything else is treated as a plain path
+pub fn uri_to_path(uri: &str) -> PathBuf {
+    let path = uri.strip_prefix("file://").unwrap_or(uri);
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| path.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    PathBuf::from(String::from_utf8_lossy(&decoded).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(message: Value) -> String {
+        let body = message.to_string();
+        format!("Content-Length: {}\r\n\r\n{}", body.len(), body)
+    }
+
+    #[test]
+    fn test_session_publishes_diagnostics_and_symbols() {
+        let uri = "file:///tmp/treescan%20lsp/main.rs";
+        let input = [
+            frame(json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {} })),
+            frame(
+                json!({ "jsonrpc": "2.0", "method": "textDocument/didOpen", "params": {
+                "textDocument": { "uri": uri, "languageId": "rust", "version": 1,
+                                  "text": "fn main() { x.unwrap(); }" } } }),
+            ),
+            frame(
+                json!({ "jsonrpc": "2.0", "id": 2, "method": "textDocument/documentSymbol",
+                          "params": { "textDocument": { "uri": uri } } }),
+            ),
+            frame(json!({ "jsonrpc": "2.0", "id": 3, "method": "shutdown" })),
+            frame(json!({ "jsonrpc": "2.0", "method": "exit" })),
+        ]
+        .concat();
+
+        let mut output = Vec::new();
+        serve(&mut input.as_bytes(), &mut output).unwrap();
+        let mut replies = Vec::new();
+        let mut reader = output.as_slice();
+        while let Some(message) = read_message(&mut reader).unwrap() {
+            replies.push(message);
+        }
+
+        assert_eq!(replies.len(), 4);
+        assert_eq!(
+            replies[0]["result"]["capabilities"]["documentSymbolProvider"],
+            true
+        );
+        let diagnostic = &replies[1]["params"]["diagnostics"][0];
+        assert_eq!(diagnostic["code"], "unwrap_usage");
+        assert_eq!(
+            diagnostic["range"]["start"],
+            json!({ "line": 0, "character": 12 })
+        );
+        assert_eq!(
+            diagnostic["range"]["end"],
+            json!({ "line": 0, "character": 22 })
+        );
+        assert_eq!(replies[2]["result"][0]["name"], "main");
+        assert_eq!(replies[3]["id"], 3);
+        assert_eq!(uri_to_path(uri), PathBuf::from("/tmp/treescan lsp/main.rs"));
+    }
+}
@@ -0,0 +1,130 @@
+use crate::analyzer::{AnalysisResult, CodeAnalyzer};
+use crate::ast::parse_tree;
+use crate::injection::host_position;
+use crate::language::SupportedLanguage;
+use crate::position::ColumnUnit;
+use std::ops::Range;
+use tree_sitter::{Node, Tree};
+
+// Ways to read a macro body as Rust, tried in order: items (macro_rules
+// transcribers, `lazy_static!`), statements (`tokio::select!` arms) and
+// comma-separated expressions (`println!`, `vec!`, `assert_eq!`)
+const WRAPPERS: &[(&str, &str)] = &[
+    ("", ""),
+    ("fn __body() {\n", "\n}"),
+    ("fn __body() { [\n", "\n]; }"),
+];
+
+const MACRO_NOTE: &str = " (in macro body)";
+
+// Byte ranges inside the delimiters of macro invocation bodies and macro_rules
+// transcribers. Nested token trees are part of their enclosing body.
+pub fn macro_bodies(tree: &Tree) -> Vec<Range<usize>> {
+    let mut bodies = Vec::new();
+    let mut pending = vec![tree.root_node()];
+    while let Some(node) = pending.pop() {
+        let body = match node.kind() {
+            "macro_invocation" => last_token_tree(&node),
+            "macro_rule" => node.child_by_field_name("right"),
+            _ => {
+                let mut cursor = node.walk();
+                pending.extend(node.named_children(&mut cursor));
+                continue;
+            }
+        };
+        if let Some(body) = body.filter(|b| b.end_byte() - b.start_byte() > 2) {
+            bodies.push(body.start_byte() + 1..body.end_byte() - 1);
+        }
+    }
+    bodies.sort_by_key(|range| range.start);
+    bodies
+}
+
+fn last_token_tree<'tree>(node: &Node<'tree>) -> Option<Node<'tree>> {
+    let mut cursor = node.walk();
+    let found = node
+        .named_children(&mut cursor)
+        .filter(|child| child.kind() == "token_tree")
+        .last();
+    found
+}
+
+// Heuristically re-parses each macro body as Rust and runs `analyzer` over it.
+// `$metavariables` become `_metavariables` (same length, so positions hold), and
+// bodies that parse cleanly under none of the wrappers are skipped rather than
+// reported as syntax errors. The analyzer recurses into macros nested in a body.
+// Findings come back with their host byte offset.
+pub fn analyze_macro_bodies(
+    tree: &Tree,
+    source: &str,
+    analyzer: &CodeAnalyzer,
+    unit: ColumnUnit,
+) -> Vec<(usize, AnalysisResult)> {
+    let language = SupportedLanguage::Rust.tree_sitter_language();
+    let mut results = Vec::new();
+    for body in macro_bodies(tree) {
+        let content = source[body.clone()].replace('$', "_");
+        let parsed = WRAPPERS.iter().find_map(|(prefix, suffix)| {
+            let text = format!("{}{}{}", prefix, content, suffix);
+            let tree = parse_tree(&text, &language).ok()?;
+            (!tree.root_node().has_error()).then_some((prefix.len(), text, tree))
+        });
+        let Some((offset, text, body_tree)) = parsed else {
+            continue;
+        };
+        let Ok(found) = analyzer.analyze_embedded(&body_tree, &text, &language) else {
+            continue;
+        };
+        for mut result in found {
+            let Some(byte) = unit.byte_offset(&text, result.line, result.column) else {
+                continue;
+            };
+            // Findings on the wrapper itself (e.g. the function it declares)
+            if byte < offset || byte >= offset + content.len() {
+                continue;
+            }
+            let host = body.start + byte - offset;
+            (result.line, result.column) = host_position(source, host, unit);
+            if !result.message.ends_with(MACRO_NOTE) {
+                result.message.push_str(MACRO_NOTE);
+            }
+            results.push((host, result));
+        }
+    }
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_macro_bodies_are_analyzed() {
+        let source = "macro_rules! make {\n    ($name:ident) => { fn $name() { a.unwrap(); } };\n}\n\
+                      fn main() {\n    println!(\"{}\", b.unwrap());\n    weird!(=> c.unwrap() <=);\n}\n";
+        let language = SupportedLanguage::Rust.tree_sitter_language();
+        let tree = parse_tree(source, &language).unwrap();
+        assert_eq!(macro_bodies(&tree).len(), 3);
+
+        let analyzer = CodeAnalyzer::new_rust_analyzer();
+        let found = analyze_macro_bodies(&tree, source, &analyzer, ColumnUnit::Byte);
+        let first = &found[0].1;
+        assert_eq!((first.line, first.column), (2, 37));
+        assert_eq!(first.message, "Use of .unwrap() can cause panics (in macro body)");
+        // `weird!` is no Rust, so it is skipped
+        let mut lines: Vec<usize> = found.iter().map(|(_, r)| r.line).collect();
+        lines.dedup();
+        assert_eq!(lines, vec![2, 5]);
+
+        // The analyzer reports the same findings once enabled
+        let expected = found.len();
+        let analyzer = analyzer.with_macro_bodies(true);
+        let in_macros = |results: Vec<crate::analyzer::AnalysisResult>| {
+            results
+                .iter()
+                .filter(|r| r.message.ends_with("(in macro body)"))
+                .count()
+        };
+        assert_eq!(in_macros(analyzer.analyze(source, &language).unwrap()), expected);
+    }
+}
@@ -1,11 +1,56 @@
+use std::borrow::Cow;
 use std::env;
-use std::ffi::CString;
-use std::path::Path;
+use std::io::{self, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
 use std::process;
-use treescan::{
-    analyze_go_code, analyze_js_code, analyze_rust_code, free_string, parse_c_ast, parse_cpp_ast,
-    parse_java_ast, parse_js_ast, parse_rust_ast, parse_ts_ast, parse_zig_ast,
-};
+use std::time::{Duration, Instant};
+use treescan::api::{api_changes, api_surface, format_api_text, format_changes_text};
+use treescan::analyzer::{analyze_source, write_analysis, RuleConfig, Severity};
+use treescan::ast_diff::{diff_to_json, diff_trees, format_diff_text};
+use treescan::ast::{parse_tree, parse_tree_with_timeout, write_ast_node, AstFormat, AstOptions};
+use treescan::badge::render_badge;
+use treescan::baseline::{Baseline, DEFAULT_BASELINE_FILE};
+use treescan::batch::BatchConfig;
+use treescan::bench::run_bench;
+use treescan::cache::{ResultCache, DEFAULT_CACHE_DIR};
+use treescan::config::{find_config_file, header_language, read_config, ProjectConfig};
+use treescan::config_check::check_config;
+use treescan::config_layers::{layered_settings, EnvLayer};
+use treescan::diagnostics::collect_syntax_errors;
+use treescan::doc_coverage::doc_coverage;
+use treescan::explain::{explain, RuleExplanation};
+use treescan::fragment::parse_fragment;
+use treescan::files::{walk_source_files, WalkOptions};
+use treescan::git::changed_lines;
+use treescan::grammar::load_grammar;
+use treescan::highlight::{highlight, HighlightFormat};
+use treescan::hotspots::{commit_counts, format_hotspots_table, rank_hotspots};
+use treescan::language::SupportedLanguage;
+use treescan::license::{format_license_text, license_report, HeaderTemplate};
+use treescan::locale::BUNDLED_LOCALES;
+use treescan::node_at::node_at;
+use treescan::overflow::cap_per_rule;
+use treescan::position::ColumnUnit;
+use treescan::prom::render_report;
+use treescan::plugin::validate_plugins;
+use treescan::query_rules::validate_query_dirs;
+use treescan::review::build_review;
+use treescan::rule_pack::{install, installed_packs, packs_dir, validate_packs, RulePack};
+use treescan::rule_trial::{format_trial_text, load_candidates, trial_report};
+use treescan::run_info::RunInfo;
+use treescan::scan::{file_report, scan_directory, top_offenders, ScanOptions};
+use treescan::sfc;
+use treescan::snapshot::{check_snapshots, format_snapshots_text, SnapshotOptions};
+use treescan::source::{map_source, read_source, SourceError, DEFAULT_MAX_FILE_SIZE};
+use treescan::stats::collect_stats;
+use treescan::store::store_report;
+use treescan::strings::{format_strings_text, strings_report};
+use treescan::summary::{count_at_severities, format_report, GroupBy, ReportFormat};
+use treescan::version::version_metadata;
+use treescan::logging::{self, Level, LogFormat};
+use treescan::{daemon, lsp, mcp, pipeline, timings, trace, tui};
+use tree_sitter::{Node, Tree};
+use regex::Regex;
 
 #[derive(Debug, PartialEq)]
 enum Command {
@@ -13,162 +58,1965 @@ enum Command {
     Analyze,
 }
 
+#[derive(Debug)]
+struct Options {
+    no_cache: bool,
+    max_file_size: u64,
+    format: AstFormat,
+    // analyze reads `--format` as a report format instead
+    report_format: ReportFormat,
+    group_by: GroupBy,
+    ast: AstOptions,
+    fragment: bool,
+    // parse: compare the AST of each file to its snapshot here (see `snapshot`)
+    snapshot_dir: Option<PathBuf>,
+    // ...or write the snapshots instead
+    update_snapshot: bool,
+    // Directory mode: add the N lowest-scoring files and most violated rules
+    top: Option<usize>,
+    // Directory mode: judge only lines changed since this git revision
+    diff_base: Option<String>,
+    fail_under: Option<f64>,
+    // Exit 1 on any finding at these severities
+    fail_on: Vec<Severity>,
+    max_issues_per_file: Option<usize>,
+    max_per_rule: Option<usize>,
+    parse_timeout: Option<Duration>,
+    exit_nonzero_on_error: bool,
+    follow_symlinks: bool,
+    include_generated: bool,
+    store: Option<PathBuf>,
+    prom_out: Option<PathBuf>,
+    baseline: Option<PathBuf>,
+    grammar: Option<PathBuf>,
+    lang: Option<String>,
+    column_unit: ColumnUnit,
+    rules: RuleConfig,
+    // Print per-rule query times after analyzing
+    timings: bool,
+    // Recorded in analyze reports under `metadata.run`
+    run: Option<RunInfo>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            no_cache: false,
+            max_file_size: DEFAULT_MAX_FILE_SIZE,
+            format: AstFormat::Text,
+            report_format: ReportFormat::Json,
+            group_by: GroupBy::default(),
+            ast: AstOptions::default(),
+            fragment: false,
+            snapshot_dir: None,
+            update_snapshot: false,
+            top: None,
+            diff_base: None,
+            fail_under: None,
+            fail_on: Vec::new(),
+            max_issues_per_file: None,
+            max_per_rule: None,
+            parse_timeout: None,
+            exit_nonzero_on_error: false,
+            follow_symlinks: false,
+            include_generated: false,
+            store: None,
+            prom_out: None,
+            baseline: None,
+            grammar: None,
+            lang: None,
+            column_unit: ColumnUnit::Byte,
+            rules: RuleConfig::default(),
+            timings: false,
+            run: None,
+        }
+    }
+}
+
+fn print_usage(program: &str) {
+    eprintln!("Usage: {} <command> [options] <file_path>", program);
+    eprintln!("       {} analyze [options] <directory>", program);
+    eprintln!("Commands:");
+    eprintln!("  parse        - Parse file and output AST");
+    eprintln!("  analyze      - Analyze code and provide metrics");
+    eprintln!("  cache clear  - Remove cached analysis results");
+    eprintln!("  rules install <path|git-url>");
+    eprintln!("               - Check a rule pack's fixtures and install it for all projects");
+    eprintln!("  rules list [--installed]");
+    eprintln!("               - List the built-in rules, or the installed rule packs as JSON");
+    eprintln!("  rules explain <rule> [--format <text|json>]");
+    eprintln!("               - Describe a rule: severity, query and a before/after example");
+    eprintln!("  rules try <rules.json|queries-dir> [--samples <n>] [--format <text|json>] [<path>]");
+    eprintln!("               - Run only the candidate rules over a tree: matches, files and sample hits");
+    eprintln!("  config check [<file>]");
+    eprintln!("               - Report every problem in the config (default: nearest .treescan.json),");
+    eprintln!("                 compiling its query directories; exits 1 when any is found");
+    eprintln!("  config show [--resolved]");
+    eprintln!("               - Print the settings from .treescan.json and TREESCAN_* variables as JSON,");
+    eprintln!("                 with where each came from; --resolved fills in every default");
+    eprintln!("  bench        - Benchmark parse/query/serialize time for a file or directory");
+    eprintln!("                 (--iterations <n>, default 10)");
+    eprintln!("  highlight <file> [--format <ansi|html>]");
+    eprintln!("               - Print the file syntax-highlighted (default: ansi)");
+    eprintln!("  diff <old> <new> [--format <text|json>]");
+    eprintln!("               - Structural diff: added, removed, modified and moved nodes");
+    eprintln!("  node-at <file> <line>:<col> [--column-unit <byte|char|utf16>]");
+    eprintln!("               - Print the node, its ancestors and enclosing function at a position");
+    eprintln!("  lsp          - Run a language server on stdin/stdout");
+    eprintln!("  serve --stdio [--metrics-addr <host:port>]");
+    eprintln!("               - Answer parse/analyze/query JSON-RPC requests on stdin/stdout");
+    eprintln!("  serve --jsonl");
+    eprintln!("               - Answer one {{\"op\": ...}} request per stdin line, one result per stdout line");
+    eprintln!("  stats [<path>]");
+    eprintln!("               - Count node kinds (functions, closures, unsafe blocks, ...) as JSON");
+    eprintln!("  api [--format <text|json>] [--exit-code] <path> [<new_path>]");
+    eprintln!("               - List the public/exported items of a file or package with signatures;");
+    eprintln!("                 with two paths, report items added, removed or changed");
+    eprintln!("  strings [--match <regex>] [--format <text|json>] [<path>]");
+    eprintln!("               - List string literals with locations and the constants they initialize;");
+    eprintln!("                 --match keeps those whose value matches");
+    eprintln!("  license [--template <file>] [--fix] [--format <text|json>] [<path>]");
+    eprintln!("               - Check each file starts with the license header (`license_header` in");
+    eprintln!("                 .treescan.json); exits 1 when any is missing, --fix inserts it");
+    eprintln!("  doc-coverage [<path>] [--min <percent>]");
+    eprintln!("               - Share of public functions and types with doc comments, as JSON;");
+    eprintln!("                 exits 1 when the overall coverage is below --min");
+    eprintln!("  hotspots [--since <date>] [--limit <n>] [--format <table|json>] [<directory>]");
+    eprintln!("               - Rank files by git commit count x cyclomatic complexity");
+    eprintln!("  tui [<path>] - Browse files, issues and source interactively; Enter/e opens $EDITOR");
+    eprintln!("  badge [--out <badge.svg>] [--label <text>] [<directory>]");
+    eprintln!("               - Render the average score as an SVG badge (default: badge.svg, .)");
+    eprintln!("  baseline import --from <eslint|golangci> <report.json|-> [--out <file>]");
+    eprintln!("               - Convert ESLint/golangci-lint JSON output into a treescan baseline");
+    eprintln!("  baseline create <directory> [--out <file>]");
+    eprintln!("               - Record every current issue in a baseline");
+    eprintln!("  mcp          - Run a Model Context Protocol server on stdin/stdout");
+    eprintln!("  review (--pr <n> | --diff <file|->)");
+    eprintln!("               - Print a GitHub review payload for issues on changed lines");
+    eprintln!();
+    eprintln!("Options:");
+    eprintln!("  -q, --quiet  - Print only the payload on stdout and errors on stderr");
+    eprintln!("  -v, -vv      - Log timings (-v) and per-rule diagnostics (-vv) to stderr");
+    eprintln!("  --log-format <text|json>");
+    eprintln!("               - Format of stderr logs (default: text)");
+    eprintln!("  --no-cache   - Analyze without reading or writing the result cache");
+    eprintln!("  --timings    - Print each rule's total query time across the analysis to stderr,");
+    eprintln!("                 slowest first (implies --no-cache)");
+    eprintln!("  --format <text|json|sexp-compat>");
+    eprintln!("               - AST output format for parse (default: text); sexp-compat");
+    eprintln!("                 matches `tree-sitter parse` output exactly");
+    eprintln!("  --format <json|text|markdown>");
+    eprintln!("               - Report format for analyze (default: json)");
+    eprintln!("  --group-by <file|rule|severity>");
+    eprintln!("               - Organize text and markdown analyze reports (default: file)");
+    eprintln!("  --positions  - Annotate parse text output with [row,col - row,col] ranges");
+    eprintln!("  --bytes      - Include byte offsets in parse output");
+    eprintln!("  --named-only - Omit anonymous nodes (punctuation, keywords) from parse output");
+    eprintln!("  --fragment   - Parse a snippet (statements, members, ...) inside minimal scaffolding");
+    eprintln!("  --check-snapshot <dir>");
+    eprintln!("               - parse: compare each file's AST (file or directory) to <dir>/<path>.snap");
+    eprintln!("                 and exit 1 on drift or a missing snapshot");
+    eprintln!("  --update-snapshot <dir>");
+    eprintln!("               - parse: write the snapshots instead, to accept intended tree changes");
+    eprintln!("  --max-depth <n>");
+    eprintln!("               - Stop parse output at depth n (the root is 0), eliding deeper nodes");
+    eprintln!("  --max-issues-per-file <n>");
+    eprintln!("               - Stop analyzing a file once n issues are found");
+    eprintln!("  --max-per-rule <n>");
+    eprintln!("               - Show n findings of each rule per file and summarize the rest");
+    eprintln!("  --parse-timeout-ms <ms>");
+    eprintln!("               - Fail a file whose parse takes longer than this");
+    eprintln!("  --fail-on <severities>");
+    eprintln!("               - Exit 1 when analyze finds issues at these severities (e.g. error or");
+    eprintln!("                 error,warning), whatever the score; none clears `fail_on` from the config");
+    eprintln!("  --exit-nonzero-on-error");
+    eprintln!("               - Exit with status 1 when parse finds ERROR/MISSING nodes");
+    eprintln!("  --follow-symlinks");
+    eprintln!("               - Follow symbolic links when analyzing a directory");
+    eprintln!("  --include-generated");
+    eprintln!("               - Analyze generated files (DO NOT EDIT, @generated, *.pb.go) in directories");
+    eprintln!("  --grammar <libtree-sitter-foo.so> --lang <foo>");
+    eprintln!("               - Parse with a grammar loaded from a shared library");
+    eprintln!("  --header-lang <c|cpp>");
+    eprintln!("               - Parse .h files as this language instead of guessing from their contents");
+    eprintln!("  --column-unit <byte|char|utf16>");
+    eprintln!("               - Unit for reported columns (default: byte)");
+    eprintln!("  --magic-number-allowlist <v1,v2,...>");
+    eprintln!("               - Literals never reported as magic numbers (default: 0,1,2)");
+    eprintln!("  --magic-numbers-in-tests");
+    eprintln!("               - Also check _test.go files for magic numbers");
+    eprintln!("  --macro-bodies");
+    eprintln!("               - Also run the Rust rules over macro_rules! bodies and macro arguments");
+    eprintln!("  --min-severity <error|warning|info|style>");
+    eprintln!("               - Omit findings below this severity from reports and scores");
+    eprintln!("  --max-chain-length <calls>");
+    eprintln!("               - Report method call chains longer than this (default: 6; 0 turns it off)");
+    eprintln!("  --max-boolean-operators <n>");
+    eprintln!("               - Report &&/|| expressions with more operators than this (default: 3; 0 turns it off)");
+    eprintln!("  --locale <name>");
+    eprintln!("               - Report rule messages and suggestions in this language (bundled: ja)");
+    eprintln!("  --max-line-length <chars>");
+    eprintln!("               - Report lines longer than this (line_too_long)");
+    eprintln!("  --max-file-lines <lines>");
+    eprintln!("               - Report files longer than this (file_too_long)");
+    eprintln!("  --queries <dir>");
+    eprintln!("               - Add rules from <dir>/<lang>/lints.scm captures (@warning.<name>, ...)");
+    eprintln!("  --pack <name>");
+    eprintln!("               - Enable an installed rule pack (see `rules install`)");
+    eprintln!("  --plugin <librules.so>");
+    eprintln!("               - Add the rules a shared library registers via treescan_plugin_register");
+    eprintln!("  --baseline <file>");
+    eprintln!("               - Hide issues recorded in a baseline when analyzing a directory");
+    eprintln!("  --top <n>    - Directory analysis: list the n lowest-scoring files and most violated rules");
+    eprintln!("  --diff-base <rev>");
+    eprintln!("               - Directory analysis: only score issues on lines changed since <rev> (git)");
+    eprintln!("  --fail-under <score>");
+    eprintln!("               - Directory analysis: exit 1 when the average score is below <score>");
+    eprintln!("  --prom-out <metrics.prom>");
+    eprintln!("               - Write Prometheus gauges for the analysis run to a file");
+    eprintln!("  --store <results.db>");
+    eprintln!("               - Append the analysis run to a SQLite database; needs the sqlite3");
+    eprintln!("                 command-line tool on PATH");
+    eprintln!("  --max-file-size <bytes>");
+    eprintln!("               - Skip files larger than this (default: {})", DEFAULT_MAX_FILE_SIZE);
+    eprintln!();
+    eprintln!("Supported extensions:");
+    eprintln!("  Parse: .rs, .java, .zig, .c, .h, .js, .jsx, .mjs, .cjs, .ts, .mts, .cts, .tsx, .cpp, .cc, .cxx");
+    eprintln!("  Analyze: .rs, .go, .js, .jsx, .mjs, .cjs");
+}
+
 fn main() {
-    let args: Vec<String> = env::args().collect();
+    run();
+    trace::flush();
+}
+
+fn run() {
+    let mut args: Vec<String> = env::args().collect();
+    if let Err(e) = init_logging(&mut args) {
+        logging::error(&e);
+        process::exit(1);
+    }
 
-    if args.len() != 3 {
-        eprintln!("Usage: {} <command> <file_path>", args[0]);
-        eprintln!("Commands:");
-        eprintln!("  parse    - Parse file and output AST");
-        eprintln!("  analyze  - Analyze code and provide metrics");
-        eprintln!();
-        eprintln!("Supported extensions:");
-        eprintln!("  Parse: .rs, .java, .zig, .c, .h, .js, .jsx, .ts, .tsx, .cpp, .cc, .cxx");
-        eprintln!("  Analyze: .rs, .go, .js, .jsx");
+    if args.len() < 2 {
+        print_usage(&args[0]);
         process::exit(1);
     }
 
-    let command = match args[1].to_lowercase().as_str() {
+    if args[1] == "cache" {
+        run_cache_command(&args[2..]);
+        return;
+    }
+    if args[1] == "bench" {
+        run_bench_command(&args[2..]);
+        return;
+    }
+    if args[1] == "rules" {
+        run_rules_command(&args[2..]);
+        return;
+    }
+    if args[1] == "config" {
+        run_config_command(&args[2..]);
+        return;
+    }
+    if args[1] == "highlight" {
+        run_highlight_command(&args[2..]);
+        return;
+    }
+    if args[1] == "diff" {
+        run_diff_command(&args[2..]);
+        return;
+    }
+    if args[1] == "node-at" {
+        run_node_at_command(&args[2..]);
+        return;
+    }
+    if args[1] == "lsp" {
+        if let Err(e) = lsp::run_stdio() {
+            logging::error(&format!("Language server failed: {}", e));
+            process::exit(1);
+        }
+        return;
+    }
+
+    // Settings from the nearest .treescan.json; command-line flags override them
+    let mut options = Options::default();
+    let discovered = env::current_dir()
+        .map_err(|e| e.to_string())
+        .and_then(|dir| ProjectConfig::discover(&dir).map_err(|e| e.to_string()));
+    match discovered {
+        Ok((_, config)) => {
+            SupportedLanguage::set_header_language(config.header_language);
+            if args[1] == "serve" {
+                run_serve_command(&args[2..], config);
+                return;
+            }
+            if args[1] == "review" {
+                run_review_command(&args[2..], &config);
+                return;
+            }
+            if args[1] == "stats" {
+                run_stats_command(&args[2..], &config);
+                return;
+            }
+            if args[1] == "api" {
+                run_api_command(&args[2..], &config);
+                return;
+            }
+            if args[1] == "strings" {
+                run_strings_command(&args[2..], &config);
+                return;
+            }
+            if args[1] == "license" {
+                run_license_command(&args[2..], &config);
+                return;
+            }
+            if args[1] == "doc-coverage" {
+                run_doc_coverage_command(&args[2..], &config);
+                return;
+            }
+            if args[1] == "hotspots" {
+                run_hotspots_command(&args[2..], &config);
+                return;
+            }
+            if args[1] == "tui" {
+                run_tui_command(&args[2..], &config);
+                return;
+            }
+            if args[1] == "badge" {
+                run_badge_command(&args[2..], &config);
+                return;
+            }
+            if args[1] == "baseline" {
+                run_baseline_command(&args[2..], &config);
+                return;
+            }
+            if args[1] == "mcp" {
+                if let Err(e) = mcp::run_stdio(config) {
+                    logging::error(&format!("MCP server failed: {}", e));
+                    process::exit(1);
+                }
+                return;
+            }
+            options.rules = config.rules;
+            options.max_issues_per_file = config.max_issues_per_file;
+            options.max_per_rule = config.max_findings_per_rule;
+            options.include_generated = config.include_generated;
+            options.fail_on = config.fail_on;
+        }
+        Err(e) => {
+            logging::error(&e.to_string());
+            process::exit(1);
+        }
+    }
+    let mut positional = Vec::new();
+    let mut format_name = None;
+    let mut iter = args[1..].iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--no-cache" => options.no_cache = true,
+            "--timings" => options.timings = true,
+            "--exit-nonzero-on-error" => options.exit_nonzero_on_error = true,
+            "--follow-symlinks" => options.follow_symlinks = true,
+            "--positions" => options.ast.positions = true,
+            "--bytes" => options.ast.bytes = true,
+            "--named-only" => options.ast.named_only = true,
+            "--fragment" => options.fragment = true,
+            "--check-snapshot" => options.snapshot_dir = Some(parse_flag_value(arg, iter.next())),
+            "--update-snapshot" => {
+                options.snapshot_dir = Some(parse_flag_value(arg, iter.next()));
+                options.update_snapshot = true;
+            }
+            "--top" => options.top = Some(parse_flag_value(arg, iter.next())),
+            "--diff-base" => options.diff_base = Some(parse_flag_value(arg, iter.next())),
+            "--fail-under" => options.fail_under = Some(parse_flag_value(arg, iter.next())),
+            "--fail-on" => {
+                let names: String = parse_flag_value(arg, iter.next());
+                options.fail_on = match parse_severities(&names) {
+                    Some(severities) => severities,
+                    None => {
+                        logging::error("Option '--fail-on' expects error, warning, info, style or none, comma-separated");
+                        process::exit(1);
+                    }
+                }
+            }
+            "--max-depth" => options.ast.max_depth = Some(parse_flag_value(arg, iter.next())),
+            "--include-generated" => options.include_generated = true,
+            "--magic-numbers-in-tests" => options.rules.magic_numbers_in_tests = true,
+            "--macro-bodies" => options.rules.macro_bodies = true,
+            "--max-chain-length" => {
+                let max: usize = parse_flag_value(arg, iter.next());
+                options.rules.max_chain_length = (max > 0).then_some(max);
+            }
+            "--max-boolean-operators" => {
+                let max: usize = parse_flag_value(arg, iter.next());
+                options.rules.max_boolean_operators = (max > 0).then_some(max);
+            }
+            "--locale" => {
+                let locale: String = parse_flag_value(arg, iter.next());
+                if !BUNDLED_LOCALES.contains(&locale.as_str()) {
+                    logging::warn(&format!(
+                        "No bundled messages for locale '{}'; only rules that ship translations change",
+                        locale
+                    ));
+                }
+                options.rules.locale = Some(locale);
+            }
+            "--max-line-length" => {
+                options.rules.length_limits.max_line_length = Some(parse_flag_value(arg, iter.next()))
+            }
+            "--max-file-lines" => {
+                options.rules.length_limits.max_file_lines = Some(parse_flag_value(arg, iter.next()))
+            }
+            "--magic-number-allowlist" => {
+                let values: String = parse_flag_value(arg, iter.next());
+                options.rules.magic_number_allowlist = values
+                    .split(',')
+                    .map(|v| v.trim().to_string())
+                    .filter(|v| !v.is_empty())
+                    .collect();
+            }
+            "--column-unit" => {
+                options.column_unit = match iter.next().and_then(|u| ColumnUnit::from_name(u)) {
+                    Some(unit) => unit,
+                    None => {
+                        logging::error("Option '--column-unit' expects 'byte', 'char' or 'utf16'");
+                        process::exit(1);
+                    }
+                }
+            }
+            "--min-severity" => {
+                options.rules.min_severity = match iter.next().and_then(|s| Severity::from_name(s)) {
+                    Some(severity) => Some(severity),
+                    None => {
+                        logging::error("Option '--min-severity' expects 'error', 'warning', 'info' or 'style'");
+                        process::exit(1);
+                    }
+                }
+            }
+            "--queries" => options.rules.query_dirs.push(parse_flag_value(arg, iter.next())),
+            "--plugin" => options.rules.plugins.push(parse_flag_value(arg, iter.next())),
+            "--pack" => options.rules.packs.push(parse_flag_value(arg, iter.next())),
+            "--grammar" => options.grammar = Some(parse_flag_value::<PathBuf>(arg, iter.next())),
+            "--lang" => options.lang = Some(parse_flag_value(arg, iter.next())),
+            "--header-lang" => match iter.next().and_then(|name| header_language(name)) {
+                Some(lang) => SupportedLanguage::set_header_language(Some(lang)),
+                None => {
+                    logging::error("Option '--header-lang' expects 'c' or 'cpp'");
+                    process::exit(1);
+                }
+            },
+            "--baseline" => options.baseline = Some(parse_flag_value::<PathBuf>(arg, iter.next())),
+            "--prom-out" => options.prom_out = Some(parse_flag_value::<PathBuf>(arg, iter.next())),
+            "--store" => options.store = Some(parse_flag_value::<PathBuf>(arg, iter.next())),
+            "--max-file-size" => options.max_file_size = parse_flag_value(arg, iter.next()),
+            "--parse-timeout-ms" => {
+                options.parse_timeout = Some(Duration::from_millis(parse_flag_value(arg, iter.next())))
+            }
+            "--max-issues-per-file" => {
+                options.max_issues_per_file = Some(parse_flag_value(arg, iter.next()))
+            }
+            "--max-per-rule" => options.max_per_rule = Some(parse_flag_value(arg, iter.next())),
+            "--format" => {
+                let name = iter.next().map_or("", String::as_str);
+                let (ast, report) = (AstFormat::from_name(name), ReportFormat::from_name(name));
+                if ast.is_none() && report.is_none() {
+                    logging::error("Option '--format' expects 'text', 'json', 'markdown' or 'sexp-compat'");
+                    process::exit(1);
+                }
+                options.format = ast.unwrap_or(AstFormat::Text);
+                options.report_format = report.unwrap_or(ReportFormat::Json);
+                format_name = Some(name);
+            }
+            "--group-by" => {
+                options.group_by = match iter.next().and_then(|g| GroupBy::from_name(g)) {
+                    Some(group_by) => group_by,
+                    None => {
+                        logging::error("Option '--group-by' expects 'file', 'rule' or 'severity'");
+                        process::exit(1);
+                    }
+                }
+            }
+            flag if flag.starts_with("--") => {
+                logging::error(&format!("Unknown option '{}'", flag));
+                process::exit(1);
+            }
+            _ => positional.push(arg.clone()),
+        }
+    }
+
+    if positional.len() != 2 {
+        print_usage(&args[0]);
+        process::exit(1);
+    }
+    if let Err(e) = validate_query_dirs(&options.rules.query_dirs) {
+        logging::error(&e.to_string());
+        process::exit(1);
+    }
+    if let Err(e) = validate_packs(&options.rules.packs) {
+        logging::error(&e.to_string());
+        process::exit(1);
+    }
+    if let Err(e) = validate_plugins(&options.rules.plugins) {
+        logging::error(&e.to_string());
+        process::exit(1);
+    }
+
+    let command = match positional[0].to_lowercase().as_str() {
         "parse" => Command::Parse,
         "analyze" => Command::Analyze,
         _ => {
-            eprintln!("Error: Unknown command '{}'", args[1]);
-            eprintln!("Available commands: parse, analyze");
+            logging::error(&format!("Unknown command '{}'", positional[0]));
+            eprintln!("Available commands: parse, analyze, cache");
             process::exit(1);
         }
     };
 
-    let file_path = &args[2];
+    // Each command only understands its own formats
+    if let Some(name) = format_name {
+        let (supported, expected) = match command {
+            Command::Parse => (AstFormat::from_name(name).is_some(), "'text', 'json' or 'sexp-compat'"),
+            Command::Analyze => (ReportFormat::from_name(name).is_some(), "'json', 'text' or 'markdown'"),
+        };
+        if !supported {
+            logging::error(&format!(
+                "Option '--format' of the {} command expects {}, not '{}'",
+                positional[0].to_lowercase(),
+                expected,
+                name
+            ));
+            process::exit(1);
+        }
+    }
+
+    let file_path = &positional[1];
 
     if !Path::new(file_path).exists() {
-        eprintln!("Error: File '{}' does not exist", file_path);
+        logging::error(&format!("File '{}' does not exist", file_path));
+        process::exit(1);
+    }
+
+    if let Some(dir) = &options.snapshot_dir {
+        if command != Command::Parse || options.fragment || options.grammar.is_some() {
+            logging::error("Snapshots are checked by the parse command, with bundled grammars and whole files");
+            process::exit(1);
+        }
+        run_snapshot_check(file_path, dir, &options);
+        return;
+    }
+
+    // Cached results ran no queries, so there would be nothing to time
+    if options.timings && command == Command::Analyze {
+        timings::enable();
+        options.no_cache = true;
+    }
+    if command == Command::Analyze {
+        let settings = format!(
+            "{:?};{:?};{:?};{:?};{}",
+            options.rules,
+            options.max_issues_per_file,
+            options.column_unit,
+            options.diff_base,
+            options.include_generated
+        );
+        options.run = Some(RunInfo::start(env::args().collect(), &settings));
+    }
+    if command == Command::Analyze && Path::new(file_path).is_dir() {
+        run_directory_analysis(file_path, &options);
+        return;
+    }
+
+    // An external grammar replaces extension detection; it can only parse
+    let external = match (&options.grammar, &options.lang) {
+        (Some(grammar), Some(name)) if command == Command::Parse => {
+            match load_grammar(grammar, name) {
+                Ok(language) => Some(language),
+                Err(e) => {
+                    logging::error(&e.to_string());
+                    process::exit(1);
+                }
+            }
+        }
+        (None, None) => None,
+        _ => {
+            logging::error("'--grammar' and '--lang' must be given together, with the parse command");
+            process::exit(1);
+        }
+    };
+    if external.is_some() && options.fragment {
+        logging::error("'--fragment' needs a bundled grammar and can't be used with '--grammar'");
         process::exit(1);
     }
 
-    let language = match infer_language_from_path(file_path, &command) {
+    let inferred = match &external {
+        Some(_) => options.lang.clone(),
+        None => infer_language_from_path(file_path, &command),
+    };
+    let language = match inferred {
         Some(lang) => lang,
         None => {
-            eprintln!(
-                "Error: Unsupported file extension for '{}' with command '{:?}'",
+            logging::error(&format!(
+                "Unsupported file extension for '{}' with command '{:?}'",
                 file_path, command
-            );
+            ));
             match command {
-                Command::Parse => eprintln!("Parse supports: .rs, .java, .zig, .c, .h, .js, .jsx, .ts, .tsx, .cpp, .cc, .cxx"),
-                Command::Analyze => eprintln!("Analyze supports: .rs, .go, .js, .jsx"),
+                Command::Parse => eprintln!("Parse supports: .rs, .java, .zig, .c, .h, .js, .jsx, .mjs, .cjs, .ts, .mts, .cts, .tsx, .cpp, .cc, .cxx"),
+                Command::Analyze => eprintln!("Analyze supports: .rs, .go, .js, .jsx, .mjs, .cjs, .vue, .svelte"),
             }
             process::exit(1);
         }
     };
 
     match command {
-        Command::Parse => println!("Parsing {} file: {}", language, file_path),
-        Command::Analyze => println!("Analyzing {} file: {}", language, file_path),
+        Command::Parse => logging::info(&format!("Parsing {} file: {}", language, file_path)),
+        Command::Analyze => logging::info(&format!("Analyzing {} file: {}", language, file_path)),
     }
-    println!("----------------------------------------");
+    let started = Instant::now();
 
-    let c_file_path = match CString::new(file_path.as_str()) {
-        Ok(cstring) => cstring,
-        Err(_) => {
-            eprintln!("Error: Invalid file path contains null bytes");
+    // Safety: nothing in this process writes the file. Large generated files are mapped
+    // rather than copied, so the file must not be edited during the run.
+    let source_code = match unsafe { map_source(file_path, Some(options.max_file_size)) } {
+        Ok(source) => source,
+        Err(e @ SourceError::TooLarge { .. }) => {
+            logging::warn(&format!("Skipped '{}': {}", file_path, e));
+            return;
+        }
+        Err(e) => {
+            logging::error(&format!("Failed to read '{}': {}", file_path, e));
             process::exit(1);
         }
     };
 
-    let result_ptr = match command {
-        Command::Parse => match language.as_str() {
-            "Rust" => parse_rust_ast(c_file_path.as_ptr()),
-            "Java" => parse_java_ast(c_file_path.as_ptr()),
-            "Zig" => parse_zig_ast(c_file_path.as_ptr()),
-            "C" => parse_c_ast(c_file_path.as_ptr()),
-            "JavaScript" => parse_js_ast(c_file_path.as_ptr()),
-            "TypeScript" => parse_ts_ast(c_file_path.as_ptr()),
-            "C++" => parse_cpp_ast(c_file_path.as_ptr()),
-            _ => {
-                eprintln!("Error: Parsing not supported for language '{}'", language);
+    let mut analyzed = None;
+    let result = match command {
+        Command::Parse if options.fragment => match SupportedLanguage::from_name(&language) {
+            Some(lang) => parse_fragment(&source_code, lang, options.parse_timeout)
+                .map_err(|e| e.to_string())
+                .and_then(|fragment| write_parse_output(&source_code, &fragment.tree, fragment.root(), &options)),
+            None => Err(format!("Unsupported language '{}'", language)),
+        },
+        Command::Parse => match external.or_else(|| {
+            SupportedLanguage::from_name(&language).map(|lang| lang.tree_sitter_language())
+        }) {
+            Some(ts_language) => parse_tree_with_timeout(&source_code, &ts_language, options.parse_timeout)
+                .map_err(|e| e.to_string())
+                .and_then(|tree| write_parse_output(&source_code, &tree, tree.root_node(), &options)),
+            None => Err(format!("Unsupported language '{}'", language)),
+        },
+        Command::Analyze => {
+            let view = sfc::is_component(Path::new(file_path)).then(|| sfc::script_view(&source_code));
+            let source_code = view.as_deref().unwrap_or(&source_code);
+            write_analyze_output(source_code, &language, file_path, &options).map(|report| {
+                analyzed = Some(report);
+                0
+            })
+        }
+    };
+    logging::debug(
+        "finished",
+        &[
+            ("file", serde_json::json!(file_path)),
+            ("elapsed_ms", logging::millis(started.elapsed())),
+        ],
+    );
+    if timings::enabled() {
+        eprint!("{}", timings::format_timings_text(&timings::take()));
+    }
+    let record = options.store.is_some() || options.prom_out.is_some();
+    if let (Some(result), true) = (&analyzed, record) {
+        let language = SupportedLanguage::from_name(&language).map_or("", |l| l.name());
+        let report = file_report(Path::new(file_path), language, result.clone(), &scan_options(&options));
+        store_run(&report, &options);
+    }
+
+    match result {
+        Ok(syntax_errors) => {
+            if syntax_errors > 0 && options.exit_nonzero_on_error {
                 process::exit(1);
             }
+            if let Some(report) = &analyzed {
+                check_fail_on(report, file_path, &options);
+            }
+        }
+        Err(e) => {
+            let operation = match command {
+                Command::Parse => "parse",
+                Command::Analyze => "analyze",
+            };
+            logging::error(&format!("Failed to {} the file: {}", operation, e));
+            process::exit(1);
+        }
+    }
+}
+
+// Exits 1 when any snapshot drifted, is missing or could not be checked
+fn run_snapshot_check(path: &str, dir: &Path, options: &Options) {
+    let walk_options = WalkOptions {
+        follow_symlinks: options.follow_symlinks,
+        include_generated: options.include_generated,
+    };
+    let files = walk_source_files(Path::new(path), &walk_options).files;
+    let snapshot_options = SnapshotOptions {
+        format: options.format,
+        ast: options.ast,
+        update: options.update_snapshot,
+    };
+    let report = check_snapshots(Path::new(path), &files, dir, &snapshot_options);
+    print!("{}", format_snapshots_text(&report));
+    let failed = ["drifted", "missing", "errors"]
+        .iter()
+        .any(|key| report[key].as_u64().unwrap_or(0) > 0);
+    if failed {
+        process::exit(1);
+    }
+}
+
+// Takes the logging flags out of `args` wherever they appear, for every command
+fn init_logging(args: &mut Vec<String>) -> Result<(), String> {
+    let mut level = Level::Info;
+    let mut format = LogFormat::Text;
+    let mut rest = Vec::with_capacity(args.len());
+    let mut iter = args.drain(..);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--quiet" | "-q" => level = Level::Error,
+            "--verbose" | "-v" => level = Level::Debug,
+            "-vv" => level = Level::Trace,
+            "--log-format" => {
+                let name = iter.next().unwrap_or_default();
+                format = LogFormat::from_name(&name)
+                    .ok_or_else(|| format!("Unknown log format '{}' (expected text or json)", name))?;
+            }
+            _ => rest.push(arg),
+        }
+    }
+    drop(iter);
+    *args = rest;
+    logging::init(level, format);
+    Ok(())
+}
+
+// "error,warning" -> [Error, Warning]; "none" clears a configured list
+fn parse_severities(names: &str) -> Option<Vec<Severity>> {
+    if names == "none" {
+        return Some(Vec::new());
+    }
+    names.split(',').map(|name| Severity::from_name(name.trim())).collect()
+}
+
+// Exits 1 when the report has findings at a `--fail-on` severity
+fn check_fail_on(report: &serde_json::Value, path: &str, options: &Options) {
+    let failing = count_at_severities(report, path, &options.fail_on);
+    if failing > 0 {
+        let names: Vec<&str> = options.fail_on.iter().map(Severity::as_str).collect();
+        logging::error(&format!(
+            "{} issue(s) at severity {} fail the run",
+            failing,
+            names.join("/")
+        ));
+        process::exit(1);
+    }
+}
+
+fn parse_flag_value<T: std::str::FromStr>(flag: &str, value: Option<&String>) -> T {
+    match value.map(|v| v.parse()) {
+        Some(Ok(parsed)) => parsed,
+        _ => {
+            logging::error(&format!("Option '{}' requires a valid value", flag));
+            process::exit(1);
+        }
+    }
+}
+
+// Returns the number of syntax errors found in the tree
+// `root` is the whole tree, or with --fragment just the snippet inside its scaffolding
+fn write_parse_output(source_code: &str, tree: &Tree, root: Node, options: &Options) -> Result<usize, String> {
+    let syntax_errors = collect_syntax_errors(tree, source_code, options.column_unit);
+
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    let written = match options.format {
+        // Nothing but the tree, exactly as the tree-sitter CLI prints it
+        AstFormat::SexpCompat => write_ast_node(root, source_code, options.format, &options.ast, &mut out)
+            .and_then(|_| writeln!(out)),
+        AstFormat::Text => write_ast_node(root, source_code, options.format, &options.ast, &mut out).and_then(|_| {
+            writeln!(out)?;
+            if !syntax_errors.is_empty() {
+                writeln!(out)?;
+                writeln!(out, "Syntax diagnostics ({}):", syntax_errors.len())?;
+                for error in &syntax_errors {
+                    writeln!(out, "  {}", error)?;
+                }
+            }
+            Ok(())
+        }),
+        AstFormat::Json => (|| {
+            out.write_all(b"{\"tree\":")?;
+            write_ast_node(root, source_code, options.format, &options.ast, &mut out)?;
+            out.write_all(b",\"diagnostics\":")?;
+            serde_json::to_writer(&mut out, &syntax_errors)?;
+            out.write_all(b",\"metadata\":")?;
+            serde_json::to_writer(&mut out, &version_metadata(&tree.language()))?;
+            writeln!(out, "}}")
+        })(),
+    };
+    written.and_then(|_| out.flush()).map_err(|e| e.to_string())?;
+    Ok(syntax_errors.len())
+}
+
+// The report is read back to stamp `metadata.run` into it, render text/markdown and
+// check --fail-on
+fn write_analyze_output(
+    source_code: &str,
+    language: &str,
+    file_path: &str,
+    options: &Options,
+) -> Result<serde_json::Value, String> {
+    let mut json = Vec::new();
+    analyze_with_cache(source_code, language, file_path, options, &mut json)?;
+    let mut report: serde_json::Value = serde_json::from_slice(&json).map_err(|e| e.to_string())?;
+    if let Some(run) = &options.run {
+        run.stamp(&mut report);
+    }
+    let shown = shown_report(&report, options);
+    let output = match options.report_format {
+        ReportFormat::Json => {
+            serde_json::to_string_pretty(&shown).map_err(|e| e.to_string())? + "\n"
+        }
+        _ => render_summary(&shown, file_path, options),
+    };
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    out.write_all(output.as_bytes())
+        .and_then(|_| out.flush())
+        .map_err(|e| e.to_string())?;
+    Ok(report)
+}
+
+// The report as printed: --max-per-rule summarizes each rule's findings past the cap.
+// --fail-on, --store and --prom-out still see every finding.
+fn shown_report<'a>(
+    report: &'a serde_json::Value,
+    options: &Options,
+) -> Cow<'a, serde_json::Value> {
+    match options.max_per_rule {
+        Some(max) => {
+            let mut shown = report.clone();
+            cap_per_rule(&mut shown, max);
+            Cow::Owned(shown)
+        }
+        None => Cow::Borrowed(report),
+    }
+}
+
+fn render_summary(report: &serde_json::Value, path: &str, options: &Options) -> String {
+    format_report(report, path, options.report_format, options.group_by)
+}
+
+// Uncached runs stream the report straight to `out`; cached runs need the text anyway
+fn analyze_with_cache<W: Write>(
+    source_code: &str,
+    language: &str,
+    file_path: &str,
+    options: &Options,
+    out: &mut W,
+) -> Result<(), String> {
+    let lang = SupportedLanguage::from_name(language)
+        .ok_or_else(|| format!("Unsupported language '{}'", language))?;
+    let mut analyzer = lang
+        .analyzer_for_path(Path::new(file_path), &options.rules)
+        .ok_or_else(|| format!("Analysis not supported for language '{}'", language))?;
+    if let Some(max_issues) = options.max_issues_per_file {
+        analyzer = analyzer.with_max_issues(max_issues);
+    }
+    if let Some(timeout) = options.parse_timeout {
+        analyzer = analyzer.with_parse_timeout(timeout);
+    }
+    analyzer = analyzer.with_column_unit(options.column_unit);
+    let language = lang.tree_sitter_language();
+    if options.no_cache {
+        return write_analysis(source_code, &language, &analyzer, out).map_err(|e| e.to_string());
+    }
+
+    let cache = ResultCache::new(DEFAULT_CACHE_DIR);
+    let key = ResultCache::key(source_code, &analyzer);
+    let output = match cache.get(&key) {
+        Some(cached) => cached,
+        None => {
+            let output =
+                analyze_source(source_code, &language, &analyzer).map_err(|e| e.to_string())?;
+            if let Err(e) = cache.put(&key, &output) {
+                logging::warn(&format!("Failed to write result cache: {}", e));
+            }
+            output
+        }
+    };
+    out.write_all(output.as_bytes()).map_err(|e| e.to_string())
+}
+
+fn run_directory_analysis(dir: &str, options: &Options) {
+    logging::info(&format!("Analyzing directory: {}", dir));
+    let started = Instant::now();
+
+    let mut scan = scan_options(options);
+    if let Some(base) = &options.diff_base {
+        match changed_lines(Path::new(dir), base) {
+            Ok(changed) => scan.batch.changed_lines = Some(changed),
+            Err(e) => {
+                logging::error(&e.to_string());
+                process::exit(1);
+            }
+        }
+    }
+    let mut report = scan_directory(Path::new(dir), &scan);
+    logging::debug(
+        "scanned",
+        &[
+            ("directory", serde_json::json!(dir)),
+            ("files", serde_json::json!(report["files"].as_array().map_or(0, Vec::len))),
+            ("elapsed_ms", logging::millis(started.elapsed())),
+        ],
+    );
+    if let Some(base) = &options.diff_base {
+        report["diff_base"] = serde_json::json!(base);
+    }
+    if let Some(path) = &options.baseline {
+        match Baseline::load(path) {
+            Ok(baseline) => {
+                baseline.apply(&mut report);
+            }
+            Err(e) => {
+                logging::error(&e.to_string());
+                process::exit(1);
+            }
+        }
+    }
+    if let Some(n) = options.top {
+        report["top"] = top_offenders(&report, n);
+    }
+    for skipped in report["skipped"].as_array().into_iter().flatten() {
+        logging::warn(&format!(
+            "Skipped '{}': {}",
+            skipped["path"].as_str().unwrap_or(""),
+            skipped["reason"].as_str().unwrap_or("")
+        ));
+    }
+    if let Some(run) = &options.run {
+        run.stamp(&mut report);
+    }
+    let shown = shown_report(&report, options);
+    match options.report_format {
+        ReportFormat::Json => println!("{}", serde_json::to_string_pretty(&shown).unwrap_or_default()),
+        _ => print!("{}", render_summary(&shown, dir, options)),
+    }
+    if timings::enabled() {
+        eprint!("{}", timings::format_timings_text(&timings::take()));
+    }
+    store_run(&report, options);
+    check_fail_on(&report, dir, options);
+
+    // No analyzed files (e.g. a diff touching none) has nothing to fail on
+    let average = report["summary"]["average_score"].as_f64();
+    if let (Some(min), Some(average)) = (options.fail_under, average) {
+        if average < min {
+            logging::error(&format!(
+                "Average score {:.1} is below the required {:.1}",
+                average, min
+            ));
+            process::exit(1);
+        }
+    }
+}
+
+fn scan_options(options: &Options) -> ScanOptions {
+    ScanOptions {
+        walk: WalkOptions {
+            follow_symlinks: options.follow_symlinks,
+            include_generated: options.include_generated,
         },
-        Command::Analyze => match language.as_str() {
-            "Rust" => analyze_rust_code(c_file_path.as_ptr()),
-            "Go" => analyze_go_code(c_file_path.as_ptr()),
-            "JavaScript" => analyze_js_code(c_file_path.as_ptr()),
-            _ => {
-                eprintln!("Error: Analysis not supported for language '{}'", language);
+        batch: BatchConfig {
+            threads: None,
+            max_issues_per_file: options.max_issues_per_file,
+            max_file_size: Some(options.max_file_size),
+            parse_timeout: options.parse_timeout,
+            cache_dir: (!options.no_cache).then(|| PathBuf::from(DEFAULT_CACHE_DIR)),
+            column_unit: options.column_unit,
+            rules: options.rules.clone(),
+            changed_lines: None,
+        },
+    }
+}
+
+// Records the run in the --store database and --prom-out file, if given
+fn store_run(report: &serde_json::Value, options: &Options) {
+    if let Some(db) = &options.store {
+        if let Err(e) = store_report(db, report) {
+            logging::error(&format!("Failed to store results in '{}': {}", db.display(), e));
+            process::exit(1);
+        }
+    }
+    if let Some(path) = &options.prom_out {
+        if let Err(e) = std::fs::write(path, render_report(report)) {
+            logging::error(&format!("Failed to write metrics to '{}': {}", path.display(), e));
+            process::exit(1);
+        }
+    }
+}
+
+fn run_cache_command(args: &[String]) {
+    match args.first().map(String::as_str) {
+        Some("clear") => {
+            let cache = ResultCache::new(DEFAULT_CACHE_DIR);
+            match cache.clear() {
+                Ok(removed) => println!(
+                    "Removed {} cached result(s) from {}",
+                    removed,
+                    cache.dir().display()
+                ),
+                Err(e) => {
+                    logging::error(&format!("Failed to clear cache: {}", e));
+                    process::exit(1);
+                }
+            }
+        }
+        _ => {
+            eprintln!("Usage: treescan cache clear");
+            process::exit(1);
+        }
+    }
+}
+
+fn run_rules_command(args: &[String]) {
+    let packs_dir = packs_dir();
+    match args.iter().map(String::as_str).collect::<Vec<_>>().as_slice() {
+        ["install", source] => match install(source, &packs_dir) {
+            Ok(pack) => println!(
+                "Installed {} {} ({} rule(s)) to {}",
+                pack.name,
+                pack.version,
+                pack.rule_count(),
+                pack.dir.display()
+            ),
+            Err(e) => {
+                logging::error(&format!("Failed to install rule pack: {}", e));
                 process::exit(1);
             }
         },
-    };
+        ["list", "--installed"] => {
+            let packs: Vec<serde_json::Value> =
+                installed_packs(&packs_dir).iter().map(RulePack::to_json).collect();
+            println!("{}", serde_json::to_string_pretty(&packs).unwrap_or_default());
+        }
+        ["list"] => {
+            for language in SupportedLanguage::ALL {
+                let Some(analyzer) = language.analyzer() else {
+                    continue;
+                };
+                for rule in analyzer.rules() {
+                    println!("{}\t{}\t{}", language.name(), rule.name, rule.severity.as_str());
+                }
+            }
+        }
+        ["explain", rule] => explain_rule(rule, "text"),
+        ["explain", rule, "--format", format @ ("text" | "json")] => explain_rule(rule, format),
+        ["try", ..] => try_rules(&args[1..]),
+        _ => {
+            eprintln!("Usage: treescan rules install <path|git-url>");
+            eprintln!("       treescan rules list [--installed]");
+            eprintln!("       treescan rules explain <rule> [--format <text|json>]");
+            eprintln!("       treescan rules try <rules.json|queries-dir> [--samples <n>] [--format <text|json>] [<path>]");
+            process::exit(1);
+        }
+    }
+}
 
-    if result_ptr.is_null() {
-        let operation = match command {
-            Command::Parse => "parse",
-            Command::Analyze => "analyze",
-        };
-        eprintln!(
-            "Error: Failed to {} the file. The file might be malformed or contain invalid syntax.",
-            operation
+// Runs before config discovery, which would stop at the first problem
+fn run_config_command(args: &[String]) {
+    let nearest = || {
+        env::current_dir()
+            .ok()
+            .and_then(|dir| find_config_file(&dir))
+    };
+    let path = match args
+        .iter()
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .as_slice()
+    {
+        ["check"] => nearest(),
+        ["check", file] => Some(PathBuf::from(file)),
+        ["show"] => return show_config(nearest(), false),
+        ["show", "--resolved"] => return show_config(nearest(), true),
+        _ => {
+            eprintln!("Usage: treescan config check [<file>]");
+            eprintln!("       treescan config show [--resolved]");
+            process::exit(1);
+        }
+    };
+    let Some(path) = path else {
+        logging::error("No .treescan.json found in this directory or its parents");
+        process::exit(1);
+    };
+    let problems = match check_config(&path) {
+        Ok(problems) => problems,
+        Err(e) => {
+            logging::error(&format!(
+                "Failed to read config '{}': {}",
+                path.display(),
+                e
+            ));
+            process::exit(1);
+        }
+    };
+    if problems.is_empty() {
+        println!("{}: OK", path.display());
+        return;
+    }
+    for problem in &problems {
+        println!(
+            "{}:{}:{}: {}",
+            path.display(),
+            problem.line,
+            problem.column,
+            problem.message
         );
+    }
+    println!("{} problem(s) in {}", problems.len(), path.display());
+    process::exit(1);
+}
+
+// The settings a run would start from, before its command-line flags
+fn show_config(path: Option<PathBuf>, resolved: bool) {
+    let layers = EnvLayer::from_env()
+        .map_err(|e| e.to_string())
+        .and_then(|env| {
+            let file = match &path {
+                Some(path) => Some(read_config(path).map_err(|e| e.to_string())?),
+                None => None,
+            };
+            ProjectConfig::from_layers(file.as_ref().unwrap_or(&serde_json::json!({})), &env)
+                .map_err(|e| match &path {
+                    Some(path) => format!("Invalid config '{}': {}", path.display(), e),
+                    None => format!("Invalid settings: {}", e),
+                })?;
+            Ok((env, file))
+        });
+    let (env, file) = match layers {
+        Ok(layers) => layers,
+        Err(e) => {
+            logging::error(&e);
+            process::exit(1);
+        }
+    };
+    let file = path.as_deref().zip(file.as_ref());
+    let shown = layered_settings(file, &env, resolved);
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&shown).unwrap_or_default()
+    );
+}
+
+// Runs only the candidate rules, so their authors can see how noisy they are before
+// adding them to a config
+fn try_rules(args: &[String]) {
+    let usage =
+        "Usage: treescan rules try <rules.json|queries-dir> [--samples <n>] [--format <text|json>] [<path>]";
+    let mut json = false;
+    let mut samples = 5;
+    let mut paths = Vec::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--samples" => samples = parse_flag_value(arg, iter.next()),
+            "--format" => {
+                json = match iter.next().map(|f| f.as_str()) {
+                    Some("text") => false,
+                    Some("json") => true,
+                    _ => {
+                        eprintln!("{}", usage);
+                        process::exit(1);
+                    }
+                }
+            }
+            _ if !arg.starts_with("--") => paths.push(PathBuf::from(arg)),
+            _ => {
+                eprintln!("{}", usage);
+                process::exit(1);
+            }
+        }
+    }
+    let (rules_path, root) = match paths.as_slice() {
+        [rules_path] => (rules_path, PathBuf::from(".")),
+        [rules_path, root] => (rules_path, root.clone()),
+        _ => {
+            eprintln!("{}", usage);
+            process::exit(1);
+        }
+    };
+    let candidates = match load_candidates(rules_path) {
+        Ok(candidates) => candidates,
+        Err(e) => {
+            logging::error(&format!("Failed to load candidate rules: {}", e));
+            process::exit(1);
+        }
+    };
+    let files = walk_source_files(&root, &WalkOptions::default()).files;
+    let report = trial_report(&root, &files, &candidates, samples);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report).unwrap_or_default());
+    } else {
+        print!("{}", format_trial_text(&report));
+    }
+}
+
+// Custom rules from the project config are explained alongside the built-in ones
+fn explain_rule(rule: &str, format: &str) {
+    let config = env::current_dir()
+        .map_err(|e| e.to_string())
+        .and_then(|dir| ProjectConfig::discover(&dir).map_err(|e| e.to_string()));
+    let config = match config {
+        Ok((_, config)) => config,
+        Err(e) => {
+            logging::error(&format!("Failed to load config: {}", e));
+            process::exit(1);
+        }
+    };
+    let explanations = explain(rule, &config.rules);
+    if explanations.is_empty() {
+        logging::error(&format!(
+            "Unknown rule '{}'; `treescan rules list` shows the built-in rules",
+            rule
+        ));
         process::exit(1);
     }
+    if format == "json" {
+        let json: Vec<serde_json::Value> =
+            explanations.iter().map(RuleExplanation::to_json).collect();
+        println!("{}", serde_json::to_string_pretty(&json).unwrap_or_default());
+        return;
+    }
+    let texts: Vec<String> = explanations.iter().map(RuleExplanation::format_text).collect();
+    print!("{}", texts.join("\n"));
+}
+
+fn run_stats_command(args: &[String], config: &ProjectConfig) {
+    let path = match args {
+        [] => ".".to_string(),
+        [path] if !path.starts_with("--") => path.clone(),
+        _ => {
+            eprintln!("Usage: treescan stats [<path>]");
+            process::exit(1);
+        }
+    };
+    let walk_options = WalkOptions {
+        include_generated: config.include_generated,
+        ..WalkOptions::default()
+    };
+    let walked = walk_source_files(Path::new(&path), &walk_options);
+    let report = collect_stats(&walked.files);
+    println!("{}", serde_json::to_string_pretty(&report).unwrap_or_default());
+}
 
-    // todo: use actual functions rather than ffi interface needed for library
-    unsafe {
-        if let Ok(c_str) = std::ffi::CStr::from_ptr(result_ptr).to_str() {
-            println!("{}", c_str);
-        } else {
-            eprintln!("Error: Failed to convert result to valid UTF-8");
+fn run_doc_coverage_command(args: &[String], config: &ProjectConfig) {
+    let mut min: Option<f64> = None;
+    let mut path = None;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--min" => min = Some(parse_flag_value(arg, iter.next())),
+            _ if !arg.starts_with("--") && path.is_none() => path = Some(arg.clone()),
+            _ => {
+                eprintln!("Usage: treescan doc-coverage [<path>] [--min <percent>]");
+                process::exit(1);
+            }
         }
-        free_string(result_ptr);
+    }
+    let walk_options = WalkOptions {
+        include_generated: config.include_generated,
+        ..WalkOptions::default()
+    };
+    let walked = walk_source_files(Path::new(path.as_deref().unwrap_or(".")), &walk_options);
+    let report = doc_coverage(&walked.files);
+    println!("{}", serde_json::to_string_pretty(&report).unwrap_or_default());
+
+    // Nothing to document passes any threshold
+    let coverage = report["total"]["coverage"].as_f64().unwrap_or(100.0);
+    if let Some(min) = min.filter(|min| coverage < *min) {
+        logging::error(&format!(
+            "Doc coverage {:.1}% is below the required {:.1}%",
+            coverage, min
+        ));
+        process::exit(1);
     }
 }
 
-fn infer_language_from_path(file_path: &str, command: &Command) -> Option<String> {
-    let path = Path::new(file_path);
-    let extension = path.extension()?.to_str()?;
+fn run_api_command(args: &[String], config: &ProjectConfig) {
+    let usage = "Usage: treescan api [--format <text|json>] [--exit-code] <path> [<new_path>]";
+    let mut json = true;
+    let mut exit_code = false;
+    let mut paths = Vec::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--format" => {
+                json = match iter.next().map(|f| f.as_str()) {
+                    Some("text") => false,
+                    Some("json") => true,
+                    _ => {
+                        logging::error("Option '--format' expects 'text' or 'json'");
+                        process::exit(1);
+                    }
+                }
+            }
+            "--exit-code" => exit_code = true,
+            _ if !arg.starts_with("--") => paths.push(PathBuf::from(arg)),
+            _ => {
+                eprintln!("{}", usage);
+                process::exit(1);
+            }
+        }
+    }
+    let walk_options = WalkOptions {
+        include_generated: config.include_generated,
+        ..WalkOptions::default()
+    };
+    let surface = |root: &PathBuf| api_surface(root, &walk_source_files(root, &walk_options).files);
+    match paths.as_slice() {
+        [path] => {
+            let surface = surface(path);
+            if json {
+                println!("{}", serde_json::to_string_pretty(&surface).unwrap_or_default());
+            } else {
+                print!("{}", format_api_text(&surface));
+            }
+        }
+        [old, new] => {
+            let changes = api_changes(&surface(old), &surface(new));
+            if json {
+                println!("{}", serde_json::to_string_pretty(&changes).unwrap_or_default());
+            } else {
+                print!("{}", format_changes_text(&changes));
+            }
+            if exit_code && changes["breaking"] == true {
+                process::exit(1);
+            }
+        }
+        _ => {
+            eprintln!("{}", usage);
+            process::exit(1);
+        }
+    }
+}
 
-    match extension.to_lowercase().as_str() {
-        "rs" => Some("Rust".to_string()),
-        "java" => {
-            match command {
-                Command::Parse => Some("Java".to_string()),
-                Command::Analyze => None,
+fn run_strings_command(args: &[String], config: &ProjectConfig) {
+    let usage = "Usage: treescan strings [--match <regex>] [--format <text|json>] [<path>]";
+    let mut json = true;
+    let mut pattern = None;
+    let mut paths = Vec::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--format" => {
+                json = match iter.next().map(|f| f.as_str()) {
+                    Some("text") => false,
+                    Some("json") => true,
+                    _ => {
+                        logging::error("Option '--format' expects 'text' or 'json'");
+                        process::exit(1);
+                    }
+                }
+            }
+            "--match" => {
+                let text: String = parse_flag_value(arg, iter.next());
+                pattern = match Regex::new(&text) {
+                    Ok(regex) => Some(regex),
+                    Err(e) => {
+                        logging::error(&format!("Invalid --match pattern: {}", e));
+                        process::exit(1);
+                    }
+                }
+            }
+            _ if !arg.starts_with("--") => paths.push(PathBuf::from(arg)),
+            _ => {
+                eprintln!("{}", usage);
+                process::exit(1);
             }
         }
-        "zig" => {
-            match command {
-                Command::Parse => Some("Zig".to_string()),
-                Command::Analyze => None,
+    }
+    let root = match paths.as_slice() {
+        [] => PathBuf::from("."),
+        [path] => path.clone(),
+        _ => {
+            eprintln!("{}", usage);
+            process::exit(1);
+        }
+    };
+    let walk_options = WalkOptions {
+        include_generated: config.include_generated,
+        ..WalkOptions::default()
+    };
+    let report = strings_report(&root, &walk_source_files(&root, &walk_options).files, pattern.as_ref());
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report).unwrap_or_default());
+    } else {
+        print!("{}", format_strings_text(&report));
+    }
+}
+
+fn run_license_command(args: &[String], config: &ProjectConfig) {
+    let usage = "Usage: treescan license [--template <file>] [--fix] [--format <text|json>] [<path>]";
+    let mut json = false;
+    let mut fix = false;
+    let mut template_file: Option<String> = None;
+    let mut paths = Vec::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--format" => {
+                json = match iter.next().map(|f| f.as_str()) {
+                    Some("text") => false,
+                    Some("json") => true,
+                    _ => {
+                        logging::error("Option '--format' expects 'text' or 'json'");
+                        process::exit(1);
+                    }
+                }
+            }
+            "--fix" => fix = true,
+            "--template" => template_file = Some(parse_flag_value(arg, iter.next())),
+            _ if !arg.starts_with("--") => paths.push(PathBuf::from(arg)),
+            _ => {
+                eprintln!("{}", usage);
+                process::exit(1);
             }
         }
-        "c" | "h" => {
-            match command {
-                Command::Parse => Some("C".to_string()),
-                Command::Analyze => None,
+    }
+    let root = match paths.as_slice() {
+        [] => PathBuf::from("."),
+        [path] => path.clone(),
+        _ => {
+            eprintln!("{}", usage);
+            process::exit(1);
+        }
+    };
+    let text = match (&template_file, &config.license_header) {
+        (Some(file), _) => std::fs::read_to_string(file).unwrap_or_else(|e| {
+            logging::error(&format!("Failed to read template '{}': {}", file, e));
+            process::exit(1);
+        }),
+        (None, Some(header)) => header.clone(),
+        (None, None) => {
+            logging::error("No license header: pass --template <file> or set license_header in .treescan.json");
+            process::exit(1);
+        }
+    };
+    let template = HeaderTemplate::new(&text).unwrap_or_else(|e| {
+        logging::error(&format!("Invalid license header template: {}", e));
+        process::exit(1);
+    });
+    let walk_options = WalkOptions {
+        include_generated: config.include_generated,
+        ..WalkOptions::default()
+    };
+    let report = license_report(&root, &walk_source_files(&root, &walk_options).files, &template, fix);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report).unwrap_or_default());
+    } else {
+        print!("{}", format_license_text(&report));
+    }
+    let failing = report["missing"].as_u64().unwrap_or(0) + report["mismatched"].as_u64().unwrap_or(0);
+    if failing > 0 && !fix {
+        process::exit(1);
+    }
+}
+
+fn run_tui_command(args: &[String], config: &ProjectConfig) {
+    let path = match args {
+        [] => PathBuf::from("."),
+        [path] if !path.starts_with("--") => PathBuf::from(path),
+        _ => {
+            eprintln!("Usage: treescan tui [<path>]");
+            process::exit(1);
+        }
+    };
+    let options = Options {
+        rules: config.rules.clone(),
+        max_issues_per_file: config.max_issues_per_file,
+        include_generated: config.include_generated,
+        ..Options::default()
+    };
+    let report = scan_directory(&path, &scan_options(&options));
+    if let Err(e) = tui::run(&report) {
+        logging::error(&e.to_string());
+        process::exit(1);
+    }
+}
+
+fn run_hotspots_command(args: &[String], config: &ProjectConfig) {
+    let usage = || -> ! {
+        eprintln!("Usage: treescan hotspots [--since <date>] [--limit <n>] [--format <table|json>] [<directory>]");
+        process::exit(1);
+    };
+    let mut since: Option<String> = None;
+    let mut limit: Option<usize> = None;
+    let mut json = false;
+    let mut dir = None;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--since" => since = Some(parse_flag_value(arg, iter.next())),
+            "--limit" => limit = Some(parse_flag_value(arg, iter.next())),
+            "--format" => match iter.next().map(String::as_str) {
+                Some("table") => json = false,
+                Some("json") => json = true,
+                _ => usage(),
+            },
+            flag if flag.starts_with("--") => usage(),
+            _ if dir.is_none() => dir = Some(PathBuf::from(arg)),
+            _ => usage(),
+        }
+    }
+    let dir = dir.unwrap_or_else(|| PathBuf::from("."));
+
+    let churn = match commit_counts(&dir, since.as_deref()) {
+        Ok(churn) => churn,
+        Err(e) => {
+            logging::error(&e.to_string());
+            process::exit(1);
+        }
+    };
+    let options = Options {
+        rules: config.rules.clone(),
+        max_issues_per_file: config.max_issues_per_file,
+        include_generated: config.include_generated,
+        ..Options::default()
+    };
+    let report = scan_directory(&dir, &scan_options(&options));
+    // Report paths are as walked; git paths are absolute, so match on canonical paths
+    let commits = report["files"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|file| {
+            let path = file["path"].as_str()?;
+            let count = churn.get(&std::fs::canonicalize(path).ok()?)?;
+            Some((path.to_string(), *count))
+        })
+        .collect();
+
+    let mut hotspots = rank_hotspots(&report, &commits);
+    if let (Some(limit), Some(list)) = (limit, hotspots.as_array_mut()) {
+        list.truncate(limit);
+    }
+    if json {
+        println!("{}", serde_json::to_string_pretty(&hotspots).unwrap_or_default());
+    } else {
+        print!("{}", format_hotspots_table(&hotspots));
+    }
+}
+
+fn run_badge_command(args: &[String], config: &ProjectConfig) {
+    let mut out = PathBuf::from("badge.svg");
+    let mut label = "code quality".to_string();
+    let mut dir = None;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--out" => out = parse_flag_value(arg, iter.next()),
+            "--label" => label = parse_flag_value(arg, iter.next()),
+            flag if flag.starts_with("--") => {
+                eprintln!("Usage: treescan badge [--out <badge.svg>] [--label <text>] [<directory>]");
+                process::exit(1);
             }
+            _ => dir = Some(arg.clone()),
         }
-        "js" | "jsx" => Some("JavaScript".to_string()),
-        "ts" | "tsx" => {
-            match command {
-                Command::Parse => Some("TypeScript".to_string()),
-                Command::Analyze => None,
+    }
+
+    let options = Options {
+        rules: config.rules.clone(),
+        max_issues_per_file: config.max_issues_per_file,
+        include_generated: config.include_generated,
+        ..Options::default()
+    };
+    let dir = dir.unwrap_or_else(|| ".".to_string());
+    let report = scan_directory(Path::new(&dir), &scan_options(&options));
+    let score = report["summary"]["average_score"].as_f64();
+    if let Err(e) = std::fs::write(&out, render_badge(&label, score)) {
+        logging::error(&format!("Failed to write '{}': {}", out.display(), e));
+        process::exit(1);
+    }
+    match score {
+        Some(score) => logging::info(&format!("Wrote {} (score {:.1})", out.display(), score)),
+        None => logging::info(&format!("Wrote {} (no files analyzed)", out.display())),
+    }
+}
+
+fn run_baseline_command(args: &[String], config: &ProjectConfig) {
+    let usage = || -> ! {
+        eprintln!("Usage: treescan baseline import --from <eslint|golangci> <report.json|-> [--out <file>]");
+        eprintln!("       treescan baseline create <directory> [--out <file>]");
+        process::exit(1);
+    };
+    let mut from = None;
+    let mut out = PathBuf::from(DEFAULT_BASELINE_FILE);
+    let mut positional = Vec::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--from" => from = Some(parse_flag_value::<String>(arg, iter.next())),
+            "--out" => out = parse_flag_value(arg, iter.next()),
+            _ => positional.push(arg.as_str()),
+        }
+    }
+
+    let baseline = match positional.as_slice() {
+        ["import", input] => {
+            let text = if *input == "-" {
+                let mut text = String::new();
+                io::stdin().read_to_string(&mut text).map(|_| text)
+            } else {
+                std::fs::read_to_string(input)
+            };
+            let report: serde_json::Value = match text.map_err(|e| e.to_string()).and_then(|t| {
+                serde_json::from_str(&t).map_err(|e| e.to_string())
+            }) {
+                Ok(report) => report,
+                Err(e) => {
+                    logging::error(&format!("Failed to read '{}': {}", input, e));
+                    process::exit(1);
+                }
+            };
+            // Reported paths are made relative to where treescan runs
+            let root = env::current_dir().unwrap_or_default();
+            let (baseline, summary) = match from.as_deref() {
+                Some("eslint") => Baseline::from_eslint(&report, &root),
+                Some("golangci") | Some("golangci-lint") => Baseline::from_golangci(&report, &root),
+                _ => usage(),
+            };
+            logging::info(&format!(
+                "Imported {} issue(s); {} had no treescan equivalent",
+                summary.imported, summary.unmapped
+            ));
+            baseline
+        }
+        ["create", dir] if from.is_none() => {
+            let options = Options {
+                rules: config.rules.clone(),
+                max_issues_per_file: config.max_issues_per_file,
+                include_generated: config.include_generated,
+                ..Options::default()
+            };
+            Baseline::from_report(&scan_directory(Path::new(dir), &scan_options(&options)))
+        }
+        _ => usage(),
+    };
+
+    let text = serde_json::to_string_pretty(&baseline.to_json()).unwrap_or_default();
+    if let Err(e) = std::fs::write(&out, text + "\n") {
+        logging::error(&format!("Failed to write '{}': {}", out.display(), e));
+        process::exit(1);
+    }
+    logging::info(&format!(
+        "Wrote {} issue(s) to {}",
+        baseline.issues.len(),
+        out.display()
+    ));
+}
+
+fn run_review_command(args: &[String], config: &ProjectConfig) {
+    let diff = match args {
+        [flag, path] if flag == "--diff" && path == "-" => {
+            let mut diff = String::new();
+            io::stdin().read_to_string(&mut diff).map(|_| diff).map_err(|e| e.to_string())
+        }
+        [flag, path] if flag == "--diff" => std::fs::read_to_string(path).map_err(|e| e.to_string()),
+        // Needs the GitHub CLI and a checkout of the PR head in the working directory
+        [flag, number] if flag == "--pr" => process::Command::new("gh")
+            .args(["pr", "diff", number.as_str()])
+            .output()
+            .map_err(|e| format!("could not run 'gh pr diff': {}", e))
+            .and_then(|output| {
+                if output.status.success() {
+                    String::from_utf8(output.stdout).map_err(|e| e.to_string())
+                } else {
+                    Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+                }
+            }),
+        _ => {
+            eprintln!("Usage: treescan review --pr <number>");
+            eprintln!("       treescan review --diff <file|->");
+            process::exit(1);
+        }
+    };
+
+    match diff {
+        Ok(diff) => {
+            let review = build_review(
+                &diff,
+                Path::new("."),
+                &config.rules,
+                config.max_findings_per_rule,
+            );
+            println!("{}", serde_json::to_string_pretty(&review).unwrap_or_default());
+        }
+        Err(e) => {
+            logging::error(&format!("Failed to read the diff: {}", e));
+            process::exit(1);
+        }
+    }
+}
+
+fn run_serve_command(args: &[String], config: ProjectConfig) {
+    let mut stdio = false;
+    let mut jsonl = false;
+    let mut unknown = false;
+    let mut metrics_addr = None;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--stdio" => stdio = true,
+            "--jsonl" => jsonl = true,
+            "--metrics-addr" => metrics_addr = Some(parse_flag_value::<String>(arg, iter.next())),
+            _ => unknown = true,
+        }
+    }
+    if stdio == jsonl || unknown || (jsonl && metrics_addr.is_some()) {
+        eprintln!("Usage: treescan serve --stdio [--metrics-addr <host:port>]");
+        eprintln!("       treescan serve --jsonl");
+        process::exit(1);
+    }
+    let served = if jsonl {
+        pipeline::run_stdio(config)
+    } else {
+        daemon::run_stdio(config, metrics_addr.as_deref())
+    };
+    if let Err(e) = served {
+        logging::error(&format!("Server failed: {}", e));
+        process::exit(1);
+    }
+}
+
+fn run_highlight_command(args: &[String]) {
+    let mut format = HighlightFormat::Ansi;
+    let mut path = None;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--format" => {
+                format = match iter.next().and_then(|f| HighlightFormat::from_name(f)) {
+                    Some(format) => format,
+                    None => {
+                        logging::error("Option '--format' expects 'ansi' or 'html'");
+                        process::exit(1);
+                    }
+                }
             }
+            _ => path = Some(arg.clone()),
         }
-        "cpp" | "cc" | "cxx" | "hpp" | "hxx" => {
-            match command {
-                Command::Parse => Some("C++".to_string()),
-                Command::Analyze => None,
+    }
+
+    let path = match path {
+        Some(path) => path,
+        None => {
+            eprintln!("Usage: treescan highlight <file> [--format <ansi|html>]");
+            process::exit(1);
+        }
+    };
+    let language = match SupportedLanguage::detect(Path::new(&path)) {
+        Some(language) => language,
+        None => {
+            logging::error(&format!("Unsupported file extension for '{}'", path));
+            process::exit(1);
+        }
+    };
+    let highlighted = read_source(&path, None)
+        .map_err(|e| e.to_string())
+        .and_then(|source| highlight(&source, language, format).map_err(|e| e.to_string()));
+    match highlighted {
+        Ok(output) => print!("{}", output),
+        Err(e) => {
+            logging::error(&format!("Failed to highlight '{}': {}", path, e));
+            process::exit(1);
+        }
+    }
+}
+
+fn run_diff_command(args: &[String]) {
+    let usage = "Usage: treescan diff <old> <new> [--format <text|json>]";
+    let mut json = false;
+    let mut paths = Vec::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--format" => {
+                json = match iter.next().map(|f| f.as_str()) {
+                    Some("text") => false,
+                    Some("json") => true,
+                    _ => {
+                        logging::error("Option '--format' expects 'text' or 'json'");
+                        process::exit(1);
+                    }
+                }
             }
+            _ => paths.push(arg.clone()),
         }
-        "go" => {
-            match command {
-                Command::Parse => None,
-                Command::Analyze => Some("Go".to_string()),
+    }
+
+    let (old_path, new_path) = match paths.as_slice() {
+        [old_path, new_path] => (old_path, new_path),
+        _ => {
+            eprintln!("{}", usage);
+            process::exit(1);
+        }
+    };
+    let language = match (
+        SupportedLanguage::detect(Path::new(old_path)),
+        SupportedLanguage::detect(Path::new(new_path)),
+    ) {
+        (Some(old), Some(new)) if old == new => old,
+        (Some(_), Some(_)) => {
+            logging::error(&format!("'{}' and '{}' are in different languages", old_path, new_path));
+            process::exit(1);
+        }
+        _ => {
+            logging::error(&format!("Unsupported file extension for '{}' or '{}'", old_path, new_path));
+            process::exit(1);
+        }
+    };
+    let parse = |path: &str| {
+        let source = read_source(path, None).map_err(|e| e.to_string())?;
+        let tree = parse_tree(&source, &language.tree_sitter_language()).map_err(|e| e.to_string())?;
+        Ok::<_, String>((source, tree))
+    };
+    let ((old_source, old_tree), (new_source, new_tree)) = match (parse(old_path), parse(new_path)) {
+        (Ok(old), Ok(new)) => (old, new),
+        (Err(e), _) | (_, Err(e)) => {
+            logging::error(&format!("Failed to parse: {}", e));
+            process::exit(1);
+        }
+    };
+
+    let changes = diff_trees(&old_tree, &old_source, &new_tree, &new_source);
+    if json {
+        let report = diff_to_json(old_path, new_path, &changes);
+        println!("{}", serde_json::to_string_pretty(&report).unwrap_or_default());
+    } else {
+        print!("{}", format_diff_text(&changes));
+    }
+}
+
+fn run_node_at_command(args: &[String]) {
+    let usage = "Usage: treescan node-at <file> <line>:<col> [--column-unit <byte|char|utf16>]";
+    let mut unit = ColumnUnit::Byte;
+    let mut positional = Vec::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--column-unit" => {
+                unit = match iter.next().and_then(|u| ColumnUnit::from_name(u)) {
+                    Some(unit) => unit,
+                    None => {
+                        logging::error("Option '--column-unit' expects 'byte', 'char' or 'utf16'");
+                        process::exit(1);
+                    }
+                }
             }
+            _ => positional.push(arg.clone()),
+        }
+    }
+
+    let (path, position) = match positional.as_slice() {
+        [path, position] => (path, position),
+        _ => {
+            eprintln!("{}", usage);
+            process::exit(1);
+        }
+    };
+    let (line, column) = match position
+        .split_once(':')
+        .and_then(|(line, column)| Some((line.parse::<usize>().ok()?, column.parse::<usize>().ok()?)))
+    {
+        Some((line, column)) if line > 0 && column > 0 => (line, column),
+        _ => {
+            logging::error(&format!("Position '{}' must be <line>:<col> (1-based)", position));
+            process::exit(1);
+        }
+    };
+    let language = match SupportedLanguage::detect(Path::new(path)) {
+        Some(language) => language,
+        None => {
+            logging::error(&format!("Unsupported file extension for '{}'", path));
+            process::exit(1);
+        }
+    };
+    let source = match read_source(path, None) {
+        Ok(source) => source,
+        Err(e) => {
+            logging::error(&format!("Failed to read '{}': {}", path, e));
+            process::exit(1);
+        }
+    };
+    let offset = match unit.byte_offset(&source, line, column) {
+        Some(offset) => offset,
+        None => {
+            logging::error(&format!("Position {} is outside '{}'", position, path));
+            process::exit(1);
+        }
+    };
+    let tree = match parse_tree(&source, &language.tree_sitter_language()) {
+        Ok(tree) => tree,
+        Err(e) => {
+            logging::error(&format!("Failed to parse '{}': {}", path, e));
+            process::exit(1);
+        }
+    };
+    let result = node_at(&tree, &source, offset, unit);
+    println!("{}", serde_json::to_string_pretty(&result).unwrap_or_default());
+}
+
+fn run_bench_command(args: &[String]) {
+    let mut iterations = 10;
+    let mut path = None;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--iterations" => iterations = parse_flag_value(arg, iter.next()),
+            _ => path = Some(arg.clone()),
+        }
+    }
+
+    let path = match path {
+        Some(path) => path,
+        None => {
+            eprintln!("Usage: treescan bench [--iterations <n>] <path>");
+            process::exit(1);
+        }
+    };
+
+    let walked = walk_source_files(Path::new(&path), &WalkOptions::default());
+    let report = run_bench(&walked.files, iterations).map_err(|e| e.to_string());
+    match report {
+        Ok(report) => println!("{}", serde_json::to_string_pretty(&report).unwrap_or_default()),
+        Err(e) => {
+            logging::error(&format!("Benchmark failed: {}", e));
+            process::exit(1);
         }
-        _ => None,
     }
 }
 
+fn infer_language_from_path(file_path: &str, command: &Command) -> Option<String> {
+    let language = match command {
+        Command::Analyze => SupportedLanguage::detect(Path::new(file_path))
+            .or_else(|| sfc::detect(Path::new(file_path)))?,
+        Command::Parse => SupportedLanguage::detect(Path::new(file_path))?,
+    };
+
+    let supported = match command {
+        Command::Parse => language.supports_parse(),
+        Command::Analyze => language.supports_analyze(),
+    };
+    supported.then(|| language.name().to_string())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -199,6 +2047,18 @@ mod tests {
             infer_language_from_path("app.ts", &Command::Parse),
             Some("TypeScript".to_string())
         );
+        assert_eq!(
+            infer_language_from_path("App.tsx", &Command::Parse),
+            Some("TSX".to_string())
+        );
+        assert_eq!(
+            infer_language_from_path("index.d.ts", &Command::Parse),
+            Some("TypeScript".to_string())
+        );
+        assert_eq!(
+            infer_language_from_path("server.mjs", &Command::Analyze),
+            Some("JavaScript".to_string())
+        );
         assert_eq!(
             infer_language_from_path("main.cpp", &Command::Parse),
             Some("C++".to_string())
@@ -0,0 +1,242 @@
+use crate::config::ProjectConfig;
+use crate::daemon::{error_response, Daemon, INVALID_PARAMS, METHOD_NOT_FOUND, PARSE_ERROR};
+use serde_json::{json, Value};
+use std::io::{self, BufRead, Write};
+
+const PROTOCOL_VERSION: &str = "2024-11-05";
+
+// Model Context Protocol server over stdio (newline-delimited JSON-RPC), exposing
+// treescan's parse, analysis, query and outline features as tools
+pub fn run_stdio(config: ProjectConfig) -> io::Result<()> {
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    serve(config, &mut stdin.lock(), &mut stdout.lock())
+}
+
+pub fn serve<R: BufRead, W: Write>(
+    config: ProjectConfig,
+    input: &mut R,
+    output: &mut W,
+) -> io::Result<()> {
+    let mut daemon = Daemon::new(config);
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let reply = match serde_json::from_str::<Value>(&line) {
+            Ok(message) => handle(&mut daemon, &message),
+            Err(e) => Some(error_response(Value::Null, PARSE_ERROR, e.to_string())),
+        };
+        if let Some(reply) = reply {
+            writeln!(output, "{}", reply)?;
+            output.flush()?;
+        }
+    }
+}
+
+fn handle(daemon: &mut Daemon, message: &Value) -> Option<Value> {
+    let method = message["method"].as_str().unwrap_or("");
+    // Notifications (`notifications/initialized`, cancellations) need no reply
+    let id = message.get("id").cloned()?;
+    let params = &message["params"];
+
+    let result = match method {
+        "initialize" => json!({
+            "protocolVersion": PROTOCOL_VERSION,
+            "capabilities": { "tools": {} },
+            "serverInfo": { "name": "treescan", "version": env!("CARGO_PKG_VERSION") },
+        }),
+        "ping" => json!({}),
+        "tools/list" => json!({ "tools": tools() }),
+        "tools/call" => {
+            let name = params["name"].as_str().unwrap_or("");
+            let arguments = &params["arguments"];
+            let method = match name {
+                "parse_file" => "parse",
+                "analyze_file" => "analyze",
+                "run_query" => "query",
+                "extract_symbols" => "symbols",
+                _ => {
+                    return Some(error_response(
+                        id,
+                        INVALID_PARAMS,
+                        format!("Unknown tool: {}", name),
+                    ))
+                }
+            };
+            // Tool failures are results the model can read, not protocol errors
+            match daemon.handle(method, arguments) {
+                Ok(value) => tool_result(value.to_string(), false),
+                Err((_, error)) => tool_result(error, true),
+            }
+        }
+        _ => {
+            return Some(error_response(
+                id,
+                METHOD_NOT_FOUND,
+                format!("Method not found: {}", method),
+            ))
+        }
+    };
+    Some(json!({ "jsonrpc": "2.0", "id": id, "result": result }))
+}
+
+fn tool_result(text: String, is_error: bool) -> Value {
+    json!({ "content": [{ "type": "text", "text": text }], "isError": is_error })
+}
+
+fn tools() -> Value {
+    let path = json!({ "type": "string", "description": "Path of the source file" });
+    let language = json!({
+        "type": "string",
+        "description": "Language name; inferred from the path when omitted",
+    });
+    json!([
+        {
+            "name": "parse_file",
+            "description": "Parse a file and return its syntax tree plus any syntax errors",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "path": path,
+                    "language": language,
+                    "format": { "type": "string", "enum": ["json", "text"] },
+                },
+                "required": ["path"],
+            },
+        },
+        {
+            "name": "analyze_file",
+            "description": "Run treescan's quality rules on a file and return issues, score and metrics",
+            "inputSchema": {
+                "type": "object",
+                "properties": { "path": path, "language": language },
+                "required": ["path"],
+            },
+        },
+        {
+            "name": "run_query",
+            "description": "Run a tree-sitter query against a file and return every capture",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "path": path,
+                    "language": language,
+                    "query": { "type": "string", "description": "tree-sitter query (S-expression)" },
+                },
+                "required": ["path", "query"],
+            },
+        },
+        {
+            "name": "extract_symbols",
+            "description": "List the functions, types, methods and modules declared in a file",
+            "inputSchema": {
+                "type": "object",
+                "properties": { "path": path, "language": language },
+                "required": ["path"],
+            },
+        },
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    // Feeds `lines` to a fresh server and returns its replies
+    fn exchange(lines: &[String]) -> Vec<Value> {
+        let input: String = lines.iter().map(|line| format!("{}\n", line)).collect();
+        let mut output = Vec::new();
+        serve(ProjectConfig::default(), &mut input.as_bytes(), &mut output).unwrap();
+        String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    fn call(id: u64, name: &str, arguments: Value) -> String {
+        json!({ "jsonrpc": "2.0", "id": id, "method": "tools/call",
+                "params": { "name": name, "arguments": arguments } })
+        .to_string()
+    }
+
+    #[test]
+    fn test_initialize_and_list_tools() {
+        let replies = exchange(&[
+            json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {} }).to_string(),
+            json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }).to_string(),
+            json!({ "jsonrpc": "2.0", "id": 2, "method": "tools/list" }).to_string(),
+        ]);
+
+        // The notification gets no reply
+        assert_eq!(replies.len(), 2);
+        assert_eq!(replies[0]["result"]["protocolVersion"], PROTOCOL_VERSION);
+        let names: Vec<&str> = replies[1]["result"]["tools"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|tool| tool["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            names,
+            ["parse_file", "analyze_file", "run_query", "extract_symbols"]
+        );
+    }
+
+    #[test]
+    fn test_tools_read_files() {
+        let dir = TempDir::new("mcp");
+        let file = dir.write("lib.rs", "pub fn area(w: u32) -> u32 { w.max(1) }\n");
+        let path = file.display().to_string();
+
+        let replies = exchange(&[
+            call(1, "extract_symbols", json!({ "path": path })),
+            call(2, "analyze_file", json!({ "path": path })),
+        ]);
+        let text = |reply: &Value| -> Value {
+            assert_eq!(reply["result"]["isError"], false);
+            serde_json::from_str(reply["result"]["content"][0]["text"].as_str().unwrap()).unwrap()
+        };
+        assert_eq!(text(&replies[0])[0]["name"], "area");
+        assert!(text(&replies[1])["score"].is_number());
+    }
+
+    #[test]
+    fn test_tool_failures_are_results() {
+        let dir = TempDir::new("mcp");
+        let path = dir.write("lib.rs", "fn f() {}\n").display().to_string();
+
+        let bad_query = json!({ "path": path, "query": "(not_a_node" });
+        let replies = exchange(&[
+            call(1, "analyze_file", json!({ "path": dir.join("missing.rs") })),
+            call(2, "run_query", bad_query),
+        ]);
+        for reply in &replies {
+            assert_eq!(reply["result"]["isError"], true);
+            assert!(reply["result"]["content"][0]["text"].is_string());
+        }
+    }
+
+    #[test]
+    fn test_protocol_errors() {
+        let replies = exchange(&[
+            call(1, "format_file", json!({})),
+            json!({ "jsonrpc": "2.0", "id": 2, "method": "resources/list" }).to_string(),
+            "{ not json".to_string(),
+        ]);
+
+        assert_eq!(replies[0]["error"]["code"], INVALID_PARAMS);
+        assert_eq!(replies[0]["error"]["message"], "Unknown tool: format_file");
+        assert_eq!(replies[1]["error"]["code"], METHOD_NOT_FOUND);
+        assert_eq!(replies[2]["id"], Value::Null);
+        assert_eq!(replies[2]["error"]["code"], PARSE_ERROR);
+    }
+}
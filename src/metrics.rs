@@ -0,0 +1,118 @@
+use serde_json::Value;
+use tree_sitter::{Node, Tree};
+
+// Node kinds that open an extra path through the code, across the bundled grammars
+const DECISION_KINDS: &[&str] = &[
+    "if_expression",
+    "if_statement",
+    "while_expression",
+    "while_statement",
+    "for_expression",
+    "for_statement",
+    "for_in_statement",
+    "enhanced_for_statement",
+    "loop_expression",
+    "do_statement",
+    "match_arm",
+    "switch_case",
+    "case_statement",
+    "expression_case",
+    "type_case",
+    "communication_case",
+    "catch_clause",
+    "conditional_expression",
+    "ternary_expression",
+];
+
+const FUNCTION_KINDS: &[&str] = &[
+    "function_item",
+    "function_declaration",
+    "function_definition",
+    "method_declaration",
+    "method_definition",
+    "function_expression",
+    "arrow_function",
+    "closure_expression",
+    "func_literal",
+];
+
+#[derive(Debug, Clone, Default)]
+pub struct FileMetrics {
+    pub total_lines: usize,
+    pub lines_of_code: usize,
+    pub comment_lines: usize,
+    pub function_count: usize,
+    pub cyclomatic_complexity: usize,
+}
+
+impl FileMetrics {
+    pub fn compute(tree: &Tree, source_code: &str) -> Self {
+        let mut metrics = FileMetrics {
+            total_lines: source_code.lines().count(),
+            lines_of_code: source_code
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .count(),
+            cyclomatic_complexity: 1,
+            ..Default::default()
+        };
+
+        let mut cursor = tree.walk();
+        loop {
+            let node = cursor.node();
+            metrics.visit(&node);
+            if cursor.goto_first_child() {
+                continue;
+            }
+            while !cursor.goto_next_sibling() {
+                if !cursor.goto_parent() {
+                    return metrics;
+                }
+            }
+        }
+    }
+
+    fn visit(&mut self, node: &Node) {
+        let kind = node.kind();
+        if DECISION_KINDS.contains(&kind) || is_short_circuit(node) {
+            self.cyclomatic_complexity += 1;
+        }
+        if FUNCTION_KINDS.contains(&kind) {
+            self.function_count += 1;
+        }
+        if kind.contains("comment") {
+            self.comment_lines += node.end_position().row - node.start_position().row + 1;
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        serde_json::to_value(self).unwrap_or(Value::Null)
+    }
+}
+
+fn is_short_circuit(node: &Node) -> bool {
+    node.kind() == "binary_expression"
+        && node
+            .child_by_field_name("operator")
+            .is_some_and(|op| matches!(op.kind(), "&&" | "||"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::parse_tree;
+
+    #[test]
+    fn test_metrics_counts_decisions_and_functions() {
+        let source = "// entry\nfn main() {\n    if a && b {\n        for x in y {}\n    }\n}\n\nfn other() {}\n";
+        let tree = parse_tree(source, &tree_sitter_rust::LANGUAGE.into()).unwrap();
+        let metrics = FileMetrics::compute(&tree, source);
+
+        assert_eq!(metrics.total_lines, 8);
+        assert_eq!(metrics.lines_of_code, 7);
+        assert_eq!(metrics.comment_lines, 1);
+        assert_eq!(metrics.function_count, 2);
+        // 1 + if + && + for
+        assert_eq!(metrics.cyclomatic_complexity, 4);
+    }
+}
@@ -0,0 +1,263 @@
+use crate::analyzer::{fingerprint, AnalysisResult, Severity};
+use crate::language::SupportedLanguage;
+use crate::position::ColumnUnit;
+use regex::Regex;
+use tree_sitter::{Node, Tree};
+
+pub const NAMING_CONVENTION: &str = "naming_convention";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum NameKind {
+    // Functions and methods
+    Function,
+    // Structs, enums, traits, classes, interfaces and aliases
+    Type,
+    // Rust const/static items, Go constants
+    Constant,
+}
+
+impl NameKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NameKind::Function => "function",
+            NameKind::Type => "type",
+            NameKind::Constant => "constant",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "function" => Some(NameKind::Function),
+            "type" => Some(NameKind::Type),
+            "constant" => Some(NameKind::Constant),
+            _ => None,
+        }
+    }
+}
+
+// The names of one kind of declaration must match `pattern`
+#[derive(Debug, Clone)]
+pub struct NamingRule {
+    pub kind: NameKind,
+    pub pattern: Regex,
+}
+
+impl NamingRule {
+    pub fn new(kind: NameKind, pattern: &str) -> Result<Self, regex::Error> {
+        Ok(NamingRule {
+            kind,
+            pattern: Regex::new(pattern)?,
+        })
+    }
+}
+
+// Each language's own conventions. Go and JavaScript allow a leading capital on
+// functions: exported Go names and React components need one.
+pub fn default_naming_rules(lang: SupportedLanguage) -> Vec<NamingRule> {
+    let patterns: &[(NameKind, &str)] = match lang {
+        SupportedLanguage::Rust => &[
+            (NameKind::Function, r"^_*[a-z][a-z0-9_]*$"),
+            (NameKind::Type, r"^[A-Z][A-Za-z0-9]*$"),
+            (NameKind::Constant, r"^_*[A-Z][A-Z0-9_]*$"),
+        ],
+        SupportedLanguage::Go => &[
+            // `go test` wants `TestX_y`/`ExampleT_m` names
+            (
+                NameKind::Function,
+                r"^(?:(?:Test|Benchmark|Example|Fuzz)\w*|[A-Za-z][A-Za-z0-9]*)$",
+            ),
+            (NameKind::Type, r"^[A-Za-z][A-Za-z0-9]*$"),
+        ],
+        SupportedLanguage::JavaScript | SupportedLanguage::TypeScript | SupportedLanguage::Tsx => {
+            &[
+                (NameKind::Function, r"^[_$]*[A-Za-z][A-Za-z0-9$]*$"),
+                (NameKind::Type, r"^[A-Z][A-Za-z0-9]*$"),
+            ]
+        }
+        _ => &[],
+    };
+    patterns
+        .iter()
+        .filter_map(|(kind, pattern)| NamingRule::new(*kind, pattern).ok())
+        .collect()
+}
+
+const IDENTIFIER_KINDS: &[&str] = &[
+    "identifier",
+    "field_identifier",
+    "property_identifier",
+    "private_property_identifier",
+    "type_identifier",
+];
+
+fn declared_kind(node: &Node) -> Option<NameKind> {
+    match node.kind() {
+        "function_item"
+        | "function_signature_item"
+        | "function_declaration"
+        | "generator_function_declaration"
+        | "method_declaration"
+        | "method_definition" => Some(NameKind::Function),
+        // `const f = () => ...` is how most JavaScript functions are declared
+        "variable_declarator" => node
+            .child_by_field_name("value")
+            .filter(|value| matches!(value.kind(), "arrow_function" | "function_expression"))
+            .map(|_| NameKind::Function),
+        "struct_item"
+        | "enum_item"
+        | "union_item"
+        | "trait_item"
+        | "type_item"
+        | "type_spec"
+        | "class_declaration"
+        | "interface_declaration"
+        | "type_alias_declaration"
+        | "enum_declaration" => Some(NameKind::Type),
+        "const_item" | "static_item" | "const_spec" => Some(NameKind::Constant),
+        _ => None,
+    }
+}
+
+// Rust `extern` blocks declare functions named by another language
+fn is_foreign(node: &Node) -> bool {
+    node.parent()
+        .and_then(|list| list.parent())
+        .is_some_and(|owner| owner.kind() == "foreign_mod_item")
+}
+
+// Declared names that don't match their kind's pattern
+pub fn naming_findings(
+    tree: &Tree,
+    source: &str,
+    rules: &[NamingRule],
+    unit: ColumnUnit,
+) -> Vec<(usize, AnalysisResult)> {
+    let mut results = Vec::new();
+    let mut stack = vec![tree.root_node()];
+    while let Some(node) = stack.pop() {
+        let rule = declared_kind(&node)
+            .filter(|_| !is_foreign(&node))
+            .and_then(|kind| rules.iter().find(|rule| rule.kind == kind));
+        if let Some(rule) = rule {
+            let mut cursor = node.walk();
+            // Go lists several constants in one spec
+            let names: Vec<Node> = node.children_by_field_name("name", &mut cursor).collect();
+            for name in names {
+                if !IDENTIFIER_KINDS.contains(&name.kind()) {
+                    continue;
+                }
+                let text = name.utf8_text(source.as_bytes()).unwrap_or("");
+                let identifier = text.trim_start_matches('#');
+                if rule.pattern.is_match(identifier) {
+                    continue;
+                }
+                let (line, column) = unit.start_of(&name, source);
+                let severity = Severity::Style;
+                results.push((
+                    name.start_byte(),
+                    AnalysisResult {
+                        rule_name: NAMING_CONVENTION.to_string(),
+                        score_impact: severity.base_score_impact(),
+                        severity,
+                        message: format!(
+                            "{} name `{}` does not match {}",
+                            capitalized(rule.kind.as_str()),
+                            identifier,
+                            rule.pattern.as_str()
+                        ),
+                        line,
+                        column,
+                        text: text.to_string(),
+                        suggestion: Some(
+                            "Rename it to follow the project's naming convention".to_string(),
+                        ),
+                        category: None,
+                        fingerprint: fingerprint(NAMING_CONVENTION, &name, text, source),
+                    },
+                ));
+            }
+        }
+        let mut cursor = node.walk();
+        let children: Vec<Node> = node.named_children(&mut cursor).collect();
+        stack.extend(children.into_iter().rev());
+    }
+    results
+}
+
+fn capitalized(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::parse_tree;
+
+    #[test]
+    fn test_names_checked_against_language_conventions() {
+        let names = |source: &str, lang: SupportedLanguage, rules: &[NamingRule]| {
+            let tree = parse_tree(source, &lang.tree_sitter_language()).unwrap();
+            naming_findings(&tree, source, rules, ColumnUnit::Byte)
+                .into_iter()
+                .map(|(_, r)| r.message)
+                .collect::<Vec<_>>()
+        };
+
+        let rust = default_naming_rules(SupportedLanguage::Rust);
+        assert_eq!(
+            names(
+                "fn parseFile() {}\nstruct my_type;\nconst maxSize: u32 = 1;\nstatic _UNUSED: u8 = 0;\n\
+                 extern \"C\" { fn GetTickCount() -> u32; }\n",
+                SupportedLanguage::Rust,
+                &rust
+            ),
+            vec![
+                "Function name `parseFile` does not match ^_*[a-z][a-z0-9_]*$",
+                "Type name `my_type` does not match ^[A-Z][A-Za-z0-9]*$",
+                "Constant name `maxSize` does not match ^_*[A-Z][A-Z0-9_]*$",
+            ]
+        );
+
+        let go = default_naming_rules(SupportedLanguage::Go);
+        assert_eq!(
+            names(
+                "package m\nfunc parse_file() {}\nfunc TestParse_empty(t *testing.T) {}\ntype ok struct{}\n",
+                SupportedLanguage::Go,
+                &go
+            )
+            .len(),
+            1
+        );
+
+        let js = default_naming_rules(SupportedLanguage::JavaScript);
+        assert_eq!(
+            names(
+                "function App() {}\nconst load_items = () => {};\nclass A { #do_it() {} render() {} }\n",
+                SupportedLanguage::JavaScript,
+                &js
+            ),
+            vec![
+                "Function name `load_items` does not match ^[_$]*[A-Za-z][A-Za-z0-9$]*$",
+                "Function name `do_it` does not match ^[_$]*[A-Za-z][A-Za-z0-9$]*$",
+            ]
+        );
+
+        // Project patterns replace the defaults
+        let constants = [NamingRule::new(NameKind::Constant, "^k[A-Z]").unwrap()];
+        assert_eq!(
+            names(
+                "package m\nconst (\n\tkLimit = 1\n\tmaxSize, MinSize = 2, 3\n)\n",
+                SupportedLanguage::Go,
+                &constants
+            ),
+            vec![
+                "Constant name `maxSize` does not match ^k[A-Z]",
+                "Constant name `MinSize` does not match ^k[A-Z]",
+            ]
+        );
+    }
+}
@@ -0,0 +1,115 @@
+use crate::outline::{symbol_name, SymbolKind};
+use crate::position::ColumnUnit;
+use serde_json::{json, Value};
+use tree_sitter::{Node, Tree};
+
+// Leaf text longer than this is left out of node descriptions
+const MAX_TEXT_LEN: usize = 80;
+
+// Describes what is under the cursor at byte `offset`: the innermost named node,
+// its ancestors (innermost first, up to the root) and the function or method that
+// encloses it, if any. Positions are 1-based in `unit`.
+pub fn node_at(tree: &Tree, source: &str, offset: usize, unit: ColumnUnit) -> Value {
+    let root = tree.root_node();
+    let node = root
+        .named_descendant_for_byte_range(offset, offset)
+        .unwrap_or(root);
+
+    let mut ancestors = Vec::new();
+    let mut enclosing_function = Value::Null;
+    let mut current = node.parent();
+    while let Some(ancestor) = current {
+        if enclosing_function.is_null() {
+            enclosing_function = describe_function(&ancestor, source, unit);
+        }
+        ancestors.push(describe(&ancestor, source, unit));
+        current = ancestor.parent();
+    }
+
+    json!({
+        "node": describe(&node, source, unit),
+        "ancestors": ancestors,
+        "enclosing_function": enclosing_function,
+    })
+}
+
+fn describe(node: &Node, source: &str, unit: ColumnUnit) -> Value {
+    let (start_line, start_column) = unit.start_of(node, source);
+    let (end_line, end_column) = unit.end_of(node, source);
+    let mut description = json!({
+        "kind": node.kind(),
+        "start": { "line": start_line, "column": start_column },
+        "end": { "line": end_line, "column": end_column },
+    });
+    if let Some(field) = field_name(node) {
+        description["field"] = json!(field);
+    }
+    let text = node.utf8_text(source.as_bytes()).unwrap_or("");
+    if node.child_count() == 0 || text.len() <= MAX_TEXT_LEN && !text.contains('\n') {
+        description["text"] = json!(text);
+    }
+    description
+}
+
+// The field of its parent that `node` fills (`name`, `body`, ...), if any
+fn field_name(node: &Node) -> Option<&'static str> {
+    let parent = node.parent()?;
+    let mut cursor = parent.walk();
+    if !cursor.goto_first_child() {
+        return None;
+    }
+    loop {
+        if cursor.node() == *node {
+            return cursor.field_name();
+        }
+        if !cursor.goto_next_sibling() {
+            return None;
+        }
+    }
+}
+
+fn describe_function(node: &Node, source: &str, unit: ColumnUnit) -> Value {
+    let is_function = matches!(
+        SymbolKind::from_node_kind(node.kind()),
+        Some(SymbolKind::Function | SymbolKind::Method)
+    );
+    if !is_function {
+        return Value::Null;
+    }
+    let name = symbol_name(node)
+        .and_then(|name| name.utf8_text(source.as_bytes()).ok())
+        .unwrap_or("");
+    let mut description = describe(node, source, unit);
+    description["name"] = json!(name);
+    // The body is already described by the ancestor chain
+    if let Some(object) = description.as_object_mut() {
+        object.remove("text");
+    }
+    description
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::parse_tree;
+
+    #[test]
+    fn test_node_at_reports_ancestors_and_function() {
+        let source = "struct P;\nimpl P {\n    fn area(&self) -> u32 { self.w * 2 }\n}\n";
+        let language = tree_sitter_rust::LANGUAGE.into();
+        let tree = parse_tree(source, &language).unwrap();
+        let offset = ColumnUnit::Byte.byte_offset(source, 3, 34).unwrap();
+
+        let result = node_at(&tree, source, offset, ColumnUnit::Byte);
+        assert_eq!(result["node"]["kind"], "field_identifier");
+        assert_eq!(result["node"]["text"], "w");
+        assert_eq!(result["node"]["field"], "field");
+        assert_eq!(result["ancestors"][0]["kind"], "field_expression");
+        assert_eq!(
+            result["ancestors"].as_array().unwrap().last().unwrap()["kind"],
+            "source_file"
+        );
+        assert_eq!(result["enclosing_function"]["name"], "area");
+        assert_eq!(result["enclosing_function"]["start"]["line"], 3);
+    }
+}
@@ -0,0 +1,537 @@
+use crate::ast::{walk, NodeVisitor};
+use crate::position::ColumnUnit;
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use std::io;
+use tree_sitter::{Node, Tree};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolKind {
+    Module,
+    Class,
+    Struct,
+    Enum,
+    Interface,
+    Function,
+    Method,
+    Constant,
+    TypeAlias,
+}
+
+impl SymbolKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SymbolKind::Module => "module",
+            SymbolKind::Class => "class",
+            SymbolKind::Struct => "struct",
+            SymbolKind::Enum => "enum",
+            SymbolKind::Interface => "interface",
+            SymbolKind::Function => "function",
+            SymbolKind::Method => "method",
+            SymbolKind::Constant => "constant",
+            SymbolKind::TypeAlias => "type",
+        }
+    }
+
+    // Node kinds are unique enough across the bundled grammars to share one table
+    pub(crate) fn from_node_kind(kind: &str) -> Option<Self> {
+        match kind {
+            "mod_item" | "namespace_definition" | "internal_module" => Some(SymbolKind::Module),
+            "class_declaration" | "class_specifier" | "abstract_class_declaration" => {
+                Some(SymbolKind::Class)
+            }
+            "struct_item" | "struct_specifier" => Some(SymbolKind::Struct),
+            "enum_item" | "enum_declaration" | "enum_specifier" => Some(SymbolKind::Enum),
+            "trait_item" | "interface_declaration" => Some(SymbolKind::Interface),
+            "function_item"
+            | "function_declaration"
+            | "function_definition"
+            | "generator_function_declaration" => Some(SymbolKind::Function),
+            "method_declaration" | "method_definition" | "constructor_declaration" => {
+                Some(SymbolKind::Method)
+            }
+            "const_item" | "static_item" => Some(SymbolKind::Constant),
+            "type_item" | "type_alias_declaration" | "type_spec" => Some(SymbolKind::TypeAlias),
+            "impl_item" => Some(SymbolKind::Class),
+            _ => None,
+        }
+    }
+}
+
+// A named declaration and the declarations nested inside it. Positions are
+// 1-based, in the requested column unit.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    pub start: (usize, usize),
+    pub end: (usize, usize),
+    // Range of the name itself, for editors that highlight the selection
+    pub name_start: (usize, usize),
+    pub name_end: (usize, usize),
+    pub children: Vec<Symbol>,
+    // Functions and methods only
+    pub signature: Option<Signature>,
+}
+
+// Parameters and return type as written in the source; types are left as source text
+// rather than resolved, and are None where the language leaves them out
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Signature {
+    // Rust `self` and Go method receivers
+    pub receiver: Option<Parameter>,
+    pub parameters: Vec<Parameter>,
+    pub return_type: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Parameter {
+    // None for unnamed C parameters and Go results-style lists
+    pub name: Option<String>,
+    pub type_name: Option<String>,
+}
+
+// Builds the declaration outline (functions, types, methods, modules) of a file
+pub fn extract_symbols(tree: &Tree, source: &str, unit: ColumnUnit) -> Vec<Symbol> {
+    let mut collector = SymbolCollector {
+        source,
+        unit,
+        stack: vec![(usize::MAX, Vec::new())],
+        open: Vec::new(),
+    };
+    // The collector never performs I/O
+    let _ = walk(tree.root_node(), &mut collector);
+    collector
+        .stack
+        .pop()
+        .map(|(_, symbols)| symbols)
+        .unwrap_or_default()
+}
+
+struct SymbolCollector<'a> {
+    source: &'a str,
+    unit: ColumnUnit,
+    // Children gathered so far for each open symbol, tagged with its node id
+    stack: Vec<(usize, Vec<Symbol>)>,
+    open: Vec<Symbol>,
+}
+
+impl NodeVisitor for SymbolCollector<'_> {
+    fn enter(&mut self, node: &Node, _depth: usize, _first_sibling: bool) -> io::Result<()> {
+        let Some(kind) = SymbolKind::from_node_kind(node.kind()) else {
+            return Ok(());
+        };
+        let Some(name_node) = symbol_name(node) else {
+            return Ok(());
+        };
+        let kind = match kind {
+            // Functions nested in classes and impls read better as methods
+            SymbolKind::Function if self.open.last().is_some_and(is_type_like) => {
+                SymbolKind::Method
+            }
+            kind => kind,
+        };
+        self.open.push(Symbol {
+            name: name_node
+                .utf8_text(self.source.as_bytes())
+                .unwrap_or("")
+                .to_string(),
+            kind,
+            start: self.unit.start_of(node, self.source),
+            end: self.unit.end_of(node, self.source),
+            name_start: self.unit.start_of(&name_node, self.source),
+            name_end: self.unit.end_of(&name_node, self.source),
+            children: Vec::new(),
+            signature: matches!(kind, SymbolKind::Function | SymbolKind::Method)
+                .then(|| signature(node, self.source)),
+        });
+        self.stack.push((node.id(), Vec::new()));
+        Ok(())
+    }
+
+    fn leave(&mut self, node: &Node, _depth: usize) -> io::Result<()> {
+        if self.stack.last().is_some_and(|(id, _)| *id == node.id()) {
+            let (_, children) = self.stack.pop().unwrap_or_default();
+            if let Some(mut symbol) = self.open.pop() {
+                symbol.children = children;
+                if let Some((_, siblings)) = self.stack.last_mut() {
+                    siblings.push(symbol);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn is_type_like(symbol: &Symbol) -> bool {
+    matches!(
+        symbol.kind,
+        SymbolKind::Class | SymbolKind::Struct | SymbolKind::Interface
+    )
+}
+
+pub(crate) fn symbol_name<'tree>(node: &Node<'tree>) -> Option<Node<'tree>> {
+    if let Some(name) = node.child_by_field_name("name") {
+        return Some(name);
+    }
+    // Rust impls are named after their type; C/C++ functions after their declarator
+    if node.kind() == "impl_item" {
+        return node.child_by_field_name("type");
+    }
+    let mut declarator = node.child_by_field_name("declarator")?;
+    while let Some(inner) = declarator.child_by_field_name("declarator") {
+        declarator = inner;
+    }
+    Some(declarator)
+}
+
+// Parameter lists live on the function itself, or on the function declarator for C/C++
+fn parameter_list<'tree>(node: &Node<'tree>) -> Option<Node<'tree>> {
+    if let Some(parameters) = node.child_by_field_name("parameters") {
+        return Some(parameters);
+    }
+    let mut cursor = node.walk();
+    let zig = node
+        .named_children(&mut cursor)
+        .find(|n| n.kind() == "parameters");
+    if zig.is_some() {
+        return zig;
+    }
+    let mut declarator = node.child_by_field_name("declarator")?;
+    loop {
+        if let Some(parameters) = declarator.child_by_field_name("parameters") {
+            return Some(parameters);
+        }
+        declarator = declarator.child_by_field_name("declarator")?;
+    }
+}
+
+fn signature(node: &Node, source: &str) -> Signature {
+    let text = |n: Node| n.utf8_text(source.as_bytes()).unwrap_or("").to_string();
+    let mut signature = Signature::default();
+    if let Some(receiver) = node.child_by_field_name("receiver") {
+        let mut cursor = receiver.walk();
+        let first = receiver.named_children(&mut cursor).next();
+        signature.receiver = first.and_then(|p| parameters(&p, source).into_iter().next());
+    }
+    if let Some(list) = parameter_list(node) {
+        let mut cursor = list.walk();
+        for parameter in list.named_children(&mut cursor) {
+            if parameter.kind() == "self_parameter" {
+                // `mut self` only makes the binding mutable; `&mut self` keeps its `mut`
+                let written = text(parameter);
+                let written = written.strip_prefix("mut ").unwrap_or(&written);
+                signature.receiver = Some(Parameter {
+                    name: Some("self".to_string()),
+                    type_name: Some(written.replace("self", "Self")),
+                });
+            } else {
+                signature.parameters.extend(parameters(&parameter, source));
+            }
+        }
+    }
+    signature.return_type = if node.child_by_field_name("declarator").is_some() {
+        // C/C++: the declaration's type, its qualifiers and any pointers around the name
+        node.child_by_field_name("type").map(|ty| {
+            let mut cursor = node.walk();
+            let mut words: Vec<String> = node
+                .named_children(&mut cursor)
+                .filter(|n| n.kind() == "type_qualifier")
+                .map(text)
+                .collect();
+            words.push(text(ty));
+            let (_, suffix) = declarator_name(node.child_by_field_name("declarator"), source);
+            with_suffix(words.join(" "), &suffix)
+        })
+    } else {
+        ["return_type", "result", "type"]
+            .iter()
+            .find_map(|field| node.child_by_field_name(field))
+            .map(|ty| text(ty).trim_start_matches(':').trim().to_string())
+    };
+    signature
+}
+
+// The name a C/C++ declarator declares and the `*`/`&` wrapped around it. A function
+// declarator ends the search, so a function's own parameters are not mistaken for it.
+fn declarator_name(declarator: Option<Node>, source: &str) -> (Option<String>, String) {
+    let mut suffix = String::new();
+    let mut current = declarator;
+    while let Some(node) = current {
+        current = match node.kind() {
+            "pointer_declarator" | "abstract_pointer_declarator" => {
+                suffix.push('*');
+                node.child_by_field_name("declarator")
+            }
+            "reference_declarator" | "abstract_reference_declarator" => {
+                suffix.push_str(if node.child(0).is_some_and(|t| t.kind() == "&&") {
+                    "&&"
+                } else {
+                    "&"
+                });
+                node.named_child(0)
+            }
+            "function_declarator" | "array_declarator" | "parenthesized_declarator" => node
+                .child_by_field_name("declarator")
+                .or_else(|| node.named_child(0)),
+            _ => {
+                let name = node.utf8_text(source.as_bytes()).ok().map(str::to_string);
+                return (name, suffix);
+            }
+        };
+    }
+    (None, suffix)
+}
+
+fn with_suffix(type_name: String, suffix: &str) -> String {
+    if suffix.is_empty() {
+        type_name
+    } else {
+        format!("{} {}", type_name, suffix)
+    }
+}
+
+// One entry per declared name; Go lists several names against one type
+fn parameters(node: &Node, source: &str) -> Vec<Parameter> {
+    let text = |n: Node| n.utf8_text(source.as_bytes()).unwrap_or("").to_string();
+    let type_text = |n: Node| text(n).trim_start_matches(':').trim().to_string();
+    match node.kind() {
+        "comment" | "line_comment" | "block_comment" => Vec::new(),
+        // JavaScript parameters are bare patterns
+        "identifier" | "object_pattern" | "array_pattern" | "rest_pattern" => vec![Parameter {
+            name: Some(text(*node)),
+            type_name: None,
+        }],
+        "assignment_pattern" => vec![Parameter {
+            name: node.child_by_field_name("left").map(text),
+            type_name: None,
+        }],
+        "spread_parameter" => {
+            let mut cursor = node.walk();
+            let children: Vec<Node> = node.named_children(&mut cursor).collect();
+            let name = children
+                .iter()
+                .find(|n| n.kind() == "variable_declarator")
+                .and_then(|n| n.child_by_field_name("name"))
+                .map(text);
+            let type_name = children
+                .iter()
+                .find(|n| n.kind() != "modifiers" && n.kind() != "variable_declarator")
+                .map(|n| format!("{}...", text(*n)));
+            vec![Parameter { name, type_name }]
+        }
+        // C/C++: pointers and references belong to the declarator, not the type
+        _ if node.child_by_field_name("declarator").is_some() => {
+            let (name, suffix) = declarator_name(node.child_by_field_name("declarator"), source);
+            let type_name = node
+                .child_by_field_name("type")
+                .map(|ty| with_suffix(text(ty), &suffix));
+            vec![Parameter { name, type_name }]
+        }
+        // `f(void)` takes no parameters
+        "parameter_declaration"
+            if node.child_by_field_name("type").map(text).as_deref() == Some("void") =>
+        {
+            Vec::new()
+        }
+        _ => {
+            let type_name = node.child_by_field_name("type").map(type_text);
+            let type_name = match node.kind() {
+                "variadic_parameter_declaration" => type_name.map(|t| format!("...{}", t)),
+                _ => type_name,
+            };
+            let mut cursor = node.walk();
+            let names: Vec<String> = node
+                .children_by_field_name("name", &mut cursor)
+                .chain(node.child_by_field_name("pattern"))
+                .map(text)
+                .collect();
+            if names.is_empty() {
+                return vec![Parameter {
+                    name: None,
+                    type_name,
+                }];
+            }
+            names
+                .into_iter()
+                .map(|name| Parameter {
+                    name: Some(name),
+                    type_name: type_name.clone(),
+                })
+                .collect()
+        }
+    }
+}
+
+impl Serialize for Symbol {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Symbol", 6)?;
+        state.serialize_field("children", &self.children)?;
+        state.serialize_field(
+            "end",
+            &Position {
+                line: self.end.0,
+                column: self.end.1,
+            },
+        )?;
+        state.serialize_field("kind", self.kind.as_str())?;
+        state.serialize_field("name", &self.name)?;
+        if let Some(signature) = &self.signature {
+            state.serialize_field("signature", signature)?;
+        }
+        state.serialize_field(
+            "start",
+            &Position {
+                line: self.start.0,
+                column: self.start.1,
+            },
+        )?;
+        state.end()
+    }
+}
+
+impl Serialize for Signature {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Signature", 3)?;
+        state.serialize_field("parameters", &self.parameters)?;
+        state.serialize_field("receiver", &self.receiver)?;
+        state.serialize_field("return_type", &self.return_type)?;
+        state.end()
+    }
+}
+
+impl Serialize for Parameter {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Parameter", 2)?;
+        state.serialize_field("name", &self.name)?;
+        state.serialize_field("type", &self.type_name)?;
+        state.end()
+    }
+}
+
+struct Position {
+    line: usize,
+    column: usize,
+}
+
+impl Serialize for Position {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Position", 2)?;
+        state.serialize_field("column", &self.column)?;
+        state.serialize_field("line", &self.line)?;
+        state.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::parse_tree;
+    use crate::language::SupportedLanguage;
+    use serde_json::{json, Value};
+    use std::path::Path;
+
+    #[test]
+    fn test_nested_rust_outline() {
+        let source = "struct Point { x: i32 }\n\nimpl Point {\n    fn norm(&self) -> i32 { 0 }\n}\n\nfn main() {}\n";
+        let tree = parse_tree(source, &tree_sitter_rust::LANGUAGE.into()).unwrap();
+        let symbols = extract_symbols(&tree, source, ColumnUnit::Byte);
+
+        let names: Vec<(&str, SymbolKind)> =
+            symbols.iter().map(|s| (s.name.as_str(), s.kind)).collect();
+        assert_eq!(
+            names,
+            vec![
+                ("Point", SymbolKind::Struct),
+                ("Point", SymbolKind::Class),
+                ("main", SymbolKind::Function),
+            ]
+        );
+        assert_eq!(symbols[1].children[0].name, "norm");
+        assert_eq!(symbols[1].children[0].kind, SymbolKind::Method);
+        assert_eq!(symbols[1].children[0].start, (4, 5));
+    }
+
+    #[test]
+    fn test_c_function_named_after_declarator() {
+        let source = "static int *make(void) { return 0; }\n";
+        let tree = parse_tree(source, &tree_sitter_c::LANGUAGE.into()).unwrap();
+        let symbols = extract_symbols(&tree, source, ColumnUnit::Byte);
+        assert_eq!(symbols.len(), 1);
+        assert_eq!(symbols[0].name, "make");
+    }
+
+    #[test]
+    fn test_function_signatures() {
+        let signature_of = |source: &str, path: &str| -> Value {
+            let lang = SupportedLanguage::detect(Path::new(path)).unwrap();
+            let tree = parse_tree(source, &lang.tree_sitter_language()).unwrap();
+            let mut symbols = extract_symbols(&tree, source, ColumnUnit::Byte);
+            while symbols[0].signature.is_none() {
+                symbols = std::mem::take(&mut symbols[0].children);
+            }
+            serde_json::to_value(&symbols[0].signature).unwrap()
+        };
+        let param = |name: &str, ty: &str| json!({ "name": name, "type": ty });
+
+        let rust = signature_of(
+            "impl A { fn f<T>(&mut self, (a, b): (i32, i32), c: &str) -> Option<T> { None } }",
+            "a.rs",
+        );
+        assert_eq!(rust["receiver"], param("self", "&mut Self"));
+        assert_eq!(rust["parameters"][0], param("(a, b)", "(i32, i32)"));
+        assert_eq!(rust["return_type"], "Option<T>");
+
+        let go = signature_of(
+            "package m\nfunc (s *S) f(a, b int, c ...string) (int, error) { return 0, nil }\n",
+            "a.go",
+        );
+        assert_eq!(go["receiver"], param("s", "*S"));
+        assert_eq!(
+            go["parameters"],
+            json!([
+                param("a", "int"),
+                param("b", "int"),
+                param("c", "...string")
+            ])
+        );
+        assert_eq!(go["return_type"], "(int, error)");
+
+        let c = signature_of(
+            "const char *f(int a, char **argv, void (*cb)(int)) { return 0; }",
+            "a.c",
+        );
+        assert_eq!(
+            c["parameters"],
+            json!([
+                param("a", "int"),
+                param("argv", "char **"),
+                param("cb", "void *")
+            ])
+        );
+        assert_eq!(c["return_type"], "const char *");
+
+        let ts = signature_of(
+            "function f(a: number, ...rest: T[]): Promise<void> {}",
+            "a.ts",
+        );
+        assert_eq!(
+            ts["parameters"],
+            json!([param("a", "number"), param("...rest", "T[]")])
+        );
+        assert_eq!(ts["return_type"], "Promise<void>");
+
+        let java = signature_of(
+            "class A { int m(final String s, int... xs) { return 0; } }",
+            "A.java",
+        );
+        assert_eq!(
+            java["parameters"],
+            json!([param("s", "String"), param("xs", "int...")])
+        );
+        assert_eq!(java["return_type"], "int");
+        assert_eq!(
+            signature_of("int main(void) { return 0; }", "a.c")["parameters"],
+            json!([])
+        );
+    }
+}
@@ -0,0 +1,134 @@
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+
+// Keeps the first `max` findings of each rule in every file of an `analyze` report
+// (single file or directory) and replaces the rest of each rule's findings with one
+// summary entry, placed at the first finding left out:
+// {"rule", "severity", "line", "column", "message": "+4,312 more `rule` findings",
+//  "overflow": 4312}. Scores and totals are left as they were.
+pub fn cap_per_rule(report: &mut Value, max: usize) {
+    match report["files"].as_array_mut() {
+        Some(files) => {
+            for file in files {
+                cap_file(&mut file["result"], max);
+            }
+        }
+        None => cap_file(report, max),
+    }
+}
+
+fn cap_file(result: &mut Value, max: usize) {
+    let Some(issues) = result.get_mut("issues").and_then(Value::as_array_mut) else {
+        return;
+    };
+    let mut seen: BTreeMap<String, usize> = BTreeMap::new();
+    // rule -> index of its overflow entry in `kept`
+    let mut overflow: BTreeMap<String, usize> = BTreeMap::new();
+    let mut kept = Vec::with_capacity(issues.len());
+    for issue in issues.drain(..) {
+        let rule = issue["rule"].as_str().unwrap_or("").to_string();
+        let count = seen.entry(rule.clone()).or_default();
+        *count += 1;
+        if *count <= max {
+            kept.push(issue);
+            continue;
+        }
+        match overflow.get(&rule) {
+            Some(&index) => {
+                let entry: &mut Value = &mut kept[index];
+                entry["overflow"] = json!(entry["overflow"].as_u64().unwrap_or(0) + 1);
+            }
+            None => {
+                overflow.insert(rule.clone(), kept.len());
+                kept.push(json!({
+                    "rule": rule,
+                    "severity": issue["severity"],
+                    "line": issue["line"],
+                    "column": issue["column"],
+                    "overflow": 1,
+                }));
+            }
+        }
+    }
+    for (rule, index) in overflow {
+        let entry = &mut kept[index];
+        let more = entry["overflow"].as_u64().unwrap_or(0);
+        let plural = if more == 1 { "finding" } else { "findings" };
+        entry["message"] = json!(format!("+{} more `{}` {}", thousands(more), rule, plural));
+    }
+    *issues = kept;
+}
+
+// 4312 -> "4,312"
+pub fn thousands(n: u64) -> String {
+    let digits = n.to_string();
+    let mut out = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(c);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::summary::{findings, format_text, GroupBy};
+
+    fn issue(rule: &str, line: u64) -> Value {
+        json!({ "rule": rule, "severity": "Style", "line": line, "column": 1 })
+    }
+
+    #[test]
+    fn test_findings_beyond_the_cap_summarized_per_rule() {
+        let mut issues: Vec<Value> = (1..=4_315)
+            .map(|line| issue("go_magic_number", line))
+            .collect();
+        issues.insert(1, issue("go_panic_usage", 2));
+        let mut report = json!({
+            "files": [{"path": "a.go", "result": {"issues": issues, "total_issues": 4_316}}]
+        });
+        cap_per_rule(&mut report, 3);
+
+        let result = &report["files"][0]["result"];
+        let rules: Vec<&str> = result["issues"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|i| i["rule"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            rules,
+            [
+                "go_magic_number",
+                "go_panic_usage",
+                "go_magic_number",
+                "go_magic_number",
+                "go_magic_number"
+            ]
+        );
+        let overflow = &result["issues"][4];
+        assert_eq!(overflow["overflow"], 4_312);
+        assert_eq!(
+            overflow["message"],
+            "+4,312 more `go_magic_number` findings"
+        );
+        assert_eq!(overflow["line"], 4);
+        assert_eq!(result["total_issues"], 4_316);
+        let text = format_text(&findings(&report, ""), GroupBy::File);
+        assert!(text.starts_with("a.go (4316)\n"));
+        assert!(text.contains("  4:1  Style  go_magic_number  +4,312 more"));
+        assert!(text.ends_with("4316 issues in 1 file\n"));
+
+        // A single-file report, one finding over the cap
+        let mut single = json!({"issues": [issue("unwrap_usage", 1), issue("unwrap_usage", 2)]});
+        cap_per_rule(&mut single, 1);
+        assert_eq!(
+            single["issues"][1]["message"],
+            "+1 more `unwrap_usage` finding"
+        );
+        assert_eq!(thousands(1_234_567), "1,234,567");
+    }
+}
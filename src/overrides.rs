@@ -0,0 +1,152 @@
+use regex::Regex;
+use serde_json::Value;
+use std::path::Path;
+
+// Analyzers are keyed by the set of overrides a file matches, one bit each
+pub const MAX_OVERRIDES: usize = 64;
+
+// `rules` settings for the files whose path matches `pattern`, a glob where `*` and
+// `?` stay within one directory and `**` crosses them
+#[derive(Debug, Clone)]
+pub struct PathOverride {
+    pub pattern: String,
+    pub rules: Value,
+    matcher: Regex,
+}
+
+impl PartialEq for PathOverride {
+    fn eq(&self, other: &Self) -> bool {
+        self.pattern == other.pattern && self.rules == other.rules
+    }
+}
+
+impl PathOverride {
+    pub fn new(pattern: &str, rules: Value) -> Result<Self, String> {
+        if !rules.is_object() {
+            return Err(format!(
+                "override '{}' must be an object of rule settings",
+                pattern
+            ));
+        }
+        let matcher = Regex::new(&glob_to_regex(pattern.trim_start_matches("./")))
+            .map_err(|e| format!("override '{}': {}", pattern, e))?;
+        Ok(PathOverride {
+            pattern: pattern.to_string(),
+            rules,
+            matcher,
+        })
+    }
+
+    // {"src/experimental/**": {"min_severity": "error"}}. JSON objects come back sorted,
+    // so overlapping overrides apply in pattern order: `src/**` before `src/experimental/**`.
+    pub fn list_from_json(value: &Value) -> Result<Vec<Self>, String> {
+        let patterns = value
+            .as_object()
+            .ok_or("\"overrides\" must map path patterns to rule settings")?;
+        if patterns.len() > MAX_OVERRIDES {
+            return Err(format!("at most {} overrides are supported", MAX_OVERRIDES));
+        }
+        patterns
+            .iter()
+            .map(|(pattern, rules)| PathOverride::new(pattern, rules.clone()))
+            .collect()
+    }
+
+    // Patterns are relative, so they are matched against every tail of the path that
+    // starts at a directory boundary: absolute and `./` paths match alike
+    pub fn matches(&self, path: &Path) -> bool {
+        let path = path.to_string_lossy().replace('\\', "/");
+        let path = path.trim_start_matches("./");
+        std::iter::once(path)
+            .chain(path.match_indices('/').map(|(i, _)| &path[i + 1..]))
+            .any(|tail| self.matcher.is_match(tail))
+    }
+}
+
+fn glob_to_regex(glob: &str) -> String {
+    let mut regex = String::from("^");
+    let mut chars = glob.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                // `**/` also matches no directory at all
+                if chars.next_if_eq(&'/').is_some() {
+                    regex.push_str("(?:.*/)?");
+                } else {
+                    regex.push_str(".*");
+                }
+            }
+            '*' => regex.push_str("[^/]*"),
+            '?' => regex.push_str("[^/]"),
+            c => regex.push_str(&regex::escape(c.encode_utf8(&mut [0; 4]))),
+        }
+    }
+    regex.push('$');
+    regex
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::RuleConfig;
+    use crate::language::SupportedLanguage;
+    use serde_json::json;
+
+    #[test]
+    fn test_overrides_apply_to_matching_paths() {
+        let overrides = PathOverride::list_from_json(&json!({
+            "src/experimental/**": {"min_severity": "error"},
+            "**/*_test.go": {"max_boolean_operators": 0},
+            "src/**": {"min_severity": "info"},
+        }))
+        .unwrap();
+        let matching = |path: &str| {
+            overrides
+                .iter()
+                .filter(|o| o.matches(Path::new(path)))
+                .map(|o| o.pattern.as_str())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            matching("./src/experimental/a/b.rs"),
+            ["src/**", "src/experimental/**"]
+        );
+        assert_eq!(matching("/repo/src/lib.rs"), ["src/**"]);
+        assert_eq!(matching("pkg/x_test.go"), ["**/*_test.go"]);
+        assert!(matching("lib/srcx/a.rs").is_empty());
+
+        let rules = RuleConfig {
+            overrides,
+            ..RuleConfig::default()
+        };
+        let experimental = Path::new("src/experimental/a.rs");
+        assert_eq!(
+            rules.for_path(experimental).min_severity,
+            Some(crate::analyzer::Severity::Error)
+        );
+        assert_eq!(
+            rules
+                .for_path(Path::new("x/a_test.go"))
+                .max_boolean_operators,
+            None
+        );
+        assert_eq!(rules.for_path(Path::new("main.rs")), rules);
+
+        // Only errors survive in the experimental tree
+        let source = "fn f() { x.unwrap(); }\n";
+        let analyze = |path: &str| {
+            SupportedLanguage::Rust
+                .analyzer_for_path(Path::new(path), &rules)
+                .unwrap()
+                .analyze(source, &SupportedLanguage::Rust.tree_sitter_language())
+                .unwrap()
+                .into_iter()
+                .map(|r| r.rule_name)
+                .collect::<Vec<_>>()
+        };
+        assert!(analyze("tools/a.rs").contains(&"unwrap_usage".to_string()));
+        assert!(analyze("src/experimental/a.rs").is_empty());
+        assert!(PathOverride::new("src/**", json!(3)).is_err());
+    }
+}
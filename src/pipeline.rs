@@ -0,0 +1,109 @@
+use crate::analyzer::CodeAnalyzer;
+use crate::config::ProjectConfig;
+use crate::daemon::{Daemon, INVALID_PARAMS};
+use serde_json::{json, Map, Value};
+use std::io::{self, BufRead, BufWriter, Write};
+
+// Newline-delimited JSON for build systems and data pipelines: each input line is
+// a request such as `{"op": "analyze", "path": "src/main.rs"}`, where `op` is any
+// `serve --stdio` method and the remaining keys are its params. Each request gets
+// exactly one output line, in order, echoing `op` and any `id` or `path`:
+// `{"ok": true, "op": ..., "result": ...}` or `{"ok": false, "op": ..., "error": ...}`.
+pub fn run_stdio(config: ProjectConfig) -> io::Result<()> {
+    CodeAnalyzer::warm_up();
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    serve(
+        config,
+        &mut stdin.lock(),
+        &mut BufWriter::new(stdout.lock()),
+    )
+}
+
+pub fn serve<R: BufRead, W: Write>(
+    config: ProjectConfig,
+    input: &mut R,
+    output: &mut W,
+) -> io::Result<()> {
+    let mut daemon = Daemon::new(config);
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if input.read_line(&mut line)? == 0 {
+            return output.flush();
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let reply = match serde_json::from_str::<Value>(&line) {
+            Ok(Value::Object(request)) => handle(&mut daemon, request),
+            Ok(_) => json!({ "ok": false, "error": "Request must be a JSON object" }),
+            Err(e) => json!({ "ok": false, "error": format!("Invalid JSON: {}", e) }),
+        };
+        writeln!(output, "{}", reply)?;
+        // Downstream stages see each result as soon as it is ready
+        output.flush()?;
+    }
+}
+
+fn handle(daemon: &mut Daemon, mut request: Map<String, Value>) -> Value {
+    let op = request.remove("op").unwrap_or(Value::Null);
+    let mut reply = Map::new();
+    for key in ["id", "path"] {
+        if let Some(value) = request.get(key) {
+            reply.insert(key.to_string(), value.clone());
+        }
+    }
+
+    let outcome = match op.as_str() {
+        Some(method) => daemon.handle(method, &Value::Object(request)),
+        None => Err((INVALID_PARAMS, "Missing 'op'".to_string())),
+    };
+    match outcome {
+        Ok(result) => {
+            reply.insert("ok".to_string(), Value::Bool(true));
+            reply.insert("result".to_string(), result);
+        }
+        Err((_, error)) => {
+            reply.insert("ok".to_string(), Value::Bool(false));
+            reply.insert("error".to_string(), Value::String(error));
+        }
+    }
+    reply.insert("op".to_string(), op);
+    Value::Object(reply)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_one_result_line_per_request() {
+        let input = [
+            r#"{"op": "analyze", "source": "fn main() { x.unwrap(); }", "language": "rust", "id": 7}"#,
+            "",
+            "not json",
+            r#"{"op": "analyze", "path": "missing.rs"}"#,
+            r#"{"op": "languages"}"#,
+        ]
+        .join("\n");
+
+        let mut output = Vec::new();
+        serve(ProjectConfig::default(), &mut input.as_bytes(), &mut output).unwrap();
+        let replies: Vec<Value> = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+
+        assert_eq!(replies.len(), 4);
+        assert_eq!(replies[0]["id"], 7);
+        assert_eq!(replies[0]["ok"], true);
+        assert_eq!(replies[0]["result"]["issues"][0]["rule"], "unwrap_usage");
+        assert_eq!(replies[1]["ok"], false);
+        assert_eq!(replies[2]["path"], "missing.rs");
+        assert_eq!(replies[2]["ok"], false);
+        assert_eq!(replies[3]["op"], "languages");
+    }
+}
@@ -0,0 +1,283 @@
+use crate::analyzer::{AnalysisRule, Severity};
+use crate::language::SupportedLanguage;
+use std::collections::HashMap;
+use std::ffi::{c_char, c_int, c_void, CStr};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use tree_sitter::{Node, Query};
+
+// Bumped whenever `TreescanRule` or `TreescanRegistry` change layout
+pub const PLUGIN_ABI_VERSION: u32 = 1;
+
+// Every plugin exports `int treescan_plugin_register(const TreescanRegistry *)`,
+// returning 0 on success
+pub const PLUGIN_REGISTER_SYMBOL: &str = "treescan_plugin_register";
+
+// Decides whether a capture is reported: called with the whole source and the
+// capture's byte range, returns nonzero to report it
+pub type RuleCheck = unsafe extern "C" fn(
+    source: *const u8,
+    source_len: usize,
+    start_byte: usize,
+    end_byte: usize,
+) -> c_int;
+
+// A rule as a plugin describes it. Strings are NUL-terminated UTF-8 and only need
+// to live for the duration of the `add_rule` call; `suggestion`, `capture` and
+// `check` may be null, and a `weight` of 0 means the default of 1.
+#[repr(C)]
+pub struct TreescanRule {
+    // A bundled language name: "rust", "go", "javascript", "tsx", ...
+    pub language: *const c_char,
+    pub name: *const c_char,
+    pub query: *const c_char,
+    // "error", "warning", "info" or "style"
+    pub severity: *const c_char,
+    pub message: *const c_char,
+    pub suggestion: *const c_char,
+    // Report only this capture of the query
+    pub capture: *const c_char,
+    pub weight: f64,
+    pub check: Option<RuleCheck>,
+}
+
+// Handed to `treescan_plugin_register`; plugins call `add_rule(context, &rule)` for
+// each rule, which returns 0 when the rule was accepted
+#[repr(C)]
+pub struct TreescanRegistry {
+    pub abi_version: u32,
+    pub context: *mut c_void,
+    pub add_rule: unsafe extern "C" fn(context: *mut c_void, rule: *const TreescanRule) -> c_int,
+}
+
+pub type RegisterFn = unsafe extern "C" fn(registry: *const TreescanRegistry) -> c_int;
+
+// A rule contributed by a plugin for one language
+#[derive(Debug, Clone)]
+pub struct PluginRule {
+    pub language: SupportedLanguage,
+    pub rule: AnalysisRule,
+}
+
+struct Registration {
+    rules: Vec<PluginRule>,
+    errors: Vec<String>,
+}
+
+unsafe extern "C" fn add_rule(context: *mut c_void, rule: *const TreescanRule) -> c_int {
+    let registration = &mut *(context as *mut Registration);
+    if rule.is_null() {
+        registration.errors.push("null rule".to_string());
+        return 1;
+    }
+    match plugin_rule(&*rule) {
+        Ok(rule) => {
+            registration.rules.push(rule);
+            0
+        }
+        Err(e) => {
+            registration.errors.push(e);
+            1
+        }
+    }
+}
+
+unsafe fn optional_str(s: *const c_char) -> Result<Option<String>, String> {
+    if s.is_null() {
+        return Ok(None);
+    }
+    CStr::from_ptr(s)
+        .to_str()
+        .map(|s| Some(s.to_string()))
+        .map_err(|_| "string is not valid UTF-8".to_string())
+}
+
+unsafe fn required_str(s: *const c_char, field: &str) -> Result<String, String> {
+    optional_str(s)?.ok_or_else(|| format!("missing {}", field))
+}
+
+unsafe fn plugin_rule(rule: &TreescanRule) -> Result<PluginRule, String> {
+    let name = required_str(rule.name, "name")?;
+    let describe = |e: String| format!("rule '{}': {}", name, e);
+    let language_name = required_str(rule.language, "language").map_err(describe)?;
+    let language = SupportedLanguage::from_name(&language_name)
+        .ok_or_else(|| describe(format!("unknown language '{}'", language_name)))?;
+    let severity_name = required_str(rule.severity, "severity").map_err(describe)?;
+    let severity = Severity::from_name(&severity_name)
+        .ok_or_else(|| describe(format!("unknown severity '{}'", severity_name)))?;
+    let query = required_str(rule.query, "query").map_err(describe)?;
+    // Bad queries are rejected here rather than failing every file later
+    let compiled = Query::new(&language.tree_sitter_language(), &query)
+        .map_err(|e| describe(format!("invalid query: {}", e)))?;
+    let capture = optional_str(rule.capture).map_err(describe)?;
+    if let Some(capture) = &capture {
+        if compiled.capture_index_for_name(capture).is_none() {
+            return Err(describe(format!("query has no capture @{}", capture)));
+        }
+    }
+
+    let mut analysis_rule = AnalysisRule::new(
+        name.clone(),
+        query,
+        severity,
+        required_str(rule.message, "message").map_err(describe)?,
+        optional_str(rule.suggestion).map_err(describe)?,
+    );
+    if rule.weight != 0.0 {
+        analysis_rule = analysis_rule.with_weight(rule.weight);
+    }
+    if let Some(capture) = &capture {
+        analysis_rule = analysis_rule.with_capture(capture);
+    }
+    analysis_rule.check = rule.check;
+    Ok(PluginRule {
+        language,
+        rule: analysis_rule,
+    })
+}
+
+// Runs a plugin's register function and collects the rules it adds. Any rejected
+// rule fails the whole plugin so a broken rule pack is never half-loaded.
+pub fn register_rules(register: RegisterFn) -> Result<Vec<PluginRule>, String> {
+    let mut registration = Registration {
+        rules: Vec::new(),
+        errors: Vec::new(),
+    };
+    let registry = TreescanRegistry {
+        abi_version: PLUGIN_ABI_VERSION,
+        context: &mut registration as *mut Registration as *mut c_void,
+        add_rule,
+    };
+    let status = unsafe { register(&registry) };
+    if let Some(error) = registration.errors.first() {
+        return Err(error.clone());
+    }
+    if status != 0 {
+        return Err(format!("registration returned {}", status));
+    }
+    Ok(registration.rules)
+}
+
+// Whether a plugin check accepts `node`; a panic-free plugin is trusted to stay
+// within the source it is given
+pub fn run_check(check: RuleCheck, node: &Node, source_code: &str) -> bool {
+    unsafe {
+        check(
+            source_code.as_ptr(),
+            source_code.len(),
+            node.start_byte(),
+            node.end_byte(),
+        ) != 0
+    }
+}
+
+type LoadedPlugins = Mutex<HashMap<PathBuf, Arc<Vec<PluginRule>>>>;
+
+// Loads the plugin at `path` once per process; later calls reuse its rules
+pub fn load_plugin(path: &Path) -> Result<Arc<Vec<PluginRule>>, Box<dyn std::error::Error>> {
+    static LOADED: OnceLock<LoadedPlugins> = OnceLock::new();
+    let loaded = LOADED.get_or_init(Default::default);
+    let key = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    if let Some(rules) = loaded.lock().unwrap().get(&key) {
+        return Ok(rules.clone());
+    }
+    let rules = Arc::new(
+        open_plugin(path)?
+            .map_err(|e| format!("Plugin '{}' failed to register: {}", path.display(), e))?,
+    );
+    loaded.lock().unwrap().insert(key, rules.clone());
+    Ok(rules)
+}
+
+#[cfg(unix)]
+fn open_plugin(path: &Path) -> Result<Result<Vec<PluginRule>, String>, Box<dyn std::error::Error>> {
+    let function = crate::grammar::library_symbol(path, PLUGIN_REGISTER_SYMBOL, "plugin")?;
+    let register: RegisterFn = unsafe { std::mem::transmute(function) };
+    Ok(register_rules(register))
+}
+
+#[cfg(not(unix))]
+fn open_plugin(path: &Path) -> Result<Result<Vec<PluginRule>, String>, Box<dyn std::error::Error>> {
+    Err(format!(
+        "Cannot load plugin '{}': plugins are only supported on Unix",
+        path.display()
+    )
+    .into())
+}
+
+// Loads every plugin up front so a missing or broken one fails before any analysis
+pub fn validate_plugins(paths: &[PathBuf]) -> Result<(), Box<dyn std::error::Error>> {
+    for path in paths {
+        load_plugin(path)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::CodeAnalyzer;
+
+    unsafe extern "C" fn long_names_only(
+        source: *const u8,
+        len: usize,
+        start: usize,
+        end: usize,
+    ) -> c_int {
+        let source = std::slice::from_raw_parts(source, len);
+        (source[start..end].len() > 3) as c_int
+    }
+
+    unsafe extern "C" fn register(registry: *const TreescanRegistry) -> c_int {
+        let registry = &*registry;
+        assert_eq!(registry.abi_version, PLUGIN_ABI_VERSION);
+        let rule = TreescanRule {
+            language: c"rust".as_ptr(),
+            name: c"long_let_name".as_ptr(),
+            query: c"(let_declaration pattern: (identifier) @name)".as_ptr(),
+            severity: c"style".as_ptr(),
+            message: c"Long binding name".as_ptr(),
+            suggestion: std::ptr::null(),
+            capture: std::ptr::null(),
+            weight: 0.0,
+            check: Some(long_names_only),
+        };
+        (registry.add_rule)(registry.context, &rule)
+    }
+
+    unsafe extern "C" fn register_bad(registry: *const TreescanRegistry) -> c_int {
+        let registry = &*registry;
+        let rule = TreescanRule {
+            language: c"rust".as_ptr(),
+            name: c"broken".as_ptr(),
+            query: c"(not_a_node)".as_ptr(),
+            severity: c"error".as_ptr(),
+            message: c"never".as_ptr(),
+            suggestion: std::ptr::null(),
+            capture: std::ptr::null(),
+            weight: 0.0,
+            check: None,
+        };
+        (registry.add_rule)(registry.context, &rule)
+    }
+
+    #[test]
+    fn test_plugin_rules_register_and_run_checks() {
+        let rules = register_rules(register).unwrap();
+        assert_eq!(rules.len(), 1);
+        assert_eq!(rules[0].language, SupportedLanguage::Rust);
+
+        let mut analyzer = CodeAnalyzer::new();
+        analyzer.add_rule(rules[0].rule.clone());
+        let source = "fn main() { let a = 1; let total = 2; }";
+        let results = analyzer
+            .analyze(source, &SupportedLanguage::Rust.tree_sitter_language())
+            .unwrap();
+        let texts: Vec<&str> = results.iter().map(|r| r.text.as_str()).collect();
+        assert_eq!(texts, ["total"]);
+
+        let error = register_rules(register_bad).unwrap_err();
+        assert!(error.starts_with("rule 'broken': invalid query"));
+        assert!(load_plugin(Path::new("/nonexistent/libplugin.so")).is_err());
+    }
+}
@@ -0,0 +1,104 @@
+use tree_sitter::Node;
+
+// Unit in which reported columns are counted. tree-sitter works in bytes, editors
+// usually want characters (Unicode scalar values) or UTF-16 code units (LSP).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColumnUnit {
+    #[default]
+    Byte,
+    Char,
+    Utf16,
+}
+
+impl ColumnUnit {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "byte" => Some(ColumnUnit::Byte),
+            "char" => Some(ColumnUnit::Char),
+            "utf16" | "utf-16" => Some(ColumnUnit::Utf16),
+            _ => None,
+        }
+    }
+
+    // Converts the 0-based byte `column` of the position at `byte_offset` into this unit
+    pub fn convert(&self, source: &str, byte_offset: usize, column: usize) -> usize {
+        if *self == ColumnUnit::Byte {
+            return column;
+        }
+        let line_start = byte_offset.saturating_sub(column);
+        let prefix = match source.get(line_start..byte_offset) {
+            Some(prefix) => prefix,
+            // Offsets inside a multi-byte character; fall back to the raw column
+            None => return column,
+        };
+        match self {
+            ColumnUnit::Byte => column,
+            ColumnUnit::Char => prefix.chars().count(),
+            ColumnUnit::Utf16 => prefix.encode_utf16().count(),
+        }
+    }
+
+    // Byte offset of the 1-based (line, column) position given in this unit; None
+    // when the line doesn't exist or the column is past its end
+    pub fn byte_offset(&self, source: &str, line: usize, column: usize) -> Option<usize> {
+        let line_start = match line.checked_sub(1)? {
+            0 => 0,
+            n => source.match_indices('\n').nth(n - 1)?.0 + 1,
+        };
+        let text = source[line_start..].split('\n').next().unwrap_or("");
+        let target = column.checked_sub(1)?;
+        let in_line = match self {
+            ColumnUnit::Byte => Some(target).filter(|&t| text.is_char_boundary(t)),
+            ColumnUnit::Char => text.char_indices().map(|(i, _)| i).chain([text.len()]).nth(target),
+            ColumnUnit::Utf16 => {
+                let mut units = 0;
+                text.char_indices()
+                    .map(|(i, c)| {
+                        let at = (i, units);
+                        units += c.len_utf16();
+                        at
+                    })
+                    .chain([(text.len(), text.encode_utf16().count())])
+                    .find(|&(_, at)| at == target)
+                    .map(|(i, _)| i)
+            }
+        }?;
+        Some(line_start + in_line)
+    }
+
+    // 1-based (line, column) of the start of `node`
+    pub fn start_of(&self, node: &Node, source: &str) -> (usize, usize) {
+        let point = node.start_position();
+        let column = self.convert(source, node.start_byte(), point.column);
+        (point.row + 1, column + 1)
+    }
+
+    // 1-based (line, column) of the end of `node`
+    pub fn end_of(&self, node: &Node, source: &str) -> (usize, usize) {
+        let point = node.end_position();
+        let column = self.convert(source, node.end_byte(), point.column);
+        (point.row + 1, column + 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_column_units() {
+        // "日本" is 6 bytes, 2 chars, 2 UTF-16 units; the emoji is 4 bytes, 1 char, 2 units
+        let source = "a\r\nlet 日本🎉 = x;";
+        let offset = source.find('=').unwrap();
+        let byte_column = offset - 3;
+
+        assert_eq!(ColumnUnit::Byte.convert(source, offset, byte_column), 15);
+        assert_eq!(ColumnUnit::Char.convert(source, offset, byte_column), 8);
+        assert_eq!(ColumnUnit::Utf16.convert(source, offset, byte_column), 9);
+
+        assert_eq!(ColumnUnit::Byte.byte_offset(source, 2, 16), Some(offset));
+        assert_eq!(ColumnUnit::Char.byte_offset(source, 2, 9), Some(offset));
+        assert_eq!(ColumnUnit::Utf16.byte_offset(source, 2, 10), Some(offset));
+        assert_eq!(ColumnUnit::Char.byte_offset(source, 3, 1), None);
+    }
+}
@@ -0,0 +1,147 @@
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+// Builder for the Prometheus text exposition format (version 0.0.4)
+#[derive(Debug, Default)]
+pub struct Exposition {
+    text: String,
+}
+
+impl Exposition {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn gauge<'a>(
+        &mut self,
+        name: &str,
+        help: &str,
+        samples: impl IntoIterator<Item = (Vec<(&'a str, String)>, f64)>,
+    ) -> &mut Self {
+        self.metric(name, help, "gauge", samples)
+    }
+
+    pub fn counter<'a>(
+        &mut self,
+        name: &str,
+        help: &str,
+        samples: impl IntoIterator<Item = (Vec<(&'a str, String)>, f64)>,
+    ) -> &mut Self {
+        self.metric(name, help, "counter", samples)
+    }
+
+    fn metric<'a>(
+        &mut self,
+        name: &str,
+        help: &str,
+        kind: &str,
+        samples: impl IntoIterator<Item = (Vec<(&'a str, String)>, f64)>,
+    ) -> &mut Self {
+        let _ = writeln!(self.text, "# HELP {} {}", name, help);
+        let _ = writeln!(self.text, "# TYPE {} {}", name, kind);
+        for (labels, value) in samples {
+            self.text.push_str(name);
+            if !labels.is_empty() {
+                let labels: Vec<String> = labels
+                    .iter()
+                    .map(|(key, value)| format!("{}=\"{}\"", key, escape_label(value)))
+                    .collect();
+                let _ = write!(self.text, "{{{}}}", labels.join(","));
+            }
+            let _ = writeln!(self.text, " {}", value);
+        }
+        self
+    }
+
+    pub fn finish(&mut self) -> String {
+        std::mem::take(&mut self.text)
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+pub const SEVERITIES: [&str; 4] = ["Error", "Warning", "Info", "Style"];
+
+// Gauges for a directory scan report (see `scan::scan_directory`)
+pub fn render_report(report: &Value) -> String {
+    let files = report["files"].as_array().map(Vec::as_slice).unwrap_or(&[]);
+    let mut issues: BTreeMap<&str, f64> = SEVERITIES.iter().map(|s| (*s, 0.0)).collect();
+    let mut scores = Vec::new();
+    for file in files {
+        let result = &file["result"];
+        if let (Some(path), Some(score)) = (file["path"].as_str(), result["score"].as_f64()) {
+            scores.push((vec![("file", path.to_string())], score));
+        }
+        for issue in result["issues"].as_array().into_iter().flatten() {
+            if let Some(count) = issue["severity"].as_str().and_then(|s| issues.get_mut(s)) {
+                *count += 1.0;
+            }
+        }
+    }
+
+    let summary = &report["summary"];
+    let mut exposition = Exposition::new();
+    exposition
+        .gauge("treescan_score", "Quality score of a file (0-10)", scores)
+        .gauge(
+            "treescan_issues_total",
+            "Issues found in the last run, by severity",
+            issues
+                .into_iter()
+                .map(|(severity, count)| (vec![("severity", severity.to_string())], count)),
+        )
+        .gauge(
+            "treescan_files_analyzed",
+            "Files analyzed in the last run",
+            [(vec![], summary["files_analyzed"].as_f64().unwrap_or(0.0))],
+        )
+        .gauge(
+            "treescan_files_failed",
+            "Files that could not be analyzed in the last run",
+            [(vec![], summary["files_failed"].as_f64().unwrap_or(0.0))],
+        );
+    if let Some(average) = summary["average_score"].as_f64() {
+        exposition.gauge(
+            "treescan_average_score",
+            "Average file score in the last run",
+            [(vec![], average)],
+        );
+    }
+    if let Some(minutes) = summary["remediation_minutes"].as_f64() {
+        exposition.gauge(
+            "treescan_remediation_minutes",
+            "Estimated minutes to fix every issue found in the last run",
+            [(vec![], minutes)],
+        );
+    }
+    exposition.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_render_report() {
+        let report = json!({
+            "summary": { "files_analyzed": 1, "files_failed": 0, "average_score": 7.5 },
+            "files": [{ "path": "src/\"odd\".rs", "result": {
+                "score": 7.5,
+                "issues": [{ "severity": "Warning" }, { "severity": "Warning" }],
+            } }],
+        });
+        let text = render_report(&report);
+        assert!(text.contains("# TYPE treescan_score gauge\n"));
+        assert!(text.contains("treescan_score{file=\"src/\\\"odd\\\".rs\"} 7.5\n"));
+        assert!(text.contains("treescan_issues_total{severity=\"Warning\"} 2\n"));
+        assert!(text.contains("treescan_issues_total{severity=\"Error\"} 0\n"));
+        assert!(text.contains("treescan_average_score 7.5\n"));
+    }
+}
@@ -0,0 +1,64 @@
+use crate::ast::parse_tree;
+use serde_json::{json, Value};
+use tree_sitter::{Language, Query, QueryCursor, StreamingIterator, Tree};
+
+// Runs an ad-hoc tree-sitter query and returns every capture as JSON
+pub fn run_query(
+    source_code: &str,
+    language: &Language,
+    query_text: &str,
+) -> Result<Value, Box<dyn std::error::Error>> {
+    let tree = parse_tree(source_code, language)?;
+    let query = Query::new(language, query_text)?;
+    Ok(query_captures(&tree, source_code, &query))
+}
+
+// Captures of an already-compiled query, for callers that reuse queries
+pub fn query_captures(tree: &Tree, source_code: &str, query: &Query) -> Value {
+    let capture_names = query.capture_names();
+    let mut cursor = QueryCursor::new();
+    let mut captures = Vec::new();
+
+    let mut matches = cursor.matches(query, tree.root_node(), source_code.as_bytes());
+    while let Some(match_) = matches.next() {
+        for capture in match_.captures {
+            let node = capture.node;
+            let start = node.start_position();
+            let end = node.end_position();
+            captures.push(json!({
+                "pattern": match_.pattern_index,
+                "capture": capture_names[capture.index as usize],
+                "kind": node.kind(),
+                "text": node.utf8_text(source_code.as_bytes()).unwrap_or(""),
+                "start": { "line": start.row + 1, "column": start.column + 1 },
+                "end": { "line": end.row + 1, "column": end.column + 1 },
+            }));
+        }
+    }
+
+    Value::Array(captures)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_query_returns_captures() {
+        let language: Language = tree_sitter_rust::LANGUAGE.into();
+        let result = run_query(
+            "fn a() {}\nfn b() {}",
+            &language,
+            "(function_item name: (identifier) @name)",
+        )
+        .unwrap();
+
+        let captures = result.as_array().unwrap();
+        assert_eq!(captures.len(), 2);
+        assert_eq!(captures[1]["capture"], "name");
+        assert_eq!(captures[1]["text"], "b");
+        assert_eq!(captures[1]["start"]["line"], 2);
+
+        assert!(run_query("fn a() {}", &language, "(not_a_node)").is_err());
+    }
+}
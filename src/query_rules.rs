@@ -0,0 +1,242 @@
+use crate::analyzer::{AnalysisRule, Severity};
+use crate::explain::RuleDoc;
+use crate::language::SupportedLanguage;
+use crate::locale::Translation;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tree_sitter::Query;
+
+// Query file read from each language directory, as in nvim-treesitter's layout
+pub const LINTS_FILE_NAME: &str = "lints.scm";
+
+// Directory name for a language under a queries root (nvim-treesitter parser names)
+pub fn query_dir_name(language: SupportedLanguage) -> &'static str {
+    match language {
+        SupportedLanguage::Rust => "rust",
+        SupportedLanguage::Java => "java",
+        SupportedLanguage::Zig => "zig",
+        SupportedLanguage::C => "c",
+        SupportedLanguage::Cpp => "cpp",
+        SupportedLanguage::JavaScript => "javascript",
+        SupportedLanguage::TypeScript => "typescript",
+        SupportedLanguage::Tsx => "tsx",
+        SupportedLanguage::Go => "go",
+    }
+}
+
+pub fn lints_path(dir: &Path, language: SupportedLanguage) -> PathBuf {
+    dir.join(query_dir_name(language)).join(LINTS_FILE_NAME)
+}
+
+// One rule per severity capture in `<dir>/<lang>/lints.scm`: `@warning.unwrap`
+// becomes the Warning rule `unwrap`. Other captures are helpers for predicates and
+// are not reported. `(#set! message "...")` and `(#set! suggestion "...")` in a
+// pattern describe its rules, and `message.ja`/`suggestion.ja` translate them.
+// `description`, `example.before` and `example.after` document them for `rules
+// explain`. A missing file yields no rules.
+pub fn load_query_rules(
+    dir: &Path,
+    language: SupportedLanguage,
+) -> Result<Vec<AnalysisRule>, Box<dyn std::error::Error>> {
+    let path = lints_path(dir, language);
+    if !path.is_file() {
+        return Ok(Vec::new());
+    }
+    let source = fs::read_to_string(&path)?;
+    let query = Query::new(&language.tree_sitter_language(), &source)
+        .map_err(|e| format!("Invalid query '{}': {}", path.display(), e))?;
+
+    let rules = query
+        .capture_names()
+        .iter()
+        .filter_map(|capture| {
+            let (severity, name) = severity_for_capture(capture)?;
+            let name = name.unwrap_or("lints").replace('.', "_");
+            let message = capture_property(&query, &source, capture, "message")
+                .unwrap_or_else(|| format!("Matched @{} from {}", capture, path.display()));
+            Some(
+                AnalysisRule::new(
+                    name,
+                    source.clone(),
+                    severity,
+                    message,
+                    capture_property(&query, &source, capture, "suggestion"),
+                )
+                .with_capture(capture)
+                .with_translations(capture_translations(&query, &source, capture))
+                .with_doc(RuleDoc {
+                    description: capture_property(&query, &source, capture, "description"),
+                    before: capture_property(&query, &source, capture, "example.before"),
+                    after: capture_property(&query, &source, capture, "example.after"),
+                }),
+            )
+        })
+        .collect();
+    Ok(rules)
+}
+
+// The first `#set!` value for `key` that targets `capture`, either by naming it
+// (`(#set! @warning.unwrap message "...")`) or by sitting in a pattern that uses it
+fn capture_property(query: &Query, source: &str, capture: &str, key: &str) -> Option<String> {
+    let capture_id = query.capture_index_for_name(capture)? as usize;
+    (0..query.pattern_count()).find_map(|pattern| {
+        let (start, end) = (query.start_byte_for_pattern(pattern), query.end_byte_for_pattern(pattern));
+        let text = &source[start..end];
+        // `@warning.unwrap` must not match inside `@warning.unwrap_or`
+        let in_pattern = text.match_indices(&format!("@{}", capture)).any(|(i, m)| {
+            !text[i + m.len()..].starts_with(|c: char| c.is_alphanumeric() || c == '_' || c == '.')
+        });
+        query
+            .property_settings(pattern)
+            .iter()
+            .filter(|property| &*property.key == key)
+            .find(|property| match property.capture_id {
+                Some(id) => id == capture_id,
+                None => in_pattern,
+            })
+            .and_then(|property| property.value.as_deref().map(str::to_string))
+    })
+}
+
+// `message.<locale>` and `suggestion.<locale>` properties of the capture, by locale
+fn capture_translations(
+    query: &Query,
+    source: &str,
+    capture: &str,
+) -> BTreeMap<String, Translation> {
+    let locales: BTreeSet<String> = (0..query.pattern_count())
+        .flat_map(|pattern| query.property_settings(pattern))
+        .filter_map(|property| {
+            let (key, locale) = property.key.split_once('.')?;
+            matches!(key, "message" | "suggestion").then(|| locale.to_string())
+        })
+        .collect();
+    locales
+        .into_iter()
+        .filter_map(|locale| {
+            let property = |key: &str| {
+                capture_property(query, source, capture, &format!("{}.{}", key, locale))
+            };
+            let translation = Translation {
+                message: property("message"),
+                suggestion: property("suggestion"),
+            };
+            (translation != Translation::default()).then_some((locale, translation))
+        })
+        .collect()
+}
+
+// Checks every language's lints file under each directory so bad queries fail up front
+pub fn validate_query_dirs(dirs: &[PathBuf]) -> Result<(), Box<dyn std::error::Error>> {
+    for dir in dirs {
+        if !dir.is_dir() {
+            return Err(format!("Query directory '{}' does not exist", dir.display()).into());
+        }
+        for language in SupportedLanguage::ALL {
+            load_query_rules(dir, language)?;
+        }
+    }
+    Ok(())
+}
+
+// `error`, `warning`/`warn`, `info`/`hint`/`note` and `style`, optionally followed by
+// `.<rule name>`
+fn severity_for_capture(capture: &str) -> Option<(Severity, Option<&str>)> {
+    let (prefix, name) = match capture.split_once('.') {
+        Some((prefix, name)) => (prefix, Some(name)),
+        None => (capture, None),
+    };
+    let severity = match prefix {
+        "error" => Severity::Error,
+        "warning" | "warn" => Severity::Warning,
+        "info" | "hint" | "note" => Severity::Info,
+        "style" => Severity::Style,
+        _ => return None,
+    };
+    Some((severity, name.filter(|n| !n.is_empty())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    const LINTS: &str = r#"((call_expression
+  function: (field_expression field: (field_identifier) @method) @warning.unwrap)
+ (#eq? @method "unwrap"))
+(macro_invocation macro: (identifier) @info.todo_macro (#eq? @info.todo_macro "todo")
+ (#set! message "todo!() left in code") (#set! suggestion "Implement it or return an error")
+ (#set! message.ja "todo!() が残っています"))
+"#;
+
+    // A queries root with `lints` as the Rust lints file
+    fn queries(lints: &str) -> TempDir {
+        let dir = TempDir::new("queries");
+        dir.write("rust/lints.scm", lints);
+        dir
+    }
+
+    #[test]
+    fn test_severity_captures_become_rules() {
+        let dir = queries(LINTS);
+        let rules = load_query_rules(&dir, SupportedLanguage::Rust).unwrap();
+        let names: Vec<&str> = rules.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, ["unwrap", "todo_macro"]);
+
+        let mut analyzer = crate::analyzer::CodeAnalyzer::new();
+        for rule in rules {
+            analyzer.add_rule(rule);
+        }
+        let source = "fn main() { x.unwrap(); todo!(); }\n";
+        let language = SupportedLanguage::Rust.tree_sitter_language();
+        let results = analyzer.analyze(source, &language).unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].rule_name, "unwrap");
+        assert_eq!(results[0].text, "x.unwrap");
+        assert!(results[0].message.starts_with("Matched @warning.unwrap"));
+        assert!(matches!(results[1].severity, Severity::Info));
+        assert_eq!(results[1].message, "todo!() left in code");
+        assert_eq!(
+            results[1].suggestion.as_deref(),
+            Some("Implement it or return an error")
+        );
+    }
+
+    #[test]
+    fn test_set_properties_translate_rules() {
+        let dir = queries(LINTS);
+        let todo = load_query_rules(&dir, SupportedLanguage::Rust)
+            .unwrap()
+            .remove(1);
+        assert_eq!(
+            todo.translations["ja"].message.as_deref(),
+            Some("todo!() が残っています")
+        );
+        assert_eq!(todo.translations["ja"].suggestion, None);
+    }
+
+    #[test]
+    fn test_missing_lints_file_has_no_rules() {
+        let dir = queries(LINTS);
+        assert!(load_query_rules(&dir, SupportedLanguage::Go)
+            .unwrap()
+            .is_empty());
+        assert!(validate_query_dirs(&[dir.to_path_buf()]).is_ok());
+    }
+
+    #[test]
+    fn test_invalid_queries_are_rejected() {
+        let dir = queries("(not_a_node) @error");
+        let error = load_query_rules(&dir, SupportedLanguage::Rust).unwrap_err();
+        assert!(error.to_string().starts_with("Invalid query"));
+        assert!(validate_query_dirs(&[dir.to_path_buf()]).is_err());
+    }
+
+    #[test]
+    fn test_missing_query_dir_is_rejected() {
+        let dir = TempDir::new("queries");
+        let error = validate_query_dirs(&[dir.join("missing")]).unwrap_err();
+        assert!(error.to_string().contains("does not exist"));
+    }
+}
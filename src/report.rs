@@ -0,0 +1,114 @@
+use crate::analyzer::{AnalysisResult, CodeScore, Severity};
+use crate::metrics::FileMetrics;
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use serde_json::Value;
+use std::io::Write;
+
+// Borrowed view over one file's analysis, serialized straight to the output
+// stream instead of through an intermediate serde_json::Value tree.
+// Field order is alphabetical to match the historical Value-based output.
+pub struct AnalysisReport<'a> {
+    pub results: &'a [AnalysisResult],
+    pub score: &'a CodeScore,
+    pub metrics: Option<&'a FileMetrics>,
+    // treescan, tree-sitter and grammar versions (see `version::version_metadata`)
+    pub metadata: Option<Value>,
+    pub truncated: bool,
+}
+
+impl AnalysisReport<'_> {
+    pub fn write_pretty<W: Write>(&self, out: W) -> serde_json::Result<()> {
+        serde_json::to_writer_pretty(out, self)
+    }
+}
+
+impl Serialize for AnalysisReport<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("AnalysisReport", 10)?;
+        state.serialize_field("breakdown", &Breakdown(self.score))?;
+        state.serialize_field("issues", self.results)?;
+        state.serialize_field("max_score", &self.score.max_score)?;
+        if let Some(metadata) = &self.metadata {
+            state.serialize_field("metadata", metadata)?;
+        }
+        if let Some(metrics) = self.metrics {
+            state.serialize_field("metrics", metrics)?;
+        }
+        state.serialize_field("rating", &self.score.rating)?;
+        state.serialize_field("remediation_minutes", &self.score.remediation_minutes)?;
+        state.serialize_field("score", &self.score.overall_score)?;
+        state.serialize_field("summary", &self.score.summary)?;
+        state.serialize_field("total_issues", &self.score.total_issues)?;
+        if self.truncated {
+            state.serialize_field("truncated", &true)?;
+        }
+        state.end()
+    }
+}
+
+struct Breakdown<'a>(&'a CodeScore);
+
+impl Serialize for Breakdown<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let breakdown = &self.0.breakdown;
+        let mut state = serializer.serialize_struct("ScoreBreakdown", 6)?;
+        state.serialize_field("deductions", &Deductions(self.0))?;
+        state.serialize_field("errors", &breakdown.errors)?;
+        state.serialize_field("info_issues", &breakdown.info_issues)?;
+        state.serialize_field("size_bonus", &breakdown.size_bonus)?;
+        state.serialize_field("style_issues", &breakdown.style_issues)?;
+        state.serialize_field("warnings", &breakdown.warnings)?;
+        state.end()
+    }
+}
+
+struct Deductions<'a>(&'a CodeScore);
+
+impl Serialize for Deductions<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let breakdown = &self.0.breakdown;
+        let mut state = serializer.serialize_struct("Deductions", 4)?;
+        state.serialize_field("from_errors", &breakdown.error_deduction)?;
+        state.serialize_field("from_info", &breakdown.info_deduction)?;
+        state.serialize_field("from_style", &breakdown.style_deduction)?;
+        state.serialize_field("from_warnings", &breakdown.warning_deduction)?;
+        state.end()
+    }
+}
+
+impl Serialize for AnalysisResult {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("AnalysisResult", 10)?;
+        if let Some(category) = &self.category {
+            state.serialize_field("category", category)?;
+        }
+        state.serialize_field("column", &self.column)?;
+        state.serialize_field("fingerprint", &self.fingerprint)?;
+        state.serialize_field("line", &self.line)?;
+        state.serialize_field("message", &self.message)?;
+        state.serialize_field("rule", &self.rule_name)?;
+        state.serialize_field("score_impact", &self.score_impact)?;
+        state.serialize_field("severity", &self.severity)?;
+        state.serialize_field("suggestion", &self.suggestion)?;
+        state.serialize_field("text", &self.text)?;
+        state.end()
+    }
+}
+
+impl Serialize for Severity {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl Serialize for FileMetrics {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("FileMetrics", 5)?;
+        state.serialize_field("comment_lines", &self.comment_lines)?;
+        state.serialize_field("cyclomatic_complexity", &self.cyclomatic_complexity)?;
+        state.serialize_field("function_count", &self.function_count)?;
+        state.serialize_field("lines_of_code", &self.lines_of_code)?;
+        state.serialize_field("total_lines", &self.total_lines)?;
+        state.end()
+    }
+}
@@ -0,0 +1,174 @@
+use crate::analyzer::{AnalysisResult, RuleConfig};
+use crate::diff::parse_unified_diff;
+use crate::language::SupportedLanguage;
+use crate::overflow::thousands;
+use crate::source::read_source;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+
+// Builds a GitHub "create a review" payload (POST /repos/{owner}/{repo}/pulls/{n}/reviews)
+// with one inline comment per finding on a line the diff added. Changed files are
+// read from `root`, which should be a checkout of the PR head. With `max_per_rule`,
+// a rule gets at most that many comments per file and the rest are counted in the
+// review body.
+pub fn build_review(
+    diff: &str,
+    root: &Path,
+    rules: &RuleConfig,
+    max_per_rule: Option<usize>,
+) -> Value {
+    let mut comments = Vec::new();
+    let mut seen = HashSet::new();
+    // (path, rule) -> findings commented on, and those left out
+    let mut per_rule: BTreeMap<(String, String), (usize, u64)> = BTreeMap::new();
+
+    for file in parse_unified_diff(diff) {
+        if file.added_lines.is_empty() {
+            continue;
+        }
+        let full_path = root.join(&file.path);
+        let Some(lang) = SupportedLanguage::detect(&full_path) else {
+            continue;
+        };
+        let Some(analyzer) = lang.analyzer_for_path(&full_path, rules) else {
+            continue;
+        };
+        let Ok(source) = read_source(&full_path, None) else {
+            continue;
+        };
+        let Ok(results) = analyzer.analyze(&source, &lang.tree_sitter_language()) else {
+            continue;
+        };
+
+        let path = file.path.to_string_lossy().replace('\\', "/");
+        for result in results {
+            // Rules with several captures can report the same spot more than once
+            let key = (path.clone(), result.line, result.rule_name.clone());
+            if file.added_lines.contains(&result.line) && seen.insert(key) {
+                let counts = per_rule
+                    .entry((path.clone(), result.rule_name.clone()))
+                    .or_default();
+                if max_per_rule.is_some_and(|max| counts.0 >= max) {
+                    counts.1 += 1;
+                    continue;
+                }
+                counts.0 += 1;
+                comments.push(json!({
+                    "path": path,
+                    "line": result.line,
+                    "side": "RIGHT",
+                    "body": comment_body(&result),
+                }));
+            }
+        }
+    }
+
+    let left_out: u64 = per_rule.values().map(|(_, more)| more).sum();
+    let mut body = match comments.len() as u64 + left_out {
+        0 => "treescan found no issues on the changed lines.".to_string(),
+        1 => "treescan found 1 issue on the changed lines.".to_string(),
+        n => format!("treescan found {} issues on the changed lines.", n),
+    };
+    for ((path, rule), (_, more)) in per_rule.iter().filter(|(_, (_, more))| *more > 0) {
+        let plural = if *more == 1 { "finding" } else { "findings" };
+        body.push_str(&format!(
+            "\n\n+{} more `{}` {} in {}",
+            thousands(*more),
+            rule,
+            plural,
+            path
+        ));
+    }
+    json!({ "event": "COMMENT", "body": body, "comments": comments })
+}
+
+fn comment_body(result: &AnalysisResult) -> String {
+    let mut body = format!(
+        "**{}** ({}): {}",
+        result.rule_name,
+        result.severity.as_str(),
+        result.message
+    );
+    if let Some(suggestion) = &result.suggestion {
+        body.push_str("\n\n");
+        body.push_str(suggestion);
+    }
+    // Lets bots that sync comments recognize the same issue on a later push
+    body.push_str(&format!("\n\n<!-- treescan:{} -->", result.fingerprint));
+    body
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    const SOURCE: &str = "fn a() { x.unwrap(); }\nfn b() { y.unwrap(); }\n";
+    // Adds both lines of SOURCE
+    const NEW_FILE: &str = "--- a/src/main.rs\n+++ b/src/main.rs\n@@ -0,0 +1,2 @@\n+fn a() { x.unwrap(); }\n+fn b() { y.unwrap(); }\n";
+
+    fn checkout() -> TempDir {
+        let root = TempDir::new("review");
+        root.write("src/main.rs", SOURCE);
+        root
+    }
+
+    #[test]
+    fn test_comments_only_on_added_lines() {
+        let root = checkout();
+        let diff = "--- a/src/main.rs\n+++ b/src/main.rs\n@@ -1,1 +1,2 @@\n fn a() { x.unwrap(); }\n+fn b() { y.unwrap(); }\n";
+
+        let review = build_review(diff, &root, &RuleConfig::default(), None);
+        let comments = review["comments"].as_array().unwrap();
+        assert_eq!(comments.len(), 1);
+        assert_eq!(comments[0]["path"], "src/main.rs");
+        assert_eq!(comments[0]["line"], 2);
+        assert_eq!(comments[0]["side"], "RIGHT");
+        let body = comments[0]["body"].as_str().unwrap();
+        assert!(body.starts_with("**unwrap_usage**"));
+        assert!(body.contains("<!-- treescan:"));
+        assert_eq!(
+            review["body"],
+            "treescan found 1 issue on the changed lines."
+        );
+    }
+
+    #[test]
+    fn test_max_per_rule_counts_the_rest_in_the_body() {
+        let root = checkout();
+        let capped = build_review(NEW_FILE, &root, &RuleConfig::default(), Some(1));
+        assert_eq!(capped["comments"].as_array().unwrap().len(), 1);
+        assert_eq!(
+            capped["body"],
+            "treescan found 2 issues on the changed lines.\n\n+1 more `unwrap_usage` finding in src/main.rs"
+        );
+    }
+
+    #[test]
+    fn test_unreadable_and_unsupported_files_are_skipped() {
+        let root = TempDir::new("review");
+        root.write("notes.txt", "x.unwrap()\n");
+        let diff = format!(
+            "{}--- a/notes.txt\n+++ b/notes.txt\n@@ -0,0 +1,1 @@\n+x.unwrap()\n",
+            NEW_FILE
+        );
+
+        // src/main.rs is missing from this checkout
+        let review = build_review(&diff, &root, &RuleConfig::default(), None);
+        assert_eq!(review["comments"], json!([]));
+        assert_eq!(
+            review["body"],
+            "treescan found no issues on the changed lines."
+        );
+    }
+
+    #[test]
+    fn test_empty_or_malformed_diff_has_no_comments() {
+        let root = checkout();
+        for diff in ["", "not a diff\n"] {
+            let review = build_review(diff, &root, &RuleConfig::default(), None);
+            assert_eq!(review["comments"], json!([]));
+        }
+    }
+}
@@ -0,0 +1,401 @@
+use crate::analyzer::{AnalysisRule, CodeAnalyzer};
+use crate::language::SupportedLanguage;
+use crate::query_rules::{load_query_rules, validate_query_dirs};
+use crate::script::ScriptRule;
+use serde_json::{json, Value};
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{Arc, Mutex, OnceLock};
+
+// Manifest at the root of every rule pack
+pub const MANIFEST_FILE_NAME: &str = "treescan-pack.json";
+
+// A shareable set of custom rules:
+//
+//   treescan-pack.json   {"name", "version", "description", "queries", "scripts", "fixtures"}
+//   queries/<lang>/lints.scm
+//   fixtures/bad.rs + fixtures/bad.rs.expected.json   [{"rule": "...", "line": 3}]
+//
+// `queries` and `fixtures` are directories relative to the manifest (defaulting to
+// `queries` and `fixtures` when present) and `scripts` holds `rules.scripts` entries.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RulePack {
+    pub name: String,
+    pub version: String,
+    pub description: Option<String>,
+    pub dir: PathBuf,
+    pub queries: Option<PathBuf>,
+    pub scripts: Vec<ScriptRule>,
+    pub fixtures: Option<PathBuf>,
+}
+
+impl RulePack {
+    // Reads and validates the pack in `dir`: bad queries or scripts fail here
+    pub fn load(dir: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let manifest_path = dir.join(MANIFEST_FILE_NAME);
+        let text = fs::read_to_string(&manifest_path)
+            .map_err(|e| format!("Cannot read '{}': {}", manifest_path.display(), e))?;
+        let manifest: Value = serde_json::from_str(&text)
+            .map_err(|e| format!("Invalid manifest '{}': {}", manifest_path.display(), e))?;
+        let invalid = |e: String| format!("Invalid manifest '{}': {}", manifest_path.display(), e);
+        let field = |key: &str| manifest.get(key).and_then(Value::as_str);
+
+        let name = field("name").ok_or_else(|| invalid("missing \"name\"".to_string()))?;
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+            || name.starts_with('.')
+        {
+            return Err(invalid(format!("invalid pack name '{}'", name)).into());
+        }
+        let version = field("version").ok_or_else(|| invalid("missing \"version\"".to_string()))?;
+        let subdir = |key: &str| match field(key) {
+            Some(path) => Some(dir.join(path)),
+            None => Some(dir.join(key)).filter(|path| path.is_dir()),
+        };
+        let queries = subdir("queries");
+        if let Some(queries) = &queries {
+            validate_query_dirs(std::slice::from_ref(queries))?;
+        }
+        let scripts = manifest
+            .get("scripts")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .map(ScriptRule::from_json)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(invalid)?;
+
+        Ok(RulePack {
+            name: name.to_string(),
+            version: version.to_string(),
+            description: field("description").map(str::to_string),
+            dir: dir.to_path_buf(),
+            queries,
+            scripts,
+            fixtures: subdir("fixtures"),
+        })
+    }
+
+    // The pack's rules for one language
+    pub fn rules(&self, language: SupportedLanguage) -> Vec<AnalysisRule> {
+        let mut rules = match &self.queries {
+            // Validated when the pack was loaded
+            Some(dir) => load_query_rules(dir, language).unwrap_or_default(),
+            None => Vec::new(),
+        };
+        rules.extend(
+            self.scripts
+                .iter()
+                .filter(|script| script.language == language)
+                .map(ScriptRule::to_analysis_rule),
+        );
+        rules
+    }
+
+    pub fn rule_count(&self) -> usize {
+        SupportedLanguage::ALL
+            .into_iter()
+            .map(|language| self.rules(language).len())
+            .sum()
+    }
+
+    // Runs the pack's rules alone over each fixture and compares the (rule, line)
+    // pairs found with its `.expected.json`. Returns how many fixtures passed.
+    pub fn check_fixtures(&self) -> Result<usize, Box<dyn std::error::Error>> {
+        let Some(dir) = &self.fixtures else {
+            return Ok(0);
+        };
+        let mut fixtures: Vec<PathBuf> = fs::read_dir(dir)?
+            .filter_map(|entry| Some(entry.ok()?.path()))
+            .filter(|path| SupportedLanguage::detect(path).is_some())
+            .collect();
+        fixtures.sort();
+
+        for fixture in &fixtures {
+            let language = SupportedLanguage::detect(fixture).unwrap_or(SupportedLanguage::Rust);
+            let expected_path = PathBuf::from(format!("{}.expected.json", fixture.display()));
+            let expected: Value = serde_json::from_str(
+                &fs::read_to_string(&expected_path)
+                    .map_err(|e| format!("Cannot read '{}': {}", expected_path.display(), e))?,
+            )
+            .map_err(|e| format!("Invalid '{}': {}", expected_path.display(), e))?;
+            let expected: BTreeSet<(String, u64)> = expected
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|e| Some((e["rule"].as_str()?.to_string(), e["line"].as_u64()?)))
+                .collect();
+
+            let mut analyzer = CodeAnalyzer::new();
+            for rule in self.rules(language) {
+                analyzer.add_rule(rule);
+            }
+            let source = fs::read_to_string(fixture)?;
+            let found: BTreeSet<(String, u64)> = analyzer
+                .analyze(&source, &language.tree_sitter_language())?
+                .into_iter()
+                .map(|result| (result.rule_name, result.line as u64))
+                .collect();
+            if found != expected {
+                let describe = |set: &BTreeSet<(String, u64)>| {
+                    set.iter()
+                        .map(|(rule, line)| format!("{}:{}", rule, line))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                };
+                return Err(format!(
+                    "Fixture '{}' failed: expected [{}], found [{}]",
+                    fixture.display(),
+                    describe(&expected),
+                    describe(&found)
+                )
+                .into());
+            }
+        }
+        Ok(fixtures.len())
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "name": self.name,
+            "version": self.version,
+            "description": self.description,
+            "path": self.dir.display().to_string(),
+            "rules": self.rule_count(),
+        })
+    }
+}
+
+// Where installed packs live: $TREESCAN_PACKS_DIR, else the user's data directory
+pub fn packs_dir() -> PathBuf {
+    if let Some(dir) = std::env::var_os("TREESCAN_PACKS_DIR") {
+        return PathBuf::from(dir);
+    }
+    let data = std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))
+        .unwrap_or_else(std::env::temp_dir);
+    data.join("treescan").join("packs")
+}
+
+// A git URL rather than a local path
+fn is_remote(source: &str) -> bool {
+    source.contains("://") || source.starts_with("git@")
+}
+
+// Validates the pack at `source` (a directory, or a git URL cloned with the `git`
+// CLI), checks its fixtures and copies it to `<packs_dir>/<name>`, replacing any
+// installed version
+pub fn install(source: &str, packs_dir: &Path) -> Result<RulePack, Box<dyn std::error::Error>> {
+    let checkout = std::env::temp_dir().join(format!("treescan-pack-{}", std::process::id()));
+    let dir = if is_remote(source) {
+        let _ = fs::remove_dir_all(&checkout);
+        let output = Command::new("git")
+            .args(["clone", "--quiet", "--depth", "1", source])
+            .arg(&checkout)
+            .output()
+            .map_err(|e| format!("could not run git: {}", e))?;
+        if !output.status.success() {
+            return Err(format!(
+                "git clone failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )
+            .into());
+        }
+        checkout.clone()
+    } else {
+        PathBuf::from(source)
+    };
+
+    let installed = install_dir(&dir, packs_dir);
+    if is_remote(source) {
+        let _ = fs::remove_dir_all(&checkout);
+    }
+    installed
+}
+
+fn install_dir(dir: &Path, packs_dir: &Path) -> Result<RulePack, Box<dyn std::error::Error>> {
+    let pack = RulePack::load(dir)?;
+    pack.check_fixtures()?;
+    let target = packs_dir.join(&pack.name);
+    if target.exists() {
+        fs::remove_dir_all(&target)?;
+    }
+    copy_dir(dir, &target)?;
+    RulePack::load(&target)
+}
+
+fn copy_dir(from: &Path, to: &Path) -> std::io::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        if entry.file_name() == ".git" {
+            continue;
+        }
+        let path = entry.path();
+        if entry.file_type()?.is_dir() {
+            copy_dir(&path, &to.join(entry.file_name()))?;
+        } else {
+            fs::copy(&path, to.join(entry.file_name()))?;
+        }
+    }
+    Ok(())
+}
+
+// Every loadable pack under `packs_dir`, by name; broken ones are skipped
+pub fn installed_packs(packs_dir: &Path) -> Vec<RulePack> {
+    let mut packs: Vec<RulePack> = fs::read_dir(packs_dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| RulePack::load(&entry.ok()?.path()).ok())
+        .collect();
+    packs.sort_by(|a, b| a.name.cmp(&b.name));
+    packs
+}
+
+// The installed pack `name`, loaded once per process
+pub fn load_installed(name: &str) -> Result<Arc<RulePack>, Box<dyn std::error::Error>> {
+    static LOADED: OnceLock<Mutex<HashMap<String, Arc<RulePack>>>> = OnceLock::new();
+    let loaded = LOADED.get_or_init(Default::default);
+    if let Some(pack) = loaded.lock().unwrap().get(name) {
+        return Ok(pack.clone());
+    }
+    let dir = packs_dir().join(name);
+    if name.contains(['/', '\\']) || !dir.join(MANIFEST_FILE_NAME).is_file() {
+        return Err(format!(
+            "Rule pack '{}' is not installed (see `treescan rules install`)",
+            name
+        )
+        .into());
+    }
+    let pack = Arc::new(RulePack::load(&dir)?);
+    loaded
+        .lock()
+        .unwrap()
+        .insert(name.to_string(), pack.clone());
+    Ok(pack)
+}
+
+// Loads every enabled pack up front so a missing or broken one fails before analysis
+pub fn validate_packs(names: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    for name in names {
+        load_installed(name)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    // A pack source under `root/src` with a query rule, a script rule and one fixture
+    fn pack_source(root: &TempDir) -> PathBuf {
+        let manifest = json!({
+            "name": "acme",
+            "version": "1.0.0",
+            "scripts": [{
+                "language": "go",
+                "name": "short_name",
+                "query": "(function_declaration name: (identifier) @name)",
+                "script": "len(text) < 3",
+            }],
+        });
+        root.write(&format!("src/{}", MANIFEST_FILE_NAME), manifest.to_string());
+        root.write(
+            "src/queries/rust/lints.scm",
+            "(macro_invocation macro: (identifier) @warning.todo (#eq? @warning.todo \"todo\"))",
+        );
+        root.write("src/fixtures/a.rs", "fn a() {\n    todo!()\n}\n");
+        root.write(
+            "src/fixtures/a.rs.expected.json",
+            r#"[{"rule": "todo", "line": 2}]"#,
+        );
+        root.join("src")
+    }
+
+    fn load_error(root: &TempDir, manifest: &str) -> String {
+        root.write(MANIFEST_FILE_NAME, manifest);
+        RulePack::load(root).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn test_install_checks_fixtures_and_lists_packs() {
+        let root = TempDir::new("packs");
+        let source = pack_source(&root);
+        let packs = root.join("installed");
+
+        let pack = install(source.to_str().unwrap(), &packs).unwrap();
+        assert_eq!(pack.dir, packs.join("acme"));
+        assert_eq!(pack.rule_count(), 2);
+        assert_eq!(pack.check_fixtures().unwrap(), 1);
+        assert_eq!(installed_packs(&packs), vec![pack]);
+
+        // Reinstalling replaces the installed copy
+        root.write("src/notes.md", "v2");
+        install(source.to_str().unwrap(), &packs).unwrap();
+        assert!(packs.join("acme/notes.md").is_file());
+    }
+
+    #[test]
+    fn test_failing_fixture_blocks_install() {
+        let root = TempDir::new("packs");
+        let source = pack_source(&root);
+        root.write("src/fixtures/a.rs.expected.json", "[]");
+        let packs = root.join("installed");
+
+        let error = install(source.to_str().unwrap(), &packs)
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("expected [], found [todo:2]"));
+        assert!(installed_packs(&packs).is_empty());
+
+        root.write("src/fixtures/a.rs.expected.json", "{ not json");
+        let error = install(source.to_str().unwrap(), &packs)
+            .unwrap_err()
+            .to_string();
+        assert!(error.starts_with("Invalid '"));
+    }
+
+    #[test]
+    fn test_manifest_errors() {
+        let root = TempDir::new("packs");
+        let missing = RulePack::load(&root).unwrap_err().to_string();
+        assert!(missing.starts_with("Cannot read"));
+
+        assert!(load_error(&root, "{").starts_with("Invalid manifest"));
+        assert!(load_error(&root, r#"{"version": "1"}"#).ends_with("missing \"name\""));
+        assert!(load_error(&root, r#"{"name": "../x", "version": "1"}"#)
+            .ends_with("invalid pack name '../x'"));
+        assert!(load_error(&root, r#"{"name": "acme"}"#).ends_with("missing \"version\""));
+        let script = r#"{"name": "acme", "version": "1", "scripts": [{"language": "go"}]}"#;
+        assert!(load_error(&root, script).starts_with("Invalid manifest"));
+    }
+
+    #[test]
+    fn test_invalid_pack_query_is_rejected() {
+        let root = TempDir::new("packs");
+        root.write("queries/rust/lints.scm", "(not_a_node) @error");
+        let error = load_error(&root, r#"{"name": "acme", "version": "1"}"#);
+        assert!(error.starts_with("Invalid query"));
+    }
+
+    #[test]
+    fn test_installed_packs_skips_broken_packs() {
+        let root = TempDir::new("packs");
+        let packs = root.join("installed");
+        install(pack_source(&root).to_str().unwrap(), &packs).unwrap();
+        root.write(&format!("installed/broken/{}", MANIFEST_FILE_NAME), "{");
+
+        let names: Vec<String> = installed_packs(&packs)
+            .into_iter()
+            .map(|p| p.name)
+            .collect();
+        assert_eq!(names, ["acme"]);
+        assert!(installed_packs(&root.join("missing")).is_empty());
+    }
+}
@@ -0,0 +1,321 @@
+use crate::analyzer::{AnalysisRule, CodeAnalyzer};
+use crate::language::SupportedLanguage;
+use crate::overflow::thousands;
+use crate::query_rules::load_query_rules;
+use crate::script::ScriptRule;
+use crate::source::read_source;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Sample hits longer than this are cut short
+const SAMPLE_WIDTH: usize = 80;
+
+// One candidate rule's hits so far
+#[derive(Default)]
+struct Tally {
+    matches: u64,
+    files: BTreeSet<String>,
+    samples: Vec<Value>,
+}
+
+// Candidate rules for `rules try`: a queries directory laid out like `query_dirs`, or a
+// JSON file holding one script rule or an array of them, as in `rules.scripts`
+pub fn load_candidates(
+    path: &Path,
+) -> Result<Vec<(SupportedLanguage, AnalysisRule)>, Box<dyn std::error::Error>> {
+    let candidates: Vec<(SupportedLanguage, AnalysisRule)> = if path.is_dir() {
+        let mut candidates = Vec::new();
+        for language in SupportedLanguage::ALL {
+            for rule in load_query_rules(path, language)? {
+                candidates.push((language, rule));
+            }
+        }
+        candidates
+    } else {
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read '{}': {}", path.display(), e))?;
+        let value: Value = serde_json::from_str(&text)
+            .map_err(|e| format!("Invalid rule file '{}': {}", path.display(), e))?;
+        let entries = match value {
+            Value::Array(entries) => entries,
+            entry => vec![entry],
+        };
+        entries
+            .iter()
+            .map(|entry| {
+                let rule = ScriptRule::from_json(entry)?;
+                Ok((rule.language, rule.to_analysis_rule()))
+            })
+            .collect::<Result<_, String>>()?
+    };
+    if candidates.is_empty() {
+        return Err(format!("No rules in '{}'", path.display()).into());
+    }
+    Ok(candidates)
+}
+
+// Runs only `candidates` over `files`, counting each rule's matches and the files they
+// are in and keeping its first `samples` hits, with paths relative to `root`. Rules
+// that never match are listed too, with 0.
+pub fn trial_report(
+    root: &Path,
+    files: &[PathBuf],
+    candidates: &[(SupportedLanguage, AnalysisRule)],
+    samples: usize,
+) -> Value {
+    let mut analyzers: BTreeMap<&'static str, (SupportedLanguage, CodeAnalyzer)> = BTreeMap::new();
+    for (language, rule) in candidates {
+        analyzers
+            .entry(language.name())
+            .or_insert_with(|| (*language, CodeAnalyzer::new()))
+            .1
+            .add_rule(rule.clone());
+    }
+    let mut tallies: BTreeMap<(&str, &str), Tally> = BTreeMap::new();
+    let mut errors = Vec::new();
+    let (mut files_scanned, mut lines_scanned) = (0u64, 0u64);
+    for path in files {
+        let Some((language, analyzer)) =
+            SupportedLanguage::detect(path).and_then(|language| analyzers.get(language.name()))
+        else {
+            continue;
+        };
+        let relative = match path.strip_prefix(root) {
+            Ok(rel) if !rel.as_os_str().is_empty() => rel,
+            _ => path.as_path(),
+        };
+        let relative = relative.display().to_string();
+        let results = read_source(path, None)
+            .map_err(|e| e.to_string())
+            .and_then(|source| {
+                lines_scanned += source.lines().count() as u64;
+                analyzer
+                    .analyze(&source, &language.tree_sitter_language())
+                    .map_err(|e| e.to_string())
+            });
+        let results = match results {
+            Ok(results) => results,
+            Err(e) => {
+                errors.push(json!({ "path": relative, "error": e }));
+                continue;
+            }
+        };
+        files_scanned += 1;
+        for result in results {
+            let Some((_, rule)) = candidates
+                .iter()
+                .find(|(l, r)| l == language && r.name == result.rule_name)
+            else {
+                continue;
+            };
+            let tally = tallies.entry((language.name(), &rule.name)).or_default();
+            tally.matches += 1;
+            tally.files.insert(relative.clone());
+            if tally.samples.len() < samples {
+                tally.samples.push(json!({
+                    "path": relative,
+                    "line": result.line,
+                    "column": result.column,
+                    "text": sample_text(&result.text),
+                    "message": result.message,
+                }));
+            }
+        }
+    }
+    let rules: Vec<Value> = candidates
+        .iter()
+        .map(|(language, rule)| {
+            let tally = tallies
+                .remove(&(language.name(), &rule.name))
+                .unwrap_or_default();
+            let per_kloc = if lines_scanned == 0 {
+                0.0
+            } else {
+                (tally.matches as f64 * 10_000.0 / lines_scanned as f64).round() / 10.0
+            };
+            json!({
+                "rule": rule.name,
+                "language": language.name(),
+                "severity": rule.severity.as_str(),
+                "matches": tally.matches,
+                "files": tally.files.len(),
+                "per_1k_lines": per_kloc,
+                "samples": tally.samples,
+            })
+        })
+        .collect();
+    json!({
+        "files_scanned": files_scanned,
+        "lines_scanned": lines_scanned,
+        "rules": rules,
+        "errors": errors,
+    })
+}
+
+// The first line of a hit, cut to `SAMPLE_WIDTH` characters
+fn sample_text(text: &str) -> String {
+    let first = text.lines().next().unwrap_or("").trim();
+    let multiline = text.trim_end().contains('\n');
+    if first.chars().count() > SAMPLE_WIDTH {
+        format!(
+            "{}...",
+            first.chars().take(SAMPLE_WIDTH).collect::<String>()
+        )
+    } else if multiline {
+        format!("{} ...", first)
+    } else {
+        first.to_string()
+    }
+}
+
+pub fn format_trial_text(report: &Value) -> String {
+    let count = |key: &str| thousands(report[key].as_u64().unwrap_or(0));
+    let mut out = format!(
+        "Scanned {} files ({} lines)\n",
+        count("files_scanned"),
+        count("lines_scanned")
+    );
+    for rule in report["rules"].as_array().into_iter().flatten() {
+        let matches = rule["matches"].as_u64().unwrap_or(0);
+        out.push_str(&format!(
+            "\n{}  {}  {}  {} {}",
+            rule["rule"].as_str().unwrap_or(""),
+            rule["language"].as_str().unwrap_or(""),
+            rule["severity"].as_str().unwrap_or(""),
+            thousands(matches),
+            if matches == 1 { "match" } else { "matches" }
+        ));
+        if matches > 0 {
+            let files = rule["files"].as_u64().unwrap_or(0);
+            out.push_str(&format!(
+                " in {} {} ({} per 1k lines)",
+                thousands(files),
+                if files == 1 { "file" } else { "files" },
+                rule["per_1k_lines"]
+            ));
+        }
+        out.push('\n');
+        for sample in rule["samples"].as_array().into_iter().flatten() {
+            out.push_str(&format!(
+                "  {}:{}:{}  {}\n",
+                sample["path"].as_str().unwrap_or(""),
+                sample["line"],
+                sample["column"],
+                sample["text"].as_str().unwrap_or("")
+            ));
+        }
+    }
+    for error in report["errors"].as_array().into_iter().flatten() {
+        out.push_str(&format!(
+            "{}: error: {}\n",
+            error["path"].as_str().unwrap_or(""),
+            error["error"].as_str().unwrap_or("")
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    const CLONE_RULES: &str = r#"[
+  {"language": "rust", "name": "clone_call", "severity": "info",
+   "query": "(call_expression function: (field_expression field: (field_identifier) @f)) @call",
+   "capture": "call", "script": "contains(text, \".clone()\")"},
+  {"language": "rust", "name": "never", "query": "(macro_invocation) @m", "script": "true"}
+]"#;
+
+    // The message load_candidates fails with for a rule file holding `text`
+    fn load_error(dir: &TempDir, text: &str) -> String {
+        let rule_file = dir.write("new_rule.json", text);
+        load_candidates(&rule_file).err().unwrap().to_string()
+    }
+
+    #[test]
+    fn test_candidate_rules_counted_with_samples() {
+        let dir = TempDir::new("try");
+        let files = vec![
+            dir.write(
+                "src/a.rs",
+                "fn a() {\n    x.clone();\n    y.clone();\n    z.len();\n}\n",
+            ),
+            dir.write("src/b.rs", "fn b() { w.clone(); }\n"),
+            dir.write("src/c.go", "package c\n"),
+        ];
+        let candidates = load_candidates(&dir.write("new_rule.json", CLONE_RULES)).unwrap();
+
+        let report = trial_report(&dir, &files, &candidates, 2);
+        // The Go file has no candidate rules, so it is not scanned
+        assert_eq!(report["files_scanned"], 2);
+        assert_eq!(report["lines_scanned"], 6);
+        let clone = &report["rules"][0];
+        assert_eq!(clone["rule"], "clone_call");
+        assert_eq!(clone["matches"], 3);
+        assert_eq!(clone["files"], 2);
+        assert_eq!(clone["per_1k_lines"], 500.0);
+        assert_eq!(clone["samples"].as_array().unwrap().len(), 2);
+        assert_eq!(clone["samples"][0]["text"], "x.clone()");
+        assert_eq!(report["rules"][1]["matches"], 0);
+
+        let text = format_trial_text(&report);
+        assert!(text.starts_with("Scanned 2 files (6 lines)\n"));
+        assert!(
+            text.contains("clone_call  Rust  Info  3 matches in 2 files (500.0 per 1k lines)\n")
+        );
+        assert!(text.contains("  src/a.rs:2:5  x.clone()\n"));
+        assert!(text.contains("never  Rust  Warning  0 matches\n"));
+    }
+
+    #[test]
+    fn test_candidates_from_a_queries_directory() {
+        let dir = TempDir::new("try");
+        dir.write(
+            "queries/rust/lints.scm",
+            "(macro_invocation) @warning.macros",
+        );
+        let file = dir.write("src/a.rs", "fn a() { println!(); }\n");
+
+        let candidates = load_candidates(&dir.join("queries")).unwrap();
+        assert_eq!(candidates.len(), 1);
+        let report = trial_report(&dir, &[file], &candidates, 5);
+        assert_eq!(report["rules"][0]["rule"], "macros");
+        assert_eq!(report["rules"][0]["matches"], 1);
+    }
+
+    #[test]
+    fn test_unreadable_files_are_reported() {
+        let dir = TempDir::new("try");
+        let candidates = load_candidates(&dir.write("new_rule.json", CLONE_RULES)).unwrap();
+
+        let report = trial_report(&dir, &[dir.join("src/missing.rs")], &candidates, 2);
+        assert_eq!(report["files_scanned"], 0);
+        assert_eq!(report["errors"][0]["path"], "src/missing.rs");
+        assert_eq!(report["rules"][0]["per_1k_lines"], 0.0);
+    }
+
+    #[test]
+    fn test_load_candidates_rejects_bad_rule_files() {
+        let dir = TempDir::new("try");
+        assert!(load_error(&dir, r#"{"language": "rust", "name": "x"}"#).contains("query"));
+        assert!(load_error(&dir, "[{").starts_with("Invalid rule file"));
+        assert!(load_error(&dir, "[]").starts_with("No rules in"));
+        let missing = load_candidates(&dir.join("missing.json")).err().unwrap();
+        assert!(missing.to_string().starts_with("Failed to read"));
+    }
+
+    #[test]
+    fn test_load_candidates_rejects_empty_or_invalid_query_dirs() {
+        let dir = TempDir::new("try");
+        std::fs::create_dir_all(dir.join("queries")).unwrap();
+        let empty = load_candidates(&dir.join("queries")).err().unwrap();
+        assert!(empty.to_string().starts_with("No rules in"));
+
+        dir.write("queries/rust/lints.scm", "(no_such_node) @warning");
+        let invalid = load_candidates(&dir.join("queries")).err().unwrap();
+        assert!(invalid.to_string().starts_with("Invalid query"));
+    }
+}
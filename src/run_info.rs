@@ -0,0 +1,84 @@
+use crate::cache::stable_hash;
+use serde_json::{json, Value};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+// How and when a report was produced, stamped into its `metadata.run` so an archived
+// report says what command and settings reproduce it
+#[derive(Debug, Clone)]
+pub struct RunInfo {
+    pub command_line: Vec<String>,
+    // Seconds since the Unix epoch
+    pub started_at: u64,
+    // Digest of the effective settings: config file and flags alike
+    pub config_hash: String,
+    started: Instant,
+}
+
+impl RunInfo {
+    pub fn start(command_line: Vec<String>, config: &str) -> Self {
+        RunInfo {
+            command_line,
+            started_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs()),
+            config_hash: format!("{:016x}", stable_hash(config.as_bytes())),
+            started: Instant::now(),
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "command_line": self.command_line,
+            "started_at": self.started_at,
+            "duration_ms": self.started.elapsed().as_millis() as u64,
+            "config_hash": self.config_hash,
+        })
+    }
+
+    // Adds `metadata.run` to `report`, creating `metadata` if needed
+    pub fn stamp(&self, report: &mut Value) {
+        if !report.is_object() {
+            return;
+        }
+        if !report["metadata"].is_object() {
+            report["metadata"] = json!({});
+        }
+        report["metadata"]["run"] = self.to_json();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_metadata_stamped_into_reports() {
+        let args = vec![
+            "treescan".to_string(),
+            "analyze".to_string(),
+            "src".to_string(),
+        ];
+        let run = RunInfo::start(args.clone(), "rules=a");
+        let mut report = json!({ "files": [], "metadata": { "treescan": "0.1.0" } });
+        run.stamp(&mut report);
+
+        let stamped = &report["metadata"]["run"];
+        assert_eq!(stamped["command_line"], json!(args));
+        assert!(stamped["started_at"].as_u64().unwrap() > 0);
+        assert!(stamped["duration_ms"].is_u64());
+        assert_eq!(report["metadata"]["treescan"], "0.1.0");
+        // Same settings, same hash
+        assert_eq!(
+            stamped["config_hash"],
+            RunInfo::start(args, "rules=a").config_hash
+        );
+        assert_ne!(
+            run.config_hash,
+            RunInfo::start(vec![], "rules=b").config_hash
+        );
+
+        let mut bare = json!({ "issues": [] });
+        run.stamp(&mut bare);
+        assert!(bare["metadata"]["run"].is_object());
+    }
+}
@@ -0,0 +1,366 @@
+use crate::batch::{analyze_many, BatchConfig};
+use crate::files::{walk_source_files, WalkOptions};
+use crate::language::SupportedLanguage;
+use crate::sfc;
+use crate::version::all_versions_metadata;
+use serde_json::{json, Value};
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Default)]
+pub struct ScanOptions {
+    pub walk: WalkOptions,
+    pub batch: BatchConfig,
+}
+
+// Analyzes every supported file under `root`. Files that cannot be reached or
+// read are reported under `skipped` / as per-file errors; the scan never aborts.
+pub fn scan_directory(root: &Path, options: &ScanOptions) -> Value {
+    let walked = walk_source_files(root, &options.walk);
+    let mut files: Vec<PathBuf> = walked
+        .files
+        .into_iter()
+        .filter(|path| {
+            SupportedLanguage::detect(path).is_some_and(|l| l.supports_analyze())
+                || sfc::is_component(path)
+        })
+        // Diff-aware scans judge only the files the change touched
+        .filter(|path| {
+            options.batch.changed_lines.as_ref().is_none_or(|changed| {
+                fs::canonicalize(path).is_ok_and(|path| changed.contains_key(&path))
+            })
+        })
+        .collect();
+    files.sort();
+
+    let results = analyze_many(&files, &options.batch);
+    let skipped = walked.skipped.iter().map(|s| s.to_json()).collect();
+    scan_report(root, results, skipped, options)
+}
+
+// The scan report for one file analyzed outside a scan, so `--store` and `--prom-out`
+// record the result already computed instead of analyzing the file again
+pub fn file_report(path: &Path, language: &str, result: Value, options: &ScanOptions) -> Value {
+    let entry = json!({
+        "path": path.display().to_string(),
+        "language": language,
+        "result": result,
+    });
+    scan_report(path, json!([entry]), Vec::new(), options)
+}
+
+fn scan_report(root: &Path, results: Value, skipped: Vec<Value>, options: &ScanOptions) -> Value {
+    let entries = results.as_array().map(Vec::as_slice).unwrap_or(&[]);
+    let analyzed: Vec<&Value> = entries
+        .iter()
+        .filter(|e| e.get("result").is_some())
+        .collect();
+
+    let total_issues: u64 = analyzed
+        .iter()
+        .filter_map(|e| e["result"]["total_issues"].as_u64())
+        .sum();
+    let remediation_minutes: u64 = analyzed
+        .iter()
+        .filter_map(|e| e["result"]["remediation_minutes"].as_u64())
+        .sum();
+    let average_score = if analyzed.is_empty() {
+        Value::Null
+    } else {
+        let total: f64 = analyzed
+            .iter()
+            .filter_map(|e| e["result"]["score"].as_f64())
+            .sum();
+        json!((total / analyzed.len() as f64 * 10.0).round() / 10.0)
+    };
+
+    let mut metadata = all_versions_metadata();
+    metadata["ratings"] = options.batch.rules.ratings.to_json();
+    let mut report = json!({
+        "metadata": metadata,
+        "root": root.display().to_string(),
+        "summary": {
+            "files_analyzed": analyzed.len(),
+            "files_failed": entries.len() - analyzed.len(),
+            "files_skipped": skipped.len(),
+            "total_issues": total_issues,
+            "average_score": average_score,
+            "remediation_minutes": remediation_minutes,
+        },
+        "files": results,
+        "skipped": skipped,
+    });
+    rollup_directories(&mut report);
+    language_breakdown(&mut report);
+    report
+}
+
+// Sets `directories` to the average score, issue count and remediation effort of every directory
+// level under the scan root (`src`, `src/api`, ...), counting each analyzed
+// file towards all of its ancestors, so ownership boundaries map to numbers
+pub fn rollup_directories(report: &mut Value) {
+    let root = PathBuf::from(report["root"].as_str().unwrap_or(""));
+    let mut directories: BTreeMap<String, (usize, f64, u64, u64)> = BTreeMap::new();
+    for file in report["files"].as_array().into_iter().flatten() {
+        let Some(score) = file["result"]["score"].as_f64() else {
+            continue;
+        };
+        let issues = file["result"]["total_issues"].as_u64().unwrap_or(0);
+        let minutes = file["result"]["remediation_minutes"].as_u64().unwrap_or(0);
+        let path = Path::new(file["path"].as_str().unwrap_or(""));
+        let relative = path.strip_prefix(&root).unwrap_or(path);
+        for dir in relative.ancestors().skip(1) {
+            if dir.as_os_str().is_empty() {
+                continue;
+            }
+            let entry = directories
+                .entry(dir.display().to_string().replace('\\', "/"))
+                .or_default();
+            entry.0 += 1;
+            entry.1 += score;
+            entry.2 += issues;
+            entry.3 += minutes;
+        }
+    }
+
+    report["directories"] = directories
+        .into_iter()
+        .map(|(path, (files, total, issues, minutes))| {
+            json!({
+                "path": path,
+                "files": files,
+                "average_score": (total / files as f64 * 10.0).round() / 10.0,
+                "total_issues": issues,
+                "remediation_minutes": minutes,
+            })
+        })
+        .collect();
+}
+
+// How many rules each `languages` entry lists under `top_rules`
+const LANGUAGE_TOP_RULES: usize = 3;
+
+// Sets `languages` to per-language file counts, lines of code, average score and
+// most frequent rules, so polyglot repos can see which ecosystem needs attention
+pub fn language_breakdown(report: &mut Value) {
+    #[derive(Default)]
+    struct Totals<'a> {
+        files: usize,
+        lines_of_code: u64,
+        score: f64,
+        issues: u64,
+        rules: BTreeMap<&'a str, usize>,
+    }
+
+    let mut languages: BTreeMap<&str, Totals> = BTreeMap::new();
+    for file in report["files"].as_array().into_iter().flatten() {
+        let result = &file["result"];
+        let (Some(language), Some(score)) = (file["language"].as_str(), result["score"].as_f64())
+        else {
+            continue;
+        };
+        let totals = languages.entry(language).or_default();
+        totals.files += 1;
+        totals.lines_of_code += result["metrics"]["lines_of_code"].as_u64().unwrap_or(0);
+        totals.score += score;
+        totals.issues += result["total_issues"].as_u64().unwrap_or(0);
+        for issue in result["issues"].as_array().into_iter().flatten() {
+            *totals.rules.entry(issue["rule"].as_str().unwrap_or("")).or_default() += 1;
+        }
+    }
+
+    let languages: Vec<Value> = languages
+        .into_iter()
+        .map(|(language, totals)| {
+            let mut rules: Vec<(&str, usize)> = totals.rules.into_iter().collect();
+            rules.sort_by_key(|(_, count)| Reverse(*count));
+            let top_rules: Vec<Value> = rules
+                .into_iter()
+                .take(LANGUAGE_TOP_RULES)
+                .map(|(rule, count)| json!({ "rule": rule, "count": count }))
+                .collect();
+            json!({
+                "language": language,
+                "files": totals.files,
+                "lines_of_code": totals.lines_of_code,
+                "average_score": (totals.score / totals.files as f64 * 10.0).round() / 10.0,
+                "total_issues": totals.issues,
+                "top_rules": top_rules,
+            })
+        })
+        .collect();
+    report["languages"] = json!(languages);
+}
+
+// The `n` lowest-scoring files and the `n` most frequently violated rules, which
+// is where a cleanup should start. Ties keep path / rule name order.
+pub fn top_offenders(report: &Value, n: usize) -> Value {
+    let mut files: Vec<&Value> = report["files"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|file| file["result"]["score"].is_number())
+        .collect();
+    files.sort_by(|a, b| {
+        let score = |file: &Value| file["result"]["score"].as_f64().unwrap_or(0.0);
+        score(a).total_cmp(&score(b))
+    });
+    let lowest: Vec<Value> = files
+        .iter()
+        .take(n)
+        .map(|file| {
+            json!({
+                "path": file["path"],
+                "score": file["result"]["score"],
+                "rating": file["result"]["rating"],
+                "total_issues": file["result"]["total_issues"],
+            })
+        })
+        .collect();
+
+    // rule -> (violations, files violating it)
+    let mut rules: BTreeMap<&str, (usize, usize)> = BTreeMap::new();
+    for file in &files {
+        let mut seen = Vec::new();
+        for issue in file["result"]["issues"].as_array().into_iter().flatten() {
+            let rule = issue["rule"].as_str().unwrap_or("");
+            let entry = rules.entry(rule).or_default();
+            entry.0 += 1;
+            if !seen.contains(&rule) {
+                seen.push(rule);
+                entry.1 += 1;
+            }
+        }
+    }
+    let mut rules: Vec<(&str, (usize, usize))> = rules.into_iter().collect();
+    rules.sort_by_key(|(_, (count, _))| Reverse(*count));
+    let most_violated: Vec<Value> = rules
+        .into_iter()
+        .take(n)
+        .map(|(rule, (count, files))| json!({ "rule": rule, "count": count, "files": files }))
+        .collect();
+
+    json!({ "lowest_scoring_files": lowest, "most_violated_rules": most_violated })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rollup_directories_averages_every_level() {
+        let file = |path: &str, score: f64, issues: u64| {
+            json!({ "path": path, "result": { "score": score, "total_issues": issues } })
+        };
+        let mut report = json!({
+            "root": "repo",
+            "files": [
+                file("repo/main.rs", 10.0, 0),
+                file("repo/src/api/a.rs", 6.0, 3),
+                file("repo/src/api/b.rs", 7.5, 1),
+                file("repo/src/core/c.rs", 9.1, 0),
+                { "path": "repo/src/core/broken.rs", "error": "unreadable" },
+            ],
+        });
+        rollup_directories(&mut report);
+
+        let directories = report["directories"].as_array().unwrap();
+        let summary: Vec<(&str, u64, f64)> = directories
+            .iter()
+            .map(|d| {
+                (
+                    d["path"].as_str().unwrap(),
+                    d["files"].as_u64().unwrap(),
+                    d["average_score"].as_f64().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![("src", 3, 7.5), ("src/api", 2, 6.8), ("src/core", 1, 9.1)]
+        );
+        assert_eq!(directories[1]["total_issues"], 4);
+    }
+
+    #[test]
+    fn test_language_breakdown() {
+        let file = |language: &str, score: f64, loc: u64, rules: &[&str]| {
+            let issues: Vec<Value> = rules.iter().map(|rule| json!({ "rule": rule })).collect();
+            json!({ "language": language, "result": {
+                "score": score, "total_issues": issues.len(), "issues": issues,
+                "metrics": { "lines_of_code": loc },
+            } })
+        };
+        let mut report = json!({ "files": [
+            file("Rust", 9.0, 100, &["unwrap_usage"]),
+            file("Go", 6.0, 40, &["go_panic_usage", "go_todo_comment", "go_panic_usage"]),
+            file("Rust", 8.0, 20, &[]),
+            { "language": "Go", "error": "unreadable" },
+        ] });
+        language_breakdown(&mut report);
+
+        assert_eq!(
+            report["languages"],
+            json!([
+                { "language": "Go", "files": 1, "lines_of_code": 40, "average_score": 6.0,
+                  "total_issues": 3, "top_rules": [
+                      { "rule": "go_panic_usage", "count": 2 },
+                      { "rule": "go_todo_comment", "count": 1 },
+                  ] },
+                { "language": "Rust", "files": 2, "lines_of_code": 120, "average_score": 8.5,
+                  "total_issues": 1, "top_rules": [{ "rule": "unwrap_usage", "count": 1 }] },
+            ])
+        );
+    }
+
+    #[test]
+    fn test_top_offenders() {
+        let file = |path: &str, score: f64, rules: &[&str]| {
+            let issues: Vec<Value> = rules.iter().map(|rule| json!({ "rule": rule })).collect();
+            json!({ "path": path, "result": { "score": score, "issues": issues } })
+        };
+        let report = json!({ "files": [
+            file("a.rs", 9.0, &["todo"]),
+            file("b.rs", 4.0, &["unwrap", "unwrap", "todo"]),
+            file("c.rs", 6.0, &["unwrap"]),
+        ] });
+
+        let top = top_offenders(&report, 2);
+        let paths: Vec<&str> = top["lowest_scoring_files"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|f| f["path"].as_str().unwrap())
+            .collect();
+        assert_eq!(paths, vec!["b.rs", "c.rs"]);
+        assert_eq!(
+            top["most_violated_rules"],
+            json!([
+                { "rule": "unwrap", "count": 3, "files": 2 },
+                { "rule": "todo", "count": 2, "files": 2 },
+            ])
+        );
+    }
+
+    #[test]
+    fn test_file_report_wraps_one_result() {
+        let result = json!({
+            "score": 8.5,
+            "total_issues": 2,
+            "remediation_minutes": 10,
+            "issues": [{ "rule": "unwrap" }, { "rule": "unwrap" }],
+        });
+        let report = file_report(Path::new("src/main.rs"), "Rust", result, &ScanOptions::default());
+
+        assert_eq!(report["root"], "src/main.rs");
+        assert_eq!(report["files"][0]["path"], "src/main.rs");
+        assert_eq!(report["files"][0]["result"]["score"], 8.5);
+        assert_eq!(report["summary"]["files_analyzed"], 1);
+        assert_eq!(report["summary"]["files_skipped"], 0);
+        assert_eq!(report["summary"]["total_issues"], 2);
+        assert_eq!(report["summary"]["average_score"], 8.5);
+        assert_eq!(report["languages"][0]["top_rules"][0]["count"], 2);
+    }
+}
@@ -0,0 +1,584 @@
+use crate::analyzer::{AnalysisRule, Severity};
+use crate::explain::RuleDoc;
+use crate::language::SupportedLanguage;
+use crate::locale::Translation;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use tree_sitter::{Node, Query};
+
+// A small expression language for rules that need a little logic on top of a
+// query. There is no Rhai or Lua engine in the dependency tree, so scripts are
+// single expressions over the matched node:
+//
+//   variables  kind, text, parent (kind or null), capture, line, lines, children
+//   functions  len(s), lower(s), contains(s, sub), starts_with(s, p), ends_with(s, p),
+//              count(s, sub), has_child(kind), has_ancestor(kind)
+//   operators  ?: || && ! == != < <= > >= + - and parentheses
+//
+// A script evaluating to true reports the rule's message, a string reports that
+// string as the message, and false, null or "" report nothing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Script {
+    source: String,
+    expr: Expr,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Int(i64),
+    Str(String),
+    Bool(bool),
+    Null,
+    Var(Var),
+    Call(Function, Vec<Expr>),
+    Not(Box<Expr>),
+    Neg(Box<Expr>),
+    Binary(Op, Box<Expr>, Box<Expr>),
+    If(Box<Expr>, Box<Expr>, Box<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Var {
+    Kind,
+    Text,
+    Parent,
+    Capture,
+    Line,
+    Lines,
+    Children,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Function {
+    Len,
+    Lower,
+    Contains,
+    StartsWith,
+    EndsWith,
+    Count,
+    HasChild,
+    HasAncestor,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Or,
+    And,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Add,
+    Sub,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum ScriptValue {
+    Int(i64),
+    Str(String),
+    Bool(bool),
+    Null,
+}
+
+impl Var {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "kind" => Some(Var::Kind),
+            "text" => Some(Var::Text),
+            "parent" => Some(Var::Parent),
+            "capture" => Some(Var::Capture),
+            "line" => Some(Var::Line),
+            "lines" => Some(Var::Lines),
+            "children" => Some(Var::Children),
+            _ => None,
+        }
+    }
+}
+
+impl Function {
+    fn from_name(name: &str) -> Option<(Self, usize)> {
+        match name {
+            "len" => Some((Function::Len, 1)),
+            "lower" => Some((Function::Lower, 1)),
+            "contains" => Some((Function::Contains, 2)),
+            "starts_with" => Some((Function::StartsWith, 2)),
+            "ends_with" => Some((Function::EndsWith, 2)),
+            "count" => Some((Function::Count, 2)),
+            "has_child" => Some((Function::HasChild, 1)),
+            "has_ancestor" => Some((Function::HasAncestor, 1)),
+            _ => None,
+        }
+    }
+}
+
+// What a script decided for one node
+#[derive(Debug, Clone, PartialEq)]
+pub enum Verdict {
+    Skip,
+    Report,
+    ReportWith(String),
+}
+
+// The node a script runs against
+pub struct ScriptContext<'a, 'tree> {
+    pub node: &'a Node<'tree>,
+    pub source: &'a str,
+    pub capture: &'a str,
+}
+
+impl Script {
+    // Parses `source`, rejecting unknown variables and functions and wrong arities
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let tokens = tokenize(source)?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let expr = parser.expr()?;
+        if let Some(token) = parser.tokens.get(parser.pos) {
+            return Err(format!("unexpected {:?}", token));
+        }
+        Ok(Script {
+            source: source.to_string(),
+            expr,
+        })
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    // Runtime type errors (e.g. `text < 3`) count as "do not report"
+    pub fn evaluate(&self, context: &ScriptContext) -> Verdict {
+        match eval(&self.expr, context) {
+            Ok(ScriptValue::Bool(true)) => Verdict::Report,
+            Ok(ScriptValue::Str(message)) if !message.is_empty() => Verdict::ReportWith(message),
+            _ => Verdict::Skip,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Int(i64),
+    Str(String),
+    Ident(String),
+    Symbol(&'static str),
+}
+
+const SYMBOLS: &[&str] = &[
+    "||", "&&", "==", "!=", "<=", ">=", "<", ">", "!", "+", "-", "?", ":", "(", ")", ",",
+];
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut rest = source;
+    while let Some(c) = rest.chars().next() {
+        if c.is_whitespace() {
+            rest = &rest[c.len_utf8()..];
+        } else if c.is_ascii_digit() {
+            let end = rest
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(rest.len());
+            let value = rest[..end]
+                .parse()
+                .map_err(|_| format!("number too large: {}", &rest[..end]))?;
+            tokens.push(Token::Int(value));
+            rest = &rest[end..];
+        } else if c.is_alphabetic() || c == '_' {
+            let end = rest
+                .find(|c: char| !c.is_alphanumeric() && c != '_')
+                .unwrap_or(rest.len());
+            tokens.push(Token::Ident(rest[..end].to_string()));
+            rest = &rest[end..];
+        } else if c == '"' || c == '\'' {
+            let mut value = String::new();
+            let mut chars = rest[1..].char_indices();
+            let end = loop {
+                match chars.next() {
+                    Some((i, q)) if q == c => break i + 2,
+                    Some((_, '\\')) => match chars.next() {
+                        Some((_, 'n')) => value.push('\n'),
+                        Some((_, 't')) => value.push('\t'),
+                        Some((_, escaped)) => value.push(escaped),
+                        None => return Err("unterminated string".to_string()),
+                    },
+                    Some((_, ch)) => value.push(ch),
+                    None => return Err("unterminated string".to_string()),
+                }
+            };
+            tokens.push(Token::Str(value));
+            rest = &rest[end..];
+        } else {
+            let symbol = SYMBOLS
+                .iter()
+                .find(|s| rest.starts_with(**s))
+                .ok_or_else(|| format!("unexpected character '{}'", c))?;
+            tokens.push(Token::Symbol(symbol));
+            rest = &rest[symbol.len()..];
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn eat(&mut self, symbol: &'static str) -> bool {
+        if self.tokens.get(self.pos) == Some(&Token::Symbol(symbol)) {
+            self.pos += 1;
+            return true;
+        }
+        false
+    }
+
+    fn expect(&mut self, symbol: &'static str) -> Result<(), String> {
+        if self.eat(symbol) {
+            Ok(())
+        } else {
+            Err(format!("expected '{}'", symbol))
+        }
+    }
+
+    // ternary: or ('?' expr ':' expr)?
+    fn expr(&mut self) -> Result<Expr, String> {
+        let condition = self.binary(0)?;
+        if !self.eat("?") {
+            return Ok(condition);
+        }
+        let then = self.expr()?;
+        self.expect(":")?;
+        let otherwise = self.expr()?;
+        Ok(Expr::If(
+            Box::new(condition),
+            Box::new(then),
+            Box::new(otherwise),
+        ))
+    }
+
+    // Precedence levels from loosest to tightest
+    fn binary(&mut self, level: usize) -> Result<Expr, String> {
+        const LEVELS: &[&[(&str, Op)]] = &[
+            &[("||", Op::Or)],
+            &[("&&", Op::And)],
+            &[
+                ("==", Op::Eq),
+                ("!=", Op::Ne),
+                ("<=", Op::Le),
+                (">=", Op::Ge),
+                ("<", Op::Lt),
+                (">", Op::Gt),
+            ],
+            &[("+", Op::Add), ("-", Op::Sub)],
+        ];
+        let Some(ops) = LEVELS.get(level) else {
+            return self.unary();
+        };
+        let mut left = self.binary(level + 1)?;
+        'operators: loop {
+            for (symbol, op) in *ops {
+                if self.eat(symbol) {
+                    let right = self.binary(level + 1)?;
+                    left = Expr::Binary(*op, Box::new(left), Box::new(right));
+                    continue 'operators;
+                }
+            }
+            return Ok(left);
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.eat("!") {
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+        if self.eat("-") {
+            return Ok(Expr::Neg(Box::new(self.unary()?)));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Expr, String> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .cloned()
+            .ok_or("unexpected end of script")?;
+        self.pos += 1;
+        match token {
+            Token::Int(value) => Ok(Expr::Int(value)),
+            Token::Str(value) => Ok(Expr::Str(value)),
+            Token::Symbol("(") => {
+                let expr = self.expr()?;
+                self.expect(")")?;
+                Ok(expr)
+            }
+            Token::Symbol(symbol) => Err(format!("unexpected '{}'", symbol)),
+            Token::Ident(name) => match name.as_str() {
+                "true" => Ok(Expr::Bool(true)),
+                "false" => Ok(Expr::Bool(false)),
+                "null" => Ok(Expr::Null),
+                _ if self.eat("(") => {
+                    let (function, arity) = Function::from_name(&name)
+                        .ok_or_else(|| format!("unknown function '{}'", name))?;
+                    let mut args = Vec::new();
+                    if !self.eat(")") {
+                        loop {
+                            args.push(self.expr()?);
+                            if self.eat(")") {
+                                break;
+                            }
+                            self.expect(",")?;
+                        }
+                    }
+                    if args.len() != arity {
+                        return Err(format!(
+                            "{}() takes {} argument(s), got {}",
+                            name,
+                            arity,
+                            args.len()
+                        ));
+                    }
+                    Ok(Expr::Call(function, args))
+                }
+                _ => Var::from_name(&name)
+                    .map(Expr::Var)
+                    .ok_or_else(|| format!("unknown variable '{}'", name)),
+            },
+        }
+    }
+}
+
+fn eval(expr: &Expr, context: &ScriptContext) -> Result<ScriptValue, String> {
+    let node = context.node;
+    let text = |node: &Node| {
+        node.utf8_text(context.source.as_bytes())
+            .unwrap_or("")
+            .to_string()
+    };
+    let string = |expr: &Expr| match eval(expr, context)? {
+        ScriptValue::Str(s) => Ok(s),
+        other => Err(format!("expected a string, got {:?}", other)),
+    };
+    let truthy = |expr: &Expr| match eval(expr, context)? {
+        ScriptValue::Bool(b) => Ok(b),
+        ScriptValue::Null => Ok(false),
+        other => Err(format!("expected a boolean, got {:?}", other)),
+    };
+    Ok(match expr {
+        Expr::Int(v) => ScriptValue::Int(*v),
+        Expr::Str(s) => ScriptValue::Str(s.clone()),
+        Expr::Bool(b) => ScriptValue::Bool(*b),
+        Expr::Null => ScriptValue::Null,
+        Expr::Var(var) => match var {
+            Var::Kind => ScriptValue::Str(node.kind().to_string()),
+            Var::Text => ScriptValue::Str(text(node)),
+            Var::Parent => node.parent().map_or(ScriptValue::Null, |p| {
+                ScriptValue::Str(p.kind().to_string())
+            }),
+            Var::Capture => ScriptValue::Str(context.capture.to_string()),
+            Var::Line => ScriptValue::Int(node.start_position().row as i64 + 1),
+            Var::Lines => {
+                ScriptValue::Int((node.end_position().row - node.start_position().row) as i64 + 1)
+            }
+            Var::Children => ScriptValue::Int(node.named_child_count() as i64),
+        },
+        Expr::Call(function, args) => match function {
+            Function::Len => ScriptValue::Int(string(&args[0])?.chars().count() as i64),
+            Function::Lower => ScriptValue::Str(string(&args[0])?.to_lowercase()),
+            Function::Contains => ScriptValue::Bool(string(&args[0])?.contains(&string(&args[1])?)),
+            Function::StartsWith => {
+                ScriptValue::Bool(string(&args[0])?.starts_with(&string(&args[1])?))
+            }
+            Function::EndsWith => {
+                ScriptValue::Bool(string(&args[0])?.ends_with(&string(&args[1])?))
+            }
+            Function::Count => {
+                let needle = string(&args[1])?;
+                if needle.is_empty() {
+                    return Err("count() needs a non-empty pattern".to_string());
+                }
+                ScriptValue::Int(string(&args[0])?.matches(&needle).count() as i64)
+            }
+            Function::HasChild => {
+                let kind = string(&args[0])?;
+                let mut cursor = node.walk();
+                let found = node.named_children(&mut cursor).any(|c| c.kind() == kind);
+                ScriptValue::Bool(found)
+            }
+            Function::HasAncestor => {
+                let kind = string(&args[0])?;
+                let mut ancestor = node.parent();
+                while let Some(current) = ancestor.filter(|a| a.kind() != kind) {
+                    ancestor = current.parent();
+                }
+                ScriptValue::Bool(ancestor.is_some())
+            }
+        },
+        Expr::Not(inner) => ScriptValue::Bool(!truthy(inner)?),
+        Expr::Neg(inner) => match eval(inner, context)? {
+            ScriptValue::Int(v) => ScriptValue::Int(-v),
+            other => return Err(format!("cannot negate {:?}", other)),
+        },
+        Expr::If(condition, then, otherwise) => {
+            if truthy(condition)? {
+                eval(then, context)?
+            } else {
+                eval(otherwise, context)?
+            }
+        }
+        Expr::Binary(Op::Or, left, right) => ScriptValue::Bool(truthy(left)? || truthy(right)?),
+        Expr::Binary(Op::And, left, right) => ScriptValue::Bool(truthy(left)? && truthy(right)?),
+        Expr::Binary(op, left, right) => {
+            let (left, right) = (eval(left, context)?, eval(right, context)?);
+            match (op, left, right) {
+                (Op::Eq, l, r) => ScriptValue::Bool(l == r),
+                (Op::Ne, l, r) => ScriptValue::Bool(l != r),
+                (Op::Add, ScriptValue::Int(l), ScriptValue::Int(r)) => {
+                    ScriptValue::Int(l.saturating_add(r))
+                }
+                (Op::Sub, ScriptValue::Int(l), ScriptValue::Int(r)) => {
+                    ScriptValue::Int(l.saturating_sub(r))
+                }
+                // `+` with a string on either side concatenates
+                (Op::Add, ScriptValue::Str(l), r) => ScriptValue::Str(l + &display(&r)),
+                (Op::Add, l, ScriptValue::Str(r)) => ScriptValue::Str(display(&l) + &r),
+                (op, ScriptValue::Int(l), ScriptValue::Int(r)) => ScriptValue::Bool(match op {
+                    Op::Lt => l < r,
+                    Op::Le => l <= r,
+                    Op::Gt => l > r,
+                    _ => l >= r,
+                }),
+                (op, l, r) => return Err(format!("cannot apply {:?} to {:?} and {:?}", op, l, r)),
+            }
+        }
+    })
+}
+
+fn display(value: &ScriptValue) -> String {
+    match value {
+        ScriptValue::Int(v) => v.to_string(),
+        ScriptValue::Str(s) => s.clone(),
+        ScriptValue::Bool(b) => b.to_string(),
+        ScriptValue::Null => "null".to_string(),
+    }
+}
+
+// A rule from `rules.scripts` in the config: a query whose captures are passed
+// through a script
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScriptRule {
+    pub language: SupportedLanguage,
+    pub name: String,
+    pub query: String,
+    pub severity: Severity,
+    pub message: String,
+    pub suggestion: Option<String>,
+    pub capture: Option<String>,
+    pub script: Script,
+    pub translations: BTreeMap<String, Translation>,
+    pub doc: RuleDoc,
+}
+
+impl ScriptRule {
+    // {"language", "name", "query", "script", "severity" (default "warning"),
+    //  "message" (default the rule name), "suggestion", "capture",
+    //  "translations" ({"ja": {"message", "suggestion"}}), "description",
+    //  "example" ({"before", "after"})}
+    pub fn from_json(value: &Value) -> Result<Self, String> {
+        let field = |key: &str| value.get(key).and_then(Value::as_str);
+        let required = |key: &str| field(key).ok_or_else(|| format!("missing \"{}\"", key));
+        let name = required("name")?.to_string();
+        let describe = |e: String| format!("script rule '{}': {}", name, e);
+        let language_name = required("language").map_err(describe)?;
+        let language = SupportedLanguage::from_name(language_name)
+            .ok_or_else(|| describe(format!("unknown language '{}'", language_name)))?;
+        let severity_name = field("severity").unwrap_or("warning");
+        let severity = Severity::from_name(severity_name)
+            .ok_or_else(|| describe(format!("unknown severity '{}'", severity_name)))?;
+        let query = required("query").map_err(describe)?.to_string();
+        let compiled = Query::new(&language.tree_sitter_language(), &query)
+            .map_err(|e| describe(format!("invalid query: {}", e)))?;
+        let capture = field("capture").map(str::to_string);
+        if let Some(capture) = &capture {
+            if compiled.capture_index_for_name(capture).is_none() {
+                return Err(describe(format!("query has no capture @{}", capture)));
+            }
+        }
+        let script = Script::parse(required("script").map_err(describe)?)
+            .map_err(|e| describe(format!("invalid script: {}", e)))?;
+        let translations = match value.get("translations") {
+            Some(translations) => Translation::from_json(translations).map_err(describe)?,
+            None => BTreeMap::new(),
+        };
+        let doc = RuleDoc::from_json(value).map_err(describe)?;
+        Ok(ScriptRule {
+            language,
+            message: field("message").unwrap_or(&name).to_string(),
+            name,
+            query,
+            severity,
+            suggestion: field("suggestion").map(str::to_string),
+            capture,
+            script,
+            translations,
+            doc,
+        })
+    }
+
+    pub fn to_analysis_rule(&self) -> AnalysisRule {
+        let mut rule = AnalysisRule::new(
+            self.name.clone(),
+            self.query.clone(),
+            self.severity.clone(),
+            self.message.clone(),
+            self.suggestion.clone(),
+        );
+        if let Some(capture) = &self.capture {
+            rule = rule.with_capture(capture);
+        }
+        rule.script = Some(self.script.clone());
+        rule.with_translations(self.translations.clone())
+            .with_doc(self.doc.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::CodeAnalyzer;
+    use serde_json::json;
+
+    #[test]
+    fn test_script_rules_filter_and_word_findings() {
+        let rule = ScriptRule::from_json(&json!({
+            "language": "rust",
+            "name": "long_match",
+            "query": "(match_expression) @match",
+            "script": "children > 1 && count(text, \"=>\") > 2 \
+                       ? \"match with \" + count(text, \"=>\") + \" arms\" : false",
+        }))
+        .unwrap();
+        let mut analyzer = CodeAnalyzer::new();
+        analyzer.add_rule(rule.to_analysis_rule());
+        let source = "fn f(x: u8) {\n    match x { 1 => {}, _ => {} }\n    \
+                      match x { 1 => {}, 2 => {}, _ => {} }\n}\n";
+        let results = analyzer
+            .analyze(source, &SupportedLanguage::Rust.tree_sitter_language())
+            .unwrap();
+        let found: Vec<(usize, &str)> = results
+            .iter()
+            .map(|r| (r.line, r.message.as_str()))
+            .collect();
+        assert_eq!(found, [(3, "match with 3 arms")]);
+
+        for (script, error) in [
+            ("size > 3", "unknown variable 'size'"),
+            ("len(text, 1)", "len() takes 1 argument(s), got 2"),
+            ("(kind == \"x\"", "expected ')'"),
+        ] {
+            assert_eq!(Script::parse(script).unwrap_err(), error);
+        }
+    }
+}
@@ -0,0 +1,203 @@
+use crate::injection::script_blocks;
+use crate::language::SupportedLanguage;
+use std::fs::File;
+use std::io::Read;
+use std::ops::Range;
+use std::path::Path;
+
+// Vue and Svelte single-file components: markup, script and style in one file.
+// Only the scripts have a bundled grammar; they are analyzed in place so every
+// position already points into the component file.
+pub const EXTENSIONS: &[&str] = &["vue", "svelte"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockKind {
+    Template,
+    Script,
+    Style,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Block {
+    pub kind: BlockKind,
+    // The grammar the block is analyzed with; none is bundled for markup and CSS
+    pub language: Option<SupportedLanguage>,
+    pub range: Range<usize>,
+}
+
+pub fn is_component(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| EXTENSIONS.contains(&e.to_lowercase().as_str()))
+}
+
+// The language a component's scripts are analyzed as, from the first 64 KiB
+pub fn detect(path: &Path) -> Option<SupportedLanguage> {
+    if !is_component(path) {
+        return None;
+    }
+    let mut head = Vec::new();
+    File::open(path)
+        .ok()?
+        .take(64 * 1024)
+        .read_to_end(&mut head)
+        .ok()?;
+    Some(component_language(&String::from_utf8_lossy(&head)))
+}
+
+// TypeScript scripts go to the TSX grammar, the TypeScript one that has an analyzer
+fn analyzed_as(language: SupportedLanguage) -> SupportedLanguage {
+    match language {
+        SupportedLanguage::TypeScript => SupportedLanguage::Tsx,
+        other => other,
+    }
+}
+
+// TSX when any script is TypeScript, since that grammar also reads plain JavaScript
+pub fn component_language(source: &str) -> SupportedLanguage {
+    script_blocks(source)
+        .into_iter()
+        .map(|(language, _)| analyzed_as(language))
+        .find(|language| *language == SupportedLanguage::Tsx)
+        .unwrap_or(SupportedLanguage::JavaScript)
+}
+
+// The top-level `<template>`, `<script>` and `<style>` bodies, in file order
+pub fn blocks(source: &str) -> Vec<Block> {
+    let mut blocks: Vec<Block> = script_blocks(source)
+        .into_iter()
+        .map(|(language, range)| Block {
+            kind: BlockKind::Script,
+            language: Some(analyzed_as(language)),
+            range,
+        })
+        .collect();
+    blocks.extend(style_bodies(source).into_iter().map(|range| Block {
+        kind: BlockKind::Style,
+        language: None,
+        range,
+    }));
+    if let Some(range) = template_body(source) {
+        blocks.push(Block {
+            kind: BlockKind::Template,
+            language: None,
+            range,
+        });
+    }
+    blocks.sort_by_key(|block| block.range.start);
+    blocks
+}
+
+fn style_bodies(source: &str) -> Vec<Range<usize>> {
+    let mut bodies = Vec::new();
+    let mut offset = 0;
+    while let Some(open) = source[offset..].find("<style").map(|i| offset + i) {
+        let Some(tag_end) = source[open..].find('>').map(|i| open + i + 1) else {
+            break;
+        };
+        let Some(close) = source[tag_end..].find("</style").map(|i| tag_end + i) else {
+            break;
+        };
+        bodies.push(tag_end..close);
+        offset = close;
+    }
+    bodies
+}
+
+// Vue's outer `<template>`, which may hold nested `<template v-slot>` elements
+fn template_body(source: &str) -> Option<Range<usize>> {
+    let open = source.find("<template")?;
+    let tag_end = source[open..].find('>').map(|i| open + i + 1)?;
+    let mut depth = 1;
+    let mut offset = tag_end;
+    loop {
+        let close = source[offset..].find("</template").map(|i| offset + i)?;
+        match source[offset..].find("<template").map(|i| offset + i) {
+            Some(next) if next < close => {
+                depth += 1;
+                offset = next + 1;
+            }
+            _ => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(tag_end..close);
+                }
+                offset = close + 1;
+            }
+        }
+    }
+}
+
+// The component with everything outside its scripts blanked to spaces, keeping line
+// breaks and byte offsets, so the script grammar sees one program at the original
+// positions
+pub fn script_view(source: &str) -> String {
+    let scripts = script_blocks(source);
+    let in_script = |byte: usize| scripts.iter().any(|(_, range)| range.contains(&byte));
+    let mut view = String::with_capacity(source.len());
+    for (byte, c) in source.char_indices() {
+        if c == '\n' || in_script(byte) {
+            view.push(c);
+        } else {
+            view.extend(std::iter::repeat_n(' ', c.len_utf8()));
+        }
+    }
+    view
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_component_scripts_analyzed_at_file_positions() {
+        let vue = "<template>\n  <List>\n    <template #item=\"{ x }\">{{ x }}</template>\n  </List>\n</template>\n\n<script setup lang=\"ts\">\nconst n: number = 1;\nconsole.log(n);\n</script>\n\n<style scoped>\n.a { color: red; }\n</style>\n";
+        let kinds: Vec<(BlockKind, Option<SupportedLanguage>)> =
+            blocks(vue).iter().map(|b| (b.kind, b.language)).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (BlockKind::Template, None),
+                (BlockKind::Script, Some(SupportedLanguage::Tsx)),
+                (BlockKind::Style, None),
+            ]
+        );
+        // The nested slot template stays inside the outer one
+        assert!(vue[blocks(vue)[0].range.clone()].ends_with("</template>\n  </List>\n"));
+        assert_eq!(component_language(vue), SupportedLanguage::Tsx);
+
+        let view = script_view(vue);
+        assert_eq!(view.len(), vue.len());
+        assert_eq!(view.lines().count(), vue.lines().count());
+        let results = SupportedLanguage::Tsx
+            .analyzer()
+            .unwrap()
+            .analyze(&view, &SupportedLanguage::Tsx.tree_sitter_language())
+            .unwrap();
+        let console = results
+            .iter()
+            .find(|r| r.rule_name == "console_log")
+            .unwrap();
+        assert_eq!((console.line, console.column), (9, 1));
+        assert!(!results.iter().any(|r| r.rule_name == "syntax_error"));
+
+        // Svelte markup sits outside any block; both scripts are one program
+        let svelte = "<script context=\"module\">\nexport const prerender = true;\n</script>\n<script>\nvar count = 0;\n</script>\n<button on:click={() => count++}>{count}</button>\n";
+        assert_eq!(component_language(svelte), SupportedLanguage::JavaScript);
+        let results = SupportedLanguage::JavaScript
+            .analyzer()
+            .unwrap()
+            .analyze(
+                &script_view(svelte),
+                &SupportedLanguage::JavaScript.tree_sitter_language(),
+            )
+            .unwrap();
+        assert_eq!(
+            results
+                .iter()
+                .map(|r| (r.rule_name.as_str(), r.line))
+                .collect::<Vec<_>>(),
+            vec![("var_usage", 5)]
+        );
+    }
+}
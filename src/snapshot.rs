@@ -0,0 +1,296 @@
+use crate::ast::{parse_tree, write_ast_node, AstFormat, AstOptions};
+use crate::diagnostics::collect_syntax_errors;
+use crate::language::SupportedLanguage;
+use crate::position::ColumnUnit;
+use crate::sfc;
+use crate::source::read_source;
+use serde_json::{json, Value};
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use tree_sitter::Tree;
+
+#[derive(Debug, Clone, Copy)]
+pub struct SnapshotOptions {
+    pub format: AstFormat,
+    pub ast: AstOptions,
+    // Write the current output instead of comparing against it
+    pub update: bool,
+}
+
+// `<dir>/src/lib.rs.snap` for `src/lib.rs`
+pub fn snapshot_path(dir: &Path, relative: &Path) -> PathBuf {
+    let mut name = relative.as_os_str().to_os_string();
+    name.push(".snap");
+    dir.join(name)
+}
+
+// What `parse` prints for the tree, minus the JSON `metadata`: its versions change with
+// every release even when the tree does not
+pub fn render_snapshot(
+    source: &str,
+    tree: &Tree,
+    format: AstFormat,
+    options: &AstOptions,
+) -> io::Result<String> {
+    let errors = collect_syntax_errors(tree, source, ColumnUnit::Byte);
+    let mut out = Vec::new();
+    let root = tree.root_node();
+    let written = match format {
+        AstFormat::Json => (|| {
+            out.write_all(b"{\"tree\":")?;
+            write_ast_node(root, source, format, options, &mut out)?;
+            out.write_all(b",\"diagnostics\":")?;
+            serde_json::to_writer(&mut out, &errors)?;
+            writeln!(out, "}}")
+        })(),
+        _ => write_ast_node(root, source, format, options, &mut out).and_then(|_| {
+            writeln!(out)?;
+            if format == AstFormat::Text && !errors.is_empty() {
+                writeln!(out)?;
+                writeln!(out, "Syntax diagnostics ({}):", errors.len())?;
+                for error in &errors {
+                    writeln!(out, "  {}", error)?;
+                }
+            }
+            Ok(())
+        }),
+    };
+    written?;
+    Ok(String::from_utf8_lossy(&out).into_owned())
+}
+
+// Compares each file's AST to its snapshot under `dir` (or writes the snapshots, with
+// `update`). Snapshots are named by the file's path relative to `root`, or by its file
+// name when `root` is the file itself. Each entry has a `status`: "ok", "drifted"
+// (with the first differing `line` and its `expected`/`actual` text), "missing",
+// "written" or "error".
+pub fn check_snapshots(
+    root: &Path,
+    files: &[PathBuf],
+    dir: &Path,
+    options: &SnapshotOptions,
+) -> Value {
+    let mut entries = Vec::new();
+    for path in files {
+        // Components hold several languages; there is no one tree to snapshot
+        if sfc::is_component(path) {
+            continue;
+        }
+        let Some(lang) = SupportedLanguage::detect(path) else {
+            continue;
+        };
+        let relative = match path.strip_prefix(root) {
+            Ok(rel) if !rel.as_os_str().is_empty() => rel,
+            _ => Path::new(path.file_name().unwrap_or(path.as_os_str())),
+        };
+        let snapshot = snapshot_path(dir, relative);
+        let entry = read_source(path, None)
+            .map_err(|e| e.to_string())
+            .and_then(|source| {
+                let tree =
+                    parse_tree(&source, &lang.tree_sitter_language()).map_err(|e| e.to_string())?;
+                let actual = render_snapshot(&source, &tree, options.format, &options.ast)
+                    .map_err(|e| e.to_string())?;
+                let expected = fs::read_to_string(&snapshot).ok();
+                if options.update {
+                    if expected.as_deref() == Some(actual.as_str()) {
+                        return Ok(json!({ "status": "ok" }));
+                    }
+                    if let Some(parent) = snapshot.parent() {
+                        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+                    }
+                    fs::write(&snapshot, &actual).map_err(|e| e.to_string())?;
+                    return Ok(json!({ "status": "written" }));
+                }
+                Ok(match expected {
+                    None => json!({ "status": "missing" }),
+                    Some(expected) => match first_difference(&expected, &actual) {
+                        None => json!({ "status": "ok" }),
+                        Some((line, expected, actual)) => json!({
+                            "status": "drifted",
+                            "line": line,
+                            "expected": expected,
+                            "actual": actual,
+                        }),
+                    },
+                })
+            });
+        let mut entry = entry.unwrap_or_else(|e| json!({ "status": "error", "error": e }));
+        entry["path"] = json!(relative.display().to_string());
+        entry["snapshot"] = json!(snapshot.display().to_string());
+        entries.push(entry);
+    }
+    let count = |status: &str| entries.iter().filter(|e| e["status"] == status).count();
+    json!({
+        "checked": entries.len(),
+        "drifted": count("drifted"),
+        "missing": count("missing"),
+        "written": count("written"),
+        "errors": count("error"),
+        "files": entries,
+    })
+}
+
+// 1-based number of the first line that differs, with both versions of it (empty past
+// the end of either)
+fn first_difference(expected: &str, actual: &str) -> Option<(usize, String, String)> {
+    let (mut expected_lines, mut actual_lines) = (expected.lines(), actual.lines());
+    let mut line = 0;
+    loop {
+        line += 1;
+        match (expected_lines.next(), actual_lines.next()) {
+            (None, None) => return None,
+            (e, a) if e == a => continue,
+            (e, a) => {
+                return Some((
+                    line,
+                    e.unwrap_or("").to_string(),
+                    a.unwrap_or("").to_string(),
+                ))
+            }
+        }
+    }
+}
+
+// Only what needs attention, then the counts
+pub fn format_snapshots_text(report: &Value) -> String {
+    let mut out = String::new();
+    for entry in report["files"].as_array().into_iter().flatten() {
+        let snapshot = entry["snapshot"].as_str().unwrap_or("");
+        match entry["status"].as_str().unwrap_or("") {
+            "drifted" => out.push_str(&format!(
+                "{}: drifted at line {}\n  - {}\n  + {}\n",
+                snapshot,
+                entry["line"],
+                entry["expected"].as_str().unwrap_or("").trim(),
+                entry["actual"].as_str().unwrap_or("").trim()
+            )),
+            "missing" => out.push_str(&format!("{}: missing\n", snapshot)),
+            "written" => out.push_str(&format!("{}: written\n", snapshot)),
+            "error" => out.push_str(&format!(
+                "{}: error: {}\n",
+                entry["path"].as_str().unwrap_or(""),
+                entry["error"].as_str().unwrap_or("")
+            )),
+            _ => {}
+        }
+    }
+    let count = |key: &str| report[key].as_u64().unwrap_or(0);
+    out.push_str(&format!(
+        "{} snapshot(s) checked: {} drifted, {} missing, {} written, {} error(s)\n",
+        count("checked"),
+        count("drifted"),
+        count("missing"),
+        count("written"),
+        count("errors")
+    ));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    fn options(update: bool) -> SnapshotOptions {
+        SnapshotOptions {
+            format: AstFormat::Text,
+            ast: AstOptions::default(),
+            update,
+        }
+    }
+
+    // A Rust and a nested Go file under `src`, with their snapshots to go in `snapshots`
+    fn project() -> (TempDir, Vec<PathBuf>) {
+        let dir = TempDir::new("snapshots");
+        let files = vec![
+            dir.write("src/a.rs", "fn a() {}\n"),
+            dir.write("src/nested/b.go", "package b\n"),
+        ];
+        (dir, files)
+    }
+
+    #[test]
+    fn test_missing_snapshots_written_on_update() {
+        let (dir, files) = project();
+        let (src, snapshots) = (dir.join("src"), dir.join("snapshots"));
+
+        let report = check_snapshots(&src, &files, &snapshots, &options(false));
+        assert_eq!(report["missing"], 2);
+        assert!(!snapshots.exists());
+
+        let report = check_snapshots(&src, &files, &snapshots, &options(true));
+        assert_eq!(report["written"], 2);
+        assert!(snapshots.join("nested/b.go.snap").is_file());
+        let written = fs::read_to_string(snapshots.join("a.rs.snap")).unwrap();
+        assert!(written.starts_with("(source_file)\n  (function_item)\n"));
+
+        // Snapshots that already match are left alone
+        let report = check_snapshots(&src, &files, &snapshots, &options(true));
+        assert_eq!(report["written"], 0);
+        let report = check_snapshots(&src, &files, &snapshots, &options(false));
+        assert_eq!(report["checked"], 2);
+        assert_eq!(report["drifted"], 0);
+        assert_eq!(report["missing"], 0);
+    }
+
+    #[test]
+    fn test_snapshot_drift_detected() {
+        let (dir, files) = project();
+        let (src, snapshots) = (dir.join("src"), dir.join("snapshots"));
+        check_snapshots(&src, &files, &snapshots, &options(true));
+
+        // The tree changes shape
+        dir.write("src/a.rs", "struct A;\n");
+        let report = check_snapshots(&src, &files, &snapshots, &options(false));
+        assert_eq!(report["drifted"], 1);
+        let drift = &report["files"][0];
+        assert_eq!(drift["path"], "a.rs");
+        assert_eq!(drift["line"], 2);
+        assert!(drift["expected"]
+            .as_str()
+            .unwrap()
+            .contains("function_item"));
+        assert!(drift["actual"].as_str().unwrap().contains("struct_item"));
+        let text = format_snapshots_text(&report);
+        assert!(text.contains("a.rs.snap: drifted at line 2\n"));
+        assert!(
+            text.ends_with("2 snapshot(s) checked: 1 drifted, 0 missing, 0 written, 0 error(s)\n")
+        );
+    }
+
+    #[test]
+    fn test_single_file_named_after_itself() {
+        let (dir, files) = project();
+        let single = check_snapshots(
+            &files[1],
+            &files[1..],
+            &dir.join("snapshots"),
+            &options(false),
+        );
+        assert_eq!(single["files"][0]["path"], "b.go");
+        assert_eq!(single["files"][0]["status"], "missing");
+    }
+
+    #[test]
+    fn test_unreadable_files_and_unwritable_snapshots_are_errors() {
+        let (dir, files) = project();
+        let src = dir.join("src");
+        let missing = check_snapshots(
+            &src,
+            &[src.join("gone.rs")],
+            &dir.join("snapshots"),
+            &options(false),
+        );
+        assert_eq!(missing["errors"], 1);
+        assert_eq!(missing["files"][0]["path"], "gone.rs");
+
+        // The snapshot directory can't be created over a file
+        let blocked = dir.write("blocked", "");
+        let report = check_snapshots(&src, &files, &blocked, &options(true));
+        assert_eq!(report["errors"], 2);
+        assert_eq!(report["written"], 0);
+        assert!(report["files"][0]["error"].is_string());
+    }
+}
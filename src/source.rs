@@ -0,0 +1,269 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read};
+use std::ops::Deref;
+use std::path::Path;
+use crate::trace;
+
+// Files at least this large are memory-mapped instead of copied onto the heap
+pub const MMAP_THRESHOLD: u64 = 1024 * 1024;
+pub const DEFAULT_MAX_FILE_SIZE: u64 = 10 * 1024 * 1024;
+
+#[derive(Debug)]
+pub enum SourceError {
+    TooLarge { size: u64, limit: u64 },
+    InvalidUtf8,
+    Io(io::Error),
+}
+
+impl fmt::Display for SourceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SourceError::TooLarge { size, limit } => write!(
+                f,
+                "file is {} bytes, exceeding the maximum file size of {} bytes",
+                size, limit
+            ),
+            SourceError::InvalidUtf8 => write!(f, "file is not valid UTF-8"),
+            SourceError::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for SourceError {}
+
+impl From<io::Error> for SourceError {
+    fn from(e: io::Error) -> Self {
+        SourceError::Io(e)
+    }
+}
+
+enum Contents {
+    Owned(String),
+    #[cfg(unix)]
+    Mapped(mmap::Mapping),
+}
+
+// File contents validated as UTF-8, either owned or memory-mapped
+pub struct SourceFile {
+    contents: Contents,
+}
+
+impl SourceFile {
+    pub fn as_str(&self) -> &str {
+        match &self.contents {
+            Contents::Owned(text) => text,
+            // Safety: validated in `map_source`, whose contract keeps the file unchanged since
+            #[cfg(unix)]
+            Contents::Mapped(mapping) => unsafe {
+                std::str::from_utf8_unchecked(mapping.as_slice())
+            },
+        }
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        match &self.contents {
+            Contents::Owned(text) => text.as_bytes(),
+            #[cfg(unix)]
+            Contents::Mapped(mapping) => mapping.as_slice(),
+        }
+    }
+
+    pub fn is_mapped(&self) -> bool {
+        !matches!(self.contents, Contents::Owned(_))
+    }
+}
+
+impl Deref for SourceFile {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+// Reads a source file onto the heap, refusing anything over `max_size` bytes
+pub fn read_source(
+    path: impl AsRef<Path>,
+    max_size: Option<u64>,
+) -> Result<SourceFile, SourceError> {
+    let _span = trace::span("read").attr("path", &path.as_ref().display().to_string());
+    let (file, size) = open_source(path.as_ref(), max_size)?;
+    read_owned(file, size)
+}
+
+/// Like `read_source`, but files of `MMAP_THRESHOLD` bytes or more are mapped read-only
+/// instead of copied.
+///
+/// # Safety
+/// The file must not be modified or truncated while the returned SourceFile is alive.
+/// The mapped pages follow the file on disk, so a write changes text that has already
+/// been handed out as `&str` (and may leave it invalid UTF-8), and a truncation makes
+/// reading it fault. The contents are validated once, here, not on every access.
+pub unsafe fn map_source(
+    path: impl AsRef<Path>,
+    max_size: Option<u64>,
+) -> Result<SourceFile, SourceError> {
+    let _span = trace::span("read").attr("path", &path.as_ref().display().to_string());
+    let (file, size) = open_source(path.as_ref(), max_size)?;
+    if size < MMAP_THRESHOLD {
+        return read_owned(file, size);
+    }
+    // Safety: the caller keeps the file unchanged while the SourceFile is alive
+    let contents = unsafe { map_or_read(file, size)? };
+    // Validated once here; `as_str` relies on it for as long as the mapping lives
+    #[cfg(unix)]
+    if let Contents::Mapped(mapping) = &contents {
+        std::str::from_utf8(mapping.as_slice()).map_err(|_| SourceError::InvalidUtf8)?;
+    }
+    Ok(SourceFile { contents })
+}
+
+fn open_source(path: &Path, max_size: Option<u64>) -> Result<(File, u64), SourceError> {
+    let file = File::open(path)?;
+    let size = file.metadata()?.len();
+    if let Some(limit) = max_size {
+        if size > limit {
+            return Err(SourceError::TooLarge { size, limit });
+        }
+    }
+    Ok((file, size))
+}
+
+fn read_owned(mut file: File, size: u64) -> Result<SourceFile, SourceError> {
+    let mut bytes = Vec::with_capacity(size as usize);
+    file.read_to_end(&mut bytes)?;
+    let text = String::from_utf8(bytes).map_err(|_| SourceError::InvalidUtf8)?;
+    Ok(SourceFile {
+        contents: Contents::Owned(text),
+    })
+}
+
+// Safety: as for `map_source`
+#[cfg(unix)]
+unsafe fn map_or_read(file: File, size: u64) -> Result<Contents, SourceError> {
+    match unsafe { mmap::Mapping::new(&file, size as usize) } {
+        Ok(mapping) => Ok(Contents::Mapped(mapping)),
+        // Some filesystems (procfs, certain FUSE mounts) refuse mmap
+        Err(_) => Ok(read_owned(file, size)?.contents),
+    }
+}
+
+#[cfg(not(unix))]
+unsafe fn map_or_read(file: File, size: u64) -> Result<Contents, SourceError> {
+    Ok(read_owned(file, size)?.contents)
+}
+
+#[cfg(unix)]
+mod mmap {
+    use std::fs::File;
+    use std::io;
+    use std::os::unix::io::AsRawFd;
+
+    pub struct Mapping {
+        ptr: *mut libc::c_void,
+        len: usize,
+    }
+
+    impl Mapping {
+        // Safety: `file` must not change while the mapping is alive (see `map_source`)
+        pub unsafe fn new(file: &File, len: usize) -> io::Result<Self> {
+            let ptr = unsafe {
+                libc::mmap(
+                    std::ptr::null_mut(),
+                    len,
+                    libc::PROT_READ,
+                    libc::MAP_PRIVATE,
+                    file.as_raw_fd(),
+                    0,
+                )
+            };
+            if ptr == libc::MAP_FAILED {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(Self { ptr, len })
+        }
+
+        pub fn as_slice(&self) -> &[u8] {
+            unsafe { std::slice::from_raw_parts(self.ptr as *const u8, self.len) }
+        }
+    }
+
+    impl Drop for Mapping {
+        fn drop(&mut self) {
+            unsafe {
+                libc::munmap(self.ptr, self.len);
+            }
+        }
+    }
+
+    // The mapping is read-only and never aliased mutably
+    unsafe impl Send for Mapping {}
+    unsafe impl Sync for Mapping {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    fn large_source() -> String {
+        "// padding\n".repeat((MMAP_THRESHOLD / 11 + 1) as usize)
+    }
+
+    #[test]
+    fn test_map_source_maps_large_files() {
+        let dir = TempDir::new("source");
+        let source = large_source();
+        let path = dir.write("large.rs", &source);
+
+        let file = unsafe { map_source(&path, None) }.unwrap();
+        assert_eq!(file.as_str(), source);
+        assert_eq!(file.is_mapped(), cfg!(unix));
+    }
+
+    #[test]
+    fn test_small_files_are_read_onto_the_heap() {
+        let dir = TempDir::new("source");
+        let path = dir.write("small.rs", "fn main() {}\n");
+
+        let file = unsafe { map_source(&path, None) }.unwrap();
+        assert_eq!(&*file, "fn main() {}\n");
+        assert!(!file.is_mapped());
+        let file = read_source(dir.write("large.rs", large_source()), None).unwrap();
+        assert!(!file.is_mapped());
+    }
+
+    #[test]
+    fn test_size_limit_is_enforced() {
+        let dir = TempDir::new("source");
+        let path = dir.write("large.rs", large_source());
+
+        match read_source(&path, Some(100)) {
+            Err(SourceError::TooLarge { limit, .. }) => assert_eq!(limit, 100),
+            _ => panic!("expected the size limit to be enforced"),
+        }
+        let mapped = unsafe { map_source(&path, Some(100)) };
+        assert!(matches!(mapped, Err(SourceError::TooLarge { .. })));
+    }
+
+    #[test]
+    fn test_invalid_utf8_is_rejected() {
+        let dir = TempDir::new("source");
+        let mut bytes = vec![b' '; MMAP_THRESHOLD as usize];
+        bytes.push(0xff);
+        let path = dir.write("binary.rs", &bytes);
+
+        let mapped = unsafe { map_source(&path, None) };
+        assert!(matches!(mapped, Err(SourceError::InvalidUtf8)));
+        let read = read_source(&path, None);
+        assert!(matches!(read, Err(SourceError::InvalidUtf8)));
+    }
+
+    #[test]
+    fn test_missing_file_is_an_io_error() {
+        let dir = TempDir::new("source");
+        let error = read_source(dir.join("missing.rs"), None).err();
+        assert!(matches!(error, Some(SourceError::Io(e)) if e.kind() == io::ErrorKind::NotFound));
+    }
+}
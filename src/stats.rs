@@ -0,0 +1,163 @@
+use crate::ast::{parse_tree, walk, NodeVisitor};
+use crate::language::SupportedLanguage;
+use crate::source::read_source;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::io;
+use std::path::PathBuf;
+use tree_sitter::{Node, Tree};
+
+// Composition categories and the node kinds that count towards them, across the
+// bundled grammars
+const CATEGORIES: &[(&str, &[&str])] = &[
+    (
+        "functions",
+        &[
+            "function_item",
+            "function_declaration",
+            "function_definition",
+            "generator_function_declaration",
+            "method_declaration",
+            "method_definition",
+            "constructor_declaration",
+        ],
+    ),
+    (
+        "closures",
+        &[
+            "closure_expression",
+            "arrow_function",
+            "function_expression",
+            "func_literal",
+            "lambda_expression",
+        ],
+    ),
+    (
+        "classes",
+        &[
+            "class_declaration",
+            "abstract_class_declaration",
+            "class_specifier",
+        ],
+    ),
+    (
+        "structs",
+        &["struct_item", "struct_specifier", "struct_type"],
+    ),
+    (
+        "interfaces",
+        &["trait_item", "interface_declaration", "interface_type"],
+    ),
+    ("unsafe_blocks", &["unsafe_block"]),
+];
+
+pub type KindCounts = BTreeMap<String, usize>;
+
+// How many named nodes of each kind the tree contains
+pub fn count_node_kinds(tree: &Tree) -> KindCounts {
+    let mut counter = KindCounter {
+        counts: KindCounts::new(),
+    };
+    // The counter never performs I/O
+    let _ = walk(tree.root_node(), &mut counter);
+    counter.counts
+}
+
+// Rolls node kind counts up into the composition categories; every category is
+// present, zero or not, so dashboards get a stable shape
+pub fn categorize(counts: &KindCounts) -> BTreeMap<&'static str, usize> {
+    CATEGORIES
+        .iter()
+        .map(|(category, kinds)| {
+            let total = kinds.iter().filter_map(|kind| counts.get(*kind)).sum();
+            (*category, total)
+        })
+        .collect()
+}
+
+// Node statistics per file plus project totals. Files that can't be read or
+// parsed are listed with an `error` and left out of the totals.
+pub fn collect_stats(files: &[PathBuf]) -> Value {
+    let mut total = KindCounts::new();
+    let mut languages: BTreeMap<&'static str, usize> = BTreeMap::new();
+    let mut entries = Vec::new();
+
+    for path in files {
+        let Some(lang) = SupportedLanguage::detect(path) else {
+            continue;
+        };
+        let counted = read_source(path, None)
+            .map_err(|e| e.to_string())
+            .and_then(|source| {
+                parse_tree(&source, &lang.tree_sitter_language()).map_err(|e| e.to_string())
+            })
+            .map(|tree| count_node_kinds(&tree));
+        let path = path.display().to_string();
+        match counted {
+            Ok(counts) => {
+                for (kind, count) in &counts {
+                    *total.entry(kind.clone()).or_default() += count;
+                }
+                *languages.entry(lang.name()).or_default() += 1;
+                entries.push(json!({
+                    "path": path,
+                    "language": lang.name(),
+                    "categories": categorize(&counts),
+                    "nodes": counts,
+                }));
+            }
+            Err(e) => entries.push(json!({ "path": path, "language": lang.name(), "error": e })),
+        }
+    }
+
+    json!({
+        "files": entries,
+        "total": {
+            "files": languages.values().sum::<usize>(),
+            "languages": languages,
+            "categories": categorize(&total),
+            "nodes": total,
+        },
+    })
+}
+
+struct KindCounter {
+    counts: KindCounts,
+}
+
+impl NodeVisitor for KindCounter {
+    fn enter(&mut self, node: &Node, _depth: usize, _first_sibling: bool) -> io::Result<()> {
+        if node.is_named() {
+            *self.counts.entry(node.kind().to_string()).or_default() += 1;
+        }
+        Ok(())
+    }
+
+    fn leave(&mut self, _node: &Node, _depth: usize) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counts_kinds_and_categories() {
+        let source = "struct S;\nfn f() { let g = |x: u32| x; unsafe { g(1); } }\n";
+        let language = tree_sitter_rust::LANGUAGE.into();
+        let tree = parse_tree(source, &language).unwrap();
+
+        let counts = count_node_kinds(&tree);
+        assert_eq!(counts["function_item"], 1);
+        assert_eq!(counts["identifier"], 5);
+        assert!(!counts.contains_key("{"));
+
+        let categories = categorize(&counts);
+        assert_eq!(categories["functions"], 1);
+        assert_eq!(categories["closures"], 1);
+        assert_eq!(categories["structs"], 1);
+        assert_eq!(categories["unsafe_blocks"], 1);
+        assert_eq!(categories["classes"], 0);
+    }
+}
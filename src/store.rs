@@ -0,0 +1,204 @@
+use serde_json::Value;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Bumped whenever the tables below change shape
+pub const SCHEMA_VERSION: u32 = 1;
+
+const SCHEMA: &str = "\
+CREATE TABLE IF NOT EXISTS runs (
+    id INTEGER PRIMARY KEY,
+    started_at TEXT NOT NULL,
+    root TEXT NOT NULL,
+    treescan_version TEXT NOT NULL,
+    files_analyzed INTEGER NOT NULL,
+    files_failed INTEGER NOT NULL,
+    total_issues INTEGER NOT NULL,
+    average_score REAL
+);
+CREATE TABLE IF NOT EXISTS files (
+    id INTEGER PRIMARY KEY,
+    run_id INTEGER NOT NULL REFERENCES runs(id),
+    path TEXT NOT NULL,
+    language TEXT,
+    score REAL,
+    rating TEXT,
+    total_issues INTEGER,
+    lines_of_code INTEGER,
+    error TEXT
+);
+CREATE TABLE IF NOT EXISTS issues (
+    id INTEGER PRIMARY KEY,
+    file_id INTEGER NOT NULL REFERENCES files(id),
+    rule TEXT NOT NULL,
+    severity TEXT NOT NULL,
+    line INTEGER NOT NULL,
+    column INTEGER NOT NULL,
+    message TEXT NOT NULL,
+    score_impact REAL,
+    fingerprint TEXT
+);
+CREATE INDEX IF NOT EXISTS files_run ON files(run_id);
+CREATE INDEX IF NOT EXISTS issues_file ON issues(file_id);
+CREATE INDEX IF NOT EXISTS issues_rule ON issues(rule);
+";
+
+// Appends a directory scan report (see `scan::scan_directory`) to the SQLite
+// database at `db`, creating the schema on first use. There is no SQLite crate in
+// the dependency tree, so the statements are piped through the `sqlite3` CLI.
+pub fn store_report(db: &Path, report: &Value) -> Result<(), Box<dyn std::error::Error>> {
+    let started_at = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let version = schema_version(db)?;
+    if version > SCHEMA_VERSION {
+        return Err(format!(
+            "'{}' has schema version {}, newer than this treescan's {}",
+            db.display(),
+            version,
+            SCHEMA_VERSION
+        )
+        .into());
+    }
+    let sql = render_sql(report, started_at);
+
+    let mut child = Command::new("sqlite3")
+        .arg("-bail")
+        .arg(db)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("could not run sqlite3, which --store needs on PATH: {}", e))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(sql.as_bytes())?;
+    }
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(format!(
+            "sqlite3 failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+        .into());
+    }
+    Ok(())
+}
+
+fn schema_version(db: &Path) -> Result<u32, Box<dyn std::error::Error>> {
+    if !db.exists() {
+        return Ok(0);
+    }
+    let output = Command::new("sqlite3")
+        .arg(db)
+        .arg("PRAGMA user_version;")
+        .output()
+        .map_err(|e| format!("could not run sqlite3, which --store needs on PATH: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "sqlite3 failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+        .into());
+    }
+    let version = String::from_utf8_lossy(&output.stdout);
+    Ok(version.trim().parse().map_err(|_| {
+        format!(
+            "unexpected schema version '{}' from sqlite3",
+            version.trim()
+        )
+    })?)
+}
+
+// One transaction: schema, the run row, then each file and its issues
+pub fn render_sql(report: &Value, started_at: u64) -> String {
+    let summary = &report["summary"];
+    let mut sql = String::from("BEGIN;\n");
+    sql.push_str(SCHEMA);
+    sql.push_str(&format!("PRAGMA user_version = {};\n", SCHEMA_VERSION));
+    sql.push_str(&format!(
+        "INSERT INTO runs (started_at, root, treescan_version, files_analyzed, files_failed, \
+         total_issues, average_score) VALUES (datetime({}, 'unixepoch'), {}, {}, {}, {}, {}, {});\n",
+        started_at,
+        text(&report["root"]),
+        quote(env!("CARGO_PKG_VERSION")),
+        number(&summary["files_analyzed"]),
+        number(&summary["files_failed"]),
+        number(&summary["total_issues"]),
+        number(&summary["average_score"]),
+    ));
+
+    for file in report["files"].as_array().into_iter().flatten() {
+        let result = &file["result"];
+        sql.push_str(&format!(
+            "INSERT INTO files (run_id, path, language, score, rating, total_issues, \
+             lines_of_code, error) VALUES ((SELECT max(id) FROM runs), {}, {}, {}, {}, {}, {}, {});\n",
+            text(&file["path"]),
+            text(&file["language"]),
+            number(&result["score"]),
+            text(&result["rating"]),
+            number(&result["total_issues"]),
+            number(&result["metrics"]["lines_of_code"]),
+            text(&file["error"]),
+        ));
+        for issue in result["issues"].as_array().into_iter().flatten() {
+            sql.push_str(&format!(
+                "INSERT INTO issues (file_id, rule, severity, line, column, message, score_impact, \
+                 fingerprint) VALUES ((SELECT max(id) FROM files), {}, {}, {}, {}, {}, {}, {});\n",
+                text(&issue["rule"]),
+                text(&issue["severity"]),
+                number(&issue["line"]),
+                number(&issue["column"]),
+                text(&issue["message"]),
+                number(&issue["score_impact"]),
+                text(&issue["fingerprint"]),
+            ));
+        }
+    }
+    sql.push_str("COMMIT;\n");
+    sql
+}
+
+fn quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+fn text(value: &Value) -> String {
+    value.as_str().map_or_else(|| "NULL".to_string(), quote)
+}
+
+fn number(value: &Value) -> String {
+    match value {
+        Value::Number(n) => n.to_string(),
+        _ => "NULL".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_render_sql_escapes_and_nests_rows() {
+        let report = json!({
+            "root": "src",
+            "summary": { "files_analyzed": 1, "files_failed": 1, "total_issues": 1, "average_score": 8.5 },
+            "files": [
+                { "path": "src/it's.rs", "language": "Rust", "result": {
+                    "score": 8.5, "rating": "Good", "total_issues": 1, "metrics": { "lines_of_code": 3 },
+                    "issues": [{ "rule": "unwrap_usage", "severity": "Warning", "line": 2,
+                                 "column": 5, "message": "Use of .unwrap()", "score_impact": -2.25,
+                                 "fingerprint": "00ff" }] } },
+                { "path": "src/gone.rs", "error": "No such file" },
+            ],
+        });
+
+        let sql = render_sql(&report, 0);
+        assert!(sql.starts_with("BEGIN;\nCREATE TABLE IF NOT EXISTS runs"));
+        assert!(sql.contains("PRAGMA user_version = 1;"));
+        assert!(sql.contains("'src/it''s.rs', 'Rust', 8.5, 'Good', 1, 3, NULL"));
+        assert!(sql.contains("'src/gone.rs', NULL, NULL, NULL, NULL, NULL, 'No such file'"));
+        assert!(sql.contains("'unwrap_usage', 'Warning', 2, 5, 'Use of .unwrap()', -2.25, '00ff'"));
+        assert!(sql.trim_end().ends_with("COMMIT;"));
+    }
+}
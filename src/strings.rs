@@ -0,0 +1,297 @@
+use crate::ast::parse_tree;
+use crate::language::SupportedLanguage;
+use crate::source::read_source;
+use regex::Regex;
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use tree_sitter::{Node, Tree};
+
+// A string literal as written, for i18n audits and hunting hardcoded URLs or secrets
+#[derive(Debug, Clone, PartialEq)]
+pub struct StringLiteral {
+    // 1-based, byte columns
+    pub line: usize,
+    pub column: usize,
+    // Including quotes and prefixes
+    pub text: String,
+    // Between the delimiters, escapes left as written
+    pub value: String,
+    // The constant the literal initializes, when it is the whole initializer
+    pub constant: Option<String>,
+}
+
+fn literal_kinds(lang: SupportedLanguage) -> &'static [&'static str] {
+    match lang {
+        SupportedLanguage::Rust => &["string_literal", "raw_string_literal"],
+        SupportedLanguage::Go => &["interpreted_string_literal", "raw_string_literal"],
+        SupportedLanguage::JavaScript | SupportedLanguage::TypeScript | SupportedLanguage::Tsx => {
+            &["string", "template_string"]
+        }
+        SupportedLanguage::Java | SupportedLanguage::C => &["string_literal"],
+        SupportedLanguage::Cpp => &["string_literal", "raw_string_literal"],
+        SupportedLanguage::Zig => &["string", "multiline_string"],
+    }
+}
+
+pub fn string_literals(tree: &Tree, source: &str, lang: SupportedLanguage) -> Vec<StringLiteral> {
+    let kinds = literal_kinds(lang);
+    let mut literals = Vec::new();
+    let mut stack = vec![tree.root_node()];
+    while let Some(node) = stack.pop() {
+        if kinds.contains(&node.kind()) {
+            let point = node.start_position();
+            literals.push(StringLiteral {
+                line: point.row + 1,
+                column: point.column + 1,
+                text: text(&node, source).to_string(),
+                value: value(&node, source),
+                constant: constant_name(&node, source),
+            });
+        }
+        // Template substitutions can hold literals of their own
+        let mut cursor = node.walk();
+        let children: Vec<Node> = node.named_children(&mut cursor).collect();
+        stack.extend(children.into_iter().rev());
+    }
+    literals
+}
+
+fn text<'a>(node: &Node, source: &'a str) -> &'a str {
+    node.utf8_text(source.as_bytes()).unwrap_or("")
+}
+
+fn value(node: &Node, source: &str) -> String {
+    // Content and escape children span exactly what sits between the delimiters
+    let last = node.named_child_count().checked_sub(1);
+    if let (Some(first), Some(last)) = (node.named_child(0), last.and_then(|i| node.named_child(i)))
+    {
+        return source[first.start_byte()..last.end_byte()].to_string();
+    }
+    // Zig's `\\` lines have no content node; other literals without one are empty
+    if node.kind() == "multiline_string" {
+        let lines = text(node, source).lines();
+        let lines: Vec<&str> = lines
+            .map(|l| l.trim_start().trim_start_matches("\\\\"))
+            .collect();
+        return lines.join("\n");
+    }
+    String::new()
+}
+
+fn constant_name(node: &Node, source: &str) -> Option<String> {
+    let mut parent = node.parent()?;
+    // Go binds values through an expression list
+    if parent.kind() == "expression_list" && parent.named_child_count() == 1 {
+        parent = parent.parent()?;
+    }
+    let first_token = |n: &Node| n.child(0).map(|t| t.kind().to_string());
+    let name = match parent.kind() {
+        "const_item" | "static_item" | "const_spec" => parent.child_by_field_name("name"),
+        "variable_declarator" => {
+            let declaration = parent.parent()?;
+            let constant = match declaration.kind() {
+                "lexical_declaration" => first_token(&declaration).as_deref() == Some("const"),
+                "field_declaration" | "local_variable_declaration" => {
+                    let mut cursor = declaration.walk();
+                    let modifiers = declaration
+                        .named_children(&mut cursor)
+                        .find(|n| n.kind() == "modifiers");
+                    modifiers
+                        .is_some_and(|m| text(&m, source).split_whitespace().any(|w| w == "final"))
+                }
+                _ => false,
+            };
+            parent.child_by_field_name("name").filter(|_| constant)
+        }
+        "variable_declaration" if first_token(&parent).as_deref() == Some("const") => {
+            let mut cursor = parent.walk();
+            let name = parent
+                .named_children(&mut cursor)
+                .find(|n| n.kind() == "identifier");
+            name
+        }
+        "init_declarator" => {
+            let declaration = parent.parent()?;
+            let mut cursor = declaration.walk();
+            let constant = declaration.named_children(&mut cursor).any(|n| {
+                n.kind() == "type_qualifier" && matches!(text(&n, source), "const" | "constexpr")
+            });
+            let mut declarator = parent.child_by_field_name("declarator")?;
+            while let Some(inner) = declarator.child_by_field_name("declarator") {
+                declarator = inner;
+            }
+            Some(declarator).filter(|_| constant)
+        }
+        _ => None,
+    };
+    name.map(|n| text(&n, source).to_string())
+}
+
+// Every literal in `files` whose value matches `pattern` (all of them without one),
+// with paths relative to `root`
+pub fn strings_report(root: &Path, files: &[PathBuf], pattern: Option<&Regex>) -> Value {
+    let mut entries = Vec::new();
+    for path in files {
+        let Some(lang) = SupportedLanguage::detect(path) else {
+            continue;
+        };
+        let relative = match path.strip_prefix(root) {
+            Ok(rel) if !rel.as_os_str().is_empty() => rel,
+            _ => path.as_path(),
+        };
+        let relative = relative.display().to_string();
+        let literals = read_source(path, None)
+            .map_err(|e| e.to_string())
+            .and_then(|source| {
+                let tree =
+                    parse_tree(&source, &lang.tree_sitter_language()).map_err(|e| e.to_string())?;
+                Ok(string_literals(&tree, &source, lang))
+            });
+        match literals {
+            Ok(literals) => {
+                for literal in literals {
+                    if pattern.is_some_and(|p| !p.is_match(&literal.value)) {
+                        continue;
+                    }
+                    entries.push(json!({
+                        "path": relative,
+                        "line": literal.line,
+                        "column": literal.column,
+                        "text": literal.text,
+                        "value": literal.value,
+                        "constant": literal.constant,
+                    }));
+                }
+            }
+            Err(e) => entries.push(json!({ "path": relative, "error": e })),
+        }
+    }
+    let total = entries.iter().filter(|e| e.get("error").is_none()).count();
+    json!({ "strings": entries, "total": total })
+}
+
+pub fn format_strings_text(report: &Value) -> String {
+    let mut out = String::new();
+    for entry in report["strings"].as_array().into_iter().flatten() {
+        let path = entry["path"].as_str().unwrap_or("");
+        if let Some(error) = entry["error"].as_str() {
+            out.push_str(&format!("{}: error: {}\n", path, error));
+            continue;
+        }
+        let constant = match entry["constant"].as_str() {
+            Some(name) => format!("{} = ", name),
+            None => String::new(),
+        };
+        out.push_str(&format!(
+            "{}:{}:{}  {}{}\n",
+            path,
+            entry["line"],
+            entry["column"],
+            constant,
+            entry["text"].as_str().unwrap_or("")
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    const JAVA: &str = "class A { static final String U = \"http://a\"; String s = \"hi\"; }\n";
+
+    fn literals(source: &str, lang: SupportedLanguage) -> Vec<(String, Option<String>)> {
+        let tree = parse_tree(source, &lang.tree_sitter_language()).unwrap();
+        string_literals(&tree, source, lang)
+            .into_iter()
+            .map(|l| (l.value, l.constant))
+            .collect()
+    }
+
+    fn named(value: &str, constant: Option<&str>) -> (String, Option<String>) {
+        (value.to_string(), constant.map(str::to_string))
+    }
+
+    #[test]
+    fn test_rust_literals_keep_escapes_and_constants() {
+        assert_eq!(
+            literals(
+                "const URL: &str = \"https://x\";\nfn f() { g(r#\"raw\"#, \"a\\n\"); }\n",
+                SupportedLanguage::Rust
+            ),
+            vec![
+                named("https://x", Some("URL")),
+                named("raw", None),
+                named("a\\n", None)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_zig_multiline_strings_join_lines() {
+        assert_eq!(
+            literals(
+                "const m =\n    \\\\a\n    \\\\b\n;\n",
+                SupportedLanguage::Zig
+            ),
+            vec![named("a\nb", Some("m"))]
+        );
+    }
+
+    #[test]
+    fn test_go_and_javascript_literals() {
+        assert_eq!(
+            literals(
+                "package m\nconst Key = \"k\"\nvar v = ``\n",
+                SupportedLanguage::Go
+            ),
+            vec![named("k", Some("Key")), named("", None)]
+        );
+        assert_eq!(
+            literals(
+                "const a = `t${'in'}`; let b = \"x\";",
+                SupportedLanguage::JavaScript
+            ),
+            vec![
+                named("t${'in'}", Some("a")),
+                named("in", None),
+                named("x", None)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_report_filters_by_pattern() {
+        let dir = TempDir::new("strings");
+        let file = dir.write("A.java", JAVA);
+        let urls = Regex::new("^https?://").unwrap();
+
+        let report = strings_report(&dir, std::slice::from_ref(&file), Some(&urls));
+        assert_eq!(report["total"], 1);
+        assert_eq!(report["strings"][0]["constant"], "U");
+        assert_eq!(
+            format_strings_text(&report),
+            "A.java:1:35  U = \"http://a\"\n"
+        );
+
+        let all = strings_report(&dir, &[file], None);
+        assert_eq!(all["total"], 2);
+        assert_eq!(all["strings"][1]["value"], "hi");
+    }
+
+    #[test]
+    fn test_report_skips_unsupported_and_records_unreadable_files() {
+        let dir = TempDir::new("strings");
+        let files = vec![
+            dir.write("notes.txt", "\"not code\""),
+            dir.join("Missing.java"),
+        ];
+
+        let report = strings_report(&dir, &files, None);
+        assert_eq!(report["total"], 0);
+        assert_eq!(report["strings"].as_array().unwrap().len(), 1);
+        assert_eq!(report["strings"][0]["path"], "Missing.java");
+        assert!(format_strings_text(&report).starts_with("Missing.java: error: "));
+    }
+}
@@ -0,0 +1,494 @@
+use crate::analyzer::Severity;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+
+// Human-readable renderings of an `analyze` report, for terminals and PR comments
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Json,
+    Text,
+    Markdown,
+}
+
+impl ReportFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "json" => Some(ReportFormat::Json),
+            "text" => Some(ReportFormat::Text),
+            "markdown" | "md" => Some(ReportFormat::Markdown),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GroupBy {
+    #[default]
+    File,
+    // One section per rule, for triaging one class of issue across a repo
+    Rule,
+    Severity,
+}
+
+impl GroupBy {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "file" => Some(GroupBy::File),
+            "rule" => Some(GroupBy::Rule),
+            "severity" => Some(GroupBy::Severity),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Finding<'a> {
+    pub path: &'a str,
+    pub rule: &'a str,
+    pub severity: &'a str,
+    pub line: u64,
+    pub column: u64,
+    pub message: &'a str,
+    // Findings summarized by an overflow entry (see `overflow::cap_per_rule`), else 0
+    pub overflow: u64,
+}
+
+// How many findings `items` stand for, counting each overflow entry's findings
+fn count(items: &[Finding]) -> usize {
+    items.iter().map(|f| f.overflow.max(1) as usize).sum()
+}
+
+// Flattens a directory report, or a single-file report analyzed from `path`
+pub fn findings<'a>(report: &'a Value, path: &'a str) -> Vec<Finding<'a>> {
+    let files: Vec<(&str, &Value)> = match report["files"].as_array() {
+        Some(files) => files
+            .iter()
+            .map(|file| (file["path"].as_str().unwrap_or(""), &file["result"]))
+            .collect(),
+        None => vec![(path, report)],
+    };
+    files
+        .into_iter()
+        .flat_map(|(path, result)| {
+            result["issues"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .map(move |issue| Finding {
+                    path,
+                    rule: issue["rule"].as_str().unwrap_or(""),
+                    severity: issue["severity"].as_str().unwrap_or(""),
+                    line: issue["line"].as_u64().unwrap_or(0),
+                    column: issue["column"].as_u64().unwrap_or(0),
+                    message: issue["message"].as_str().unwrap_or(""),
+                    overflow: issue["overflow"].as_u64().unwrap_or(0),
+                })
+        })
+        .collect()
+}
+
+// How many findings have one of `severities`
+pub fn count_at_severities(report: &Value, path: &str, severities: &[Severity]) -> usize {
+    findings(report, path)
+        .iter()
+        .filter(|finding| severities.iter().any(|s| s.as_str() == finding.severity))
+        .count()
+}
+
+fn severity_rank(severity: &str) -> usize {
+    ["Error", "Warning", "Info", "Style"]
+        .iter()
+        .position(|s| *s == severity)
+        .unwrap_or(4)
+}
+
+// Groups in display order: files by path, rules by descending count, severities
+// from Error down; findings keep source order within a group
+fn group<'a>(findings: &[Finding<'a>], by: GroupBy) -> Vec<(String, Vec<Finding<'a>>)> {
+    let mut groups: BTreeMap<String, Vec<Finding>> = BTreeMap::new();
+    for finding in findings {
+        let key = match by {
+            GroupBy::File => finding.path,
+            GroupBy::Rule => finding.rule,
+            GroupBy::Severity => finding.severity,
+        };
+        groups
+            .entry(key.to_string())
+            .or_default()
+            .push(finding.clone());
+    }
+    let mut groups: Vec<(String, Vec<Finding>)> = groups.into_iter().collect();
+    match by {
+        GroupBy::File => {}
+        GroupBy::Rule => groups.sort_by_key(|(_, items)| std::cmp::Reverse(count(items))),
+        GroupBy::Severity => groups.sort_by_key(|(severity, _)| severity_rank(severity)),
+    }
+    for (_, items) in &mut groups {
+        items.sort_by(|a, b| (a.path, a.line, a.column).cmp(&(b.path, b.line, b.column)));
+    }
+    groups
+}
+
+fn totals(findings: &[Finding]) -> String {
+    let files: BTreeSet<&str> = findings.iter().map(|f| f.path).collect();
+    let plural = |n: usize, word: &str| format!("{} {}{}", n, word, if n == 1 { "" } else { "s" });
+    format!(
+        "{} in {}",
+        plural(count(findings), "issue"),
+        plural(files.len(), "file")
+    )
+}
+
+pub fn format_text(findings: &[Finding], by: GroupBy) -> String {
+    let mut out = String::new();
+    for (key, items) in group(findings, by) {
+        out.push_str(&format!("{} ({})\n", key, count(&items)));
+        let rows: Vec<Vec<String>> = items
+            .iter()
+            .map(|f| {
+                let location = match by {
+                    GroupBy::File => format!("{}:{}", f.line, f.column),
+                    _ => format!("{}:{}:{}", f.path, f.line, f.column),
+                };
+                match by {
+                    GroupBy::Rule => vec![location, f.severity.into(), f.message.into()],
+                    GroupBy::Severity => vec![location, f.rule.into(), f.message.into()],
+                    GroupBy::File => {
+                        vec![location, f.severity.into(), f.rule.into(), f.message.into()]
+                    }
+                }
+            })
+            .collect();
+        out.push_str(&text_table(&rows));
+        out.push('\n');
+    }
+    out.push_str(&totals(findings));
+    out.push('\n');
+    out
+}
+
+pub fn format_markdown(findings: &[Finding], by: GroupBy) -> String {
+    let mut out = String::new();
+    let headings = match by {
+        GroupBy::File => vec!["Line", "Severity", "Rule", "Message"],
+        GroupBy::Rule => vec!["Location", "Severity", "Message"],
+        GroupBy::Severity => vec!["Location", "Rule", "Message"],
+    };
+    for (key, items) in group(findings, by) {
+        out.push_str(&format!("### {} ({})\n\n", escape(&key), count(&items)));
+        let rows: Vec<Vec<String>> = items
+            .iter()
+            .map(|f| {
+                let location = format!("`{}:{}:{}`", f.path, f.line, f.column);
+                let message = escape(f.message);
+                match by {
+                    GroupBy::File => vec![
+                        format!("{}:{}", f.line, f.column),
+                        f.severity.into(),
+                        format!("`{}`", f.rule),
+                        message,
+                    ],
+                    GroupBy::Rule => vec![location, f.severity.into(), message],
+                    GroupBy::Severity => vec![location, format!("`{}`", f.rule), message],
+                }
+            })
+            .collect();
+        out.push_str(&markdown_table(&headings, &rows));
+        out.push('\n');
+    }
+    out.push_str(&format!("**{}**\n", totals(findings)));
+    out
+}
+
+// The findings, then the report-wide sections a directory report carries
+// (`directories`, ...), each as a table
+pub fn format_report(report: &Value, path: &str, format: ReportFormat, by: GroupBy) -> String {
+    let findings = findings(report, path);
+    let mut out = match format {
+        ReportFormat::Markdown => format_markdown(&findings, by),
+        _ => format_text(&findings, by),
+    };
+    for section in sections(report) {
+        out.push('\n');
+        match format {
+            ReportFormat::Markdown => {
+                out.push_str(&format!("### {}\n\n", section.title));
+                let rows: Vec<Vec<String>> = section
+                    .rows
+                    .iter()
+                    .map(|row| row.iter().map(|cell| escape(cell)).collect())
+                    .collect();
+                out.push_str(&markdown_table(&section.headings, &rows));
+            }
+            _ => {
+                out.push_str(&format!("{}\n", section.title));
+                let headings = section.headings.iter().map(|h| h.to_string()).collect();
+                let rows: Vec<Vec<String>> =
+                    std::iter::once(headings).chain(section.rows).collect();
+                out.push_str(&text_table(&rows));
+            }
+        }
+    }
+    out
+}
+
+// A report-wide table: its title, column headings and rows
+struct Section {
+    title: &'static str,
+    headings: Vec<&'static str>,
+    rows: Vec<Vec<String>>,
+}
+
+fn sections(report: &Value) -> Vec<Section> {
+    let mut sections = Vec::new();
+    let mut add = |title, headings, entries: &Value, keys: &[&str]| {
+        if let Some(entries) = entries.as_array().filter(|e| !e.is_empty()) {
+            sections.push(Section {
+                title,
+                headings,
+                rows: entries
+                    .iter()
+                    .map(|entry| keys.iter().map(|key| cell(&entry[*key])).collect())
+                    .collect(),
+            });
+        }
+    };
+    // --top
+    add(
+        "Lowest-scoring files",
+        vec!["File", "Score", "Rating", "Issues"],
+        &report["top"]["lowest_scoring_files"],
+        &["path", "score", "rating", "total_issues"],
+    );
+    add(
+        "Most violated rules",
+        vec!["Rule", "Violations", "Files"],
+        &report["top"]["most_violated_rules"],
+        &["rule", "count", "files"],
+    );
+    add(
+        "Languages",
+        vec![
+            "Language",
+            "Files",
+            "Lines of code",
+            "Average score",
+            "Issues",
+            "Top rules",
+        ],
+        &report["languages"],
+        &[
+            "language",
+            "files",
+            "lines_of_code",
+            "average_score",
+            "total_issues",
+            "top_rules",
+        ],
+    );
+    add(
+        "Directories",
+        vec![
+            "Directory",
+            "Files",
+            "Average score",
+            "Issues",
+            "Remediation minutes",
+        ],
+        &report["directories"],
+        &[
+            "path",
+            "files",
+            "average_score",
+            "total_issues",
+            "remediation_minutes",
+        ],
+    );
+    sections
+}
+
+fn cell(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        // `top_rules`
+        Value::Array(rules) => rules
+            .iter()
+            .map(|rule| format!("{} ({})", cell(&rule["rule"]), cell(&rule["count"])))
+            .collect::<Vec<_>>()
+            .join(", "),
+        Value::Null => "-".to_string(),
+        other => other.to_string(),
+    }
+}
+
+// Each column padded to its widest cell, two spaces apart, indented under a heading.
+// The last column is left ragged.
+fn text_table(rows: &[Vec<String>]) -> String {
+    let widths = column_widths(rows);
+    let mut out = String::new();
+    for row in rows {
+        let mut line = String::from(" ");
+        for (i, cell) in row.iter().enumerate() {
+            line.push(' ');
+            if i + 1 == row.len() {
+                line.push_str(cell);
+            } else {
+                line.push_str(&pad(cell, widths[i]));
+                line.push(' ');
+            }
+        }
+        out.push_str(line.trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+// A markdown table padded so the columns line up in the raw text too
+fn markdown_table(headings: &[&str], rows: &[Vec<String>]) -> String {
+    let headings: Vec<String> = headings.iter().map(|h| h.to_string()).collect();
+    let all: Vec<Vec<String>> = std::iter::once(headings.clone())
+        .chain(rows.iter().cloned())
+        .collect();
+    // Three dashes at least, for the separator row
+    let widths: Vec<usize> = column_widths(&all).into_iter().map(|w| w.max(3)).collect();
+    let line = |cells: Vec<String>| format!("| {} |\n", cells.join(" | "));
+    let mut out = line(
+        headings
+            .iter()
+            .zip(&widths)
+            .map(|(h, w)| pad(h, *w))
+            .collect(),
+    );
+    out.push_str(&line(widths.iter().map(|w| "-".repeat(*w)).collect()));
+    for row in rows {
+        out.push_str(&line(
+            row.iter().zip(&widths).map(|(c, w)| pad(c, *w)).collect(),
+        ));
+    }
+    out
+}
+
+fn column_widths(rows: &[Vec<String>]) -> Vec<usize> {
+    let mut widths = Vec::new();
+    for row in rows {
+        for (i, cell) in row.iter().enumerate() {
+            let width = cell.chars().count();
+            match widths.get_mut(i) {
+                Some(max) => *max = width.max(*max),
+                None => widths.push(width),
+            }
+        }
+    }
+    widths
+}
+
+fn pad(text: &str, width: usize) -> String {
+    format!(
+        "{}{}",
+        text,
+        " ".repeat(width.saturating_sub(text.chars().count()))
+    )
+}
+
+// Pipes would end a table cell early
+fn escape(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_group_by_rule_orders_by_count() {
+        let issue = |rule: &str, severity: &str, line: u64| {
+            json!({ "rule": rule, "severity": severity, "line": line, "column": 1, "message": "m" })
+        };
+        let report = json!({ "files": [
+            { "path": "a.rs", "result": { "issues": [issue("unwrap_usage", "Warning", 3)] } },
+            { "path": "b.rs", "result": { "issues": [
+                issue("todo_comment", "Info", 1),
+                issue("unwrap_usage", "Warning", 9),
+            ] } },
+        ] });
+        let findings = findings(&report, "");
+
+        let text = format_text(&findings, GroupBy::Rule);
+        let headings: Vec<&str> = text.lines().filter(|l| !l.starts_with(' ')).collect();
+        assert_eq!(
+            headings,
+            vec![
+                "unwrap_usage (2)",
+                "",
+                "todo_comment (1)",
+                "",
+                "3 issues in 2 files"
+            ]
+        );
+        assert!(text.contains("  b.rs:9:1  Warning  m\n"));
+        assert_eq!(count_at_severities(&report, "", &[Severity::Warning]), 2);
+        assert_eq!(count_at_severities(&report, "", &[Severity::Error]), 0);
+
+        let markdown = format_markdown(&findings, GroupBy::Severity);
+        assert!(markdown.starts_with("### Warning (2)\n"));
+        assert!(markdown.contains("| `b.rs:1:1` | `todo_comment` | m       |"));
+
+        // Directory reports add their report-wide tables, aligned
+        let mut report = report;
+        report["root"] = json!("");
+        report["directories"] = json!([
+            { "path": "src", "files": 12, "average_score": 7.5, "total_issues": 40, "remediation_minutes": 300 },
+            { "path": "src/api", "files": 2, "average_score": 9.0, "total_issues": 1, "remediation_minutes": 5 },
+        ]);
+        let text = format_report(&report, "", ReportFormat::Text, GroupBy::File);
+        assert!(text.contains(
+            "3 issues in 2 files\n\nDirectories\n\
+             \x20 Directory  Files  Average score  Issues  Remediation minutes\n\
+             \x20 src        12     7.5            40      300\n\
+             \x20 src/api    2      9.0            1       5\n"
+        ));
+        report["top"] = json!({
+            "lowest_scoring_files": [
+                { "path": "b.rs", "score": 6.25, "rating": "Fair", "total_issues": 2 },
+                { "path": "a.rs", "score": 8.0, "rating": "Good", "total_issues": 1 },
+            ],
+            "most_violated_rules": [{ "rule": "unwrap_usage", "count": 2, "files": 2 }],
+        });
+        let text = format_report(&report, "", ReportFormat::Text, GroupBy::File);
+        assert!(text.contains(
+            "Lowest-scoring files\n\
+             \x20 File  Score  Rating  Issues\n\
+             \x20 b.rs  6.25   Fair    2\n\
+             \x20 a.rs  8.0    Good    1\n\
+             \nMost violated rules\n\
+             \x20 Rule          Violations  Files\n\
+             \x20 unwrap_usage  2           2\n\
+             \nDirectories\n"
+        ));
+        report["languages"] = json!([{
+            "language": "Rust", "files": 2, "lines_of_code": 120, "average_score": 7.1,
+            "total_issues": 3, "top_rules": [
+                { "rule": "unwrap_usage", "count": 2 }, { "rule": "todo_comment", "count": 1 }
+            ]
+        }]);
+        let text = format_report(&report, "", ReportFormat::Text, GroupBy::File);
+        assert!(text.contains(
+            "Languages\n\
+             \x20 Language  Files  Lines of code  Average score  Issues  Top rules\n\
+             \x20 Rust      2      120            7.1            3       unwrap_usage (2), todo_comment (1)\n\
+             \nDirectories\n"
+        ));
+        let markdown = format_report(&report, "", ReportFormat::Markdown, GroupBy::File);
+        assert!(markdown.contains("| Rust     | 2     | 120           | 7.1           | 3      |"));
+        assert!(
+            markdown.contains("### Lowest-scoring files\n\n| File | Score | Rating | Issues |\n")
+        );
+        assert!(markdown.contains(
+            "### Directories\n\n\
+             | Directory | Files | Average score | Issues | Remediation minutes |\n\
+             | --------- | ----- | ------------- | ------ | ------------------- |\n\
+             | src       | 12    | 7.5           | 40     | 300                 |\n"
+        ));
+    }
+}
@@ -0,0 +1,113 @@
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+// Per-rule query time summed over every file a run analyzes, for `--timings`. Off
+// unless enabled, so analyzers pay one atomic load per rule.
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static TIMINGS: Mutex<BTreeMap<(String, String), RuleTiming>> = Mutex::new(BTreeMap::new());
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RuleTiming {
+    pub language: String,
+    pub rule: String,
+    pub files: usize,
+    pub findings: usize,
+    pub total: Duration,
+    // The single slowest file, which points at input the query handles badly
+    pub slowest: Duration,
+}
+
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+// One rule's run over one file
+pub fn record(language: &str, rule: &str, findings: usize, elapsed: Duration) {
+    let mut timings = TIMINGS.lock().unwrap_or_else(|e| e.into_inner());
+    let entry = timings
+        .entry((language.to_string(), rule.to_string()))
+        .or_insert_with(|| RuleTiming {
+            language: language.to_string(),
+            rule: rule.to_string(),
+            ..RuleTiming::default()
+        });
+    entry.files += 1;
+    entry.findings += findings;
+    entry.total += elapsed;
+    entry.slowest = entry.slowest.max(elapsed);
+}
+
+// Everything recorded so far, slowest rule first, and starts over
+pub fn take() -> Vec<RuleTiming> {
+    let timings = std::mem::take(&mut *TIMINGS.lock().unwrap_or_else(|e| e.into_inner()));
+    let mut timings: Vec<RuleTiming> = timings.into_values().collect();
+    timings.sort_by_key(|t| Reverse(t.total));
+    timings
+}
+
+fn millis(duration: Duration) -> f64 {
+    (duration.as_secs_f64() * 1000.0 * 1000.0).round() / 1000.0
+}
+
+pub fn format_timings_text(timings: &[RuleTiming]) -> String {
+    let total: Duration = timings.iter().map(|t| t.total).sum();
+    let mut out = format!(
+        "{:>10}  {:>6}  {:>10}  {:>6}  {:<12} rule\n",
+        "total ms", "share", "slowest ms", "files", "language"
+    );
+    for t in timings {
+        let share = match total.is_zero() {
+            true => 0.0,
+            false => t.total.as_secs_f64() / total.as_secs_f64() * 100.0,
+        };
+        out.push_str(&format!(
+            "{:>10.3}  {:>5.1}%  {:>10.3}  {:>6}  {:<12} {}\n",
+            millis(t.total),
+            share,
+            millis(t.slowest),
+            t.files,
+            t.language,
+            t.rule
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::CodeAnalyzer;
+
+    #[test]
+    fn test_rule_timings_aggregate_across_files() {
+        enable();
+        let analyzer = CodeAnalyzer::new_rust_analyzer();
+        let language = tree_sitter_rust::LANGUAGE.into();
+        for source in [
+            "fn a() { x.unwrap(); }",
+            "fn b() { y.unwrap(); z.unwrap(); }",
+        ] {
+            analyzer.analyze(source, &language).unwrap();
+        }
+        // Other tests analyze concurrently, so only check this rule adds up
+        let timings = take();
+        let unwrap = timings
+            .iter()
+            .find(|t| t.language == "Rust" && t.rule == "unwrap_usage")
+            .unwrap();
+        assert!(unwrap.files >= 2 && unwrap.findings >= 3);
+        assert!(unwrap.slowest <= unwrap.total);
+        assert!(timings.windows(2).all(|w| w[0].total >= w[1].total));
+
+        let text = format_timings_text(&timings);
+        assert!(text.starts_with("  total ms   share"));
+        assert!(text.contains(" unwrap_usage\n"));
+    }
+}
@@ -0,0 +1,184 @@
+// Tracing spans for file read, parse, per-rule query and serialization. With the
+// `otel` feature they are collected and exported by `flush` as OTLP/HTTP JSON;
+// without it `Span` is empty and every call compiles away.
+
+pub struct Span {
+    #[cfg(feature = "otel")]
+    inner: otel::ActiveSpan,
+}
+
+#[cfg_attr(not(feature = "otel"), allow(unused_variables))]
+pub fn span(name: &'static str) -> Span {
+    Span {
+        #[cfg(feature = "otel")]
+        inner: otel::ActiveSpan::start(name),
+    }
+}
+
+impl Span {
+    #[cfg_attr(not(feature = "otel"), allow(unused_mut, unused_variables))]
+    pub fn attr(mut self, key: &'static str, value: &str) -> Self {
+        #[cfg(feature = "otel")]
+        self.inner.attributes.push((key, value.to_string()));
+        self
+    }
+}
+
+// Sends the spans recorded so far to `$OTEL_EXPORTER_OTLP_ENDPOINT` (default
+// http://localhost:4318); export failures are reported but never fatal
+pub fn flush() {
+    #[cfg(feature = "otel")]
+    if let Err(e) = otel::export() {
+        crate::logging::warn(&format!("Failed to export traces: {}", e));
+    }
+}
+
+#[cfg(feature = "otel")]
+mod otel {
+    use crate::cache::stable_hash;
+    use serde_json::{json, Value};
+    use std::cell::RefCell;
+    use std::io::{Read, Write};
+    use std::net::TcpStream;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::{Mutex, OnceLock};
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    const DEFAULT_ENDPOINT: &str = "http://localhost:4318";
+
+    static FINISHED: Mutex<Vec<Value>> = Mutex::new(Vec::new());
+    static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+    thread_local! {
+        // Open spans on this thread; the innermost is the parent of the next one
+        static STACK: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+    }
+
+    pub struct ActiveSpan {
+        name: &'static str,
+        span_id: String,
+        parent_id: Option<String>,
+        start: u128,
+        pub attributes: Vec<(&'static str, String)>,
+    }
+
+    impl ActiveSpan {
+        pub fn start(name: &'static str) -> Self {
+            let span_id = format!("{:016x}", random_u64());
+            let parent_id = STACK.with(|stack| {
+                let mut stack = stack.borrow_mut();
+                let parent = stack.last().cloned();
+                stack.push(span_id.clone());
+                parent
+            });
+            ActiveSpan {
+                name,
+                span_id,
+                parent_id,
+                start: now_nanos(),
+                attributes: Vec::new(),
+            }
+        }
+    }
+
+    impl Drop for ActiveSpan {
+        fn drop(&mut self) {
+            STACK.with(|stack| {
+                stack.borrow_mut().retain(|id| id != &self.span_id);
+            });
+            let attributes: Vec<Value> = self
+                .attributes
+                .iter()
+                .map(|(key, value)| json!({ "key": key, "value": { "stringValue": value } }))
+                .collect();
+            let span = json!({
+                "traceId": trace_id(),
+                "spanId": self.span_id,
+                "parentSpanId": self.parent_id.clone().unwrap_or_default(),
+                "name": self.name,
+                "kind": 1,
+                "startTimeUnixNano": self.start.to_string(),
+                "endTimeUnixNano": now_nanos().to_string(),
+                "attributes": attributes,
+            });
+            if let Ok(mut finished) = FINISHED.lock() {
+                finished.push(span);
+            }
+        }
+    }
+
+    // One trace per process run
+    fn trace_id() -> &'static str {
+        static TRACE_ID: OnceLock<String> = OnceLock::new();
+        TRACE_ID.get_or_init(|| format!("{:016x}{:016x}", random_u64(), random_u64()))
+    }
+
+    // Unique enough for span ids without pulling in a random number crate
+    fn random_u64() -> u64 {
+        let seed = format!(
+            "{}:{}:{}",
+            now_nanos(),
+            std::process::id(),
+            NEXT_ID.fetch_add(1, Ordering::Relaxed)
+        );
+        stable_hash(seed.as_bytes())
+    }
+
+    fn now_nanos() -> u128 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or(0)
+    }
+
+    pub fn export() -> Result<(), Box<dyn std::error::Error>> {
+        let spans = std::mem::take(&mut *FINISHED.lock().map_err(|e| e.to_string())?);
+        if spans.is_empty() {
+            return Ok(());
+        }
+        let service = std::env::var("OTEL_SERVICE_NAME").unwrap_or_else(|_| "treescan".to_string());
+        let body = json!({ "resourceSpans": [{
+            "resource": { "attributes": [
+                { "key": "service.name", "value": { "stringValue": service } },
+            ] },
+            "scopeSpans": [{
+                "scope": { "name": "treescan", "version": env!("CARGO_PKG_VERSION") },
+                "spans": spans,
+            }],
+        }] })
+        .to_string();
+
+        let endpoint = std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT")
+            .unwrap_or_else(|_| DEFAULT_ENDPOINT.to_string());
+        let authority = endpoint
+            .strip_prefix("http://")
+            .ok_or("only http:// OTLP endpoints are supported")?
+            .trim_end_matches('/');
+        let (host, base) = match authority.split_once('/') {
+            Some((host, path)) => (host, format!("/{}", path)),
+            None => (authority, String::new()),
+        };
+
+        let mut stream = TcpStream::connect(host)?;
+        stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+        write!(
+            stream,
+            "POST {}/v1/traces HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            base,
+            host,
+            body.len(),
+            body
+        )?;
+        let mut response = String::new();
+        stream.read_to_string(&mut response)?;
+        let status = response.lines().next().unwrap_or("");
+        if !status
+            .split_whitespace()
+            .nth(1)
+            .is_some_and(|code| code.starts_with('2'))
+        {
+            return Err(format!("collector answered '{}'", status).into());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,540 @@
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Write};
+use std::process::Command;
+
+// One finding as shown in the issue list; line and column are 1-based
+#[derive(Debug, Clone, PartialEq)]
+pub struct TuiIssue {
+    pub line: usize,
+    pub column: usize,
+    pub rule: String,
+    pub severity: String,
+    pub message: String,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TuiFile {
+    pub path: String,
+    pub score: Option<f64>,
+    pub issues: Vec<TuiIssue>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Pane {
+    Files,
+    Issues,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Key {
+    Up,
+    Down,
+    Left,
+    Right,
+    Tab,
+    Enter,
+    Escape,
+    Char(char),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Action {
+    None,
+    Quit,
+    // Open `path` at `line`:`column` in the editor
+    Open(String, usize, usize),
+}
+
+// Navigation state of the results browser, independent of the terminal
+#[derive(Debug, Clone, PartialEq)]
+pub struct TuiState {
+    pub files: Vec<TuiFile>,
+    pub pane: Pane,
+    pub file: usize,
+    pub issue: usize,
+}
+
+impl TuiState {
+    // Files from a directory scan report, lowest score first; files that failed to
+    // analyze sort last with no score
+    pub fn from_report(report: &Value) -> Self {
+        let mut files: Vec<TuiFile> = report["files"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|file| {
+                let result = &file["result"];
+                let mut issues: Vec<TuiIssue> = result["issues"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .map(|issue| TuiIssue {
+                        line: issue["line"].as_u64().unwrap_or(1) as usize,
+                        column: issue["column"].as_u64().unwrap_or(1) as usize,
+                        rule: issue["rule"].as_str().unwrap_or("").to_string(),
+                        severity: issue["severity"].as_str().unwrap_or("").to_string(),
+                        message: issue["message"].as_str().unwrap_or("").to_string(),
+                        text: issue["text"].as_str().unwrap_or("").to_string(),
+                    })
+                    .collect();
+                issues.sort_by_key(|issue| (issue.line, issue.column));
+                Some(TuiFile {
+                    path: file["path"].as_str()?.to_string(),
+                    score: result["score"].as_f64(),
+                    issues,
+                })
+            })
+            .collect();
+        files.sort_by(|a, b| {
+            let score = |f: &TuiFile| f.score.unwrap_or(f64::INFINITY);
+            score(a)
+                .total_cmp(&score(b))
+                .then_with(|| a.path.cmp(&b.path))
+        });
+        TuiState {
+            files,
+            pane: Pane::Files,
+            file: 0,
+            issue: 0,
+        }
+    }
+
+    pub fn selected_file(&self) -> Option<&TuiFile> {
+        self.files.get(self.file)
+    }
+
+    pub fn selected_issue(&self) -> Option<&TuiIssue> {
+        self.selected_file()?.issues.get(self.issue)
+    }
+
+    pub fn handle_key(&mut self, key: Key) -> Action {
+        let issue_count = self.selected_file().map_or(0, |f| f.issues.len());
+        match (key, self.pane) {
+            (Key::Char('q'), _) | (Key::Char('\u{3}'), _) => return Action::Quit,
+            (Key::Escape, Pane::Files) => return Action::Quit,
+            (Key::Escape, Pane::Issues) | (Key::Left | Key::Char('h'), _) => {
+                self.pane = Pane::Files
+            }
+            (Key::Tab, Pane::Files) | (Key::Right | Key::Char('l') | Key::Enter, Pane::Files) => {
+                self.pane = Pane::Issues
+            }
+            (Key::Tab, Pane::Issues) => self.pane = Pane::Files,
+            (Key::Up | Key::Char('k'), Pane::Files) => {
+                self.file = self.file.saturating_sub(1);
+                self.issue = 0;
+            }
+            (Key::Down | Key::Char('j'), Pane::Files) => {
+                self.file = (self.file + 1).min(self.files.len().saturating_sub(1));
+                self.issue = 0;
+            }
+            (Key::Up | Key::Char('k'), Pane::Issues) => self.issue = self.issue.saturating_sub(1),
+            (Key::Down | Key::Char('j'), Pane::Issues) => {
+                self.issue = (self.issue + 1).min(issue_count.saturating_sub(1))
+            }
+            (Key::Enter, Pane::Issues) | (Key::Char('e' | 'o'), _) => {
+                if let Some(file) = self.selected_file() {
+                    let (line, column) = self
+                        .selected_issue()
+                        .filter(|_| self.pane == Pane::Issues || key == Key::Enter)
+                        .or(file.issues.first())
+                        .map_or((1, 1), |issue| (issue.line, issue.column));
+                    return Action::Open(file.path.clone(), line, column);
+                }
+            }
+            _ => {}
+        }
+        Action::None
+    }
+
+    // The screen as `height` lines of at most `width` visible characters.
+    // `source` holds the selected file's lines for the preview.
+    pub fn render(&self, width: usize, height: usize, source: Option<&[String]>) -> Vec<String> {
+        let width = width.max(20);
+        let height = height.max(6);
+        let left = (width / 3).clamp(12, 48);
+        let right = width - left - 1;
+        let body = height - 2;
+        let issue_rows = (body / 2).max(1);
+        let preview_rows = body - issue_rows - 1;
+
+        let average = {
+            let scores: Vec<f64> = self.files.iter().filter_map(|f| f.score).collect();
+            if scores.is_empty() {
+                0.0
+            } else {
+                scores.iter().sum::<f64>() / scores.len() as f64
+            }
+        };
+        let mut lines = vec![style(
+            &fit(
+                &format!(
+                    " treescan  {} file(s)  average score {:.1}",
+                    self.files.len(),
+                    average
+                ),
+                width,
+            ),
+            "7",
+        )];
+
+        let file_cells = self.file_cells(left, body);
+        let issue_cells = self.issue_cells(right, issue_rows);
+        let preview_cells = self.preview_cells(right, preview_rows, source);
+        let separator = style(&fit(&"─".repeat(right), right), "2");
+        let right_cells = issue_cells
+            .into_iter()
+            .chain(std::iter::once(separator))
+            .chain(preview_cells);
+        for (file_cell, right_cell) in file_cells.into_iter().zip(right_cells) {
+            lines.push(format!("{}{}{}", file_cell, style("│", "2"), right_cell));
+        }
+
+        lines.push(style(
+            &fit(
+                " ↑↓/jk move  Tab/←→ switch pane  Enter/e open in $EDITOR  q quit",
+                width,
+            ),
+            "7",
+        ));
+        lines
+    }
+
+    fn file_cells(&self, width: usize, rows: usize) -> Vec<String> {
+        let offset = scroll_offset(self.file, rows);
+        (offset..offset + rows)
+            .map(|i| match self.files.get(i) {
+                Some(file) => {
+                    let score = file
+                        .score
+                        .map_or("  - ".to_string(), |s| format!("{:>4.1}", s));
+                    let text = fit(&format!("{} {}", score, file.path), width);
+                    if i == self.file {
+                        style(&text, if self.pane == Pane::Files { "7" } else { "1" })
+                    } else {
+                        text
+                    }
+                }
+                None => " ".repeat(width),
+            })
+            .collect()
+    }
+
+    fn issue_cells(&self, width: usize, rows: usize) -> Vec<String> {
+        let issues = self.selected_file().map_or(&[][..], |f| &f.issues[..]);
+        if issues.is_empty() {
+            let mut cells = vec![fit(" No issues", width)];
+            cells.resize(rows, " ".repeat(width));
+            return cells;
+        }
+        let offset = scroll_offset(self.issue, rows);
+        (offset..offset + rows)
+            .map(|i| match issues.get(i) {
+                Some(issue) => {
+                    let text = fit(
+                        &format!(
+                            "{:>5}:{:<3} {:<7} {} {}",
+                            issue.line, issue.column, issue.severity, issue.rule, issue.message
+                        ),
+                        width,
+                    );
+                    if i == self.issue && self.pane == Pane::Issues {
+                        style(&text, "7")
+                    } else {
+                        style(&text, severity_color(&issue.severity))
+                    }
+                }
+                None => " ".repeat(width),
+            })
+            .collect()
+    }
+
+    // Source around the selected issue with its span highlighted; other lines
+    // with findings get a marker in the gutter
+    fn preview_cells(&self, width: usize, rows: usize, source: Option<&[String]>) -> Vec<String> {
+        let blank = " ".repeat(width);
+        let (Some(file), Some(source)) = (self.selected_file(), source) else {
+            return vec![blank; rows];
+        };
+        let selected = self.selected_issue().or(file.issues.first());
+        let focus = selected.map_or(1, |issue| issue.line);
+        let start = focus.saturating_sub(rows / 2).max(1);
+        let flagged: HashMap<usize, &str> = file
+            .issues
+            .iter()
+            .map(|issue| (issue.line, issue.severity.as_str()))
+            .collect();
+
+        (start..start + rows)
+            .map(|number| {
+                let Some(text) = source.get(number - 1) else {
+                    return blank.clone();
+                };
+                let text = text.replace('\t', "    ");
+                let marker = match flagged.get(&number) {
+                    Some(severity) => style("●", severity_color(severity)),
+                    None => " ".to_string(),
+                };
+                let gutter = format!("{}{:>5} ", marker, number);
+                let available = width.saturating_sub(7);
+                match selected.filter(|issue| issue.line == number) {
+                    Some(issue) => format!("{}{}", gutter, highlight_span(&text, issue, available)),
+                    None => format!("{}{}", gutter, fit(&text, available)),
+                }
+            })
+            .collect()
+    }
+}
+
+// First row to show so that `selected` stays visible
+fn scroll_offset(selected: usize, rows: usize) -> usize {
+    (selected + 1).saturating_sub(rows)
+}
+
+fn severity_color(severity: &str) -> &'static str {
+    match severity {
+        "Error" => "31",
+        "Warning" => "33",
+        "Info" => "36",
+        _ => "2",
+    }
+}
+
+fn style(text: &str, sgr: &str) -> String {
+    format!("\x1b[{}m{}\x1b[0m", sgr, text)
+}
+
+// Truncates or pads `text` to exactly `width` characters
+fn fit(text: &str, width: usize) -> String {
+    let mut fitted: String = text.chars().take(width).collect();
+    let len = fitted.chars().count();
+    fitted.extend(std::iter::repeat_n(' ', width - len));
+    fitted
+}
+
+// `line` with the issue's span (its first line, from its byte column) in reverse video
+fn highlight_span(line: &str, issue: &TuiIssue, width: usize) -> String {
+    let mut start = issue.column.saturating_sub(1).min(line.len());
+    while !line.is_char_boundary(start) {
+        start -= 1;
+    }
+    let span_len = issue.text.lines().next().unwrap_or("").len().max(1);
+    let mut end = (start + span_len).min(line.len());
+    while !line.is_char_boundary(end) {
+        end += 1;
+    }
+    let before: String = line[..start].chars().take(width).collect();
+    let room = width - before.chars().count();
+    let span: String = line[start..end].chars().take(room).collect();
+    let room = room - span.chars().count();
+    let after: String = line[end..].chars().take(room).collect();
+    let pad = room - after.chars().count();
+    format!(
+        "{}\x1b[7m{}\x1b[0m{}{}",
+        before,
+        span,
+        after,
+        " ".repeat(pad)
+    )
+}
+
+// Arrow keys arrive as escape sequences, everything else as single characters
+fn decode_key(bytes: &[u8]) -> Option<Key> {
+    match bytes {
+        [0x1b, b'[', b'A', ..] | [0x1b, b'O', b'A', ..] => Some(Key::Up),
+        [0x1b, b'[', b'B', ..] | [0x1b, b'O', b'B', ..] => Some(Key::Down),
+        [0x1b, b'[', b'C', ..] | [0x1b, b'O', b'C', ..] => Some(Key::Right),
+        [0x1b, b'[', b'D', ..] | [0x1b, b'O', b'D', ..] => Some(Key::Left),
+        [0x1b] => Some(Key::Escape),
+        [b'\t', ..] => Some(Key::Tab),
+        [b'\r', ..] | [b'\n', ..] => Some(Key::Enter),
+        _ => std::str::from_utf8(bytes)
+            .ok()?
+            .chars()
+            .next()
+            .map(Key::Char),
+    }
+}
+
+// `$VISUAL` or `$EDITOR` (default vi) at a location; VS Code-style editors take
+// `-g path:line:col`, everything else `+line path`
+fn editor_command(path: &str, line: usize, column: usize) -> Command {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    let mut words = editor.split_whitespace();
+    let program = words.next().unwrap_or("vi").to_string();
+    let mut command = Command::new(&program);
+    command.args(words);
+    let name = std::path::Path::new(&program)
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("");
+    if matches!(name, "code" | "codium" | "cursor") {
+        command
+            .arg("-g")
+            .arg(format!("{}:{}:{}", path, line, column));
+    } else {
+        command.arg(format!("+{}", line)).arg(path);
+    }
+    command
+}
+
+#[cfg(unix)]
+struct RawTerminal {
+    original: libc::termios,
+}
+
+#[cfg(unix)]
+impl RawTerminal {
+    fn enter() -> io::Result<Self> {
+        let mut original = unsafe { std::mem::zeroed::<libc::termios>() };
+        if unsafe { libc::tcgetattr(libc::STDIN_FILENO, &mut original) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let mut raw = original;
+        unsafe { libc::cfmakeraw(&mut raw) };
+        if unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &raw) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // Alternate screen, hidden cursor
+        print!("\x1b[?1049h\x1b[?25l");
+        io::stdout().flush()?;
+        Ok(RawTerminal { original })
+    }
+
+    fn size() -> (usize, usize) {
+        let mut size = unsafe { std::mem::zeroed::<libc::winsize>() };
+        if unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) } != 0
+            || size.ws_col == 0
+        {
+            return (80, 24);
+        }
+        (size.ws_col as usize, size.ws_row as usize)
+    }
+
+    fn read_key() -> io::Result<Option<Key>> {
+        let mut buffer = [0u8; 16];
+        let read = unsafe {
+            libc::read(
+                libc::STDIN_FILENO,
+                buffer.as_mut_ptr() as *mut libc::c_void,
+                buffer.len(),
+            )
+        };
+        if read < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        if read == 0 {
+            // stdin closed
+            return Ok(Some(Key::Char('q')));
+        }
+        Ok(decode_key(&buffer[..read as usize]))
+    }
+}
+
+#[cfg(unix)]
+impl Drop for RawTerminal {
+    fn drop(&mut self) {
+        print!("\x1b[?25h\x1b[?1049l");
+        let _ = io::stdout().flush();
+        unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &self.original) };
+    }
+}
+
+// Runs the browser until the user quits; the editor runs with the terminal restored
+#[cfg(unix)]
+pub fn run(report: &Value) -> Result<(), Box<dyn std::error::Error>> {
+    if unsafe { libc::isatty(libc::STDIN_FILENO) == 0 || libc::isatty(libc::STDOUT_FILENO) == 0 } {
+        return Err("the TUI needs an interactive terminal".into());
+    }
+    let mut state = TuiState::from_report(report);
+    let mut sources: HashMap<String, Vec<String>> = HashMap::new();
+    let mut terminal = Some(RawTerminal::enter()?);
+    loop {
+        let (width, height) = RawTerminal::size();
+        let source = state.selected_file().map(|file| {
+            sources
+                .entry(file.path.clone())
+                .or_insert_with(|| {
+                    fs::read_to_string(&file.path)
+                        .map(|text| text.lines().map(str::to_string).collect())
+                        .unwrap_or_default()
+                })
+                .as_slice()
+        });
+        let screen = state.render(width, height, source);
+        print!("\x1b[H{}", screen.join("\r\n"));
+        io::stdout().flush()?;
+
+        let Some(key) = RawTerminal::read_key()? else {
+            continue;
+        };
+        match state.handle_key(key) {
+            Action::None => {}
+            Action::Quit => return Ok(()),
+            Action::Open(path, line, column) => {
+                drop(terminal.take());
+                let status = editor_command(&path, line, column).status();
+                terminal = Some(RawTerminal::enter()?);
+                if let Err(e) = status {
+                    return Err(format!("could not start the editor: {}", e).into());
+                }
+            }
+        }
+    }
+}
+
+#[cfg(not(unix))]
+pub fn run(_report: &Value) -> Result<(), Box<dyn std::error::Error>> {
+    Err("the TUI is only supported on Unix terminals".into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_navigation_and_rendering() {
+        let issue = |line: u64, rule: &str| {
+            json!({"line": line, "column": 5, "rule": rule, "severity": "Warning",
+                   "message": "m", "text": "x.unwrap()"})
+        };
+        let report = json!({"files": [
+            {"path": "good.rs", "result": {"score": 9.5, "issues": []}},
+            {"path": "bad.rs", "result": {"score": 4.0, "issues": [issue(3, "b"), issue(1, "a")]}},
+            {"path": "broken.rs", "error": "parse failed"},
+        ]});
+        let mut state = TuiState::from_report(&report);
+        let paths: Vec<&str> = state.files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(paths, ["bad.rs", "good.rs", "broken.rs"]);
+        assert_eq!(state.files[0].issues[0].rule, "a");
+
+        assert_eq!(state.handle_key(Key::Enter), Action::None);
+        assert_eq!(state.pane, Pane::Issues);
+        state.handle_key(Key::Down);
+        state.handle_key(Key::Down);
+        assert_eq!(state.issue, 1);
+        assert_eq!(
+            state.handle_key(Key::Enter),
+            Action::Open("bad.rs".to_string(), 3, 5)
+        );
+
+        let source: Vec<String> = ["fn a() {", "}", "    x.unwrap();"]
+            .map(String::from)
+            .to_vec();
+        let screen = state.render(60, 12, Some(&source));
+        assert_eq!(screen.len(), 12);
+        assert!(screen[0].contains("3 file(s)  average score 6.8"));
+        assert!(screen
+            .iter()
+            .any(|line| line.contains("    \x1b[7mx.unwrap()\x1b[0m;")));
+
+        state.handle_key(Key::Escape);
+        assert_eq!(state.handle_key(Key::Char('q')), Action::Quit);
+        assert_eq!(decode_key(b"\x1b[B"), Some(Key::Down));
+    }
+}
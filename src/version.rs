@@ -0,0 +1,97 @@
+use crate::language::SupportedLanguage;
+use serde_json::{json, Map, Value};
+use tree_sitter::Language;
+
+// "name=version;..." for tree-sitter and the grammar crates in Cargo.lock, recorded by
+// build.rs
+const DEPENDENCY_VERSIONS: &str = env!("TREESCAN_DEPENDENCY_VERSIONS");
+
+pub fn dependency_version(name: &str) -> Option<&'static str> {
+    DEPENDENCY_VERSIONS
+        .split(';')
+        .filter_map(|entry| entry.split_once('='))
+        .find(|(crate_name, _)| *crate_name == name)
+        .map(|(_, version)| version)
+}
+
+// Changes whenever tree-sitter or any grammar is upgraded, so cached results
+// produced by older grammars stop matching
+pub fn grammar_fingerprint() -> &'static str {
+    DEPENDENCY_VERSIONS
+}
+
+pub fn grammar_crate(lang: SupportedLanguage) -> &'static str {
+    match lang {
+        SupportedLanguage::Rust => "tree-sitter-rust",
+        SupportedLanguage::Java => "tree-sitter-java",
+        SupportedLanguage::Zig => "tree-sitter-zig",
+        SupportedLanguage::C => "tree-sitter-c",
+        SupportedLanguage::Cpp => "tree-sitter-cpp",
+        SupportedLanguage::JavaScript => "tree-sitter-javascript",
+        SupportedLanguage::TypeScript | SupportedLanguage::Tsx => "tree-sitter-typescript",
+        SupportedLanguage::Go => "tree-sitter-go",
+    }
+}
+
+fn grammar_json(language: &Language) -> Value {
+    let mut grammar = json!({ "abi_version": language.abi_version() });
+    match SupportedLanguage::from_tree_sitter(language) {
+        Some(lang) => {
+            let name = grammar_crate(lang);
+            grammar["crate"] = json!(name);
+            grammar["version"] = json!(dependency_version(name));
+        }
+        // Loaded with --grammar; only the grammar itself knows what it is
+        None => grammar["name"] = json!(language.name()),
+    }
+    grammar
+}
+
+// `metadata` for parse and single-file analyze output
+pub fn version_metadata(language: &Language) -> Value {
+    json!({
+        "treescan": env!("CARGO_PKG_VERSION"),
+        "tree_sitter": dependency_version("tree-sitter"),
+        "tree_sitter_abi": tree_sitter::LANGUAGE_VERSION,
+        "grammar": grammar_json(language),
+    })
+}
+
+// `metadata` for directory reports, covering every bundled grammar
+pub fn all_versions_metadata() -> Value {
+    let grammars: Map<String, Value> = SupportedLanguage::ALL
+        .iter()
+        .map(|lang| {
+            (
+                lang.name().to_string(),
+                grammar_json(&lang.tree_sitter_language()),
+            )
+        })
+        .collect();
+    json!({
+        "treescan": env!("CARGO_PKG_VERSION"),
+        "tree_sitter": dependency_version("tree-sitter"),
+        "tree_sitter_abi": tree_sitter::LANGUAGE_VERSION,
+        "grammars": grammars,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_versions_come_from_the_lock_file() {
+        assert!(dependency_version("tree-sitter").is_some_and(|v| v.starts_with("0.")));
+        assert_eq!(dependency_version("serde"), None);
+
+        let metadata = version_metadata(&SupportedLanguage::Go.tree_sitter_language());
+        assert_eq!(metadata["grammar"]["crate"], "tree-sitter-go");
+        assert_eq!(
+            metadata["grammar"]["version"],
+            dependency_version("tree-sitter-go").unwrap()
+        );
+        assert!(metadata["grammar"]["abi_version"].as_u64().unwrap() >= 13);
+        assert!(all_versions_metadata()["grammars"]["TSX"].is_object());
+    }
+}